@@ -0,0 +1,564 @@
+//! Bibliography files: BibTeX (`.bib`) and CSL-JSON
+//!
+//! Both are read into the same list of [`Entry`] values, keeping what
+//! citations and reference lists show: authors, year, title, where the work
+//! appeared and a link. Everything else in the file is ignored.
+//!
+//! BibTeX values may be `{braced}`, `"quoted"`, numbers or `@string` macros
+//! joined with `#`. Braces are dropped from the result and the common escapes
+//! (`\&`, `\%`, `--`, `~`) become the characters they stand for; other TeX
+//! commands are kept as written.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// A parsed bibliography, in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bibliography {
+    pub entries: Vec<Entry>,
+}
+
+/// One work of a bibliography
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry {
+    /// The citation key, `smith2023` in `[@smith2023]`
+    pub key: String,
+    /// Authors, or the editors when there are no authors
+    pub authors: Vec<Name>,
+    pub year: Option<String>,
+    pub title: Option<String>,
+    /// Journal, book or proceedings the work appeared in
+    pub container: Option<String>,
+    pub volume: Option<String>,
+    pub pages: Option<String>,
+    pub publisher: Option<String>,
+    pub doi: Option<String>,
+    pub url: Option<String>,
+}
+
+/// A person's name, or an organisation's as `family` alone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Name {
+    pub family: String,
+    pub given: Option<String>,
+}
+
+/// Where and why a bibliography could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct BibliographyError {
+    /// Zero-based line
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BibliographyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line + 1, self.message)
+    }
+}
+
+impl std::error::Error for BibliographyError {}
+
+impl Bibliography {
+    /// Parse `text` as CSL-JSON when `path` ends in `.json` (or, without a
+    /// telling extension, when it starts with `[`), as BibTeX otherwise
+    pub fn parse(text: &str, path: &Path) -> Result<Bibliography, BibliographyError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") => parse_csl_json(text),
+            Some("bib") | Some("bibtex") => parse_bibtex(text),
+            _ if text.trim_start().starts_with('[') => parse_csl_json(text),
+            _ => parse_bibtex(text),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+/// Parse BibTeX: `@article{key, author = {..}, year = 2023, ...}` entries
+pub fn parse_bibtex(text: &str) -> Result<Bibliography, BibliographyError> {
+    let mut parser = BibParser {
+        text,
+        pos: 0,
+        macros: HashMap::new(),
+    };
+    let mut entries = Vec::new();
+
+    // Anything outside an entry is a comment
+    while let Some(offset) = parser.rest().find('@') {
+        parser.pos += offset + 1;
+        let kind = parser.identifier().to_ascii_lowercase();
+        parser.skip_whitespace();
+        let close = match parser.peek() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return Err(parser.error(format!("expected '{{' after '@{kind}'"))),
+        };
+        parser.pos += 1;
+
+        match kind.as_str() {
+            "comment" | "preamble" => parser.skip_group(close)?,
+            "string" => {
+                let fields = parser.fields(close)?;
+                parser.macros.extend(fields);
+            }
+            _ => {
+                let key = parser.key()?;
+                let fields = parser.fields(close)?;
+                entries.push(bibtex_entry(key, fields));
+            }
+        }
+    }
+
+    Ok(Bibliography { entries })
+}
+
+struct BibParser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// `@string` definitions, by lowercase name
+    macros: HashMap<String, String>,
+}
+
+impl<'a> BibParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn identifier(&mut self) -> String {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || "_-:.+/'".contains(c)))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        rest[..len].to_string()
+    }
+
+    fn error(&self, message: impl Into<String>) -> BibliographyError {
+        BibliographyError {
+            line: self.text[..self.pos].matches('\n').count(),
+            message: message.into(),
+        }
+    }
+
+    /// The citation key up to the first comma
+    fn key(&mut self) -> Result<String, BibliographyError> {
+        let rest = self.rest();
+        let Some(end) = rest.find([',', '}', ')']) else {
+            return Err(self.error("entry is never closed"));
+        };
+        let key = rest[..end].trim().to_string();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(self.error("entry has no citation key"));
+        }
+        self.pos += end;
+        if self.peek() == Some(',') {
+            self.pos += 1;
+        }
+        Ok(key)
+    }
+
+    /// `name = value` pairs up to `close`, with lowercase names
+    fn fields(&mut self, close: char) -> Result<Vec<(String, String)>, BibliographyError> {
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Err(self.error("entry is never closed")),
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(fields);
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let name = self.identifier().to_ascii_lowercase();
+            if name.is_empty() {
+                return Err(self.error(format!(
+                    "expected a field name, found '{}'",
+                    self.peek().unwrap_or(' ')
+                )));
+            }
+            self.skip_whitespace();
+            if self.bump() != Some('=') {
+                return Err(self.error(format!("expected '=' after field '{name}'")));
+            }
+            let value = self.value()?;
+            fields.push((name, value));
+        }
+    }
+
+    /// A value: pieces joined with `#`, raw (braces and escapes kept)
+    fn value(&mut self) -> Result<String, BibliographyError> {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    self.skip_group('}')?;
+                    value.push_str(&self.text[start..self.pos - 1]);
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    let mut depth = 0;
+                    loop {
+                        match self.bump() {
+                            None => return Err(self.error("quoted value is never closed")),
+                            Some('{') => depth += 1,
+                            Some('}') => depth -= 1,
+                            Some('"') if depth == 0 => break,
+                            Some(_) => {}
+                        }
+                    }
+                    value.push_str(&self.text[start..self.pos - 1]);
+                }
+                Some(_) => {
+                    let word = self.identifier();
+                    if word.is_empty() {
+                        return Err(self.error("expected a value"));
+                    }
+                    match self.macros.get(&word.to_ascii_lowercase()) {
+                        Some(expansion) => value.push_str(expansion),
+                        None => value.push_str(&word),
+                    }
+                }
+                None => return Err(self.error("expected a value")),
+            }
+            self.skip_whitespace();
+            if self.peek() != Some('#') {
+                return Ok(value);
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skip to just past the `close` that balances an opening already read
+    fn skip_group(&mut self, close: char) -> Result<(), BibliographyError> {
+        let open = if close == ')' { '(' } else { '{' };
+        let mut depth = 0;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                _ if c == open => depth += 1,
+                _ if c == close && depth == 0 => return Ok(()),
+                _ if c == close => depth -= 1,
+                _ => {}
+            }
+        }
+        Err(self.error("group is never closed"))
+    }
+}
+
+fn bibtex_entry(key: String, fields: Vec<(String, String)>) -> Entry {
+    let field = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| clean(value))
+                .filter(|value| !value.is_empty())
+        })
+    };
+    let people = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| bibtex_names(value))
+            .unwrap_or_default()
+    };
+
+    let mut authors = people("author");
+    if authors.is_empty() {
+        authors = people("editor");
+    }
+    let year = field(&["year"])
+        .or_else(|| field(&["date"]).map(|date| date.chars().take_while(|c| *c != '-').collect()));
+
+    Entry {
+        key,
+        authors,
+        year,
+        title: field(&["title"]),
+        container: field(&["journal", "journaltitle", "booktitle"]),
+        volume: field(&["volume"]),
+        pages: field(&["pages"]),
+        publisher: field(&["publisher", "institution", "school", "organization"]),
+        doi: field(&["doi"]),
+        url: field(&["url"]),
+    }
+}
+
+/// Split `A and B and {C and D}` into names
+fn bibtex_names(value: &str) -> Vec<Name> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            _ if depth == 0
+                && bytes
+                    .get(i..i + 5)
+                    .is_some_and(|word| word.eq_ignore_ascii_case(b" and ")) =>
+            {
+                names.push(&value[start..i]);
+                i += " and ".len();
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    names.push(&value[start..]);
+
+    names
+        .into_iter()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(bibtex_name)
+        .collect()
+}
+
+/// `Last, First`, `First von Last`, or a `{Braced Organisation}`
+fn bibtex_name(name: &str) -> Name {
+    if name.starts_with('{') && name.ends_with('}') {
+        return Name {
+            family: clean(name),
+            given: None,
+        };
+    }
+    let parts: Vec<&str> = name.split(',').map(str::trim).collect();
+    if parts.len() > 1 {
+        // `Last, First` or `Last, Jr, First`
+        let given = clean(parts[parts.len() - 1]);
+        return Name {
+            family: clean(parts[0]),
+            given: (!given.is_empty()).then_some(given),
+        };
+    }
+
+    let words: Vec<&str> = name.split_whitespace().collect();
+    // The family name starts at the first lowercase particle (`van`, `de`),
+    // or is the last word
+    let split = words
+        .iter()
+        .take(words.len().saturating_sub(1))
+        .position(|word| {
+            word.trim_start_matches('{')
+                .starts_with(|c: char| c.is_lowercase())
+        })
+        .unwrap_or(words.len().saturating_sub(1));
+    let given = clean(&words[..split].join(" "));
+    Name {
+        family: clean(&words[split..].join(" ")),
+        given: (!given.is_empty()).then_some(given),
+    }
+}
+
+/// Drop braces, resolve common escapes and collapse whitespace
+fn clean(value: &str) -> String {
+    let text = value
+        .replace(['{', '}'], "")
+        .replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_")
+        .replace("\\$", "$")
+        .replace("---", "—")
+        .replace("--", "–")
+        .replace('~', " ");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse CSL-JSON: an array of items with `id`, `author`, `issued`, ...
+pub fn parse_csl_json(text: &str) -> Result<Bibliography, BibliographyError> {
+    let value: Value = serde_json::from_str(text).map_err(|err| BibliographyError {
+        line: err.line().saturating_sub(1),
+        message: err.to_string(),
+    })?;
+    let Value::Array(items) = value else {
+        return Err(BibliographyError {
+            line: 0,
+            message: "CSL-JSON must be an array of items".to_string(),
+        });
+    };
+
+    let mut entries = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let key = match item.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => {
+                return Err(BibliographyError {
+                    line: 0,
+                    message: format!("item {} has no \"id\"", index + 1),
+                })
+            }
+        };
+        let text_field = |name: &str| match item.get(name) {
+            Some(Value::String(text)) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            Some(Value::Number(number)) => Some(number.to_string()),
+            _ => None,
+        };
+
+        let mut authors = csl_names(item.get("author"));
+        if authors.is_empty() {
+            authors = csl_names(item.get("editor"));
+        }
+        entries.push(Entry {
+            key,
+            authors,
+            year: csl_year(item.get("issued")),
+            title: text_field("title"),
+            container: text_field("container-title"),
+            volume: text_field("volume"),
+            pages: text_field("page"),
+            publisher: text_field("publisher"),
+            doi: text_field("DOI"),
+            url: text_field("URL"),
+        });
+    }
+
+    Ok(Bibliography { entries })
+}
+
+fn csl_names(value: Option<&Value>) -> Vec<Name> {
+    let Some(Value::Array(names)) = value else {
+        return Vec::new();
+    };
+    names
+        .iter()
+        .filter_map(|name| {
+            let part = |key: &str| name.get(key).and_then(Value::as_str).map(str::to_string);
+            match (part("family"), part("literal")) {
+                (Some(family), _) => Some(Name {
+                    family,
+                    given: part("given"),
+                }),
+                (None, Some(literal)) => Some(Name {
+                    family: literal,
+                    given: None,
+                }),
+                (None, None) => None,
+            }
+        })
+        .collect()
+}
+
+/// The year of a CSL date: `{"date-parts": [[2023, 5]]}` or `{"raw": "2023-05"}`
+fn csl_year(value: Option<&Value>) -> Option<String> {
+    let date = value?;
+    if let Some(year) = date.pointer("/date-parts/0/0") {
+        return match year {
+            Value::Number(year) => Some(year.to_string()),
+            Value::String(year) => Some(year.clone()),
+            _ => None,
+        };
+    }
+    ["raw", "literal"]
+        .iter()
+        .find_map(|key| date.get(*key).and_then(Value::as_str))
+        .map(|raw| raw.chars().take_while(|c| *c != '-').collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bibtex() {
+        let bib = parse_bibtex(
+            r#"Comments are ignored.
+@string{acm = "ACM Press"}
+@article{smith2023,
+  author = {Smith, John and Mary {de la} Cruz and {World Health Organization}},
+  title = {Rivers {\&} Floods},
+  journal = "Hydrology" # { Letters},
+  year = 2023,
+  pages = {1--10},
+}
+@book(lee2020, editor = "Lee, Ann", publisher = acm, date = {2020-04-01})
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(bib.entries.len(), 2);
+        let smith = bib.get("smith2023").unwrap();
+        let families: Vec<&str> = smith.authors.iter().map(|n| n.family.as_str()).collect();
+        assert_eq!(
+            families,
+            vec!["Smith", "de la Cruz", "World Health Organization"]
+        );
+        assert_eq!(smith.authors[0].given.as_deref(), Some("John"));
+        assert_eq!(smith.title.as_deref(), Some("Rivers & Floods"));
+        assert_eq!(smith.container.as_deref(), Some("Hydrology Letters"));
+        assert_eq!(smith.pages.as_deref(), Some("1–10"));
+
+        let lee = bib.get("lee2020").unwrap();
+        assert_eq!(lee.authors[0].family, "Lee");
+        assert_eq!(lee.year.as_deref(), Some("2020"));
+        assert_eq!(lee.publisher.as_deref(), Some("ACM Press"));
+    }
+
+    #[test]
+    fn test_bibtex_errors_have_lines() {
+        let err = parse_bibtex("@article{key,\n  title = {Open\n").unwrap_err();
+        assert!(err.message.contains("never closed"), "{err}");
+
+        let err = parse_bibtex("\n@article{key, title {x}}").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("'='"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_csl_json() {
+        let bib = parse_csl_json(
+            r#"[{"id": "smith2023", "type": "article-journal", "title": "Rivers",
+                "author": [{"family": "Smith", "given": "John"}, {"literal": "WHO"}],
+                "issued": {"date-parts": [[2023, 5]]}, "container-title": "Hydrology",
+                "DOI": "10.1000/xyz"}]"#,
+        )
+        .unwrap();
+        let smith = bib.get("smith2023").unwrap();
+        assert_eq!(smith.authors[1].family, "WHO");
+        assert_eq!(smith.year.as_deref(), Some("2023"));
+        assert_eq!(smith.doi.as_deref(), Some("10.1000/xyz"));
+
+        let err = parse_csl_json("[{\"title\": \"No id\"}]").unwrap_err();
+        assert!(err.message.contains("\"id\""), "{err}");
+    }
+}
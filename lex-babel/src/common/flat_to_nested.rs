@@ -39,6 +39,8 @@
 //!    - Add it as a child to the current parent (top of stack)
 //!    - Push it onto the stack as the new current container
 //!
+//!    - If the previous event was a `Source`, record its span on the new node
+//!
 //! 3. **Processing Content Events (Inline):**
 //!    - Add the content to the current parent (top of stack)
 //!    - Do NOT modify the stack (content is a leaf)
@@ -97,39 +99,49 @@ enum StackNode {
         level: usize,
         content: Vec<InlineContent>,
         children: Vec<DocNode>,
+        id: Option<String>,
+        span: Option<SourceSpan>,
     },
     Paragraph {
         content: Vec<InlineContent>,
+        span: Option<SourceSpan>,
     },
     List {
         items: Vec<ListItem>,
         ordered: bool,
         style: ListStyle,
         form: ListForm,
+        span: Option<SourceSpan>,
     },
     ListItem {
         content: Vec<InlineContent>,
         children: Vec<DocNode>,
+        checked: Option<bool>,
+        span: Option<SourceSpan>,
     },
     Definition {
         term: Vec<InlineContent>,
         description: Vec<DocNode>,
         in_term: bool,
+        span: Option<SourceSpan>,
     },
     Verbatim {
         subject: Option<String>,
         language: Option<String>,
         content: String,
+        span: Option<SourceSpan>,
     },
     Annotation {
         label: String,
         parameters: Vec<(String, String)>,
         content: Vec<DocNode>,
+        span: Option<SourceSpan>,
     },
     Table {
         rows: Vec<TableRow>,
         header: Vec<TableRow>,
         caption: Option<Vec<InlineContent>>,
+        span: Option<SourceSpan>,
     },
     TableRow {
         cells: Vec<TableCell>,
@@ -139,6 +151,12 @@ enum StackNode {
         content: Vec<DocNode>,
         header: bool,
         align: TableCellAlignment,
+        colspan: usize,
+        rowspan: usize,
+    },
+    BlockQuote {
+        children: Vec<DocNode>,
+        span: Option<SourceSpan>,
     },
 }
 
@@ -151,33 +169,57 @@ impl StackNode {
                 level,
                 content,
                 children,
+                id,
+                span,
             } => DocNode::Heading(Heading {
                 level,
                 content,
                 children,
+                id,
+                span,
             }),
-            StackNode::Paragraph { content } => DocNode::Paragraph(Paragraph { content }),
+            StackNode::Paragraph { content, span } => {
+                DocNode::Paragraph(Paragraph { content, span })
+            }
             StackNode::List {
                 items,
                 ordered,
                 style,
                 form,
+                span,
             } => DocNode::List(List {
                 items,
                 ordered,
                 style,
                 form,
+                span,
+            }),
+            StackNode::ListItem {
+                content,
+                children,
+                checked,
+                span,
+            } => DocNode::ListItem(ListItem {
+                content,
+                children,
+                checked,
+                span,
             }),
-            StackNode::ListItem { content, children } => {
-                DocNode::ListItem(ListItem { content, children })
-            }
             StackNode::Definition {
-                term, description, ..
-            } => DocNode::Definition(Definition { term, description }),
+                term,
+                description,
+                span,
+                ..
+            } => DocNode::Definition(Definition {
+                term,
+                description,
+                span,
+            }),
             StackNode::Verbatim {
                 subject,
                 language,
                 content,
+                span,
             } => {
                 if let Some(lang) = &language {
                     if let Some(label) = lang.strip_prefix("lex-metadata:") {
@@ -204,6 +246,7 @@ impl StackNode {
                             if !text.is_empty() {
                                 content_nodes.push(DocNode::Paragraph(Paragraph {
                                     content: vec![InlineContent::Text(text.to_string())],
+                                    span: None,
                                 }));
                             }
                         }
@@ -212,6 +255,7 @@ impl StackNode {
                             label: label.to_string(),
                             parameters,
                             content: content_nodes,
+                            span,
                         });
                     }
                 }
@@ -219,26 +263,34 @@ impl StackNode {
                     subject,
                     language,
                     content,
+                    span,
                 })
             }
             StackNode::Annotation {
                 label,
                 parameters,
                 content,
+                span,
             } => DocNode::Annotation(Annotation {
                 label,
                 parameters,
                 content,
+                span,
             }),
             StackNode::Table {
                 rows,
                 header,
                 caption,
+                span,
             } => DocNode::Table(Table {
                 rows,
                 header,
                 caption,
+                span,
             }),
+            StackNode::BlockQuote { children, span } => {
+                DocNode::BlockQuote(BlockQuote { children, span })
+            }
             StackNode::TableRow { cells: _, .. } => {
                 // TableRow is not a DocNode, it's part of Table
                 // This should not happen if logic is correct (TableRow is consumed by Table)
@@ -265,6 +317,7 @@ impl StackNode {
             StackNode::Table { .. } => "Table",
             StackNode::TableRow { .. } => "TableRow",
             StackNode::TableCell { .. } => "TableCell",
+            StackNode::BlockQuote { .. } => "BlockQuote",
         }
     }
 
@@ -316,6 +369,10 @@ impl StackNode {
                 content.push(child);
                 Ok(())
             }
+            StackNode::BlockQuote { children, .. } => {
+                children.push(child);
+                Ok(())
+            }
             _ => Err(ConversionError::UnexpectedInline(format!(
                 "Node {} cannot have children",
                 self.type_name()
@@ -330,7 +387,7 @@ impl StackNode {
                 content.push(inline);
                 Ok(())
             }
-            StackNode::Paragraph { content } => {
+            StackNode::Paragraph { content, .. } => {
                 content.push(inline);
                 Ok(())
             }
@@ -505,7 +562,7 @@ fn auto_close_all_headings(stack: &mut Vec<StackNode>) -> Result<(), ConversionE
 /// ```
 pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
     if events.is_empty() {
-        return Ok(Document { children: vec![] });
+        return Ok(Document::default());
     }
 
     let mut stack: Vec<StackNode> = Vec::new();
@@ -514,7 +571,7 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
     // Expect StartDocument as first event
     match event_iter.next() {
         Some(Event::StartDocument) => {
-            stack.push(StackNode::Document(Document { children: vec![] }));
+            stack.push(StackNode::Document(Document::default()));
         }
         Some(other) => {
             return Err(ConversionError::MismatchedEvents {
@@ -522,12 +579,28 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                 found: format!("{other:?}"),
             });
         }
-        None => return Ok(Document { children: vec![] }),
+        None => return Ok(Document::default()),
     }
 
     // Process events
+    let mut pending_span: Option<SourceSpan> = None;
+    let mut pending_id: Option<String> = None;
     while let Some(event) = event_iter.next() {
+        // `Source` and `Anchor` events only apply to the node opened right after them
+        let span = pending_span.take();
+        let id = pending_id.take();
         match event {
+            Event::Source(next) => {
+                pending_span = Some(next.clone());
+                // An anchor may still follow the span
+                pending_id = id;
+            }
+
+            Event::Anchor(next) => {
+                pending_id = Some(next.clone());
+                pending_span = span;
+            }
+
             Event::StartDocument => {
                 return Err(ConversionError::MismatchedEvents {
                     expected: "content or EndDocument".to_string(),
@@ -569,6 +642,8 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                     level: *level,
                     content: vec![],
                     children: vec![],
+                    id,
+                    span,
                 };
                 stack.push(node);
             }
@@ -603,7 +678,10 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
             }
 
             Event::StartParagraph => {
-                stack.push(StackNode::Paragraph { content: vec![] });
+                stack.push(StackNode::Paragraph {
+                    content: vec![],
+                    span,
+                });
             }
 
             Event::EndParagraph => {
@@ -626,6 +704,7 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                     ordered: *ordered,
                     style: *style,
                     form: *form,
+                    span,
                 });
             }
 
@@ -639,10 +718,12 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                 })?;
             }
 
-            Event::StartListItem => {
+            Event::StartListItem { checked } => {
                 stack.push(StackNode::ListItem {
                     content: vec![],
                     children: vec![],
+                    checked: *checked,
+                    span,
                 });
             }
 
@@ -661,6 +742,7 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                     term: vec![],
                     description: vec![],
                     in_term: false,
+                    span,
                 });
             }
 
@@ -714,6 +796,7 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                     subject: subject.clone(),
                     language: language.clone(),
                     content: String::new(),
+                    span,
                 });
             }
 
@@ -732,6 +815,7 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                     label: label.clone(),
                     parameters: parameters.clone(),
                     content: vec![],
+                    span,
                 });
             }
 
@@ -760,11 +844,12 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                 )?;
             }
 
-            Event::StartTable => {
+            Event::StartTable { caption } => {
                 stack.push(StackNode::Table {
                     rows: vec![],
                     header: vec![],
-                    caption: None,
+                    caption: caption.clone(),
+                    span,
                 });
             }
 
@@ -827,11 +912,18 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                 }
             }
 
-            Event::StartTableCell { header, align } => {
+            Event::StartTableCell {
+                header,
+                align,
+                colspan,
+                rowspan,
+            } => {
                 stack.push(StackNode::TableCell {
                     content: vec![],
                     header: *header,
                     align: *align,
+                    colspan: *colspan,
+                    rowspan: *rowspan,
                 });
             }
 
@@ -846,11 +938,15 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                         content,
                         header,
                         align,
+                        colspan,
+                        rowspan,
                     } => {
                         let cell = TableCell {
                             content,
                             header,
                             align,
+                            colspan,
+                            rowspan,
                         };
                         let parent = stack.last_mut().ok_or_else(|| {
                             ConversionError::UnexpectedEnd("No parent for table cell".to_string())
@@ -876,6 +972,37 @@ pub fn events_to_tree(events: &[Event]) -> Result<Document, ConversionError> {
                 }
             }
 
+            Event::StartBlockQuote => {
+                stack.push(StackNode::BlockQuote {
+                    children: vec![],
+                    span,
+                });
+            }
+
+            Event::EndBlockQuote => {
+                finalize_container(
+                    &mut stack,
+                    "EndBlockQuote",
+                    "block quote",
+                    |node| match node {
+                        StackNode::BlockQuote { .. } => Ok(node),
+                        other => Err(ConversionError::MismatchedEvents {
+                            expected: "BlockQuote".to_string(),
+                            found: other.type_name().to_string(),
+                        }),
+                    },
+                )?;
+            }
+
+            Event::ThematicBreak => {
+                let parent = stack.last_mut().ok_or_else(|| {
+                    ConversionError::UnexpectedEnd(
+                        "ThematicBreak event with empty stack".to_string(),
+                    )
+                })?;
+                parent.add_child(DocNode::ThematicBreak)?;
+            }
+
             Event::Image(image) => {
                 let parent = stack.last_mut().ok_or_else(|| {
                     ConversionError::UnexpectedEnd("Image event with empty stack".to_string())
@@ -1002,10 +1129,10 @@ mod tests {
                 style: ListStyle::Bullet,
                 form: ListForm::Short,
             },
-            Event::StartListItem,
+            Event::StartListItem { checked: None },
             Event::Inline(InlineContent::Text("Item 1".to_string())),
             Event::EndListItem,
-            Event::StartListItem,
+            Event::StartListItem { checked: None },
             Event::Inline(InlineContent::Text("Item 2".to_string())),
             Event::EndListItem,
             Event::EndList,
@@ -1123,7 +1250,7 @@ mod tests {
                 style: ListStyle::Bullet,
                 form: ListForm::Short,
             },
-            Event::StartListItem,
+            Event::StartListItem { checked: None },
             Event::Inline(InlineContent::Text("Item".to_string())),
             Event::EndListItem,
             Event::EndList,
@@ -1220,8 +1347,12 @@ mod tests {
                 content: vec![InlineContent::Text("Title".to_string())],
                 children: vec![DocNode::Paragraph(Paragraph {
                     content: vec![InlineContent::Text("Content".to_string())],
+                    span: None,
                 })],
+                id: None,
+                span: None,
             })],
+            ..Default::default()
         };
 
         // Convert to events
@@ -1251,26 +1382,37 @@ mod tests {
                             ListItem {
                                 content: vec![InlineContent::Text("Item 1".to_string())],
                                 children: vec![],
+                                checked: None,
+                                span: None,
                             },
                             ListItem {
                                 content: vec![InlineContent::Text("Item 2".to_string())],
                                 children: vec![DocNode::Paragraph(Paragraph {
                                     content: vec![InlineContent::Text("Nested".to_string())],
+                                    span: None,
                                 })],
+                                checked: None,
+                                span: None,
                             },
                         ],
                         ordered: false,
                         style: ListStyle::Bullet,
                         form: ListForm::Short,
+                        span: None,
                     }),
                     DocNode::Definition(Definition {
                         term: vec![InlineContent::Text("Term".to_string())],
                         description: vec![DocNode::Paragraph(Paragraph {
                             content: vec![InlineContent::Text("Desc".to_string())],
+                            span: None,
                         })],
+                        span: None,
                     }),
                 ],
+                id: None,
+                span: None,
             })],
+            ..Default::default()
         };
 
         let events = tree_to_events(&DocNode::Document(original_doc.clone()));
@@ -1278,4 +1420,35 @@ mod tests {
 
         assert_eq!(original_doc, reconstructed);
     }
+
+    #[test]
+    fn test_round_trip_keeps_source_spans() {
+        use crate::ir::to_events::tree_to_events;
+
+        let span = SourceSpan {
+            bytes: 7..19,
+            start: SourcePosition::new(2, 0),
+            end: SourcePosition::new(2, 12),
+        };
+        let original_doc = Document {
+            children: vec![
+                DocNode::Paragraph(Paragraph {
+                    content: vec![InlineContent::Text("From source".to_string())],
+                    span: Some(span.clone()),
+                }),
+                DocNode::Paragraph(Paragraph {
+                    content: vec![InlineContent::Text("Synthetic".to_string())],
+                    span: None,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let events = tree_to_events(&DocNode::Document(original_doc.clone()));
+        assert_eq!(events[1], Event::Source(span));
+
+        // The span must not leak onto the following node
+        let reconstructed = events_to_tree(&events).unwrap();
+        assert_eq!(original_doc, reconstructed);
+    }
 }
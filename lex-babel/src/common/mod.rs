@@ -1,6 +1,8 @@
 //! Contains logic for mapping between different document representations.
 
+pub mod bibliography;
 pub mod flat_to_nested;
 pub mod links;
 pub mod nested_to_flat;
+pub mod text;
 pub mod verbatim;
@@ -19,6 +19,8 @@
 //!    - Emit inline content, if any
 //!    - Recurse into child nodes
 //!
+//!    - Nodes that carry a source span are preceded by a `Source` event
+//!
 //! 3. **Handling Inline Nodes:**
 //!    - Inline-only nodes become a single `Inline` event in place
 //!
@@ -33,8 +35,8 @@
 
 use crate::ir::events::Event;
 use crate::ir::nodes::{
-    Annotation, Definition, DocNode, Document, Heading, InlineContent, List, ListItem, Paragraph,
-    Table, TableCell, TableRow, Verbatim,
+    Annotation, BlockQuote, Definition, DocNode, Document, Heading, InlineContent, List, ListItem,
+    Paragraph, Table, TableCell, TableRow, Verbatim,
 };
 
 /// Converts a `DocNode` tree to a flat vector of `Event`s.
@@ -45,8 +47,11 @@ pub fn tree_to_events(root_node: &DocNode) -> Vec<Event> {
 }
 
 fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
+    if let Some(span) = node.span() {
+        events.push(Event::Source(span.clone()));
+    }
     match node {
-        DocNode::Document(Document { children }) => {
+        DocNode::Document(Document { children, .. }) => {
             events.push(Event::StartDocument);
             for child in children {
                 walk_node(child, events);
@@ -57,7 +62,12 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
             level,
             content,
             children,
+            id,
+            ..
         }) => {
+            if let Some(id) = id {
+                events.push(Event::Anchor(id.clone()));
+            }
             events.push(Event::StartHeading(*level));
             emit_inlines(content, events);
             if !children.is_empty() {
@@ -69,7 +79,7 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
             }
             events.push(Event::EndHeading(*level));
         }
-        DocNode::Paragraph(Paragraph { content }) => {
+        DocNode::Paragraph(Paragraph { content, .. }) => {
             events.push(Event::StartParagraph);
             emit_inlines(content, events);
             events.push(Event::EndParagraph);
@@ -79,6 +89,7 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
             ordered,
             style,
             form,
+            ..
         }) => {
             events.push(Event::StartList {
                 ordered: *ordered,
@@ -96,7 +107,9 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
                 unreachable!("ListItem should only be emitted by List");
             }
         }
-        DocNode::Definition(Definition { term, description }) => {
+        DocNode::Definition(Definition {
+            term, description, ..
+        }) => {
             events.push(Event::StartDefinition);
             events.push(Event::StartDefinitionTerm);
             emit_inlines(term, events);
@@ -116,6 +129,7 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
             subject,
             language,
             content,
+            ..
         }) => {
             events.push(Event::StartVerbatim {
                 language: language.clone(),
@@ -128,6 +142,7 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
             label,
             parameters,
             content,
+            ..
         }) => {
             // Check if this is a metadata annotation that should be serialized as a single HTML block
             let metadata_labels = [
@@ -188,9 +203,12 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
         DocNode::Table(Table {
             rows,
             header,
-            caption: _,
+            caption,
+            ..
         }) => {
-            events.push(Event::StartTable);
+            events.push(Event::StartTable {
+                caption: caption.clone(),
+            });
             for row in header {
                 walk_table_row(row, events, true);
             }
@@ -203,6 +221,14 @@ fn walk_node(node: &DocNode, events: &mut Vec<Event>) {
         DocNode::Video(video) => events.push(Event::Video(video.clone())),
         DocNode::Audio(audio) => events.push(Event::Audio(audio.clone())),
         DocNode::Inline(inline) => events.push(Event::Inline(inline.clone())),
+        DocNode::BlockQuote(BlockQuote { children, .. }) => {
+            events.push(Event::StartBlockQuote);
+            for child in children {
+                walk_node(child, events);
+            }
+            events.push(Event::EndBlockQuote);
+        }
+        DocNode::ThematicBreak => events.push(Event::ThematicBreak),
     }
 }
 
@@ -218,6 +244,8 @@ fn walk_table_cell(cell: &TableCell, events: &mut Vec<Event>) {
     events.push(Event::StartTableCell {
         header: cell.header,
         align: cell.align,
+        colspan: cell.colspan,
+        rowspan: cell.rowspan,
     });
     if !cell.content.is_empty() {
         events.push(Event::StartContent);
@@ -230,7 +258,12 @@ fn walk_table_cell(cell: &TableCell, events: &mut Vec<Event>) {
 }
 
 fn walk_list_item(item: &ListItem, events: &mut Vec<Event>) {
-    events.push(Event::StartListItem);
+    if let Some(span) = &item.span {
+        events.push(Event::Source(span.clone()));
+    }
+    events.push(Event::StartListItem {
+        checked: item.checked,
+    });
     emit_inlines(&item.content, events);
     if !item.children.is_empty() {
         events.push(Event::StartContent);
@@ -262,7 +295,10 @@ mod tests {
                     content: vec![InlineContent::Text("Intro".to_string())],
                     children: vec![DocNode::Paragraph(Paragraph {
                         content: vec![InlineContent::Text("Welcome".to_string())],
+                        span: None,
                     })],
+                    id: None,
+                    span: None,
                 }),
                 DocNode::List(List {
                     items: vec![ListItem {
@@ -271,26 +307,35 @@ mod tests {
                             subject: None,
                             language: Some("rust".to_string()),
                             content: "fn main() {}".to_string(),
+                            span: None,
                         })],
+                        checked: None,
+                        span: None,
                     }],
                     ordered: false,
                     style: ListStyle::Bullet,
                     form: ListForm::Short,
+                    span: None,
                 }),
                 DocNode::Definition(Definition {
                     term: vec![InlineContent::Text("Term".to_string())],
                     description: vec![DocNode::Paragraph(Paragraph {
                         content: vec![InlineContent::Text("Definition".to_string())],
+                        span: None,
                     })],
+                    span: None,
                 }),
                 DocNode::Annotation(Annotation {
                     label: "note".to_string(),
                     parameters: vec![("key".to_string(), "value".to_string())],
                     content: vec![DocNode::Paragraph(Paragraph {
                         content: vec![InlineContent::Text("Body".to_string())],
+                        span: None,
                     })],
+                    span: None,
                 }),
             ],
+            ..Default::default()
         })
     }
 
@@ -313,7 +358,7 @@ mod tests {
                 style: ListStyle::Bullet,
                 form: ListForm::Short,
             },
-            Event::StartListItem,
+            Event::StartListItem { checked: None },
             Event::Inline(InlineContent::Text("Item".to_string())),
             Event::StartContent,
             Event::StartVerbatim {
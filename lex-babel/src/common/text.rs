@@ -0,0 +1,66 @@
+//! Small text helpers shared by the converters.
+
+use crate::ir::nodes::InlineContent;
+
+/// The value without the double or single quotes around it, if any
+///
+/// Lex keeps the quotes of a quoted parameter value (`caption="Q1, Q2"`), and
+/// YAML front matter may quote a scalar either way.
+pub fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+/// A Lex parameter value, quoted when it would not read back as written
+///
+/// An unquoted value ends at a comma, and loses its surrounding whitespace.
+pub fn quote_param(value: &str) -> String {
+    if value.is_empty() || value.contains([',', '"']) || value != value.trim() {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Inline content flattened to its text, without formatting
+///
+/// Links give their label, images their alt text and footnote references
+/// their `[label]`.
+pub fn plain_text(content: &[InlineContent]) -> String {
+    content
+        .iter()
+        .map(|inline| match inline {
+            InlineContent::Text(text)
+            | InlineContent::Code(text)
+            | InlineContent::Math(text)
+            | InlineContent::Reference(text)
+            | InlineContent::Marker(text) => text.clone(),
+            InlineContent::Bold(children)
+            | InlineContent::Italic(children)
+            | InlineContent::Strikethrough(children) => plain_text(children),
+            InlineContent::Link(link) => plain_text(&link.content),
+            InlineContent::FootnoteRef(label) => format!("[{label}]"),
+            InlineContent::Image(image) => image.alt.clone(),
+        })
+        .collect()
+}
+
+/// A list item's content without its marker and the space after it
+///
+/// For formats that number and bullet lists themselves.
+pub fn strip_marker(content: &[InlineContent]) -> &[InlineContent] {
+    match content {
+        [InlineContent::Marker(_), InlineContent::Text(space), rest @ ..]
+            if space.trim().is_empty() =>
+        {
+            rest
+        }
+        [InlineContent::Marker(_), rest @ ..] => rest,
+        _ => content,
+    }
+}
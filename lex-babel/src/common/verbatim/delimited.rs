@@ -0,0 +1,201 @@
+//! Delimited text (CSV and TSV) as used by `doc.table` data
+//!
+//! Follows RFC 4180: fields are separated by the delimiter, records by a
+//! line break, and a field wrapped in double quotes may contain delimiters,
+//! line breaks and doubled `""` quotes. Blank lines are skipped, and every
+//! record must have as many fields as the first.
+
+use std::fmt;
+
+/// Where and why delimited text could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DelimitedError {
+    /// Zero-based line
+    pub line: usize,
+    /// Zero-based column, in characters
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DelimitedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line + 1,
+            self.column + 1,
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for DelimitedError {}
+
+pub(crate) fn parse_delimited(
+    text: &str,
+    delimiter: char,
+) -> Result<Vec<Vec<String>>, DelimitedError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut record_line = 0;
+
+    // Position of the opening quote while inside a quoted field
+    let mut open_quote: Option<(usize, usize)> = None;
+    let mut after_closing_quote = false;
+
+    let (mut line, mut column) = (0, 0);
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let (at_line, at_column) = (line, column);
+        if c == '\n' {
+            line += 1;
+            column = 0;
+        } else {
+            column += 1;
+        }
+
+        if open_quote.is_some() {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    column += 1;
+                    field.push('"');
+                } else {
+                    open_quote = None;
+                    after_closing_quote = true;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            _ if c == delimiter => {
+                fields.push(std::mem::take(&mut field));
+                after_closing_quote = false;
+            }
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                    line += 1;
+                    column = 0;
+                }
+                fields.push(std::mem::take(&mut field));
+                finish_record(&mut records, std::mem::take(&mut fields), record_line)?;
+                after_closing_quote = false;
+                record_line = line;
+            }
+            '"' if field.is_empty() && !after_closing_quote => {
+                open_quote = Some((at_line, at_column));
+            }
+            _ => {
+                let message = if after_closing_quote {
+                    format!("unexpected '{c}' after a closing quote")
+                } else if c == '"' {
+                    "quote inside an unquoted field (wrap the field in quotes and double it)"
+                        .to_string()
+                } else {
+                    field.push(c);
+                    continue;
+                };
+                return Err(DelimitedError {
+                    line: at_line,
+                    column: at_column,
+                    message,
+                });
+            }
+        }
+    }
+
+    if let Some((line, column)) = open_quote {
+        return Err(DelimitedError {
+            line,
+            column,
+            message: "quoted field is never closed".to_string(),
+        });
+    }
+    if !field.is_empty() || !fields.is_empty() || after_closing_quote {
+        fields.push(field);
+        finish_record(&mut records, fields, record_line)?;
+    }
+
+    Ok(records)
+}
+
+/// Keep a completed record unless it came from a blank line
+///
+/// `line` is the zero-based line the record started on.
+fn finish_record(
+    records: &mut Vec<Vec<String>>,
+    fields: Vec<String>,
+    line: usize,
+) -> Result<(), DelimitedError> {
+    if fields.len() == 1 && fields[0].is_empty() {
+        return Ok(());
+    }
+    if let Some(first) = records.first() {
+        if fields.len() != first.len() {
+            return Err(DelimitedError {
+                line,
+                column: 0,
+                message: format!(
+                    "record has {} fields but the first record has {}",
+                    fields.len(),
+                    first.len()
+                ),
+            });
+        }
+    }
+    records.push(fields);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoted_fields() {
+        let records = parse_delimited(
+            "name,note\r\n\"Smith, J\",\"said \"\"hi\"\"\nand left\"\n",
+            ',',
+        )
+        .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                vec!["name", "note"],
+                vec!["Smith, J", "said \"hi\"\nand left"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tabs_and_blank_lines() {
+        let records = parse_delimited("a\tb\n\n1\t\n", '\t').unwrap();
+        assert_eq!(records, vec![vec!["a", "b"], vec!["1", ""]]);
+
+        let err = parse_delimited("a\tb\n\n1\t2\t3\n", '\t').unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let err = parse_delimited("a,b\n1,\"open\n", ',').unwrap_err();
+        assert_eq!((err.line, err.column), (1, 2));
+        assert!(err.message.contains("never closed"));
+
+        let err = parse_delimited("a,b\n\"x\"y,2\n", ',').unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 4: unexpected 'y' after a closing quote"
+        );
+
+        let err = parse_delimited("a,b\n1,2,3\n", ',').unwrap_err();
+        assert!(err.message.contains("3 fields"));
+    }
+}
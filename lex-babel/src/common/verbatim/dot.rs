@@ -0,0 +1,226 @@
+//! Graphviz diagrams in verbatim blocks.
+//!
+//! A verbatim block labelled `doc.dot` (or just `dot`) holds a graph in the
+//! DOT language:
+//!
+//! ```text
+//! Request flow:
+//!     digraph {
+//!         client -> gateway -> service;
+//!     }
+//! :: doc.dot ::
+//! ```
+//!
+//! The block stays a verbatim block in the IR, so Lex and formats without
+//! images keep the DOT source. Exports that show pictures lay the graph out
+//! and render it to SVG with [layout-rs], without Graphviz installed: HTML
+//! (and so PDF and PNG) embeds the SVG inline, and Markdown given an
+//! `asset-dir` writes it to a file there and links it as an image. A graph
+//! that does not render is kept as a DOT code block, and
+//! [`crate::FormatRegistry::serialize_with_report`] warns about it.
+//!
+//! [layout-rs]: https://docs.rs/layout-rs
+
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use crate::ir::nodes::{DocNode, Document as IrDocument, Image, SourceSpan, Verbatim};
+use crate::ir::visit::{
+    walk_document, walk_document_mut, walk_node, walk_node_mut, Visitor, VisitorMut,
+};
+use crate::report::{ConversionReport, ConversionWarning, WarningKind};
+use layout::backends::svg::SVGWriter;
+use layout::gv::{DotParser, GraphBuilder};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// Labels of verbatim blocks holding DOT graphs
+pub const DOT_LABELS: &[&str] = &["doc.dot", "dot"];
+
+/// Whether a verbatim block with this language holds a DOT graph
+pub fn is_dot(verbatim: &Verbatim) -> bool {
+    verbatim
+        .language
+        .as_deref()
+        .is_some_and(|language| DOT_LABELS.contains(&language))
+}
+
+/// Lay out a DOT graph and render it as an SVG document
+///
+/// Every id in the SVG (arrow heads, clip paths, edge paths) starts with
+/// `id_prefix`, so several diagrams inlined in one page do not share them.
+/// The error says why the graph does not render.
+pub fn render_dot(source: &str, id_prefix: &str) -> Result<String, String> {
+    // The parser and the layout engine both panic on some input
+    let svg = panic::catch_unwind(AssertUnwindSafe(|| {
+        let graph = DotParser::new(source)
+            .process()
+            .map_err(|err| format!("syntax error: {err}"))?;
+        let mut builder = GraphBuilder::new();
+        builder.visit_graph(&graph);
+        let mut visual = builder.get();
+        let mut writer = SVGWriter::new();
+        visual.do_it(false, false, false, &mut writer);
+        Ok(writer.finalize())
+    }))
+    .unwrap_or_else(|_| Err("the graph cannot be laid out".to_string()))?;
+    Ok(svg
+        .replace("id=\"", &format!("id=\"{id_prefix}"))
+        .replace("url(#", &format!("url(#{id_prefix}"))
+        .replace("href=\"#", &format!("href=\"#{id_prefix}")))
+}
+
+/// File name for the rendered diagram of `source`
+///
+/// Named after the source, so unchanged diagrams keep their files across
+/// exports and builds, and two blocks with the same graph share one.
+pub fn asset_name(source: &str) -> String {
+    format!("diagram-{:016x}.svg", fnv1a(source.as_bytes()))
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is the same in every
+/// build
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Write every DOT graph that renders to an SVG file in `dir`, and put an
+/// image of it in the graph's place
+///
+/// Images link to the file by name, so `dir` is where the output goes. The
+/// block's subject becomes the image's alt text.
+pub(crate) fn write_diagrams(ir: &mut IrDocument, dir: &Path) -> Result<(), FormatError> {
+    let mut writer = DiagramWriter {
+        dir,
+        result: Ok(()),
+    };
+    walk_document_mut(&mut writer, ir);
+    writer.result
+}
+
+struct DiagramWriter<'a> {
+    dir: &'a Path,
+    result: Result<(), FormatError>,
+}
+
+impl VisitorMut for DiagramWriter<'_> {
+    fn visit_node_mut(&mut self, node: &mut DocNode) {
+        if let DocNode::Verbatim(verbatim) = node {
+            if is_dot(verbatim) && self.result.is_ok() {
+                if let Ok(svg) = render_dot(&verbatim.content, "") {
+                    let name = asset_name(&verbatim.content);
+                    let path = self.dir.join(&name);
+                    if let Err(err) = fs::write(&path, svg) {
+                        self.result = Err(ErrorDetail::new(
+                            ErrorKind::Serialization,
+                            "diagram-write",
+                            format!("Cannot write diagram '{}': {err}", path.display()),
+                        )
+                        .caused_by(err)
+                        .into());
+                        return;
+                    }
+                    let alt = verbatim.subject.clone().unwrap_or_default();
+                    *node = DocNode::Image(Image {
+                        src: name,
+                        alt,
+                        title: None,
+                    });
+                }
+            }
+            return;
+        }
+        walk_node_mut(self, node);
+    }
+}
+
+/// Warn about DOT graphs that are kept as source because they do not render
+pub(crate) fn report_dot_fallbacks(ir: &IrDocument, report: &mut ConversionReport) {
+    let mut scanner = DotScanner { span: None, report };
+    walk_document(&mut scanner, ir);
+}
+
+struct DotScanner<'r> {
+    /// Span of the innermost enclosing node that has one
+    span: Option<SourceSpan>,
+    report: &'r mut ConversionReport,
+}
+
+impl Visitor for DotScanner<'_> {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+        if let DocNode::Verbatim(verbatim) = node {
+            if is_dot(verbatim) {
+                if let Err(err) = render_dot(&verbatim.content, "") {
+                    self.report.push(
+                        ConversionWarning::new(
+                            WarningKind::DiagramFallback,
+                            format!("DOT graph cannot be rendered to SVG ({err}) and is kept as a code block"),
+                        )
+                        .with_span(self.span.clone()),
+                    );
+                }
+            }
+        }
+        walk_node(self, node);
+        self.span = outer;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_dot() {
+        let svg = render_dot("digraph { a -> b; }", "d1-").unwrap();
+        assert!(svg.contains("<svg"), "{svg}");
+        assert!(svg.contains(">a<"), "{svg}");
+        assert!(svg.contains("id=\"d1-endarrow\""), "{svg}");
+        assert!(svg.contains("url(#d1-endarrow)"), "{svg}");
+        assert!(!svg.contains("\"endarrow\""), "{svg}");
+
+        let err = render_dot("digraph { a -> ", "").unwrap_err();
+        assert!(err.starts_with("syntax error"), "{err}");
+        assert_eq!(asset_name("graph {}"), asset_name("graph {}"));
+        assert_ne!(asset_name("graph {}"), asset_name("digraph {}"));
+        assert_eq!(asset_name(""), "diagram-cbf29ce484222325.svg");
+    }
+
+    #[test]
+    fn test_write_diagrams() {
+        let dir = tempdir().unwrap();
+        let graph = |content: &str| {
+            DocNode::Verbatim(Verbatim {
+                subject: Some("Flow".to_string()),
+                language: Some("doc.dot".to_string()),
+                content: content.to_string(),
+                span: None,
+            })
+        };
+        let mut ir = IrDocument {
+            children: vec![graph("digraph { a -> b; }"), graph("digraph { a -> ")],
+            ..Default::default()
+        };
+
+        write_diagrams(&mut ir, dir.path()).unwrap();
+        let name = asset_name("digraph { a -> b; }");
+        assert_eq!(
+            ir.children[0],
+            DocNode::Image(Image {
+                src: name.clone(),
+                alt: "Flow".to_string(),
+                title: None,
+            })
+        );
+        assert!(fs::read_to_string(dir.path().join(name))
+            .unwrap()
+            .contains("<svg"));
+        assert!(matches!(ir.children[1], DocNode::Verbatim(_)));
+    }
+}
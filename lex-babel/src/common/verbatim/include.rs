@@ -0,0 +1,322 @@
+use super::{param, resolve_src, VerbatimHandler};
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::try_convert_document;
+use crate::ir::nodes::{DocNode, Document};
+use crate::ir::references::find_session;
+use crate::ir::visit::{walk_document_mut, walk_node_mut, VisitorMut};
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many files deep includes may nest
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Handler for `doc.include`: the contents of another Lex file.
+///
+/// The file named by `src=` is read relative to the including file, and its
+/// sessions are moved to the level of the include, so a chapter written with
+/// top-level sessions can be included inside a session. `session=` takes one
+/// session (with its subsessions) instead of the whole file, named by number,
+/// title or id as in a `[#2.1]` reference:
+///
+/// ```text
+/// :: doc.include src="./chapters/setup.lex" ::
+/// :: doc.include src="./reference.lex" session="2.1" ::
+/// ```
+///
+/// Includes are resolved when converting to another format; the Lex source,
+/// and so `lex format`, keeps the include line. An include that leads back to
+/// a file being included, or that nests more than [`MAX_INCLUDE_DEPTH`] files
+/// deep, is an error naming the chain of files. The metadata of included
+/// files is ignored.
+pub struct IncludeHandler;
+
+impl VerbatimHandler for IncludeHandler {
+    fn label(&self) -> &str {
+        "doc.include"
+    }
+
+    fn to_ir(&self, content: &str, params: &HashMap<String, String>) -> Option<DocNode> {
+        self.to_ir_with_context(content, params, &ConversionContext::standard())
+            .ok()
+            .flatten()
+    }
+
+    fn to_ir_with_context(
+        &self,
+        _content: &str,
+        params: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<Option<DocNode>, FormatError> {
+        let (src, path) = resolve_src(params, ctx)
+            .ok_or_else(|| include_error("include-source", "doc.include needs a src= file"))?;
+        let path = path.canonicalize().map_err(|err| {
+            FormatError::from(
+                ErrorDetail::new(
+                    ErrorKind::Parse,
+                    "include-source",
+                    format!("Cannot read included file '{src}': {err}"),
+                )
+                .caused_by(err),
+            )
+        })?;
+
+        if let Some(start) = ctx.includes.iter().position(|file| file == &path) {
+            return Err(include_error(
+                "include-cycle",
+                format!("Include cycle: {}", chain(&ctx.includes[start..], &path)),
+            ));
+        }
+        if ctx.includes.len() >= MAX_INCLUDE_DEPTH {
+            return Err(include_error(
+                "include-depth",
+                format!(
+                    "Includes nest more than {MAX_INCLUDE_DEPTH} files deep: {}",
+                    chain(ctx.includes, &path)
+                ),
+            ));
+        }
+
+        let source = fs::read_to_string(&path).map_err(|err| {
+            FormatError::from(
+                ErrorDetail::new(
+                    ErrorKind::Parse,
+                    "include-source",
+                    format!("Cannot read included file '{src}': {err}"),
+                )
+                .caused_by(err),
+            )
+        })?;
+        let doc = STRING_TO_AST.run(source).map_err(|err| {
+            include_error(
+                "include-syntax",
+                format!("Cannot parse included file '{src}': {err}"),
+            )
+        })?;
+
+        let mut includes = ctx.includes.to_vec();
+        includes.push(path.clone());
+        let inner = ctx.with_base_dir(path.parent()).with_includes(&includes);
+        // Checked while converting, so each nested file is converted once
+        let mut included =
+            try_convert_document(&doc, &inner).map_err(|err| nested_error(err, src))?;
+        if let Some(name) = param(params, "session") {
+            let session = find_session(&included, name).ok_or_else(|| {
+                include_error(
+                    "include-session",
+                    format!("No session '{name}' in included file '{src}'"),
+                )
+            })?;
+            included = Document {
+                children: vec![DocNode::Heading(session)],
+                ..Default::default()
+            };
+        }
+        included.metadata = Default::default();
+        // Spans point into the included file, not the document being converted
+        walk_document_mut(&mut SpanRemover, &mut included);
+
+        Ok(Some(DocNode::Document(included)))
+    }
+
+    fn convert_from_ir(&self, _node: &DocNode) -> Option<(String, HashMap<String, String>)> {
+        None
+    }
+}
+
+fn include_error(code: &'static str, message: impl Into<String>) -> FormatError {
+    ErrorDetail::new(ErrorKind::Parse, code, message).into()
+}
+
+/// `a.lex → b.lex → a.lex`, by file name
+fn chain(files: &[PathBuf], next: &Path) -> String {
+    files
+        .iter()
+        .map(PathBuf::as_path)
+        .chain([next])
+        .map(|file| {
+            file.file_name()
+                .unwrap_or(file.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// An error from inside the file included as `src`
+///
+/// Its position only makes sense in that file, so it moves into the message.
+/// Cycle and depth errors already name every file and pass through.
+fn nested_error(err: FormatError, src: &str) -> FormatError {
+    let mut detail = match err {
+        FormatError::Detailed(detail) => *detail,
+        other => ErrorDetail::new(other.kind(), other.code(), other.message()),
+    };
+    let position = detail.position.take();
+    if !matches!(detail.code, "include-cycle" | "include-depth") {
+        let location = match position {
+            Some(position) => format!("{src}:{}", position.line + 1),
+            None => src.to_string(),
+        };
+        detail.message = format!("In included file {location}: {}", detail.message);
+    }
+    detail.into()
+}
+
+struct SpanRemover;
+
+impl VisitorMut for SpanRemover {
+    fn visit_node_mut(&mut self, node: &mut DocNode) {
+        node.set_span(None);
+        walk_node_mut(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::{Heading, InlineContent};
+    use tempfile::tempdir;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn titles(nodes: &[DocNode], out: &mut Vec<(usize, String)>) {
+        for node in nodes {
+            if let DocNode::Heading(Heading {
+                level,
+                content,
+                children,
+                ..
+            }) = node
+            {
+                let title = content
+                    .iter()
+                    .filter_map(|inline| match inline {
+                        InlineContent::Text(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                out.push((*level, title.trim().to_string()));
+                titles(children, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_includes_a_session_subtree() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("guide.lex"),
+            "Setup\n\n    Install it.\n\n    Linux\n\n        apt install.\n\nUsage\n\n    Run it.\n",
+        )
+        .unwrap();
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+
+        let node = IncludeHandler
+            .to_ir_with_context(
+                "",
+                &params(&[("src", "guide.lex"), ("session", "1.1")]),
+                &ctx,
+            )
+            .unwrap();
+        let Some(DocNode::Document(included)) = node else {
+            panic!("expected an included document");
+        };
+        let mut out = Vec::new();
+        titles(&included.children, &mut out);
+        assert_eq!(out, vec![(3, "Linux".to_string())]);
+
+        let err = IncludeHandler
+            .to_ir_with_context("", &params(&[("src", "guide.lex"), ("session", "9")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "include-session");
+    }
+
+    #[test]
+    fn test_include_cycle_names_the_chain() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("a.lex"),
+            "Intro\n\n    Text.\n\n:: doc.include src=\"b.lex\" ::\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("b.lex"),
+            "More\n\n    Text.\n\n:: doc.include src=\"a.lex\" ::\n",
+        )
+        .unwrap();
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+
+        let err = IncludeHandler
+            .to_ir_with_context("", &params(&[("src", "a.lex")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "include-cycle");
+        assert!(
+            err.message().contains("a.lex → b.lex → a.lex"),
+            "{}",
+            err.message()
+        );
+    }
+
+    #[test]
+    fn test_missing_include() {
+        let dir = tempdir().unwrap();
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+        let err = IncludeHandler
+            .to_ir_with_context("", &params(&[("src", "missing.lex")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "include-source");
+    }
+
+    #[test]
+    fn test_deep_includes_convert_each_file_once() {
+        let dir = tempdir().unwrap();
+        let depth = MAX_INCLUDE_DEPTH - 1;
+        for n in 0..depth {
+            let include = if n + 1 < depth {
+                format!(":: doc.include src=\"part{}.lex\" ::\n", n + 1)
+            } else {
+                ":: doc.include src=\"missing.lex\" ::\n".to_string()
+            };
+            fs::write(
+                dir.path().join(format!("part{n}.lex")),
+                format!("Part {n}\n\n    Text {n}.\n\n{include}"),
+            )
+            .unwrap();
+        }
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+
+        // Checking and converting separately doubled the work at every level
+        let start = std::time::Instant::now();
+        let err = IncludeHandler
+            .to_ir_with_context("", &params(&[("src", "part0.lex")]), &ctx)
+            .unwrap_err();
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(err.code(), "include-source");
+
+        fs::write(
+            dir.path().join(format!("part{}.lex", depth - 1)),
+            "Last\n\n    The end.\n",
+        )
+        .unwrap();
+        let start = std::time::Instant::now();
+        let node = IncludeHandler
+            .to_ir_with_context("", &params(&[("src", "part0.lex")]), &ctx)
+            .unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+        let Some(DocNode::Document(included)) = node else {
+            panic!("expected an included document");
+        };
+        let mut out = Vec::new();
+        titles(&included.children, &mut out);
+        assert_eq!(out.len(), depth);
+    }
+}
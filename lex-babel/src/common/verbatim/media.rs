@@ -1,4 +1,4 @@
-use super::VerbatimHandler;
+use super::{param, VerbatimHandler};
 use crate::ir::nodes::{Audio, DocNode, Image, Video};
 use std::collections::HashMap;
 
@@ -16,12 +16,11 @@ impl VerbatimHandler for ImageHandler {
         // Let's assume params: src, alt, title.
         // If content is present, maybe treat it as alt text if alt param is missing?
 
-        let src = params.get("src").cloned().unwrap_or_default();
-        let alt = params
-            .get("alt")
-            .cloned()
+        let src = param(params, "src").map(str::to_string).unwrap_or_default();
+        let alt = param(params, "alt")
+            .map(str::to_string)
             .unwrap_or_else(|| content.trim().to_string());
-        let title = params.get("title").cloned();
+        let title = param(params, "title").map(str::to_string);
 
         Some(DocNode::Image(Image { src, alt, title }))
     }
@@ -52,9 +51,9 @@ impl VerbatimHandler for VideoHandler {
     }
 
     fn to_ir(&self, _content: &str, params: &HashMap<String, String>) -> Option<DocNode> {
-        let src = params.get("src").cloned().unwrap_or_default();
-        let title = params.get("title").cloned();
-        let poster = params.get("poster").cloned();
+        let src = param(params, "src").map(str::to_string).unwrap_or_default();
+        let title = param(params, "title").map(str::to_string);
+        let poster = param(params, "poster").map(str::to_string);
 
         Some(DocNode::Video(Video { src, title, poster }))
     }
@@ -85,8 +84,8 @@ impl VerbatimHandler for AudioHandler {
     }
 
     fn to_ir(&self, _content: &str, params: &HashMap<String, String>) -> Option<DocNode> {
-        let src = params.get("src").cloned().unwrap_or_default();
-        let title = params.get("title").cloned();
+        let src = param(params, "src").map(str::to_string).unwrap_or_default();
+        let title = param(params, "title").map(str::to_string);
 
         Some(DocNode::Audio(Audio { src, title }))
     }
@@ -46,9 +46,15 @@
 //!
 //! Lex provides standard handlers for common types within the `doc` namespace:
 //!
-//! *   `doc.table`: Markdown-style pipe tables.
+//! *   `doc.table`: Markdown-style pipe tables, or CSV/TSV data inline or from a `src=` file.
 //! *   `doc.image`: Image references.
 //! *   `doc.video`, `doc.audio`: Media references.
+//! *   `doc.include`: The contents of another Lex file, from a `src=` file.
+//!
+//! Blocks with no handler but a `src=` parameter are code samples filled from
+//! that file (see [`source`]). Blocks labelled `doc.dot` stay verbatim in the
+//! IR and are rendered as diagrams by the formats that can show them (see
+//! [`dot`]).
 //!
 //! # Usage
 //!
@@ -61,15 +67,46 @@
 //!     let node = handler.to_ir(content, &params);
 //! }
 //! ```
+//!
+//! Conversions read the registry from their [`crate::ConversionContext`]. To take part in
+//! format conversion, register handlers on the registry a [`crate::FormatRegistry`] owns:
+//!
+//! ```rust,ignore
+//! let mut formats = FormatRegistry::default();
+//! formats
+//!     .verbatim_registry_mut()
+//!     .register_namespace("acme.", Box::new(AcmeHandler));
+//! ```
 
+use crate::common::text::unquote;
 use crate::error::FormatError;
+use crate::ir::context::ConversionContext;
 use crate::ir::nodes::DocNode;
 use lex_core::lex::ast::Verbatim;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
 
+mod delimited;
+pub mod dot;
+pub mod include;
 pub mod media;
+pub mod source;
 pub mod table;
 
+/// The value of parameter `key`, without the quotes Lex keeps around a quoted value
+pub fn param<'p>(params: &'p HashMap<String, String>, key: &str) -> Option<&'p str> {
+    params.get(key).map(|value| unquote(value))
+}
+
+/// The file named by `src=`, as written and resolved against the document
+pub fn resolve_src<'p>(
+    params: &'p HashMap<String, String>,
+    ctx: &ConversionContext,
+) -> Option<(&'p str, PathBuf)> {
+    param(params, "src").map(|src| (src, ctx.resolve_path(src)))
+}
+
 /// A handler for a specific verbatim block type.
 pub trait VerbatimHandler: Send + Sync {
     /// Returns the label this handler supports (e.g., "doc.table").
@@ -82,6 +119,20 @@ pub trait VerbatimHandler: Send + Sync {
     /// * `params` - The parameters specified in the closing marker.
     fn to_ir(&self, content: &str, params: &HashMap<String, String>) -> Option<DocNode>;
 
+    /// Converts a Lex verbatim block to an IR node, with the conversion context at hand.
+    ///
+    /// Handlers that read files find them with [`resolve_src`] and report missing or malformed input as an error. Conversion keeps a block whose handler
+    /// fails as plain verbatim text; [`crate::ir::from_lex::check_verbatim_blocks`] surfaces
+    /// the error instead. The default calls [`to_ir`](Self::to_ir).
+    fn to_ir_with_context(
+        &self,
+        content: &str,
+        params: &HashMap<String, String>,
+        _ctx: &ConversionContext,
+    ) -> Result<Option<DocNode>, FormatError> {
+        Ok(self.to_ir(content, params))
+    }
+
     /// Converts an IR node back to a Lex verbatim block.
     ///
     /// Returns `Some((content, params))` if this handler can represent the given node.
@@ -117,9 +168,19 @@ impl VerbatimRegistry {
         registry.register("doc.image", Box::new(media::ImageHandler));
         registry.register("doc.video", Box::new(media::VideoHandler));
         registry.register("doc.audio", Box::new(media::AudioHandler));
+        registry.register("doc.include", Box::new(include::IncludeHandler));
         registry
     }
 
+    /// A shared registry with the standard handlers, built on first use.
+    ///
+    /// Conversions without a configured registry use this one instead of
+    /// building their own.
+    pub fn standard() -> &'static VerbatimRegistry {
+        static STANDARD: OnceLock<VerbatimRegistry> = OnceLock::new();
+        STANDARD.get_or_init(Self::default_with_standard)
+    }
+
     /// Registers a handler for an exact label.
     pub fn register(&mut self, label: &str, handler: Box<dyn VerbatimHandler>) {
         self.handlers.insert(label.to_string(), handler);
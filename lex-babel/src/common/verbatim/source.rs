@@ -0,0 +1,201 @@
+//! Verbatim blocks filled from source files.
+//!
+//! A code sample can name the file it was copied from, and conversion then
+//! reads it from disk instead of trusting the inline copy:
+//!
+//! ```text
+//! Parsing the arguments:
+//!     fn main() { ... }
+//! :: rust src="../src/main.rs" lines="10-42" ::
+//! ```
+//!
+//! `lines="10-42"` takes 1-based lines (several ranges like `1-3,8-` work
+//! too), and `region="setup"` takes the lines between `#region setup` and
+//! the matching `#endregion` comments, without the marker lines. Without
+//! either the whole file is used. Taken lines lose the indentation they
+//! share. The language is the block's label when that names one, else it is
+//! inferred from the file extension as for new snippets (see
+//! [`crate::templates::infer_language`]).
+//!
+//! Blocks whose label has a [`super::VerbatimHandler`] (like `doc.table`) read
+//! their `src=` files themselves and are not affected.
+
+use super::table::{dedent, parse_selection};
+use super::{param, resolve_src};
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use crate::ir::context::ConversionContext;
+use crate::templates::infer_language;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The code a verbatim block's `src=` parameters point at
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceSnippet {
+    pub content: String,
+    pub language: String,
+}
+
+impl SourceSnippet {
+    /// Whether `inline`, a block's own copy, still shows this snippet
+    ///
+    /// Indentation shared by all lines and trailing whitespace do not count.
+    pub fn matches(&self, inline: &str) -> bool {
+        normalize(inline) == normalize(&self.content)
+    }
+}
+
+fn normalize(text: &str) -> String {
+    dedent(text)
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Read the snippet for a block labelled `label`, if it has a `src=`
+pub fn load_source(
+    label: &str,
+    params: &HashMap<String, String>,
+    ctx: &ConversionContext,
+) -> Result<Option<SourceSnippet>, FormatError> {
+    let Some((src, path)) = resolve_src(params, ctx) else {
+        return Ok(None);
+    };
+    let text = fs::read_to_string(&path).map_err(|err| {
+        FormatError::from(
+            ErrorDetail::new(
+                ErrorKind::Parse,
+                "verbatim-source",
+                format!("Cannot read source file '{}': {err}", path.display()),
+            )
+            .caused_by(err),
+        )
+    })?;
+    let text = text.replace("\r\n", "\n");
+
+    let content = match (param(params, "lines"), param(params, "region")) {
+        (Some(_), Some(_)) => {
+            return Err(source_error(
+                "verbatim-lines",
+                "Use either lines= or region=, not both",
+            ))
+        }
+        (Some(spec), None) => {
+            let lines: Vec<&str> = text.lines().collect();
+            let indices = parse_selection("lines", spec, lines.len())
+                .map_err(|message| source_error("verbatim-lines", message))?;
+            let selected: Vec<&str> = indices.into_iter().map(|index| lines[index]).collect();
+            dedent(&selected.join("\n"))
+        }
+        (None, Some(name)) => {
+            let selected = region(&text, name).ok_or_else(|| {
+                source_error(
+                    "verbatim-region",
+                    format!("No region '{name}' in source file '{}'", path.display()),
+                )
+            })?;
+            dedent(&selected.join("\n"))
+        }
+        (None, None) => text.trim_end_matches('\n').to_string(),
+    };
+
+    Ok(Some(SourceSnippet {
+        content,
+        language: infer_language(Path::new(src), Some(label)),
+    }))
+}
+
+fn source_error(code: &'static str, message: impl Into<String>) -> FormatError {
+    ErrorDetail::new(ErrorKind::Parse, code, message).into()
+}
+
+/// The lines inside region `name`, without any region markers
+///
+/// Regions may nest, so the region ends at the `#endregion` matching its
+/// start. `None` when the region does not start or is never closed.
+fn region<'a>(text: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    let mut lines = text.lines();
+    lines
+        .by_ref()
+        .find(|line| region_start(line) == Some(name))?;
+
+    let mut depth = 1;
+    let mut selected = Vec::new();
+    for line in lines {
+        if region_start(line).is_some() {
+            depth += 1;
+        } else if line.contains("#endregion") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(selected);
+            }
+        } else {
+            selected.push(line);
+        }
+    }
+    None
+}
+
+/// The name of the region a `// #region name` line starts
+fn region_start(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("#region")?;
+    rest.split_whitespace().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const MAIN: &str = "fn main() {\n    // #region setup\n    let x = 1;\n    // #region inner\n    let y = 2;\n    // #endregion\n    // #endregion\n    run(x, y);\n}\n";
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn load(pairs: &[(&str, &str)]) -> Result<Option<SourceSnippet>, FormatError> {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), MAIN).unwrap();
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+        load_source("code", &params(pairs), &ctx)
+    }
+
+    #[test]
+    fn test_lines_and_regions() {
+        let snippet = load(&[("src", "main.rs"), ("lines", "8-9")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(snippet.content, "    run(x, y);\n}");
+        assert_eq!(snippet.language, "rust");
+
+        let snippet = load(&[("src", "main.rs"), ("region", "setup")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(snippet.content, "let x = 1;\nlet y = 2;");
+
+        let snippet = load(&[("src", "main.rs")]).unwrap().unwrap();
+        assert_eq!(snippet.content, MAIN.trim_end());
+        assert!(snippet.matches(&format!("    {}  \n", MAIN.replace('\n', "\n    "))));
+    }
+
+    #[test]
+    fn test_source_errors() {
+        assert!(load(&[]).unwrap().is_none());
+        let code = |pairs: &[(&str, &str)]| load(pairs).unwrap_err().code();
+        assert_eq!(code(&[("src", "missing.rs")]), "verbatim-source");
+        assert_eq!(
+            code(&[("src", "main.rs"), ("lines", "8-20")]),
+            "verbatim-lines"
+        );
+        assert_eq!(
+            code(&[("src", "main.rs"), ("region", "teardown")]),
+            "verbatim-region"
+        );
+    }
+}
@@ -1,13 +1,54 @@
-use super::VerbatimHandler;
+use super::delimited::parse_delimited;
+use super::{param, resolve_src, VerbatimHandler};
+use crate::common::text::quote_param;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use crate::ir::context::ConversionContext;
 use crate::ir::nodes::{
     DocNode, InlineContent, Paragraph, Table, TableCell, TableCellAlignment, TableRow,
 };
+use crate::options::parse_bool;
 use lex_core::lex::ast::Verbatim;
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 /// Handler for `doc.table` verbatim blocks.
 ///
 /// Parses markdown-style pipe tables into `DocNode::Table` and serializes them back.
+///
+/// Two extensions cover what pipe tables cannot express:
+///
+/// * Every row above the `---` separator is a header row, so headers can span
+///   several rows.
+/// * A cell holding only `<` merges into the cell to its left (colspan), and a
+///   cell holding only `^` merges into the cell above it (rowspan). Spans do
+///   not cross from the header into the body.
+///
+/// A `caption` parameter on the closing marker becomes the table caption:
+///
+/// ```text
+/// Quarterly results:
+///     | Region | Sales   | <     |
+///     | ^      | Q1      | Q2    |
+///     | ------ | ------- | ----- |
+///     | North  | 10      | 12    |
+/// :: doc.table caption="Sales by quarter" ::
+/// ```
+///
+/// With `format=csv` or `format=tsv` the body is RFC 4180 delimited data
+/// instead, or the data comes from a file named by `src=`, relative to the
+/// document (the format then defaults to the file's extension). Such tables
+/// take these parameters:
+///
+/// * `header=true`: the first record is the header row
+/// * `align="left,right,center"`: alignment per column (`l`, `r`, `c` and
+///   `none` also work)
+/// * `rows="1-10,12"` and `columns="2,1,4-"`: 1-based selection of body
+///   records and columns, in the order given
+///
+/// ```text
+/// :: doc.table src="./sales.csv", header=true, columns="1,3-4", align="l,r,r" ::
+/// ```
 pub struct TableHandler;
 
 impl VerbatimHandler for TableHandler {
@@ -15,22 +56,70 @@ impl VerbatimHandler for TableHandler {
         "doc.table"
     }
 
-    fn to_ir(&self, content: &str, _params: &HashMap<String, String>) -> Option<DocNode> {
-        Some(parse_pipe_table(content))
+    fn to_ir(&self, content: &str, params: &HashMap<String, String>) -> Option<DocNode> {
+        self.to_ir_with_context(content, params, &ConversionContext::standard())
+            .ok()
+            .flatten()
+    }
+
+    fn to_ir_with_context(
+        &self,
+        content: &str,
+        params: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<Option<DocNode>, FormatError> {
+        let mut table = match DataFormat::from_params(params)? {
+            None => parse_pipe_table(content),
+            Some(format) => {
+                let (data, origin) = match resolve_src(params, ctx) {
+                    Some((src, path)) => (read_source(&path)?, format!("'{src}'")),
+                    None => (dedent(content), "the table body".to_string()),
+                };
+                let records = parse_delimited(&data, format.delimiter()).map_err(|err| {
+                    ErrorDetail::new(
+                        ErrorKind::Parse,
+                        "table-data-syntax",
+                        format!("Malformed {} in {origin}: {err}", format.name()),
+                    )
+                    .caused_by(err)
+                })?;
+                table_from_records(records, params).map_err(|message| {
+                    ErrorDetail::new(ErrorKind::Parse, "invalid-table-option", message)
+                })?
+            }
+        };
+        table.caption =
+            param(params, "caption").map(|caption| vec![InlineContent::Text(caption.to_string())]);
+        Ok(Some(DocNode::Table(table)))
     }
 
     fn convert_from_ir(&self, node: &DocNode) -> Option<(String, HashMap<String, String>)> {
         if let DocNode::Table(table) = node {
-            Some((serialize_pipe_table(table), HashMap::new()))
+            let mut params = HashMap::new();
+            if let Some(caption) = &table.caption {
+                params.insert(
+                    "caption".to_string(),
+                    quote_param(&inline_content_to_text(caption)),
+                );
+            }
+            Some((serialize_pipe_table(table), params))
         } else {
             None
         }
     }
 
-    fn format_content(
-        &self,
-        verbatim: &Verbatim,
-    ) -> Result<Option<String>, crate::error::FormatError> {
+    fn format_content(&self, verbatim: &Verbatim) -> Result<Option<String>, FormatError> {
+        // Delimited data is kept exactly as the author wrote it
+        let params = verbatim
+            .closing_data
+            .parameters
+            .iter()
+            .map(|param| (param.key.clone(), param.value.clone()))
+            .collect();
+        if !matches!(DataFormat::from_params(&params), Ok(None)) {
+            return Ok(None);
+        }
+
         // Reconstruct content from lines
         let mut content = String::new();
         for item in &verbatim.children {
@@ -40,150 +129,322 @@ impl VerbatimHandler for TableHandler {
             }
         }
 
-        // Dedent content to ensure markdown parser sees a table, not a code block
-        let lines: Vec<&str> = content.lines().collect();
-        let min_indent = lines
-            .iter()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| line.len() - line.trim_start().len())
-            .min()
-            .unwrap_or(0);
+        // Round-trip through our own parser rather than Markdown's, which
+        // knows neither span markers nor multi-row headers
+        let table = parse_pipe_table(&content);
+        if table.header.is_empty() && table.rows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serialize_pipe_table(&table)))
+    }
+}
 
-        let dedented_content = lines
-            .iter()
-            .map(|line| {
-                if line.len() >= min_indent {
-                    &line[min_indent..]
+/// Delimited data formats a `doc.table` can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataFormat {
+    Csv,
+    Tsv,
+}
+
+impl DataFormat {
+    /// The format named by `format=`, else implied by the `src=` extension;
+    /// `None` for a pipe table
+    fn from_params(params: &HashMap<String, String>) -> Result<Option<Self>, FormatError> {
+        match param(params, "format").map(str::to_lowercase) {
+            Some(format) => match format.as_str() {
+                "csv" => Ok(Some(DataFormat::Csv)),
+                "tsv" => Ok(Some(DataFormat::Tsv)),
+                "pipe" => Ok(None),
+                _ => Err(ErrorDetail::new(
+                    ErrorKind::Parse,
+                    "invalid-table-option",
+                    format!("Unknown table format '{format}' (expected csv, tsv or pipe)"),
+                )
+                .into()),
+            },
+            None => Ok(param(params, "src").map(|src| {
+                if src.to_lowercase().ends_with(".tsv") {
+                    DataFormat::Tsv
                 } else {
-                    line
+                    DataFormat::Csv
                 }
+            })),
+        }
+    }
+
+    fn delimiter(self) -> char {
+        match self {
+            DataFormat::Csv => ',',
+            DataFormat::Tsv => '\t',
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DataFormat::Csv => "CSV",
+            DataFormat::Tsv => "TSV",
+        }
+    }
+}
+
+fn read_source(path: &Path) -> Result<String, FormatError> {
+    fs::read_to_string(path).map_err(|err| {
+        ErrorDetail::new(
+            ErrorKind::Parse,
+            "table-source",
+            format!("Cannot read table data '{}': {err}", path.display()),
+        )
+        .caused_by(err)
+        .into()
+    })
+}
+
+/// Strip the indentation shared by all non-blank lines
+pub(super) fn dedent(content: &str) -> String {
+    let indent = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    content
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Build a table from delimited records, applying the selection parameters
+fn table_from_records(
+    mut records: Vec<Vec<String>>,
+    params: &HashMap<String, String>,
+) -> Result<Table, String> {
+    let header = match param(params, "header") {
+        Some(value) => parse_bool(value).ok_or_else(|| {
+            format!("Invalid value '{value}' for 'header' (expected true or false)")
+        })?,
+        None => false,
+    };
+    let width = records.first().map_or(0, Vec::len);
+    let columns = match param(params, "columns") {
+        Some(spec) => parse_selection("columns", spec, width)?,
+        None => (0..width).collect(),
+    };
+    let alignments = match param(params, "align") {
+        Some(spec) => parse_align_spec(spec, columns.len())?,
+        None => Vec::new(),
+    };
+
+    let header_record = if header && !records.is_empty() {
+        Some(records.remove(0))
+    } else {
+        None
+    };
+    if let Some(spec) = param(params, "rows") {
+        let mut remaining: Vec<Option<Vec<String>>> = records.into_iter().map(Some).collect();
+        records = parse_selection("rows", spec, remaining.len())?
+            .into_iter()
+            .filter_map(|index| remaining[index].take())
+            .collect();
+    }
+
+    let row = |record: &[String], header: bool| TableRow {
+        cells: columns
+            .iter()
+            .enumerate()
+            .map(|(i, &column)| {
+                let content = vec![DocNode::Paragraph(Paragraph {
+                    content: vec![InlineContent::Text(record[column].clone())],
+                    span: None,
+                })];
+                let align = alignments
+                    .get(i)
+                    .copied()
+                    .unwrap_or(TableCellAlignment::None);
+                TableCell::new(content, header, align)
             })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        // Parse the dedented content as markdown
-        let doc = match crate::formats::markdown::parser::parse_from_markdown(&dedented_content) {
-            Ok(d) => d,
-            Err(e) => {
-                // We return Ok(None) instead of Err because formatting failure shouldn't break serialization
-                // It just means we can't format it nicely
-                println!("TableHandler: Markdown parse failed: {e:?}");
-                return Ok(None);
-            }
-        };
+            .collect(),
+    };
 
-        // The markdown parser should have identified a table
-        // It converts markdown tables to Lex VerbatimBlock with "table" label (or similar)
-        // with the content already formatted (by serialize_pipe_table during conversion).
-        // We just need to extract it.
-        for child in &doc.root.children {
-            if let lex_core::lex::ast::ContentItem::VerbatimBlock(verbatim) = child {
-                // Reconstruct content from lines
-                let mut formatted = String::new();
-                for item in &verbatim.children {
-                    if let lex_core::lex::ast::ContentItem::VerbatimLine(line) = item {
-                        formatted.push_str(line.content.as_string());
-                        formatted.push('\n');
-                    }
-                }
-                return Ok(Some(formatted));
-            }
+    Ok(Table {
+        header: header_record
+            .iter()
+            .map(|record| row(record, true))
+            .collect(),
+        rows: records.iter().map(|record| row(record, false)).collect(),
+        caption: None,
+        span: None,
+    })
+}
+
+/// Parse a 1-based selection such as `1,3-5,7-` into zero-based indices
+pub(super) fn parse_selection(name: &str, spec: &str, count: usize) -> Result<Vec<usize>, String> {
+    let invalid = |part: &str| {
+        format!("Invalid {name} selection '{part}' in '{spec}' (expected e.g. 1,3-5,7-)")
+    };
+    let mut indices = Vec::new();
+    for part in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (first, last) = match part.split_once('-') {
+            Some((first, "")) => (first.trim(), None),
+            Some((first, last)) => (first.trim(), Some(last.trim())),
+            None => (part, Some(part)),
+        };
+        let first: usize = first.parse().map_err(|_| invalid(part))?;
+        let last: usize = match last {
+            Some(last) => last.parse().map_err(|_| invalid(part))?,
+            None => count,
+        };
+        if first == 0 || first > last {
+            return Err(invalid(part));
+        }
+        if last > count {
+            return Err(format!(
+                "Selection '{part}' is out of range: the data has {count} {name}"
+            ));
         }
-        Ok(None)
+        indices.extend(first - 1..last);
     }
+    Ok(indices)
 }
 
-fn parse_pipe_table(content: &str) -> DocNode {
-    let mut header = Vec::new();
-    let mut rows = Vec::new();
-    let mut alignments = Vec::new();
+fn parse_align_spec(spec: &str, columns: usize) -> Result<Vec<TableCellAlignment>, String> {
+    let alignments = spec
+        .split(',')
+        .map(|part| match part.trim().to_lowercase().as_str() {
+            "l" | "left" => Ok(TableCellAlignment::Left),
+            "c" | "center" => Ok(TableCellAlignment::Center),
+            "r" | "right" => Ok(TableCellAlignment::Right),
+            "" | "none" => Ok(TableCellAlignment::None),
+            other => Err(format!(
+                "Invalid alignment '{other}' in '{spec}' (expected left, center, right or none)"
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if alignments.len() > columns {
+        return Err(format!(
+            "'align' lists {} columns but the table has {columns}",
+            alignments.len()
+        ));
+    }
+    Ok(alignments)
+}
 
+/// Cell text that merges into the cell to the left
+const COLSPAN_MARKER: &str = "<";
+/// Cell text that merges into the cell above
+const ROWSPAN_MARKER: &str = "^";
+
+fn parse_pipe_table(content: &str) -> Table {
     let lines: Vec<&str> = content
         .lines()
         .map(|l| l.trim())
         .filter(|l| !l.is_empty())
         .collect();
 
-    if lines.is_empty() {
-        return DocNode::Table(Table {
-            rows,
-            header,
-            caption: None,
-        });
-    }
+    // Rows above the separator are header rows. Without a separator only the
+    // first row is, as in a plain pipe table.
+    let (header_lines, alignments, body_lines) =
+        match lines.iter().position(|line| is_separator_row(line)) {
+            Some(index) => (
+                &lines[..index],
+                parse_alignments(lines[index]),
+                &lines[index + 1..],
+            ),
+            None => (
+                &lines[..lines.len().min(1)],
+                Vec::new(),
+                &lines[lines.len().min(1)..],
+            ),
+        };
 
-    // Parse header
-    if let Some(header_line) = lines.first() {
-        let cells = parse_table_row(header_line);
-        let mut header_row = TableRow { cells: Vec::new() };
-        for cell_content in cells {
-            header_row.cells.push(TableCell {
-                content: vec![DocNode::Paragraph(Paragraph {
-                    content: vec![InlineContent::Text(cell_content)],
-                })],
-                header: true,
-                align: TableCellAlignment::None,
-            });
-        }
-        header.push(header_row);
+    Table {
+        header: parse_section(header_lines, true, &alignments),
+        rows: parse_section(body_lines, false, &alignments),
+        caption: None,
+        span: None,
     }
+}
 
-    // Parse separator line to determine alignments
-    if lines.len() > 1 {
-        let separator = lines[1];
-        if separator.contains(['-', '|']) {
-            let parts = parse_table_row(separator);
-            for part in parts {
-                let trimmed = part.trim();
-                if trimmed.starts_with(':') && trimmed.ends_with(':') {
-                    alignments.push(TableCellAlignment::Center);
-                } else if trimmed.ends_with(':') {
-                    alignments.push(TableCellAlignment::Right);
-                } else if trimmed.starts_with(':') {
-                    alignments.push(TableCellAlignment::Left);
-                } else {
-                    alignments.push(TableCellAlignment::None);
-                }
-            }
-        }
-    }
+fn is_separator_row(line: &str) -> bool {
+    line.contains('-')
+        && parse_table_row(line).iter().all(|part| {
+            let dashes = part.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
 
-    // Parse body rows
-    for line in lines.iter().skip(2) {
-        let cells = parse_table_row(line);
-        let mut row = TableRow { cells: Vec::new() };
-        for (i, cell_content) in cells.into_iter().enumerate() {
-            let align = if i < alignments.len() {
-                alignments[i]
+fn parse_alignments(separator: &str) -> Vec<TableCellAlignment> {
+    parse_table_row(separator)
+        .iter()
+        .map(|part| {
+            let trimmed = part.trim();
+            if trimmed.starts_with(':') && trimmed.ends_with(':') {
+                TableCellAlignment::Center
+            } else if trimmed.ends_with(':') {
+                TableCellAlignment::Right
+            } else if trimmed.starts_with(':') {
+                TableCellAlignment::Left
             } else {
                 TableCellAlignment::None
-            };
-
-            row.cells.push(TableCell {
-                content: vec![DocNode::Paragraph(Paragraph {
-                    content: vec![InlineContent::Text(cell_content)],
-                })],
-                header: false,
-                align,
-            });
-        }
-        rows.push(row);
-    }
+            }
+        })
+        .collect()
+}
+
+/// Parse the rows of the header or the body, resolving span markers
+fn parse_section(lines: &[&str], header: bool, alignments: &[TableCellAlignment]) -> Vec<TableRow> {
+    let mut rows: Vec<TableRow> = Vec::new();
+    // (row, cell) of the cell covering each grid column in the previous row
+    let mut owners: Vec<Option<(usize, usize)>> = Vec::new();
 
-    // Apply alignments to header
-    if !header.is_empty() {
-        for (i, cell) in header[0].cells.iter_mut().enumerate() {
-            if i < alignments.len() {
-                cell.align = alignments[i];
+    for (r, line) in lines.iter().enumerate() {
+        let mut row = TableRow { cells: Vec::new() };
+        let mut row_owners = Vec::new();
+
+        for (col, text) in parse_table_row(line).into_iter().enumerate() {
+            if text == COLSPAN_MARKER && !row.cells.is_empty() {
+                let last = row.cells.len() - 1;
+                row.cells[last].colspan += 1;
+                row_owners.push(Some((r, last)));
+                continue;
             }
+
+            if text == ROWSPAN_MARKER {
+                if let Some(Some((owner_row, owner_cell))) = owners.get(col).copied() {
+                    let owner = &mut rows[owner_row].cells[owner_cell];
+                    let last_row = owner_row + owner.rowspan - 1;
+                    if last_row + 1 == r {
+                        owner.rowspan += 1;
+                    }
+                    // A wide cell reaches this row once per column it covers
+                    if last_row + 1 >= r {
+                        row_owners.push(Some((owner_row, owner_cell)));
+                        continue;
+                    }
+                }
+            }
+
+            let align = alignments
+                .get(col)
+                .copied()
+                .unwrap_or(TableCellAlignment::None);
+            let content = vec![DocNode::Paragraph(Paragraph {
+                content: vec![InlineContent::Text(text)],
+                span: None,
+            })];
+            row_owners.push(Some((r, row.cells.len())));
+            row.cells.push(TableCell::new(content, header, align));
         }
+
+        rows.push(row);
+        owners = row_owners;
     }
 
-    DocNode::Table(Table {
-        rows,
-        header,
-        caption: None,
-    })
+    rows
 }
 
 fn parse_table_row(line: &str) -> Vec<String> {
@@ -194,28 +455,62 @@ fn parse_table_row(line: &str) -> Vec<String> {
     line.split('|').map(|s| s.trim().to_string()).collect()
 }
 
-fn serialize_pipe_table(table: &Table) -> String {
-    let mut output = String::new();
-
-    // 1. Calculate column widths
-    let mut col_widths = Vec::new();
+/// Lay a section out on a grid, writing span markers where cells are covered
+///
+/// Also returns the alignment of the cell starting in each column.
+fn section_grid(rows: &[TableRow]) -> (Vec<Vec<Option<String>>>, Vec<Option<TableCellAlignment>>) {
+    let mut grid: Vec<Vec<Option<String>>> = vec![Vec::new(); rows.len()];
+    let mut aligns = Vec::new();
+
+    fn place(grid: &mut [Vec<Option<String>>], r: usize, c: usize, text: String) {
+        if grid[r].len() <= c {
+            grid[r].resize(c + 1, None);
+        }
+        grid[r][c] = Some(text);
+    }
 
-    // Check header
-    for row in &table.header {
-        for (i, cell) in row.cells.iter().enumerate() {
-            let width = cell_text_width(cell);
-            if i >= col_widths.len() {
-                col_widths.push(width);
-            } else {
-                col_widths[i] = col_widths[i].max(width);
+    for (r, row) in rows.iter().enumerate() {
+        let mut col = 0;
+        for cell in &row.cells {
+            while grid[r].get(col).is_some_and(Option::is_some) {
+                col += 1;
+            }
+            if aligns.len() <= col {
+                aligns.resize(col + 1, None);
             }
+            aligns[col].get_or_insert(cell.align);
+
+            let last_row = (r + cell.rowspan.max(1)).min(rows.len());
+            for span_row in r..last_row {
+                for span_col in col..col + cell.colspan.max(1) {
+                    let text = if span_row == r && span_col == col {
+                        cell_text(cell)
+                    } else if span_row == r {
+                        COLSPAN_MARKER.to_string()
+                    } else {
+                        ROWSPAN_MARKER.to_string()
+                    };
+                    place(&mut grid, span_row, span_col, text);
+                }
+            }
+            col += cell.colspan.max(1);
         }
     }
 
-    // Check body
-    for row in &table.rows {
-        for (i, cell) in row.cells.iter().enumerate() {
-            let width = cell_text_width(cell);
+    (grid, aligns)
+}
+
+fn serialize_pipe_table(table: &Table) -> String {
+    let mut output = String::new();
+
+    let (header, header_aligns) = section_grid(&table.header);
+    let (body, body_aligns) = section_grid(&table.rows);
+
+    // 1. Calculate column widths
+    let mut col_widths = Vec::new();
+    for row in header.iter().chain(&body) {
+        for (i, text) in row.iter().enumerate() {
+            let width = text.as_deref().map_or(0, str::len);
             if i >= col_widths.len() {
                 col_widths.push(width);
             } else {
@@ -229,26 +524,30 @@ fn serialize_pipe_table(table: &Table) -> String {
         *width = (*width).max(3);
     }
 
-    // 2. Serialize Header
-    for row in &table.header {
+    let push_row = |output: &mut String, row: &[Option<String>]| {
         output.push('|');
-        for (i, cell) in row.cells.iter().enumerate() {
-            let text = cell_text(cell);
+        for (i, text) in row.iter().enumerate() {
+            let text = text.as_deref().unwrap_or("");
             let width = col_widths.get(i).copied().unwrap_or(text.len());
             output.push_str(&format!(" {text:width$} |"));
         }
         output.push('\n');
+    };
+
+    // 2. Serialize Header
+    for row in &header {
+        push_row(&mut output, row);
     }
 
     // 3. Serialize Separator
     if !col_widths.is_empty() {
         output.push('|');
         for (i, width) in col_widths.iter().enumerate() {
-            let align = table
-                .header
-                .first()
-                .and_then(|row| row.cells.get(i))
-                .map(|c| c.align)
+            let align = header_aligns
+                .get(i)
+                .copied()
+                .flatten()
+                .or_else(|| body_aligns.get(i).copied().flatten())
                 .unwrap_or(TableCellAlignment::None);
 
             let dashes = "-".repeat(width.saturating_sub(2));
@@ -263,14 +562,8 @@ fn serialize_pipe_table(table: &Table) -> String {
     }
 
     // 4. Serialize Body
-    for row in &table.rows {
-        output.push('|');
-        for (i, cell) in row.cells.iter().enumerate() {
-            let text = cell_text(cell);
-            let width = col_widths.get(i).copied().unwrap_or(text.len());
-            output.push_str(&format!(" {text:width$} |"));
-        }
-        output.push('\n');
+    for row in &body {
+        push_row(&mut output, row);
     }
 
     output
@@ -279,34 +572,12 @@ fn serialize_pipe_table(table: &Table) -> String {
 fn cell_text(cell: &TableCell) -> String {
     // Simple extraction for now, similar to existing logic
     if let Some(DocNode::Paragraph(p)) = cell.content.first() {
-        p.content
-            .iter()
-            .map(|ic| match ic {
-                InlineContent::Text(t) => t.clone(),
-                InlineContent::Bold(c) => format!("*{}*", inline_content_to_text(c)),
-                InlineContent::Italic(c) => format!("_{}_", inline_content_to_text(c)),
-                InlineContent::Code(c) => format!("`{c}`"),
-                InlineContent::Math(c) => format!("${c}$"),
-                InlineContent::Reference(c) => format!("[{c}]"),
-                InlineContent::Marker(c) => c.clone(),
-                InlineContent::Image(image) => {
-                    let mut text = format!("![{}]({})", image.alt, image.src);
-                    if let Some(title) = &image.title {
-                        text.push_str(&format!(" \"{title}\""));
-                    }
-                    text
-                }
-            })
-            .collect()
+        inline_content_to_text(&p.content)
     } else {
         String::new()
     }
 }
 
-fn cell_text_width(cell: &TableCell) -> usize {
-    cell_text(cell).len()
-}
-
 fn inline_content_to_text(content: &[InlineContent]) -> String {
     content
         .iter()
@@ -325,6 +596,171 @@ fn inline_content_to_text(content: &[InlineContent]) -> String {
                 }
                 text
             }
+            InlineContent::Strikethrough(c) => inline_content_to_text(c),
+            InlineContent::Link(link) => {
+                format!("{} [{}]", inline_content_to_text(&link.content), link.url)
+            }
+            InlineContent::FootnoteRef(label) if label.chars().all(|c| c.is_ascii_digit()) => {
+                format!("[{label}]")
+            }
+            InlineContent::FootnoteRef(label) => format!("[^{label}]"),
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPANNED: &str = "\
+| Region | Sales | <   |
+| ^      | Q1    | Q2  |
+| ------ | ----- | --- |
+| North  | 10    | 12  |
+| South  | 7     | ^   |
+";
+
+    #[test]
+    fn test_span_markers_and_header_rows() {
+        let table = parse_pipe_table(SPANNED);
+
+        assert_eq!(table.header.len(), 2);
+        let top = &table.header[0].cells;
+        assert_eq!((top[0].rowspan, top[0].colspan), (2, 1));
+        assert_eq!((top[1].rowspan, top[1].colspan), (1, 2));
+        assert_eq!(table.header[1].cells.len(), 2);
+
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0].cells[2].rowspan, 2);
+        assert_eq!(table.rows[1].cells.len(), 2);
+        assert!(table.has_spans());
+    }
+
+    #[test]
+    fn test_spans_round_trip() {
+        let table = parse_pipe_table(SPANNED);
+        assert_eq!(serialize_pipe_table(&table), SPANNED);
+    }
+
+    #[test]
+    fn test_caption_parameter() {
+        let params = HashMap::from([("caption".to_string(), "Totals".to_string())]);
+        let node = TableHandler.to_ir(SPANNED, &params).unwrap();
+        let DocNode::Table(table) = &node else {
+            panic!("expected a table");
+        };
+        assert_eq!(
+            table.caption,
+            Some(vec![InlineContent::Text("Totals".to_string())])
+        );
+
+        let (_, params) = TableHandler.convert_from_ir(&node).unwrap();
+        assert_eq!(params.get("caption").map(String::as_str), Some("Totals"));
+
+        let params = HashMap::from([("caption".to_string(), "\"Q1, Q2\"".to_string())]);
+        let node = TableHandler.to_ir(SPANNED, &params).unwrap();
+        let DocNode::Table(table) = &node else {
+            panic!("expected a table");
+        };
+        assert_eq!(
+            table.caption,
+            Some(vec![InlineContent::Text("Q1, Q2".to_string())])
+        );
+        let (_, params) = TableHandler.convert_from_ir(&node).unwrap();
+        assert_eq!(
+            params.get("caption").map(String::as_str),
+            Some("\"Q1, Q2\"")
+        );
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn cell_texts(row: &TableRow) -> Vec<String> {
+        row.cells.iter().map(cell_text).collect()
+    }
+
+    #[test]
+    fn test_csv_body_with_selection() {
+        let content = "    Name,Qty,Price\n    Pear,3,1.20\n    Fig,5,0.80\n    Plum,2,0.50\n";
+        let params = params(&[
+            ("format", "csv"),
+            ("header", "true"),
+            ("columns", "3,1"),
+            ("rows", "3,1"),
+            ("align", "r"),
+        ]);
+        let Some(DocNode::Table(table)) = TableHandler.to_ir(content, &params) else {
+            panic!("expected a table");
+        };
+
+        assert_eq!(cell_texts(&table.header[0]), vec!["Price", "Name"]);
+        assert!(table.header[0].cells[0].header);
+        let body: Vec<_> = table.rows.iter().map(cell_texts).collect();
+        assert_eq!(body, vec![vec!["0.50", "Plum"], vec!["1.20", "Pear"]]);
+        assert_eq!(table.rows[0].cells[0].align, TableCellAlignment::Right);
+        assert_eq!(table.rows[0].cells[1].align, TableCellAlignment::None);
+    }
+
+    #[test]
+    fn test_formatted_table_still_converts() {
+        use crate::format::Format;
+        use crate::formats::lex::LexFormat;
+
+        let source = "Stock:\n    Name,Qty,Price\n    Pear,3,1.20\n:: doc.table format=csv, header=true, align=\"l,r,c\" ::\n";
+        let format = LexFormat::default();
+        let formatted = format.serialize(&format.parse(source).unwrap()).unwrap();
+        assert_eq!(formatted, source);
+
+        let doc = format.parse(&formatted).unwrap();
+        let ir = crate::ir::from_lex::try_convert_document(&doc, &ConversionContext::standard())
+            .unwrap();
+        let Some(DocNode::Table(table)) = ir.children.first() else {
+            panic!("expected a table: {:?}", ir.children);
+        };
+        assert_eq!(cell_texts(&table.header[0]), vec!["Name", "Qty", "Price"]);
+        assert_eq!(table.rows[0].cells[1].align, TableCellAlignment::Right);
+        assert_eq!(table.rows[0].cells[2].align, TableCellAlignment::Center);
+    }
+
+    #[test]
+    fn test_source_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("data.tsv"), "a\tb\n\"1\t2\n").unwrap();
+        let ctx = ConversionContext::standard().with_base_dir(Some(dir.path()));
+
+        let err = TableHandler
+            .to_ir_with_context("", &params(&[("src", "data.tsv")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "table-data-syntax");
+        assert!(err
+            .message()
+            .contains("Malformed TSV in 'data.tsv': line 2"));
+
+        let err = TableHandler
+            .to_ir_with_context("", &params(&[("src", "missing.csv")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "table-source");
+
+        let err = TableHandler
+            .to_ir_with_context("a,b\n", &params(&[("format", "xlsx")]), &ctx)
+            .unwrap_err();
+        assert_eq!(err.code(), "invalid-table-option");
+    }
+
+    #[test]
+    fn test_selection_out_of_range() {
+        assert_eq!(
+            parse_selection("rows", "1,3-4,6-", 7).unwrap(),
+            vec![0, 2, 3, 5, 6]
+        );
+        assert!(parse_selection("rows", "2-9", 4)
+            .unwrap_err()
+            .contains("out of range"));
+        assert!(parse_selection("columns", "0", 4).is_err());
+    }
+}
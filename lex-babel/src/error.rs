@@ -1,6 +1,21 @@
 //! Error types for format operations
+//!
+//! Most failures are a plain message in one of the simple variants. Failures
+//! that can say more (where in the input they happened, which library error
+//! caused them) use [`FormatError::Detailed`], built from an [`ErrorDetail`]:
+//!
+//! ```ignore
+//! let err: FormatError = ErrorDetail::new(ErrorKind::Parse, "xml-syntax", "unexpected end of input")
+//!     .in_format("rfc_xml")
+//!     .at(SourcePosition::new(2, 4))
+//!     .caused_by(xml_error)
+//!     .into();
+//! ```
 
+use crate::ir::nodes::SourcePosition;
+use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 
 /// Errors that can occur during format operations
 #[derive(Debug, Clone, PartialEq)]
@@ -13,6 +28,170 @@ pub enum FormatError {
     SerializationError(String),
     /// Format does not support parsing
     NotSupported(String),
+    /// Error with a code, and where known a location, format and cause
+    Detailed(Box<ErrorDetail>),
+}
+
+/// Broad category of a [`FormatError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    FormatNotFound,
+    Parse,
+    Serialization,
+    NotSupported,
+}
+
+impl ErrorKind {
+    /// Code used for errors that have no more specific one
+    pub fn default_code(&self) -> &'static str {
+        match self {
+            ErrorKind::FormatNotFound => "format-not-found",
+            ErrorKind::Parse => "parse-error",
+            ErrorKind::Serialization => "serialization-error",
+            ErrorKind::NotSupported => "not-supported",
+        }
+    }
+}
+
+/// Structured contents of [`FormatError::Detailed`]
+#[derive(Debug, Clone)]
+pub struct ErrorDetail {
+    pub kind: ErrorKind,
+    /// Stable kebab-case code, e.g. `xml-syntax`
+    pub code: &'static str,
+    pub message: String,
+    /// Name of the format that failed
+    pub format: Option<String>,
+    /// Zero-based position in the input the error points at
+    pub position: Option<SourcePosition>,
+    cause: Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl ErrorDetail {
+    pub fn new(kind: ErrorKind, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            code,
+            message: message.into(),
+            format: None,
+            position: None,
+            cause: None,
+        }
+    }
+
+    pub fn in_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    pub fn at(mut self, position: SourcePosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Keep `cause` as the [`Error::source`] of the resulting error
+    pub fn caused_by(mut self, cause: impl Error + Send + Sync + 'static) -> Self {
+        self.cause = Some(Arc::new(cause));
+        self
+    }
+}
+
+impl PartialEq for ErrorDetail {
+    /// Causes compare by their message, as library errors rarely implement `PartialEq`
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.code == other.code
+            && self.message == other.message
+            && self.format == other.format
+            && self.position == other.position
+            && self.cause.as_ref().map(ToString::to_string)
+                == other.cause.as_ref().map(ToString::to_string)
+    }
+}
+
+impl From<ErrorDetail> for FormatError {
+    fn from(detail: ErrorDetail) -> Self {
+        FormatError::Detailed(Box::new(detail))
+    }
+}
+
+impl FormatError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            FormatError::FormatNotFound(_) => ErrorKind::FormatNotFound,
+            FormatError::ParseError(_) => ErrorKind::Parse,
+            FormatError::SerializationError(_) => ErrorKind::Serialization,
+            FormatError::NotSupported(_) => ErrorKind::NotSupported,
+            FormatError::Detailed(detail) => detail.kind,
+        }
+    }
+
+    /// Stable kebab-case code identifying the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            FormatError::Detailed(detail) => detail.code,
+            other => other.kind().default_code(),
+        }
+    }
+
+    /// The message without the kind prefix that `Display` adds
+    pub fn message(&self) -> &str {
+        match self {
+            FormatError::FormatNotFound(msg)
+            | FormatError::ParseError(msg)
+            | FormatError::SerializationError(msg)
+            | FormatError::NotSupported(msg) => msg,
+            FormatError::Detailed(detail) => &detail.message,
+        }
+    }
+
+    /// Name of the format that failed, when recorded
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            FormatError::Detailed(detail) => detail.format.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Zero-based position in the input, when known
+    pub fn position(&self) -> Option<SourcePosition> {
+        match self {
+            FormatError::Detailed(detail) => detail.position,
+            _ => None,
+        }
+    }
+
+    /// Record the format that failed, unless one is already set
+    ///
+    /// Parse and serialization errors become [`FormatError::Detailed`] so the
+    /// name can be kept; the other variants already name what they are about.
+    pub fn with_format(self, format: &str) -> Self {
+        self.update_detail(|detail| {
+            detail.format.get_or_insert_with(|| format.to_string());
+        })
+    }
+
+    /// Record where in the input the error happened, unless already known
+    ///
+    /// Variants are upgraded as for [`with_format`](Self::with_format).
+    pub fn with_position(self, position: SourcePosition) -> Self {
+        self.update_detail(|detail| {
+            detail.position.get_or_insert(position);
+        })
+    }
+
+    fn update_detail(self, update: impl FnOnce(&mut ErrorDetail)) -> Self {
+        let mut detail = match self {
+            FormatError::ParseError(msg) => ErrorDetail::new(ErrorKind::Parse, "parse-error", msg),
+            FormatError::SerializationError(msg) => {
+                ErrorDetail::new(ErrorKind::Serialization, "serialization-error", msg)
+            }
+            FormatError::Detailed(detail) => *detail,
+            other => return other,
+        };
+        update(&mut detail);
+        detail.into()
+    }
 }
 
 impl fmt::Display for FormatError {
@@ -22,8 +201,63 @@ impl fmt::Display for FormatError {
             FormatError::ParseError(msg) => write!(f, "Parse error: {msg}"),
             FormatError::SerializationError(msg) => write!(f, "Serialization error: {msg}"),
             FormatError::NotSupported(msg) => write!(f, "Operation not supported: {msg}"),
+            FormatError::Detailed(detail) => match detail.kind {
+                ErrorKind::FormatNotFound => write!(f, "Format '{}' not found", detail.message),
+                ErrorKind::Parse => write!(f, "Parse error: {}", detail.message),
+                ErrorKind::Serialization => write!(f, "Serialization error: {}", detail.message),
+                ErrorKind::NotSupported => {
+                    write!(f, "Operation not supported: {}", detail.message)
+                }
+            },
+        }
+    }
+}
+
+impl Error for FormatError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FormatError::Detailed(detail) => detail
+                .cause
+                .as_deref()
+                .map(|cause| cause as &(dyn Error + 'static)),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for FormatError {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detailed_error_keeps_code_position_and_cause() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let err: FormatError = ErrorDetail::new(ErrorKind::Parse, "xml-syntax", "bad tag")
+            .in_format("rfc_xml")
+            .at(SourcePosition::new(2, 4))
+            .caused_by(io)
+            .into();
+
+        assert_eq!(err.to_string(), "Parse error: bad tag");
+        assert_eq!(err.code(), "xml-syntax");
+        assert_eq!(err.format(), Some("rfc_xml"));
+        assert_eq!(err.position(), Some(SourcePosition::new(2, 4)));
+        assert_eq!(err.source().unwrap().to_string(), "no such file");
+    }
+
+    #[test]
+    fn test_with_format_upgrades_parse_errors_only() {
+        let err = FormatError::ParseError("oops".to_string()).with_format("markdown");
+        assert_eq!(err.format(), Some("markdown"));
+        assert_eq!(err.code(), "parse-error");
+        assert_eq!(err.to_string(), "Parse error: oops");
+
+        let err = FormatError::NotSupported("nope".to_string()).with_format("markdown");
+        assert_eq!(err, FormatError::NotSupported("nope".to_string()));
+
+        let err = FormatError::ParseError("oops".to_string())
+            .with_position(SourcePosition::new(3, 0))
+            .with_position(SourcePosition::new(9, 9));
+        assert_eq!(err.position(), Some(SourcePosition::new(3, 0)));
+    }
+}
@@ -4,10 +4,15 @@
 //! The trait provides a uniform interface for parsing and serializing documents.
 
 use crate::error::FormatError;
+use crate::ir::context::ConversionContext;
+use crate::ir::nodes::Document as IrDocument;
+use crate::options::FormatOption;
+use crate::report::ConversionReport;
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
 
 /// Serialized output produced by a [`Format`] implementation.
+#[derive(Debug)]
 pub enum SerializedDocument {
     /// UTF-8 text output (e.g., lex, markdown, HTML)
     Text(String),
@@ -86,6 +91,14 @@ pub trait Format: Send + Sync {
         false
     }
 
+    /// Options accepted by [`Format::serialize_with_options`]
+    ///
+    /// [`crate::FormatRegistry`] rejects any other key, and values of the wrong
+    /// type, before serializing. The default is none.
+    fn options(&self) -> &[FormatOption] {
+        &[]
+    }
+
     /// Parse source text into a Document
     ///
     /// Default implementation returns NotSupported error.
@@ -97,6 +110,18 @@ pub trait Format: Send + Sync {
         )))
     }
 
+    /// Parse source text, converting through the IR with `ctx`
+    ///
+    /// The default ignores `ctx` and calls [`Format::parse`]. Formats that build
+    /// IR while parsing override this so configured verbatim handlers apply.
+    fn parse_with_context(
+        &self,
+        source: &str,
+        _ctx: &ConversionContext,
+    ) -> Result<Document, FormatError> {
+        self.parse(source)
+    }
+
     /// Serialize a Document into source text
     ///
     /// Default implementation returns NotSupported error.
@@ -118,13 +143,87 @@ pub trait Format: Send + Sync {
         doc: &Document,
         options: &HashMap<String, String>,
     ) -> Result<SerializedDocument, FormatError> {
-        if options.is_empty() {
-            self.serialize(doc).map(SerializedDocument::Text)
-        } else {
-            Err(FormatError::NotSupported(format!(
-                "Format '{}' does not support extra parameters",
-                self.name()
-            )))
-        }
+        reject_options(self.name(), options)?;
+        self.serialize(doc).map(SerializedDocument::Text)
+    }
+
+    /// Serialize a Document with extra parameters, converting to IR with `ctx`
+    ///
+    /// [`crate::FormatRegistry`] calls this with its own context. The default
+    /// ignores `ctx` and calls [`Format::serialize_with_options`]; serializers
+    /// built on the IR override it, usually as
+    /// `self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)`.
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        _ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_with_options(doc, options)
+    }
+
+    /// Serialize an IR document, optionally using extra parameters.
+    ///
+    /// Used when IR passes ran between parsing and serialization. `source` is the
+    /// document the IR was built from and supplies what the IR does not carry,
+    /// such as the document title. The default implementation converts the IR
+    /// back to Lex and calls [`Format::serialize_with_options`], which loses
+    /// IR-only details like heading levels; serializers that work from the IR
+    /// should override it.
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let mut doc = crate::from_ir(ir);
+        doc.root.title = source.root.title.clone();
+        self.serialize_with_options(&doc, options)
+    }
+
+    /// Whether [`Format::serialize_ir_with_options`] writes the IR directly
+    ///
+    /// When set, [`crate::FormatRegistry::serialize_with_report`] hands the
+    /// IR it reports on to the serializer instead of building it twice.
+    /// Formats that override `serialize_ir_with_options` should return `true`.
+    fn serializes_ir(&self) -> bool {
+        false
+    }
+
+    /// Whether the output is built from the data verbatim blocks and the
+    /// bibliography refer to (`doc.table src=..`, `doc.include`, citations)
+    ///
+    /// [`crate::FormatRegistry::serialize_with_report`] then fails on data
+    /// that cannot be read, instead of writing the blocks as plain text.
+    /// Formats that write the blocks back as they are should return `false`.
+    fn resolves_sources(&self) -> bool {
+        true
+    }
+
+    /// Record what parsing `source` drops or degrades
+    ///
+    /// Called by [`crate::FormatRegistry::parse_with_report`]. The default
+    /// reports nothing; formats with constructs Lex cannot hold override it.
+    fn report_parse_losses(&self, _source: &str, _report: &mut ConversionReport) {}
+
+    /// Record what serializing `ir` to this format drops or degrades
+    ///
+    /// Called by [`crate::FormatRegistry::serialize_with_report`] with the IR
+    /// the output is built from, after any passes ran. The default reports
+    /// nothing.
+    fn report_serialize_losses(&self, _ir: &IrDocument, _report: &mut ConversionReport) {}
+}
+
+/// Error for formats that take no extra parameters when some were given
+pub(crate) fn reject_options(
+    format: &str,
+    options: &HashMap<String, String>,
+) -> Result<(), FormatError> {
+    if options.is_empty() {
+        Ok(())
+    } else {
+        Err(FormatError::NotSupported(format!(
+            "Format '{format}' does not support extra parameters"
+        )))
     }
 }
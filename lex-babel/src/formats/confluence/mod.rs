@@ -0,0 +1,116 @@
+//! Confluence storage format implementation
+//!
+//! This module implements export from Lex to the Confluence "storage format",
+//! the XHTML-based markup accepted by the Confluence REST API for page bodies.
+//!
+//! # Element Mapping Table
+//!
+//! | Lex Element             | Storage Format                              | Notes                                   |
+//! |-------------------------|---------------------------------------------|-----------------------------------------|
+//! | Session                 | `<h1>`..`<h6>`                              | Top-level sessions are `<h1>`           |
+//! | Paragraph               | `<p>`                                       |                                         |
+//! | List / ListItem         | `<ul>`/`<ol>` + `<li>`                      | Lex markers dropped, Confluence numbers |
+//! | Task list               | `<ac:task-list>`                            | Unless an item holds nested blocks      |
+//! | Definition              | `<p><strong>term</strong></p>` + content    |                                         |
+//! | Verbatim                | `code` macro                                | Language and subject (as title) kept    |
+//! | `note`/`info` annotation| `info` macro                                | A `type=` parameter overrides the label |
+//! | `tip` annotation        | `tip` macro                                 |                                         |
+//! | `warning` annotation    | `warning` macro                             | Also `caution`, `danger`                |
+//! | Other annotations       | Their content                               |                                         |
+//! | Table                   | `<table>` with `<th>`/`<td>`                | Alignment as inline style               |
+//! | Image                   | `<ac:image>`                                | Local paths become attachments          |
+//! | InlineContent:          |                                             |                                         |
+//! |   Bold / Italic / Code  | `<strong>` / `<em>` / `<code>`              |                                         |
+//! |   Reference             | `<a href>` for URLs/anchors, else `[text]`  |                                         |
+//!
+//! # Options
+//!
+//! - `toc`: inject a `toc` macro at the top of the page (default: false)
+//!
+//! The document title is not part of the body; pass it as the page title when
+//! publishing. Document metadata is dropped.
+
+pub mod serializer;
+
+use crate::error::FormatError;
+use crate::format::{Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
+use crate::ir::nodes::Document as IrDocument;
+use crate::options::{bool_option, FormatOption, OptionType};
+use lex_core::lex::ast::Document;
+use std::collections::HashMap;
+
+pub use serializer::ConfluenceOptions;
+
+/// Format implementation for Confluence storage format
+#[derive(Default)]
+pub struct ConfluenceFormat;
+
+const CONFLUENCE_OPTIONS: &[FormatOption] = &[FormatOption::new(
+    "toc",
+    OptionType::Bool,
+    "Insert a table of contents macro at the top",
+)
+.with_default("false")];
+
+impl Format for ConfluenceFormat {
+    fn name(&self) -> &str {
+        "confluence"
+    }
+
+    fn description(&self) -> &str {
+        "Confluence storage format (XHTML with ac: macros)"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["confluence"]
+    }
+
+    fn supports_serialization(&self) -> bool {
+        true
+    }
+
+    fn options(&self) -> &[FormatOption] {
+        CONFLUENCE_OPTIONS
+    }
+
+    fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
+        serializer::serialize_to_confluence(doc, &ConfluenceOptions::default())
+    }
+
+    fn serialize_with_options(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let options = ConfluenceOptions {
+            toc: bool_option(self.name(), options, "toc")?.unwrap_or(false),
+        };
+        serializer::serialize_to_confluence(doc, &options).map(SerializedDocument::Text)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        _source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let options = ConfluenceOptions {
+            toc: bool_option(self.name(), options, "toc")?.unwrap_or(false),
+        };
+        serializer::serialize_ir_to_confluence(ir, &options).map(SerializedDocument::Text)
+    }
+}
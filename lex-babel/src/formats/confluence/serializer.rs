@@ -0,0 +1,392 @@
+//! Confluence storage format serialization (Lex → XHTML storage format)
+//!
+//! Pipeline: Lex AST → IR → storage-format markup
+//!
+//! The storage format is XHTML plus `ac:`/`ri:` elements for macros and
+//! resources. There is no Rust library for it, so markup is written directly
+//! from the IR tree; all text goes through [`xml_escape`] and code bodies are
+//! wrapped in CDATA.
+
+use crate::common::text::strip_marker;
+use crate::error::FormatError;
+use crate::ir::nodes::{
+    Annotation, DocNode, Document as IrDocument, Heading, InlineContent, List, ListItem, Table,
+    TableCell, TableCellAlignment, Verbatim,
+};
+use lex_core::lex::ast::Document;
+
+/// Options for Confluence export
+#[derive(Debug, Clone, Default)]
+pub struct ConfluenceOptions {
+    /// Inject a `toc` macro at the top of the page
+    pub toc: bool,
+}
+
+/// Serialize a Lex document to Confluence storage format
+///
+/// The document title is not emitted: Confluence keeps the page title outside
+/// the body, so callers pass it to the REST API separately.
+pub fn serialize_to_confluence(
+    doc: &Document,
+    options: &ConfluenceOptions,
+) -> Result<String, FormatError> {
+    serialize_ir_to_confluence(&crate::to_ir(doc), options)
+}
+
+/// Serialize an IR document to Confluence storage format
+pub(crate) fn serialize_ir_to_confluence(
+    ir_doc: &IrDocument,
+    options: &ConfluenceOptions,
+) -> Result<String, FormatError> {
+    let mut out = String::new();
+    if options.toc {
+        out.push_str("<ac:structured-macro ac:name=\"toc\" />\n");
+    }
+    write_blocks(&mut out, &ir_doc.children);
+    Ok(out)
+}
+
+fn write_blocks(out: &mut String, nodes: &[DocNode]) {
+    for node in nodes {
+        write_block(out, node);
+    }
+}
+
+fn write_block(out: &mut String, node: &DocNode) {
+    match node {
+        DocNode::Document(doc) => write_blocks(out, &doc.children),
+        DocNode::Heading(heading) => write_heading(out, heading),
+        DocNode::Paragraph(paragraph) => {
+            out.push_str("<p>");
+            write_inlines(out, &paragraph.content);
+            out.push_str("</p>\n");
+        }
+        DocNode::List(list) => write_list(out, list),
+        DocNode::ListItem(item) => write_list_item(out, item),
+        DocNode::Definition(definition) => {
+            out.push_str("<p><strong>");
+            write_inlines(out, &definition.term);
+            out.push_str("</strong></p>\n");
+            write_blocks(out, &definition.description);
+        }
+        DocNode::Verbatim(verbatim) => write_code_macro(out, verbatim),
+        DocNode::Annotation(annotation) => write_annotation(out, annotation),
+        DocNode::Table(table) => write_table(out, table),
+        DocNode::Image(image) => {
+            out.push_str("<p>");
+            write_image(out, &image.src, &image.alt);
+            out.push_str("</p>\n");
+        }
+        DocNode::Video(video) => {
+            write_media_link(out, &video.src, video.title.as_deref());
+        }
+        DocNode::Audio(audio) => {
+            write_media_link(out, &audio.src, audio.title.as_deref());
+        }
+        DocNode::Inline(inline) => {
+            out.push_str("<p>");
+            write_inlines(out, std::slice::from_ref(inline));
+            out.push_str("</p>\n");
+        }
+        DocNode::BlockQuote(quote) => {
+            out.push_str("<blockquote>\n");
+            write_blocks(out, &quote.children);
+            out.push_str("</blockquote>\n");
+        }
+        DocNode::ThematicBreak => out.push_str("<hr />\n"),
+    }
+}
+
+/// Top-level sessions (IR level 2) become `<h1>`, since the page title sits
+/// outside the body.
+fn write_heading(out: &mut String, heading: &Heading) {
+    let level = heading.level.saturating_sub(1).clamp(1, 6);
+    out.push_str(&format!("<h{level}>"));
+    write_inlines(out, &heading.content);
+    out.push_str(&format!("</h{level}>\n"));
+    write_blocks(out, &heading.children);
+}
+
+fn write_list(out: &mut String, list: &List) {
+    // Task bodies hold inline text only, so items with nested blocks stay a
+    // plain list
+    if !list.items.is_empty()
+        && list
+            .items
+            .iter()
+            .all(|item| item.checked.is_some() && item.children.is_empty())
+    {
+        write_task_list(out, list);
+        return;
+    }
+
+    let tag = if list.ordered { "ol" } else { "ul" };
+    out.push_str(&format!("<{tag}>\n"));
+    for item in &list.items {
+        write_list_item(out, item);
+    }
+    out.push_str(&format!("</{tag}>\n"));
+}
+
+/// A list made only of childless task items becomes a native task list
+fn write_task_list(out: &mut String, list: &List) {
+    out.push_str("<ac:task-list>\n");
+    for item in &list.items {
+        let status = if item.checked == Some(true) {
+            "complete"
+        } else {
+            "incomplete"
+        };
+        out.push_str(&format!(
+            "<ac:task>\n<ac:task-status>{status}</ac:task-status>\n<ac:task-body>"
+        ));
+        write_item_text(out, item);
+        out.push_str("</ac:task-body>\n</ac:task>\n");
+    }
+    out.push_str("</ac:task-list>\n");
+}
+
+fn write_list_item(out: &mut String, item: &ListItem) {
+    out.push_str("<li>");
+    match item.checked {
+        Some(true) => out.push_str("[x] "),
+        Some(false) => out.push_str("[ ] "),
+        None => {}
+    }
+    write_item_text(out, item);
+    if !item.children.is_empty() {
+        out.push('\n');
+        write_blocks(out, &item.children);
+    }
+    out.push_str("</li>\n");
+}
+
+/// The text of a list item, without its marker or the line break after it
+fn write_item_text(out: &mut String, item: &ListItem) {
+    let mut text = String::new();
+    write_inlines(&mut text, strip_marker(&item.content));
+    out.push_str(text.trim_end());
+}
+
+/// Verbatim blocks become the `code` macro
+fn write_code_macro(out: &mut String, verbatim: &Verbatim) {
+    out.push_str("<ac:structured-macro ac:name=\"code\">");
+    if let Some(language) = verbatim.language.as_deref().filter(|l| !l.is_empty()) {
+        write_macro_parameter(out, "language", code_macro_language(language));
+    }
+    if let Some(subject) = verbatim
+        .subject
+        .as_deref()
+        .map(|s| s.trim().trim_end_matches(':'))
+        .filter(|s| !s.is_empty())
+    {
+        write_macro_parameter(out, "title", subject);
+    }
+    out.push_str("<ac:plain-text-body><![CDATA[");
+    // `]]>` cannot appear inside CDATA; split it across two sections
+    let content = verbatim.content.trim_matches('\n');
+    out.push_str(&content.replace("]]>", "]]]]><![CDATA[>"));
+    out.push_str("]]></ac:plain-text-body></ac:structured-macro>\n");
+}
+
+/// Map Lex verbatim labels to the language names the code macro knows
+fn code_macro_language(language: &str) -> &str {
+    match language {
+        "python" | "python3" => "py",
+        "javascript" | "node" => "js",
+        "shell" | "sh" | "zsh" | "console" => "bash",
+        "csharp" | "cs" => "c#",
+        "c++" | "c" | "h" => "cpp",
+        "yaml" => "yml",
+        "html" | "xhtml" | "svg" => "xml",
+        "text" | "plain" | "txt" => "text",
+        other => other,
+    }
+}
+
+/// Admonition annotations become the matching panel macro; other annotations
+/// contribute their content only
+fn write_annotation(out: &mut String, annotation: &Annotation) {
+    let kind = annotation
+        .parameters
+        .iter()
+        .find(|(key, _)| key == "type")
+        .map(|(_, value)| value.as_str())
+        .unwrap_or(annotation.label.as_str());
+
+    let macro_name = match kind {
+        "note" | "info" => "info",
+        "tip" => "tip",
+        "warning" | "caution" | "danger" => "warning",
+        _ => {
+            write_blocks(out, &annotation.content);
+            return;
+        }
+    };
+
+    out.push_str(&format!("<ac:structured-macro ac:name=\"{macro_name}\">"));
+    if let Some((_, title)) = annotation.parameters.iter().find(|(key, _)| key == "title") {
+        write_macro_parameter(out, "title", title);
+    }
+    out.push_str("<ac:rich-text-body>\n");
+    write_blocks(out, &annotation.content);
+    out.push_str("</ac:rich-text-body></ac:structured-macro>\n");
+}
+
+fn write_macro_parameter(out: &mut String, name: &str, value: &str) {
+    out.push_str(&format!(
+        "<ac:parameter ac:name=\"{name}\">{}</ac:parameter>",
+        xml_escape(value)
+    ));
+}
+
+/// Tables map to plain storage tables; header rows use `<th>` and the caption
+/// follows as an emphasized paragraph (storage tables have no `<caption>`).
+fn write_table(out: &mut String, table: &Table) {
+    out.push_str("<table>\n<tbody>\n");
+    for row in table.header.iter().chain(table.rows.iter()) {
+        out.push_str("<tr>\n");
+        for cell in &row.cells {
+            write_table_cell(out, cell);
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n");
+
+    if let Some(caption) = &table.caption {
+        out.push_str("<p><em>");
+        write_inlines(out, caption);
+        out.push_str("</em></p>\n");
+    }
+}
+
+fn write_table_cell(out: &mut String, cell: &TableCell) {
+    let tag = if cell.header { "th" } else { "td" };
+    let style = match cell.align {
+        TableCellAlignment::Left => " style=\"text-align: left;\"",
+        TableCellAlignment::Center => " style=\"text-align: center;\"",
+        TableCellAlignment::Right => " style=\"text-align: right;\"",
+        TableCellAlignment::None => "",
+    };
+    let mut spans = String::new();
+    if cell.colspan > 1 {
+        spans.push_str(&format!(" colspan=\"{}\"", cell.colspan));
+    }
+    if cell.rowspan > 1 {
+        spans.push_str(&format!(" rowspan=\"{}\"", cell.rowspan));
+    }
+    out.push_str(&format!("<{tag}{style}{spans}>"));
+    match cell.content.as_slice() {
+        // A single paragraph is written inline to keep cells compact
+        [DocNode::Paragraph(paragraph)] => write_inlines(out, &paragraph.content),
+        blocks => {
+            if !blocks.is_empty() {
+                out.push('\n');
+            }
+            write_blocks(out, blocks);
+        }
+    }
+    out.push_str(&format!("</{tag}>\n"));
+}
+
+fn write_inlines(out: &mut String, content: &[InlineContent]) {
+    for inline in content {
+        match inline {
+            InlineContent::Text(text) => out.push_str(&xml_escape(text)),
+            InlineContent::Bold(children) => {
+                out.push_str("<strong>");
+                write_inlines(out, children);
+                out.push_str("</strong>");
+            }
+            InlineContent::Italic(children) => {
+                out.push_str("<em>");
+                write_inlines(out, children);
+                out.push_str("</em>");
+            }
+            InlineContent::Code(code) => {
+                out.push_str("<code>");
+                out.push_str(&xml_escape(code));
+                out.push_str("</code>");
+            }
+            InlineContent::Math(math) => {
+                out.push_str("<code>$");
+                out.push_str(&xml_escape(math));
+                out.push_str("$</code>");
+            }
+            InlineContent::Marker(marker) => out.push_str(&xml_escape(marker)),
+            InlineContent::Reference(reference) => write_reference(out, reference),
+            InlineContent::Image(image) => write_image(out, &image.src, &image.alt),
+            InlineContent::Strikethrough(children) => {
+                out.push_str("<span style=\"text-decoration: line-through;\">");
+                write_inlines(out, children);
+                out.push_str("</span>");
+            }
+            InlineContent::Link(link) => {
+                out.push_str(&format!("<a href=\"{}\">", xml_escape(&link.url)));
+                write_inlines(out, &link.content);
+                out.push_str("</a>");
+            }
+            InlineContent::FootnoteRef(label) => {
+                out.push_str(&format!("<sup>[{}]</sup>", xml_escape(label)));
+            }
+        }
+    }
+}
+
+/// URLs and in-page anchors become links; citations, footnotes and other
+/// references are kept as bracketed text
+fn write_reference(out: &mut String, reference: &str) {
+    let is_link = reference.contains("://")
+        || reference.starts_with("mailto:")
+        || reference.starts_with('/')
+        || reference.starts_with("./")
+        || reference.starts_with('#');
+
+    if is_link {
+        out.push_str(&format!(
+            "<a href=\"{}\">{}</a>",
+            xml_escape(reference),
+            xml_escape(reference)
+        ));
+    } else {
+        out.push('[');
+        out.push_str(&xml_escape(reference));
+        out.push(']');
+    }
+}
+
+/// Remote images use `ri:url`; local paths are referenced as page attachments,
+/// which the publishing client uploads alongside the page.
+fn write_image(out: &mut String, src: &str, alt: &str) {
+    out.push_str(&format!("<ac:image ac:alt=\"{}\">", xml_escape(alt)));
+    if src.contains("://") {
+        out.push_str(&format!("<ri:url ri:value=\"{}\" />", xml_escape(src)));
+    } else {
+        out.push_str(&format!(
+            "<ri:attachment ri:filename=\"{}\" />",
+            xml_escape(attachment_name(src))
+        ));
+    }
+    out.push_str("</ac:image>");
+}
+
+fn write_media_link(out: &mut String, src: &str, title: Option<&str>) {
+    let label = title.filter(|t| !t.is_empty()).unwrap_or(src);
+    out.push_str(&format!(
+        "<p><a href=\"{}\">{}</a></p>\n",
+        xml_escape(src),
+        xml_escape(label)
+    ));
+}
+
+fn attachment_name(src: &str) -> &str {
+    src.rsplit(['/', '\\']).next().unwrap_or(src)
+}
+
+/// Escape XML special characters in text and attribute values
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,259 @@
+//! CSS inlining for HTML mail bodies
+//!
+//! Mail clients commonly strip `<style>` elements, so the stylesheet is copied
+//! into `style` attributes instead. The HTML comes from the HTML serializer,
+//! whose markup is regular enough that start tags can be rewritten in place
+//! without a full DOM.
+//!
+//! Only simple selectors are supported: `tag`, `.class`, `tag.class` and
+//! comma-separated lists of those. At-rules and selectors with combinators or
+//! pseudo-classes are skipped. Declarations are applied in specificity order,
+//! then source order, and an element's own `style` attribute always wins.
+
+/// A stylesheet rule with a single simple selector
+struct Rule {
+    tag: Option<String>,
+    classes: Vec<String>,
+    declarations: Vec<String>,
+}
+
+impl Rule {
+    fn matches(&self, tag: &str, classes: &[&str]) -> bool {
+        self.tag
+            .as_deref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(tag))
+            && self.classes.iter().all(|c| classes.contains(&c.as_str()))
+    }
+
+    fn specificity(&self) -> (usize, usize) {
+        (self.classes.len(), usize::from(self.tag.is_some()))
+    }
+}
+
+/// Copy the rules of `css` into the `style` attribute of every matching
+/// element in `html`
+pub(crate) fn inline_css(html: &str, css: &str) -> String {
+    let mut rules = parse_stylesheet(css);
+    // Stable sort keeps source order within equal specificity
+    rules.sort_by_key(Rule::specificity);
+
+    let mut output = String::with_capacity(html.len() * 2);
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let is_start_tag = rest[1..].starts_with(|c: char| c.is_ascii_alphabetic());
+        let Some(end) = tag_end(rest).filter(|_| is_start_tag) else {
+            output.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+
+        output.push_str(&rewrite_start_tag(&rest[..=end], &rules));
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Index of the `>` closing the tag at the start of `tag`, skipping quoted
+/// attribute values
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn rewrite_start_tag(tag: &str, rules: &[Rule]) -> String {
+    let name_end = tag[1..]
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .map(|i| i + 1)
+        .unwrap_or(tag.len() - 1);
+    let name = &tag[1..name_end];
+
+    let class_value = attribute_value(tag, "class").unwrap_or_default();
+    let classes: Vec<&str> = class_value.split_whitespace().collect();
+
+    let mut declarations: Vec<String> = rules
+        .iter()
+        .filter(|rule| rule.matches(name, &classes))
+        .flat_map(|rule| rule.declarations.iter().cloned())
+        .collect();
+    if declarations.is_empty() {
+        return tag.to_string();
+    }
+
+    let mut tag = tag.to_string();
+    if let Some((range, existing)) = attribute_span(&tag, "style") {
+        declarations.extend(
+            existing
+                .split(';')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(str::to_string),
+        );
+        tag.replace_range(range, "");
+    }
+
+    let style = declarations.join("; ").replace('"', "&quot;");
+    let insert_at = if tag.ends_with("/>") {
+        tag.len() - 2
+    } else {
+        tag.len() - 1
+    };
+    let head = tag[..insert_at].trim_end();
+    let tail = &tag[insert_at..];
+    let spacer = if tail.starts_with('/') { " " } else { "" };
+    format!("{head} style=\"{style}\"{spacer}{tail}")
+}
+
+fn attribute_value(tag: &str, name: &str) -> Option<String> {
+    attribute_span(tag, name).map(|(_, value)| value)
+}
+
+/// Byte range of ` name="value"` within the tag, and the value
+fn attribute_span(tag: &str, name: &str) -> Option<(std::ops::Range<usize>, String)> {
+    let needle = format!(" {name}=\"");
+    let start = tag.find(&needle)?;
+    let value_start = start + needle.len();
+    let value_len = tag[value_start..].find('"')?;
+    let value = tag[value_start..value_start + value_len].replace("&quot;", "\"");
+    Some((start..value_start + value_len + 1, value))
+}
+
+fn parse_stylesheet(css: &str) -> Vec<Rule> {
+    let css = strip_comments(css);
+    let mut rules = Vec::new();
+    let mut rest = css.as_str();
+
+    while let Some(open) = rest.find('{') {
+        let prelude = rest[..open].trim();
+        let body_start = open + 1;
+        let Some(close) = matching_brace(&rest[body_start..]) else {
+            break;
+        };
+        let body = &rest[body_start..body_start + close];
+        rest = &rest[body_start + close + 1..];
+
+        // At-rules (`@media`, `@font-face`, ..) have no inline equivalent
+        if prelude.starts_with('@') || body.contains('{') {
+            continue;
+        }
+
+        let declarations: Vec<String> = body
+            .split(';')
+            .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|d| d.contains(':') && !d.contains("var("))
+            .collect();
+
+        for selector in prelude.split(',') {
+            if let Some((tag, classes)) = parse_simple_selector(selector.trim()) {
+                rules.push(Rule {
+                    tag,
+                    classes,
+                    declarations: declarations.clone(),
+                });
+            }
+        }
+    }
+    rules
+}
+
+/// Parse `tag`, `.class` or `tag.class.other`; anything else is unsupported
+fn parse_simple_selector(selector: &str) -> Option<(Option<String>, Vec<String>)> {
+    let is_ident = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+
+    let mut parts = selector.split('.');
+    let tag = parts.next()?;
+    if !tag.is_empty() && !is_ident(tag) {
+        return None;
+    }
+    let classes: Vec<String> = parts.map(str::to_string).collect();
+    if classes.iter().any(|c| !is_ident(c)) || (tag.is_empty() && classes.is_empty()) {
+        return None;
+    }
+
+    let tag = (!tag.is_empty()).then(|| tag.to_ascii_lowercase());
+    Some((tag, classes))
+}
+
+/// Position of the `}` closing a block whose `{` was just consumed
+fn matching_brace(body: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        output.push_str(&rest[..start]);
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => return output,
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies_tag_and_class_rules_in_specificity_order() {
+        let css = ".note { color: red; } p { color: black; margin: 0; }";
+        let html = inline_css("<p class=\"note\">Hi</p>", css);
+        assert_eq!(
+            html,
+            "<p class=\"note\" style=\"color: black; margin: 0; color: red\">Hi</p>"
+        );
+    }
+
+    #[test]
+    fn test_existing_style_attribute_wins() {
+        let html = inline_css(
+            "<td style=\"text-align: right\">1</td>",
+            "td { padding: 4px; }",
+        );
+        assert_eq!(html, "<td style=\"padding: 4px; text-align: right\">1</td>");
+    }
+
+    #[test]
+    fn test_skips_at_rules_and_complex_selectors() {
+        let css =
+            "@media print { p { color: red; } } div p { color: blue; } a:hover { color: green; }";
+        let html = "<div><p>Text</p><a href=\"x\">x</a></div>";
+        assert_eq!(inline_css(html, css), html);
+    }
+
+    #[test]
+    fn test_quoted_angle_brackets_and_void_tags() {
+        let html = inline_css("<img alt=\"a > b\" src=\"x.png\"/>", "img { border: 0; }");
+        assert_eq!(
+            html,
+            "<img alt=\"a > b\" src=\"x.png\" style=\"border: 0\" />"
+        );
+    }
+}
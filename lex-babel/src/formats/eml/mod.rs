@@ -0,0 +1,155 @@
+//! MIME email (.eml) implementation
+//!
+//! This module implements export from Lex to a single RFC 5322 message that
+//! can be opened directly in a mail client, e.g. to send a status report
+//! written in Lex.
+//!
+//! # Message Structure
+//!
+//! ```text
+//! multipart/alternative
+//! ├── text/plain            wrapped text (RFC text renderer, no pages)
+//! └── text/html             HTML export with CSS inlined into style=""
+//!     or
+//!     multipart/related     when the document references local images
+//!     ├── text/html         <img src="cid:image1@lex">
+//!     └── image/png ...     one part per distinct image file
+//! ```
+//!
+//! # Headers
+//!
+//! `Subject`, `From`, `To` and `Cc` come from the `subject`, `from`, `to` and
+//! `cc` metadata annotations. The subject falls back to the `title` metadata
+//! and then the document title.
+//!
+//! # Options
+//!
+//! - `subject`, `from`, `to`, `cc`: override the metadata values
+//! - `base-dir`: directory relative image paths are resolved against
+//!   (default: the current directory; the CLI passes the input file's directory)
+//! - `code-theme`: colours for code blocks, as for HTML (`none` leaves them
+//!   plain)
+
+mod inline_css;
+pub mod serializer;
+
+use crate::error::FormatError;
+use crate::format::{Format, SerializedDocument};
+use crate::formats::html::{parse_code_theme, CODE_THEME_OPTION};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::Document as IrDocument;
+use crate::options::{FormatOption, OptionType};
+use lex_core::lex::ast::Document;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub use serializer::EmlOptions;
+
+/// Format implementation for MIME email messages
+#[derive(Default)]
+pub struct EmlFormat;
+
+const EML_OPTIONS: &[FormatOption] = &[
+    FormatOption::new(
+        "subject",
+        OptionType::String,
+        "Subject header (defaults to `subject` or `title` metadata, then the document title)",
+    ),
+    FormatOption::new(
+        "from",
+        OptionType::String,
+        "From header (defaults to `from` metadata)",
+    ),
+    FormatOption::new(
+        "to",
+        OptionType::String,
+        "To header (defaults to `to` metadata)",
+    ),
+    FormatOption::new(
+        "cc",
+        OptionType::String,
+        "Cc header (defaults to `cc` metadata)",
+    ),
+    FormatOption::new(
+        "base-dir",
+        OptionType::Path,
+        "Directory relative image paths are resolved against",
+    ),
+    CODE_THEME_OPTION,
+];
+
+impl Format for EmlFormat {
+    fn name(&self) -> &str {
+        "eml"
+    }
+
+    fn description(&self) -> &str {
+        "MIME email message with HTML and plain-text alternatives"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["eml"]
+    }
+
+    fn supports_serialization(&self) -> bool {
+        true
+    }
+
+    fn options(&self) -> &[FormatOption] {
+        EML_OPTIONS
+    }
+
+    fn serialize(&self, _doc: &Document) -> Result<String, FormatError> {
+        Err(FormatError::NotSupported(
+            "EML serialization produces binary output".to_string(),
+        ))
+    }
+
+    fn serialize_with_options(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        serializer::serialize_to_eml(doc, &eml_options(options)?).map(SerializedDocument::Binary)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        serializer::serialize_ir_to_eml(ir, document_title(source), &eml_options(options)?)
+            .map(SerializedDocument::Binary)
+    }
+}
+
+fn eml_options(options: &HashMap<String, String>) -> Result<EmlOptions, FormatError> {
+    let text_option = |key: &str| options.get(key).filter(|v| !v.is_empty()).cloned();
+    let mut eml_options = EmlOptions {
+        subject: text_option("subject"),
+        from: text_option("from"),
+        to: text_option("to"),
+        cc: text_option("cc"),
+        base_dir: text_option("base-dir").map(PathBuf::from),
+        ..EmlOptions::default()
+    };
+    if let Some(name) = options.get("code-theme") {
+        eml_options.code_theme = parse_code_theme(name)?;
+    }
+    Ok(eml_options)
+}
@@ -0,0 +1,368 @@
+//! MIME message serialization (Lex → .eml)
+//!
+//! Pipeline: Lex AST → wrapped text + HTML body → MIME message bytes
+//!
+//! The message is `multipart/alternative` with a `text/plain` part rendered by
+//! the RFC text renderer (unpaginated) and a `text/html` part from the HTML
+//! serializer with `css/email.css` inlined. When the document references local
+//! images, the HTML part is wrapped in `multipart/related` together with the
+//! image files, and `<img src>` is rewritten to the matching `cid:` URL.
+
+use super::inline_css::inline_css;
+use crate::error::FormatError;
+use crate::formats::html::{CodeTheme, HtmlTheme};
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{Document as IrDocument, Metadata};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use lex_core::lex::ast::Document;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Maximum encoded line length allowed by RFC 2045
+const MAX_LINE: usize = 76;
+
+/// Options for EML export; each header falls back to document metadata
+#[derive(Debug, Clone)]
+pub struct EmlOptions {
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub cc: Option<String>,
+    /// Directory that relative image paths are resolved against
+    pub base_dir: Option<PathBuf>,
+    /// Colours code blocks are highlighted in; `None` leaves them plain, as
+    /// mail clients run no highlight.js
+    pub code_theme: Option<CodeTheme>,
+}
+
+impl Default for EmlOptions {
+    fn default() -> Self {
+        Self {
+            subject: None,
+            from: None,
+            to: None,
+            cc: None,
+            base_dir: None,
+            code_theme: Some(HtmlTheme::default().code_theme()),
+        }
+    }
+}
+
+/// Serialize a Lex document to a MIME message
+pub fn serialize_to_eml(doc: &Document, options: &EmlOptions) -> Result<Vec<u8>, FormatError> {
+    serialize_ir_to_eml(&crate::to_ir(doc), document_title(doc), options)
+}
+
+/// Serialize an IR document to a MIME message, with `title` as the last
+/// fallback for the subject
+pub(crate) fn serialize_ir_to_eml(
+    ir_doc: &IrDocument,
+    title: &str,
+    options: &EmlOptions,
+) -> Result<Vec<u8>, FormatError> {
+    let headers = MessageHeaders::resolve(&ir_doc.metadata, title, options);
+
+    let text = crate::formats::rfc_text::serializer::render_wrapped_text(ir_doc);
+    let body_html = crate::formats::html::serialize_body_html(ir_doc, options.code_theme)?;
+    let html = inline_css(
+        &wrap_html(&body_html, &headers.subject),
+        include_str!("../../../css/email.css"),
+    );
+
+    let base_dir = options
+        .base_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let (html, images) = embed_local_images(&html, &base_dir)?;
+
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    html.hash(&mut hasher);
+    let id = hasher.finish();
+    // `=_` cannot occur in quoted-printable or base64 bodies
+    let alt_boundary = format!("=_lex_alt_{id:016x}");
+    let rel_boundary = format!("=_lex_rel_{id:016x}");
+
+    let mut message = MimeWriter::default();
+    headers.write(&mut message);
+    message.line("MIME-Version: 1.0");
+    message.line("Content-Type: multipart/alternative;");
+    message.line(&format!("\tboundary=\"{alt_boundary}\""));
+    message.line("");
+
+    message.line(&format!("--{alt_boundary}"));
+    message.text_part("text/plain", &text);
+
+    message.line(&format!("--{alt_boundary}"));
+    if images.is_empty() {
+        message.text_part("text/html", &html);
+    } else {
+        message.line("Content-Type: multipart/related; type=\"text/html\";");
+        message.line(&format!("\tboundary=\"{rel_boundary}\""));
+        message.line("");
+        message.line(&format!("--{rel_boundary}"));
+        message.text_part("text/html", &html);
+        for image in &images {
+            message.line(&format!("--{rel_boundary}"));
+            message.image_part(image);
+        }
+        message.line(&format!("--{rel_boundary}--"));
+    }
+    message.line(&format!("--{alt_boundary}--"));
+
+    Ok(message.into_bytes())
+}
+
+struct MessageHeaders {
+    subject: String,
+    from: Option<String>,
+    to: Option<String>,
+    cc: Option<String>,
+}
+
+impl MessageHeaders {
+    /// Options take precedence over `subject`/`from`/`to`/`cc` metadata; the
+    /// subject falls back to the `title` metadata, then the document title.
+    fn resolve(metadata: &Metadata, title: &str, options: &EmlOptions) -> Self {
+        let lookup = |key: &str| {
+            metadata
+                .get_text(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let subject = options
+            .subject
+            .clone()
+            .or_else(|| lookup("subject"))
+            .or_else(|| lookup("title"))
+            .unwrap_or_else(|| title.trim().to_string());
+
+        Self {
+            subject,
+            from: options.from.clone().or_else(|| lookup("from")),
+            to: options.to.clone().or_else(|| lookup("to")),
+            cc: options.cc.clone().or_else(|| lookup("cc")),
+        }
+    }
+
+    fn write(&self, message: &mut MimeWriter) {
+        if let Some(from) = &self.from {
+            message.line(&format!("From: {}", encode_addresses(from)));
+        }
+        if let Some(to) = &self.to {
+            message.line(&format!("To: {}", encode_addresses(to)));
+        }
+        if let Some(cc) = &self.cc {
+            message.line(&format!("Cc: {}", encode_addresses(cc)));
+        }
+        message.line(&format!("Subject: {}", encode_header_text(&self.subject)));
+        // Opens as an unsent draft in Outlook; ignored by other clients
+        message.line("X-Unsent: 1");
+    }
+}
+
+/// A local image attached as a `multipart/related` part
+struct InlineImage {
+    content_id: String,
+    file_name: String,
+    mime_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// Rewrite local `<img src>` values to `cid:` URLs and load the files
+///
+/// Remote and `data:` URLs are left alone. The same path is attached once.
+fn embed_local_images(
+    html: &str,
+    base_dir: &Path,
+) -> Result<(String, Vec<InlineImage>), FormatError> {
+    let mut images: Vec<(String, InlineImage)> = Vec::new();
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find("<img ") {
+        let Some(src_offset) = rest[start..].find(" src=\"") else {
+            break;
+        };
+        let value_start = start + src_offset + " src=\"".len();
+        let Some(value_len) = rest[value_start..].find('"') else {
+            break;
+        };
+        let src = rest[value_start..value_start + value_len]
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+
+        output.push_str(&rest[..value_start]);
+        rest = &rest[value_start + value_len..];
+
+        if src.contains("://") || src.starts_with("data:") || src.starts_with("cid:") {
+            output.push_str(&html_escape(&src));
+            continue;
+        }
+
+        let index = match images.iter().position(|(path, _)| *path == src) {
+            Some(index) => index,
+            None => {
+                images.push((src.clone(), load_image(&src, base_dir, images.len() + 1)?));
+                images.len() - 1
+            }
+        };
+        output.push_str(&format!("cid:{}", images[index].1.content_id));
+    }
+    output.push_str(rest);
+
+    Ok((output, images.into_iter().map(|(_, image)| image).collect()))
+}
+
+fn load_image(src: &str, base_dir: &Path, number: usize) -> Result<InlineImage, FormatError> {
+    let path = base_dir.join(src);
+    let data = fs::read(&path).map_err(|e| {
+        FormatError::SerializationError(format!(
+            "Cannot attach image '{src}' ({}): {e}",
+            path.display()
+        ))
+    })?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format!("image{number}"));
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    };
+
+    Ok(InlineImage {
+        content_id: format!("image{number}@lex"),
+        file_name,
+        mime_type,
+        data,
+    })
+}
+
+fn wrap_html(body_html: &str, title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"UTF-8\">\n<title>{}</title>\n</head>\n<body>\n<div class=\"lex-document\">\n{body_html}\n</div>\n</body>\n</html>\n",
+        html_escape(title)
+    )
+}
+
+/// Accumulates message lines with CRLF endings
+#[derive(Default)]
+struct MimeWriter {
+    out: String,
+}
+
+impl MimeWriter {
+    fn line(&mut self, line: &str) {
+        self.out.push_str(line);
+        self.out.push_str("\r\n");
+    }
+
+    fn text_part(&mut self, mime_type: &str, body: &str) {
+        self.line(&format!("Content-Type: {mime_type}; charset=utf-8"));
+        self.line("Content-Transfer-Encoding: quoted-printable");
+        self.line("");
+        // The encoded body ends in CRLF, which belongs to the next boundary
+        self.out.push_str(&quoted_printable(body));
+    }
+
+    fn image_part(&mut self, image: &InlineImage) {
+        let name = image.file_name.replace(['"', '\\'], "_");
+        self.line(&format!(
+            "Content-Type: {}; name=\"{name}\"",
+            image.mime_type
+        ));
+        self.line("Content-Transfer-Encoding: base64");
+        self.line(&format!("Content-ID: <{}>", image.content_id));
+        self.line(&format!("Content-Disposition: inline; filename=\"{name}\""));
+        self.line("");
+        let encoded = BASE64.encode(&image.data);
+        for chunk in encoded.as_bytes().chunks(MAX_LINE) {
+            // Base64 output is ASCII, so every chunk is valid UTF-8
+            self.line(std::str::from_utf8(chunk).unwrap_or_default());
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.out.into_bytes()
+    }
+}
+
+/// Quoted-printable encoding (RFC 2045 §6.7) with CRLF line breaks
+fn quoted_printable(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() + text.len() / 8);
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let mut current = String::new();
+        let bytes = line.as_bytes();
+
+        for (index, &byte) in bytes.iter().enumerate() {
+            let is_last = index + 1 == bytes.len();
+            let encoded = match byte {
+                // Trailing whitespace must be encoded so it survives transport
+                b' ' | b'\t' if is_last => format!("={byte:02X}"),
+                b'=' => "=3D".to_string(),
+                b' ' | b'\t' | 33..=126 => (byte as char).to_string(),
+                _ => format!("={byte:02X}"),
+            };
+            // Leave room for the soft line break `=`
+            if current.len() + encoded.len() > MAX_LINE - 1 {
+                output.push_str(&current);
+                output.push_str("=\r\n");
+                current.clear();
+            }
+            current.push_str(&encoded);
+        }
+        output.push_str(&current);
+        output.push_str("\r\n");
+    }
+    // `split` yields a final empty segment for text ending in a newline
+    if text.ends_with('\n') {
+        output.truncate(output.len() - 2);
+    }
+    output
+}
+
+/// RFC 2047 encoded-word for header text that is not plain ASCII
+fn encode_header_text(text: &str) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(text))
+    }
+}
+
+/// Encode the display names of a comma-separated address list
+fn encode_addresses(addresses: &str) -> String {
+    addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| match address.rsplit_once('<') {
+            Some((name, email)) if !name.trim().is_empty() => {
+                format!("{} <{email}", encode_header_text(name.trim()))
+            }
+            _ => address.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,97 @@
+//! Gemtext format implementation
+//!
+//! This module implements export from Lex to Gemtext, the line-oriented markup
+//! of the Gemini protocol (`text/gemini`).
+//!
+//! # Element Mapping Table
+//!
+//! | Lex Element      | Gemtext Equivalent       | Notes                                          |
+//! |------------------|--------------------------|------------------------------------------------|
+//! | Document title   | `# Title`                |                                                |
+//! | Session          | `##` / `###`             | Deeper sessions stay `###` with a `1.2` prefix |
+//! | Paragraph        | Text line                | Lines joined; clients wrap                     |
+//! | List / ListItem  | `* item`                 | Nested items flattened, ordered markers kept   |
+//! | Definition       | Heading + text lines     | Term one level below the enclosing session     |
+//! | Verbatim         | ```` ``` ```` toggles     | Subject (or language) as alt text              |
+//! | Table            | Preformatted text table  | Caption as alt text                            |
+//! | Image/Video/Audio| `=> src label`           |                                                |
+//! | Annotation       | Content only             | Labels and metadata are dropped                |
+//! | InlineContent:   |                          |                                                |
+//! |   Bold/Italic    | Plain text               | Gemtext has no inline markup                   |
+//! |   Reference      | `=> href anchor` line    | Collected after the block (URLs/paths only)    |
+//!
+//! Citations, session references and other references that point nowhere
+//! stay inline as `[text]`, each with a `reference-flattened` warning in the
+//! conversion report; footnote numbers stay inline too. Gemtext import is not
+//! supported.
+
+pub mod serializer;
+
+use crate::error::FormatError;
+use crate::format::{reject_options, Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::Document as IrDocument;
+use crate::report::{ConversionReport, IrLossCheck};
+use lex_core::lex::ast::Document;
+use std::collections::HashMap;
+
+/// Format implementation for Gemtext
+pub struct GemtextFormat;
+
+impl Format for GemtextFormat {
+    fn name(&self) -> &str {
+        "gemtext"
+    }
+
+    fn description(&self) -> &str {
+        "Gemini gemtext (text/gemini)"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["gmi", "gemini"]
+    }
+
+    fn supports_serialization(&self) -> bool {
+        true
+    }
+
+    fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
+        serializer::serialize_to_gemtext(doc)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        reject_options(self.name(), options)?;
+        serializer::serialize_ir_to_gemtext(ir, document_title(source))
+            .map(SerializedDocument::Text)
+    }
+
+    fn report_serialize_losses(&self, ir: &IrDocument, report: &mut ConversionReport) {
+        // Gemtext stops at `###`; deeper sessions are told apart by numbering,
+        // and only references that point somewhere become `=>` lines
+        IrLossCheck {
+            format: "Gemtext",
+            max_heading_level: Some(serializer::MAX_HEADING_LEVEL),
+            linkable: Some(serializer::is_link),
+        }
+        .run(ir, report);
+    }
+}
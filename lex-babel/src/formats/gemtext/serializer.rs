@@ -0,0 +1,440 @@
+//! Gemtext serialization (Lex → Gemtext export)
+//!
+//! Pipeline: Lex AST → IR → Gemtext lines
+//!
+//! Gemtext has no inline markup and no inline links, so blocks are rendered as
+//! plain lines and every link-like reference in a block is moved to a `=>` line
+//! directly after it. The anchor text for each link line comes from
+//! [`extract_anchor_for_reference`], the same rule HTML and Markdown use.
+
+use crate::common::links::extract_anchor_for_reference;
+use crate::common::text::strip_marker;
+use crate::error::FormatError;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{
+    Annotation, Definition, DocNode, Document as IrDocument, Heading, InlineContent, List, Table,
+    Verbatim,
+};
+use lex_core::lex::ast::Document;
+
+/// Deepest heading level Gemtext supports
+pub(crate) const MAX_HEADING_LEVEL: usize = 3;
+
+/// Serialize a Lex document to Gemtext
+pub fn serialize_to_gemtext(doc: &Document) -> Result<String, FormatError> {
+    serialize_ir_to_gemtext(&crate::to_ir(doc), document_title(doc))
+}
+
+/// Serialize an IR document to Gemtext under the given document title
+pub(crate) fn serialize_ir_to_gemtext(
+    ir_doc: &IrDocument,
+    title: &str,
+) -> Result<String, FormatError> {
+    let mut writer = GemtextWriter::default();
+
+    if !title.is_empty() {
+        writer.blocks.push(format!("# {}", title.trim()));
+    }
+
+    writer.write_nodes(&ir_doc.children, 1);
+
+    let mut output = writer.blocks.join("\n\n");
+    output.push('\n');
+    Ok(output)
+}
+
+#[derive(Default)]
+struct GemtextWriter {
+    blocks: Vec<String>,
+    /// Numbering for sessions below `MAX_HEADING_LEVEL`, relative to the
+    /// nearest `###` heading
+    deep_counters: Vec<usize>,
+}
+
+impl GemtextWriter {
+    fn write_nodes(&mut self, nodes: &[DocNode], session_level: usize) {
+        for node in nodes {
+            self.write_node(node, session_level);
+        }
+    }
+
+    fn write_node(&mut self, node: &DocNode, session_level: usize) {
+        match node {
+            DocNode::Heading(heading) => self.write_heading(heading),
+            DocNode::Paragraph(paragraph) => {
+                let mut links = Vec::new();
+                let text = render_inlines(&paragraph.content, &mut links);
+                self.push_block(join_lines(&text), &links);
+            }
+            DocNode::List(list) => {
+                let mut lines = Vec::new();
+                let mut links = Vec::new();
+                self.write_list_items(list, &mut lines, &mut links, session_level);
+                self.push_list(&mut lines, &mut links);
+            }
+            DocNode::ListItem(item) => {
+                let mut links = Vec::new();
+                let text = render_inlines(&item.content, &mut links);
+                self.push_block(format!("* {}", join_lines(text.trim())), &links);
+                self.write_nodes(&item.children, session_level);
+            }
+            DocNode::Definition(definition) => self.write_definition(definition, session_level),
+            DocNode::Verbatim(verbatim) => self.write_verbatim(verbatim),
+            DocNode::Annotation(annotation) => self.write_annotation(annotation, session_level),
+            DocNode::Table(table) => self.write_table(table),
+            DocNode::Image(image) => {
+                let label = if image.alt.is_empty() {
+                    image.title.clone().unwrap_or_default()
+                } else {
+                    image.alt.clone()
+                };
+                self.blocks.push(link_line(&image.src, &label));
+            }
+            DocNode::Video(video) => {
+                let label = video.title.clone().unwrap_or_default();
+                self.blocks.push(link_line(&video.src, &label));
+            }
+            DocNode::Audio(audio) => {
+                let label = audio.title.clone().unwrap_or_default();
+                self.blocks.push(link_line(&audio.src, &label));
+            }
+            DocNode::Document(doc) => self.write_nodes(&doc.children, session_level),
+            DocNode::Inline(inline) => {
+                let mut links = Vec::new();
+                let text = render_inlines(std::slice::from_ref(inline), &mut links);
+                self.push_block(text, &links);
+            }
+            DocNode::BlockQuote(quote) => {
+                let mut inner = GemtextWriter::default();
+                inner.write_nodes(&quote.children, session_level);
+                self.blocks
+                    .extend(inner.blocks.iter().map(|block| quote_block(block)));
+            }
+            // Gemtext has no rule; a dashed text line is the usual stand-in
+            DocNode::ThematicBreak => self.blocks.push("---".to_string()),
+        }
+    }
+
+    /// Sessions map to `#`..`###`; deeper sessions stay at `###` and get a
+    /// numbering prefix so their nesting is still readable.
+    fn write_heading(&mut self, heading: &Heading) {
+        // IR levels start at 2 for top-level sessions (1 is the document title)
+        let level = heading.level.max(1);
+        let mut links = Vec::new();
+        let text = join_lines(render_inlines(&heading.content, &mut links).trim());
+
+        let line = if level <= MAX_HEADING_LEVEL {
+            self.deep_counters.clear();
+            format!("{} {text}", "#".repeat(level))
+        } else {
+            let depth = level - MAX_HEADING_LEVEL;
+            self.deep_counters.resize(depth, 0);
+            if let Some(last) = self.deep_counters.last_mut() {
+                *last += 1;
+            }
+            let has_marker = matches!(heading.content.first(), Some(InlineContent::Marker(_)));
+            if has_marker {
+                format!("### {text}")
+            } else {
+                let number = self
+                    .deep_counters
+                    .iter()
+                    .map(|n| n.max(&1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                format!("### {number} {text}")
+            }
+        };
+
+        self.push_block(line, &links);
+        self.write_nodes(&heading.children, level);
+    }
+
+    /// Definitions become a heading-style term one level below the enclosing
+    /// session, followed by the description blocks.
+    fn write_definition(&mut self, definition: &Definition, session_level: usize) {
+        let level = (session_level + 1).min(MAX_HEADING_LEVEL);
+        let mut links = Vec::new();
+        let term = render_inlines(&definition.term, &mut links);
+        let term = join_lines(term.trim().trim_end_matches(':'));
+        self.push_block(format!("{} {term}", "#".repeat(level)), &links);
+        self.write_nodes(&definition.description, session_level);
+    }
+
+    fn write_verbatim(&mut self, verbatim: &Verbatim) {
+        self.blocks
+            .push(preformatted(verbatim_alt(verbatim), &verbatim.content));
+    }
+
+    /// Flatten a list (and any nested lists) into `*` lines
+    ///
+    /// Gemtext has a single list level, so nested items follow their parent in
+    /// order. Ordered lists keep their marker after the bullet. Further
+    /// paragraphs of an item are plain lines and verbatim children are
+    /// preformatted text below it; other blocks end the list block.
+    fn write_list_items(
+        &mut self,
+        list: &List,
+        lines: &mut Vec<String>,
+        links: &mut Vec<(String, String)>,
+        session_level: usize,
+    ) {
+        for item in &list.items {
+            let content = if list.ordered {
+                item.content.as_slice()
+            } else {
+                strip_marker(&item.content)
+            };
+            let text = render_inlines(content, links);
+            let task = match item.checked {
+                Some(true) => "[x] ",
+                Some(false) => "[ ] ",
+                None => "",
+            };
+            lines.push(format!("* {task}{}", join_lines(text.trim())));
+
+            for child in &item.children {
+                match child {
+                    DocNode::List(nested) => {
+                        self.write_list_items(nested, lines, links, session_level)
+                    }
+                    DocNode::Paragraph(paragraph) => {
+                        let text = render_inlines(&paragraph.content, links);
+                        lines.push(join_lines(text.trim()));
+                    }
+                    DocNode::Verbatim(verbatim) => {
+                        lines.push(preformatted(verbatim_alt(verbatim), &verbatim.content));
+                    }
+                    _ => {
+                        self.push_list(lines, links);
+                        self.write_node(child, session_level);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Push the list lines written so far as a block
+    fn push_list(&mut self, lines: &mut Vec<String>, links: &mut Vec<(String, String)>) {
+        if !lines.is_empty() {
+            self.push_block(std::mem::take(lines).join("\n"), &std::mem::take(links));
+        }
+    }
+
+    /// Annotations keep their content so notes and warnings are not silently
+    /// lost.
+    fn write_annotation(&mut self, annotation: &Annotation, session_level: usize) {
+        self.write_nodes(&annotation.content, session_level);
+    }
+
+    /// Tables have no Gemtext equivalent, so they are laid out as aligned text
+    /// inside a preformatted block.
+    fn write_table(&mut self, table: &Table) {
+        let mut links = Vec::new();
+        let rows: Vec<Vec<String>> = table
+            .header
+            .iter()
+            .chain(table.rows.iter())
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| {
+                        let text: Vec<String> = cell
+                            .content
+                            .iter()
+                            .map(|node| match node {
+                                DocNode::Paragraph(p) => render_inlines(&p.content, &mut links),
+                                DocNode::Inline(inline) => {
+                                    render_inlines(std::slice::from_ref(inline), &mut links)
+                                }
+                                _ => String::new(),
+                            })
+                            .collect();
+                        join_lines(text.join(" ").trim())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                rows.iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(col, width)| {
+                    let cell = row.get(col).map(String::as_str).unwrap_or("");
+                    format!("{cell:<width$}")
+                })
+                .collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index + 1 == table.header.len() {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                lines.push(format!("|-{}-|", rule.join("-|-")));
+            }
+        }
+
+        let alt = table
+            .caption
+            .as_ref()
+            .map(|caption| render_inlines(caption, &mut links))
+            .unwrap_or_default();
+        let block = preformatted(alt.trim(), &lines.join("\n"));
+        self.push_block(block, &links);
+    }
+
+    /// Push a block followed by its link lines
+    fn push_block(&mut self, text: String, links: &[(String, String)]) {
+        let mut block = text;
+        for (href, label) in links {
+            block.push('\n');
+            block.push_str(&link_line(href, label));
+        }
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+        }
+    }
+}
+
+/// Prefix the text lines of a block with `> `; link lines and preformatted
+/// text cannot be quoted and are left as they are
+fn quote_block(block: &str) -> String {
+    let mut in_preformatted = false;
+    let lines: Vec<String> = block
+        .lines()
+        .map(|line| {
+            if line.starts_with("```") {
+                in_preformatted = !in_preformatted;
+                line.to_string()
+            } else if in_preformatted || line.starts_with("=>") {
+                line.to_string()
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Render inline content as plain text, collecting link-like references
+///
+/// References that point somewhere (URLs, paths, domains) are removed from the
+/// text and returned as `(href, label)` pairs; the anchor word stays in the
+/// prose. Citations, footnotes and other references are kept inline as
+/// `[text]`.
+fn render_inlines(content: &[InlineContent], links: &mut Vec<(String, String)>) -> String {
+    let mut text = String::new();
+    for (index, inline) in content.iter().enumerate() {
+        match inline {
+            InlineContent::Text(t) => text.push_str(t),
+            InlineContent::Bold(children) | InlineContent::Italic(children) => {
+                text.push_str(&render_inlines(children, links));
+            }
+            InlineContent::Code(code) => text.push_str(code),
+            InlineContent::Math(math) => {
+                text.push('$');
+                text.push_str(math);
+                text.push('$');
+            }
+            InlineContent::Marker(marker) => text.push_str(marker),
+            InlineContent::Reference(reference) if is_link(reference) => {
+                if let Some((anchor, href, _)) = extract_anchor_for_reference(content, index) {
+                    links.push((href, anchor));
+                }
+                // Drop the space left between the anchor word and the reference
+                let trimmed = text.trim_end_matches(' ').len();
+                text.truncate(trimmed);
+            }
+            InlineContent::Reference(reference) => {
+                text.push('[');
+                text.push_str(reference);
+                text.push(']');
+            }
+            InlineContent::Image(image) => {
+                text.push_str(&image.alt);
+                links.push((image.src.clone(), image.alt.clone()));
+            }
+            InlineContent::Strikethrough(children) => {
+                text.push_str(&render_inlines(children, links));
+            }
+            InlineContent::Link(link) => {
+                let label = render_inlines(&link.content, links);
+                text.push_str(&label);
+                links.push((link.url.clone(), label));
+            }
+            InlineContent::FootnoteRef(label) => {
+                text.push('[');
+                text.push_str(label);
+                text.push(']');
+            }
+        }
+    }
+    text
+}
+
+/// Whether a reference points to a resource that can become a `=>` line
+pub(crate) fn is_link(reference: &str) -> bool {
+    let reference = reference.trim();
+    if reference.contains(char::is_whitespace) {
+        return false;
+    }
+    if reference.contains("://")
+        || reference.starts_with("mailto:")
+        || reference.starts_with('/')
+        || reference.starts_with("./")
+        || reference.starts_with("../")
+    {
+        return true;
+    }
+    // Bare domains such as `example.org`; excludes citations, footnote
+    // numbers and session references like `@key`, `1`, `#2.1`
+    !reference.starts_with(['@', '#', '^'])
+        && reference.contains('.')
+        && reference.chars().any(char::is_alphabetic)
+}
+
+/// Gemtext paragraphs are single lines; clients do the wrapping
+fn join_lines(text: &str) -> String {
+    text.split('\n')
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn link_line(href: &str, label: &str) -> String {
+    let label = label.trim();
+    if label.is_empty() || label == href {
+        format!("=> {href}")
+    } else {
+        format!("=> {href} {label}")
+    }
+}
+
+/// Alt text of a preformatted block: the verbatim subject, else its language
+fn verbatim_alt(verbatim: &Verbatim) -> &str {
+    verbatim
+        .subject
+        .as_deref()
+        .map(|subject| subject.trim().trim_end_matches(':'))
+        .filter(|subject| !subject.is_empty())
+        .or(verbatim.language.as_deref())
+        .unwrap_or("")
+}
+
+fn preformatted(alt: &str, content: &str) -> String {
+    let mut block = format!("```{alt}\n");
+    block.push_str(content.trim_matches('\n'));
+    block.push_str("\n```");
+    block
+}
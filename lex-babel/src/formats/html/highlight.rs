@@ -0,0 +1,249 @@
+//! Syntax highlighting for verbatim blocks.
+//!
+//! With the `highlight` feature (on by default), code blocks are highlighted
+//! while exporting, by [syntect] with its bundled syntaxes and themes. Every
+//! token becomes a `<span>` with an inline colour and the `<pre>` takes the
+//! theme's background, so the page needs no script or stylesheet to show them
+//! and PDF and PNG exports print them as they appear on screen. Languages
+//! syntect does not know are shown as plain text in the theme's colours.
+//!
+//! Without the feature, or with `code-theme=none`, pages load highlight.js
+//! from a CDN instead and highlight in the browser.
+//!
+//! [syntect]: https://docs.rs/syntect
+
+use markup5ever_rcdom::Handle;
+
+/// Colour schemes for highlighted code: syntect's bundled themes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeTheme {
+    /// Light, after GitHub (`InspiredGitHub`)
+    GitHub,
+    SolarizedLight,
+    SolarizedDark,
+    /// Base16 Ocean, light variant
+    OceanLight,
+    /// Base16 Ocean, dark variant
+    OceanDark,
+    /// Base16 Eighties (dark)
+    Eighties,
+    /// Base16 Mocha (dark)
+    Mocha,
+}
+
+/// Values accepted by the `code-theme` option
+pub(super) const CODE_THEMES: &[&str] = &[
+    "github",
+    "solarized-light",
+    "solarized-dark",
+    "ocean-light",
+    "ocean-dark",
+    "eighties",
+    "mocha",
+    "none",
+];
+
+impl CodeTheme {
+    /// The theme called `name` in the `code-theme` option
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "github" => Some(CodeTheme::GitHub),
+            "solarized-light" => Some(CodeTheme::SolarizedLight),
+            "solarized-dark" => Some(CodeTheme::SolarizedDark),
+            "ocean-light" => Some(CodeTheme::OceanLight),
+            "ocean-dark" => Some(CodeTheme::OceanDark),
+            "eighties" => Some(CodeTheme::Eighties),
+            "mocha" => Some(CodeTheme::Mocha),
+            _ => None,
+        }
+    }
+
+    /// Name of the theme in syntect's default theme set
+    #[cfg_attr(not(feature = "highlight"), allow(dead_code))]
+    fn syntect_name(self) -> &'static str {
+        match self {
+            CodeTheme::GitHub => "InspiredGitHub",
+            CodeTheme::SolarizedLight => "Solarized (light)",
+            CodeTheme::SolarizedDark => "Solarized (dark)",
+            CodeTheme::OceanLight => "base16-ocean.light",
+            CodeTheme::OceanDark => "base16-ocean.dark",
+            CodeTheme::Eighties => "base16-eighties.dark",
+            CodeTheme::Mocha => "base16-mocha.dark",
+        }
+    }
+}
+
+/// Whether code is highlighted at export time with `theme`
+///
+/// When it is not, the page falls back to highlight.js.
+pub(super) fn highlights(theme: Option<CodeTheme>) -> bool {
+    cfg!(feature = "highlight") && theme.is_some()
+}
+
+/// Highlight every `<pre class="lex-verbatim" data-language>` under `node`
+#[cfg(not(feature = "highlight"))]
+pub(super) fn highlight_dom(_node: &Handle, _theme: Option<CodeTheme>) {}
+
+/// Highlight every `<pre class="lex-verbatim" data-language>` under `node`
+#[cfg(feature = "highlight")]
+pub(super) fn highlight_dom(node: &Handle, theme: Option<CodeTheme>) {
+    if let Some(theme) = theme {
+        engine::highlight_dom(node, theme);
+    }
+}
+
+#[cfg(feature = "highlight")]
+mod engine {
+    use super::super::serializer::{create_element, create_text};
+    use super::CodeTheme;
+    use html5ever::{ns, Attribute, LocalName, QualName};
+    use markup5ever_rcdom::{Handle, NodeData};
+    use std::sync::OnceLock;
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{Color, FontStyle, Style, ThemeSet};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    fn syntaxes() -> &'static SyntaxSet {
+        static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+    }
+
+    fn themes() -> &'static ThemeSet {
+        static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+        THEMES.get_or_init(ThemeSet::load_defaults)
+    }
+
+    pub(super) fn highlight_dom(node: &Handle, theme: CodeTheme) {
+        if let Some(language) = verbatim_language(node) {
+            let code = node
+                .children
+                .borrow()
+                .iter()
+                .find(|child| element_name(child) == Some("code"))
+                .cloned();
+            if let Some(code) = code {
+                highlight_block(node, &code, &language, theme);
+            }
+            return;
+        }
+        for child in node.children.borrow().iter() {
+            highlight_dom(child, theme);
+        }
+    }
+
+    /// Replace the text of `code` with highlighted spans and colour `pre`
+    fn highlight_block(pre: &Handle, code: &Handle, language: &str, theme: CodeTheme) {
+        let source: String = code
+            .children
+            .borrow()
+            .iter()
+            .filter_map(|child| match &child.data {
+                NodeData::Text { contents } => Some(contents.borrow().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let syntaxes = syntaxes();
+        let syntax = syntaxes
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+        let Some(theme) = themes().themes.get(theme.syntect_name()) else {
+            return;
+        };
+        let background = theme.settings.background.unwrap_or(Color::WHITE);
+
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut nodes = Vec::new();
+        for line in LinesWithEndings::from(&source) {
+            let Ok(ranges) = highlighter.highlight_line(line, syntaxes) else {
+                // Leave the block as it was rather than half highlighted
+                return;
+            };
+            for (style, text) in ranges {
+                let span = create_element("span", vec![("style", &span_css(style))]);
+                span.children.borrow_mut().push(create_text(text));
+                nodes.push(span);
+            }
+        }
+
+        *code.children.borrow_mut() = nodes;
+        // Chrome leaves backgrounds out of PDFs unless told to keep them
+        set_attr(
+            pre,
+            "style",
+            &format!(
+                "background:{};-webkit-print-color-adjust:exact;print-color-adjust:exact",
+                hex(background)
+            ),
+        );
+    }
+
+    /// Inline CSS for a token
+    ///
+    /// Every token names its colour, since the stylesheet gives code a colour
+    /// of its own that may not suit the theme's background.
+    fn span_css(style: Style) -> String {
+        let mut css = vec![format!("color:{}", hex(style.foreground))];
+        if style.font_style.contains(FontStyle::BOLD) {
+            css.push("font-weight:bold".to_string());
+        }
+        if style.font_style.contains(FontStyle::ITALIC) {
+            css.push("font-style:italic".to_string());
+        }
+        if style.font_style.contains(FontStyle::UNDERLINE) {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+
+    fn hex(color: Color) -> String {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    }
+
+    fn element_name(node: &Handle) -> Option<&str> {
+        match &node.data {
+            NodeData::Element { name, .. } => Some(&*name.local),
+            _ => None,
+        }
+    }
+
+    fn attr(node: &Handle, key: &str) -> Option<String> {
+        match &node.data {
+            NodeData::Element { attrs, .. } => attrs
+                .borrow()
+                .iter()
+                .find(|attr| &*attr.name.local == key)
+                .map(|attr| attr.value.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_attr(node: &Handle, key: &str, value: &str) {
+        if let NodeData::Element { attrs, .. } = &node.data {
+            let mut attrs = attrs.borrow_mut();
+            match attrs.iter_mut().find(|attr| &*attr.name.local == key) {
+                Some(attr) => attr.value = value.to_string().into(),
+                None => attrs.push(Attribute {
+                    name: QualName::new(None, ns!(), LocalName::from(key)),
+                    value: value.to_string().into(),
+                }),
+            }
+        }
+    }
+
+    /// The `data-language` of a verbatim `<pre>`
+    fn verbatim_language(node: &Handle) -> Option<String> {
+        if element_name(node) != Some("pre") {
+            return None;
+        }
+        let class = attr(node, "class")?;
+        if !class
+            .split_whitespace()
+            .any(|class| class == "lex-verbatim")
+        {
+            return None;
+        }
+        attr(node, "data-language")
+    }
+}
@@ -0,0 +1,91 @@
+//! TeX math as MathML.
+//!
+//! Inline math (`#x^2#`) and `doc.math` verbatim blocks are converted from
+//! TeX to MathML by [latex2mathml] while exporting, and the MathML is part of
+//! the page: browsers render it natively, so neither the page nor the PDF and
+//! PNG exports need a script or network access to show it.
+//!
+//! ```text
+//! Euler's identity:
+//!     e^{i\pi} + 1 = 0
+//! :: doc.math ::
+//! ```
+//!
+//! TeX the converter does not understand is kept as written (inline math
+//! between `$` signs, display math as a code block), and
+//! [`crate::FormatRegistry::serialize_with_report`] warns about it.
+//!
+//! [latex2mathml]: https://docs.rs/latex2mathml
+
+use super::serializer::create_foreign;
+use crate::ir::nodes::{DocNode, Document as IrDocument, InlineContent, SourceSpan};
+use crate::ir::visit::{walk_document, walk_inline, walk_node, Visitor};
+use crate::report::{ConversionReport, ConversionWarning, WarningKind};
+use html5ever::ns;
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use markup5ever_rcdom::Handle;
+
+/// Label of verbatim blocks holding display math
+pub(super) const MATH_LABEL: &str = "doc.math";
+
+/// Convert `tex` to a `<math>` element, or say why it cannot be
+///
+/// `display` renders it as a block of its own rather than within a line.
+pub(super) fn mathml(tex: &str, display: bool) -> Result<Handle, String> {
+    let style = if display {
+        DisplayStyle::Block
+    } else {
+        DisplayStyle::Inline
+    };
+    let markup = latex_to_mathml(tex.trim(), style).map_err(|err| err.to_string())?;
+    create_foreign(&markup, ns!(mathml))
+}
+
+/// Warn about math that stays TeX because it does not convert
+pub(crate) fn report_math_fallbacks(ir: &IrDocument, report: &mut ConversionReport) {
+    let mut scanner = MathScanner { span: None, report };
+    walk_document(&mut scanner, ir);
+}
+
+struct MathScanner<'r> {
+    /// Span of the innermost enclosing node that has one
+    span: Option<SourceSpan>,
+    report: &'r mut ConversionReport,
+}
+
+impl MathScanner<'_> {
+    fn check(&mut self, tex: &str, display: bool) {
+        if let Err(err) = mathml(tex, display) {
+            self.report.push(
+                ConversionWarning::new(
+                    WarningKind::MathFallback,
+                    format!("Math '{}' is kept as TeX: {err}", tex.trim()),
+                )
+                .with_span(self.span.clone()),
+            );
+        }
+    }
+}
+
+impl Visitor for MathScanner<'_> {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+        if let DocNode::Verbatim(verbatim) = node {
+            if verbatim.language.as_deref() == Some(MATH_LABEL) {
+                self.check(&verbatim.content, true);
+            }
+        }
+        walk_node(self, node);
+        self.span = outer;
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        if let InlineContent::Math(tex) = inline {
+            self.check(tex, false);
+        }
+        walk_inline(self, inline);
+    }
+}
@@ -23,20 +23,26 @@
 //! | Lex Element      | HTML Equivalent                                    | Export Notes                              | Import Notes                          |
 //! |------------------|----------------------------------------------------|-------------------------------------------|---------------------------------------|
 //! | Document         | `<div class="lex-document">`                       | Root container with document class        | Parse body content                    |
-//! | Session          | `<section class="lex-session lex-session-N">` + `<hN>` | Session → section + heading        | section + heading → Session           |
+//! | Session          | `<section class="lex-session lex-session-N" id>` + `<hN>` | Session → section + heading; id from the title | section + heading → Session |
 //! | Paragraph        | `<p class="lex-paragraph">`                        | Direct mapping with class                 | Direct mapping                        |
 //! | List             | `<ul>`/`<ol>` with `class="lex-list"`              | Ordered/unordered preserved with class    | Detect ul/ol type                     |
 //! | ListItem         | `<li class="lex-list-item">`                       | Direct mapping with class                 | Direct mapping                        |
 //! | Definition       | `<dl class="lex-definition">` `<dt>` `<dd>`        | Term in dt, description in dd             | Parse dl/dt/dd structure              |
-//! | Verbatim         | `<pre class="lex-verbatim">` `<code>`              | Language → data-language attribute        | Extract language from attribute       |
+//! | Verbatim         | `<pre class="lex-verbatim">` `<code>`              | Language → data-language; highlighted spans | Extract language from attribute     |
 //! | Annotation       | `<!-- lex:label key=val -->`                       | HTML comment format                       | Parse HTML comment pattern            |
+//! | `:: toc ::`      | `<nav class="lex-toc">` + nested `<ul>`            | Links to the section ids; `toc` option adds one | Not implemented                 |
+//! | `doc.dot`        | `<figure class="lex-diagram">` `<svg>`             | DOT → SVG laid out at export; code block if it fails | Not implemented            |
+//! | `doc.math`       | `<div class="lex-math-display">` `<math>`          | TeX → display MathML; code block if it fails | Not implemented                    |
+//! | Table            | `<table class="lex-table">` `<caption>` `<th>`/`<td>` | Spans → `colspan`/`rowspan`            | Not implemented                       |
 //! | InlineContent:   |                                                    |                                           |                                       |
 //! |   Text           | Plain text                                         | Direct                                    | Direct                                |
 //! |   Bold           | `<strong>`                                         | Semantic strong tag                       | Parse both strong and b               |
 //! |   Italic         | `<em>`                                             | Semantic emphasis tag                     | Parse both em and i                   |
 //! |   Code           | `<code>`                                           | Inline code tag                           | Direct                                |
-//! |   Math           | `<span class="lex-math">`                          | Preserve $ delimiters in span             | Parse math span                       |
+//! |   Math           | `<span class="lex-math">` `<math>`                 | TeX → MathML; $ delimiters if it fails    | Parse math span                       |
 //! |   Reference      | `<a href="url">text</a>`                           | Convert to anchor with prev word as text  | Parse anchor back to reference        |
+//! |   Session ref    | `<a href="#id">`                                   | `[#2.1]`/`[Title]` → the section's id      | Not implemented                       |
+//! |   Footnote ref   | `<sup><a href="#fn-N">`                            | Notes session → `<section class="lex-footnotes">` with back-links | Not implemented |
 //!
 //! # CSS Classes
 //!
@@ -49,6 +55,8 @@
 //! - `.lex-definition`: Definition lists
 //! - `.lex-verbatim`: Verbatim/code blocks
 //! - `.lex-math`: Math expressions
+//! - `.lex-math-display`: Display math (`doc.math` blocks)
+//! - `.lex-diagram`: Rendered DOT graphs (`doc.dot` blocks)
 //!
 //! This enables:
 //! - Precise CSS targeting for presentation
@@ -75,6 +83,39 @@
 //! - No external dependencies (except optionally-linked fonts)
 //! - Mobile-responsive viewport meta tag
 //!
+//! # Syntax Highlighting
+//!
+//! Code blocks with a language are highlighted during export (the `highlight`
+//! feature), with inline colours that survive PDF and PNG rendering. The
+//! `code-theme` option picks the colours; each [`HtmlTheme`] has its own
+//! default; `code-theme=none` leaves highlighting to highlight.js in the
+//! browser, as do builds without the feature.
+//!
+//! # Math
+//!
+//! Inline math and `doc.math` blocks are TeX, and export turns them into
+//! MathML that browsers render without scripts, PDF and PNG included. TeX
+//! that does not convert is kept as written, with a warning in the
+//! conversion report.
+//!
+//! # Diagrams
+//!
+//! `doc.dot` blocks are Graphviz graphs, laid out at export and embedded as
+//! inline SVG (see [`crate::common::verbatim::dot`]).
+//!
+//! # Source Lines
+//!
+//! With the `source-lines=true` option, every block element that came from a
+//! Lex element carries its 1-based source line as `data-lex-line`, so a
+//! preview can scroll to the element under the editor's cursor.
+//!
+//! # Table of Contents
+//!
+//! A `:: toc ::` placeholder becomes a `<nav class="lex-toc">` holding a
+//! nested list of links to the sessions. With `toc=true` one is inserted at
+//! the top of documents without a placeholder; `toc-depth` and `toc-numbered`
+//! shape it (see [`crate::ir::toc`]).
+//!
 //! # Lossy Conversions
 //!
 //! The following conversions may lose information on round-trip:
@@ -108,14 +149,26 @@
 //! - [ ] Import (HTML → Lex)
 //!   - [ ] All elements (to be implemented after export)
 
+mod highlight;
+mod math;
 mod serializer;
 
+use crate::common::verbatim::dot::report_dot_fallbacks;
 use crate::error::FormatError;
 use crate::format::Format;
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::toc::{TocOptions, TOC_OPTIONS};
+use crate::options::{bool_option, FormatOption, OptionType};
+use crate::report::{ConversionReport, IrLossCheck};
+use highlight::CODE_THEMES;
 use lex_core::lex::ast::Document;
 use std::fs;
 
+pub use highlight::CodeTheme;
+pub(crate) use math::report_math_fallbacks;
 pub use serializer::HtmlOptions;
+pub(crate) use serializer::{serialize_body_html, serialize_events_html};
 
 /// Returns the default baseline CSS used for HTML export.
 ///
@@ -141,6 +194,16 @@ pub enum HtmlTheme {
     Modern,
 }
 
+impl HtmlTheme {
+    /// The colours code is highlighted in unless `code-theme` says otherwise
+    pub fn code_theme(self) -> CodeTheme {
+        match self {
+            HtmlTheme::FancySerif => CodeTheme::SolarizedLight,
+            HtmlTheme::Modern => CodeTheme::GitHub,
+        }
+    }
+}
+
 impl Default for HtmlFormat {
     fn default() -> Self {
         Self::new(HtmlTheme::Modern)
@@ -162,8 +225,114 @@ impl HtmlFormat {
     pub fn with_modern() -> Self {
         Self::new(HtmlTheme::Modern)
     }
+
+    /// Build serializer options from the format's theme and extra parameters
+    fn html_options(
+        &self,
+        options: &std::collections::HashMap<String, String>,
+    ) -> Result<HtmlOptions, FormatError> {
+        let mut theme = self.theme;
+        if let Some(theme_str) = options.get("theme") {
+            theme = match theme_str.as_str() {
+                "fancy-serif" => HtmlTheme::FancySerif,
+                "modern" | "default" => HtmlTheme::Modern,
+                other => {
+                    return Err(FormatError::NotSupported(format!(
+                        "Unknown HTML theme '{other}' (available: {})",
+                        THEMES.join(", ")
+                    )))
+                }
+            };
+        }
+
+        let mut html_options = HtmlOptions::new(theme);
+
+        if let Some(name) = options.get("code-theme") {
+            html_options = html_options.with_code_theme(parse_code_theme(name)?);
+        }
+
+        // Handle custom CSS option (expects CSS content, not path)
+        if let Some(css_content) = options.get("custom_css") {
+            html_options = html_options.with_custom_css(css_content.clone());
+        } else if let Some(css_path) = options.get("css-path").or_else(|| options.get("css_path")) {
+            let css = fs::read_to_string(css_path).map_err(|err| {
+                FormatError::SerializationError(format!(
+                    "Failed to read CSS at '{}': {}",
+                    css_path, err
+                ))
+            })?;
+            html_options = html_options.with_custom_css(css);
+        }
+
+        let key = if options.contains_key("source-lines") {
+            "source-lines"
+        } else {
+            "source_lines"
+        };
+        if let Some(source_lines) = bool_option(self.name(), options, key)? {
+            html_options = html_options.with_source_lines(source_lines);
+        }
+
+        if let Some(toc) = TocOptions::from_format_options(self.name(), options)? {
+            html_options = html_options.with_toc(toc);
+        }
+
+        Ok(html_options)
+    }
+}
+
+/// Read a `code-theme` value: a theme, or `None` for `none`
+pub(crate) fn parse_code_theme(name: &str) -> Result<Option<CodeTheme>, FormatError> {
+    match name {
+        "none" => Ok(None),
+        other => CodeTheme::from_name(other).map(Some).ok_or_else(|| {
+            FormatError::NotSupported(format!(
+                "Unknown code theme '{other}' (available: {})",
+                CODE_THEMES.join(", ")
+            ))
+        }),
+    }
 }
 
+/// The `code-theme` option, shared with the formats that render HTML
+pub(crate) const CODE_THEME_OPTION: FormatOption = FormatOption::new(
+    "code-theme",
+    OptionType::Choice,
+    "Colours for highlighted code (github, or solarized-light with fancy-serif); none leaves it to highlight.js",
+)
+.with_allowed_values(CODE_THEMES);
+
+/// Values accepted by the `theme` option
+const THEMES: &[&str] = &["modern", "default", "fancy-serif"];
+
+const HTML_OPTIONS: &[FormatOption] = &[
+    FormatOption::new("theme", OptionType::Choice, "Stylesheet to embed")
+        .with_default("modern")
+        .with_allowed_values(THEMES),
+    CODE_THEME_OPTION,
+    FormatOption::new(
+        "css-path",
+        OptionType::Path,
+        "CSS file to embed after the theme",
+    )
+    .with_aliases(&["css_path"]),
+    FormatOption::new(
+        "custom_css",
+        OptionType::String,
+        "CSS text to embed after the theme (takes precedence over css-path)",
+    ),
+    FormatOption::new(
+        "source-lines",
+        OptionType::Bool,
+        "Mark blocks with the Lex line they came from (data-lex-line)",
+    )
+    .with_default("false")
+    .with_aliases(&["source_lines"]),
+    TOC_OPTIONS[0],
+    TOC_OPTIONS[1],
+    TOC_OPTIONS[2],
+];
+
 impl Format for HtmlFormat {
     fn name(&self) -> &str {
         "html"
@@ -181,6 +350,10 @@ impl Format for HtmlFormat {
         false // Implement after export is working
     }
 
+    fn options(&self) -> &[FormatOption] {
+        HTML_OPTIONS
+    }
+
     fn supports_serialization(&self) -> bool {
         true
     }
@@ -200,37 +373,50 @@ impl Format for HtmlFormat {
         doc: &Document,
         options: &std::collections::HashMap<String, String>,
     ) -> Result<crate::format::SerializedDocument, FormatError> {
-        let mut theme = self.theme;
-        if let Some(theme_str) = options.get("theme") {
-            theme = match theme_str.as_str() {
-                "fancy-serif" => HtmlTheme::FancySerif,
-                "modern" | "default" => HtmlTheme::Modern,
-                _ => {
-                    // Fallback to default for unknown themes, or could error.
-                    // For now, let's fallback to Modern to be safe.
-                    HtmlTheme::Modern
-                }
-            };
-        }
+        let html_options = self.html_options(options)?;
+        serializer::serialize_to_html_with_options(doc, html_options)
+            .map(crate::format::SerializedDocument::Text)
+    }
 
-        let mut html_options = HtmlOptions::new(theme);
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &std::collections::HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<crate::format::SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
 
-        // Handle custom CSS option (expects CSS content, not path)
-        if let Some(css_content) = options.get("custom_css") {
-            html_options = html_options.with_custom_css(css_content.clone());
-        } else if let Some(css_path) = options.get("css-path").or_else(|| options.get("css_path")) {
-            let css = fs::read_to_string(css_path).map_err(|err| {
-                FormatError::SerializationError(format!(
-                    "Failed to read CSS at '{}': {}",
-                    css_path, err
-                ))
-            })?;
-            html_options = html_options.with_custom_css(css);
-        }
+    fn serializes_ir(&self) -> bool {
+        true
+    }
 
-        serializer::serialize_to_html_with_options(doc, html_options)
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &crate::ir::nodes::Document,
+        options: &std::collections::HashMap<String, String>,
+    ) -> Result<crate::format::SerializedDocument, FormatError> {
+        let html_options = self.html_options(options)?;
+        serializer::serialize_ir_to_html(ir.clone(), document_title(source), html_options)
             .map(crate::format::SerializedDocument::Text)
     }
+
+    fn report_serialize_losses(
+        &self,
+        ir: &crate::ir::nodes::Document,
+        report: &mut ConversionReport,
+    ) {
+        // Deeper sessions keep their level only as a `lex-level-N` class
+        IrLossCheck {
+            format: "HTML",
+            max_heading_level: Some(6),
+            linkable: None,
+        }
+        .run(ir, report);
+        report_math_fallbacks(ir, report);
+        report_dot_fallbacks(ir, report);
+    }
 }
 
 #[cfg(test)]
@@ -4,34 +4,61 @@
 //! Pipeline: Lex AST → IR → Events → RcDom → HTML string
 
 use crate::common::nested_to_flat::tree_to_events;
+use crate::common::verbatim::dot::{render_dot, DOT_LABELS};
 use crate::error::FormatError;
+use crate::formats::html::highlight::{highlight_dom, highlights, CodeTheme};
+use crate::formats::html::math::{mathml, MATH_LABEL};
 use crate::formats::html::HtmlTheme;
 use crate::ir::events::Event;
-use crate::ir::nodes::{DocNode, InlineContent, TableCellAlignment};
+use crate::ir::footnotes::{take_notes, Footnote};
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{
+    DocNode, Document as IrDocument, InlineContent, Metadata, TableCellAlignment,
+};
+use crate::ir::references::resolve_references;
+use crate::ir::toc::{fill_toc, TocOptions, TOC_LABEL};
 use html5ever::{
     ns, serialize, serialize::SerializeOpts, serialize::TraversalScope, Attribute, LocalName,
-    QualName,
+    Namespace, QualName,
 };
 use lex_core::lex::ast::Document;
 use markup5ever_rcdom::{Handle, Node, NodeData, RcDom, SerializableHandle};
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::default::Default;
 use std::rc::Rc;
 
 /// Options for HTML serialization
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct HtmlOptions {
     /// CSS theme to use
     pub theme: HtmlTheme,
     /// Optional custom CSS to append after the baseline and theme CSS
     pub custom_css: Option<String>,
+    /// Tag block elements with the Lex line they came from (`data-lex-line`)
+    pub source_lines: bool,
+    /// Insert a table of contents when the document has no `:: toc ::`
+    /// placeholder, and fill placeholders with these options
+    pub toc: Option<TocOptions>,
+    /// Colours for highlighted code, `None` to leave it to highlight.js
+    pub code_theme: Option<CodeTheme>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self::new(HtmlTheme::default())
+    }
 }
 
 impl HtmlOptions {
+    /// Options for `theme`, with code highlighted in the theme's colours
     pub fn new(theme: HtmlTheme) -> Self {
         Self {
             theme,
             custom_css: None,
+            source_lines: false,
+            toc: None,
+            code_theme: Some(theme.code_theme()),
         }
     }
 
@@ -39,6 +66,21 @@ impl HtmlOptions {
         self.custom_css = Some(css);
         self
     }
+
+    pub fn with_source_lines(mut self, source_lines: bool) -> Self {
+        self.source_lines = source_lines;
+        self
+    }
+
+    pub fn with_toc(mut self, toc: TocOptions) -> Self {
+        self.toc = Some(toc);
+        self
+    }
+
+    pub fn with_code_theme(mut self, code_theme: Option<CodeTheme>) -> Self {
+        self.code_theme = code_theme;
+        self
+    }
 }
 
 /// Serialize a Lex document to HTML with the given theme
@@ -51,34 +93,93 @@ pub fn serialize_to_html_with_options(
     doc: &Document,
     options: HtmlOptions,
 ) -> Result<String, FormatError> {
-    // Extract document title from root session (before IR conversion loses it)
-    let title = doc.root.title.as_string();
-    let title = if title.is_empty() {
-        "Lex Document".to_string()
-    } else {
+    // Step 1: Lex AST → IR
+    let ir_doc = crate::to_ir(doc);
+
+    // The root session title is lost in IR conversion, so pass it alongside
+    serialize_ir_to_html(ir_doc, document_title(doc), options)
+}
+
+/// Serialize an IR document to HTML, titled `document_title` when that is
+/// non-empty and by the metadata title otherwise
+pub(crate) fn serialize_ir_to_html(
+    ir_doc: IrDocument,
+    document_title: &str,
+    options: HtmlOptions,
+) -> Result<String, FormatError> {
+    let metadata = ir_doc.metadata.clone();
+
+    // Document title from root session, then metadata
+    let title = document_title;
+    let title = if !title.is_empty() {
         title.to_string()
+    } else {
+        metadata
+            .title()
+            .unwrap_or_else(|| "Lex Document".to_string())
     };
 
-    // Step 1: Lex AST → IR
-    let ir_doc = crate::to_ir(doc);
+    // Steps 2-4: IR → Events → RcDom → HTML string
+    let html_string = ir_to_body_html(
+        ir_doc,
+        options.source_lines,
+        options.toc,
+        options.code_theme,
+    )?;
+
+    // Step 5: Wrap in complete HTML document with CSS
+    let complete_html = wrap_in_document(&html_string, &title, &metadata, &options)?;
+
+    Ok(complete_html)
+}
+
+/// Serialize an IR document to the inner HTML of the `lex-document` container,
+/// without the surrounding page, stylesheet or scripts
+///
+/// Code is highlighted with `code_theme`, and left plain without one.
+pub(crate) fn serialize_body_html(
+    ir_doc: &IrDocument,
+    code_theme: Option<CodeTheme>,
+) -> Result<String, FormatError> {
+    ir_to_body_html(ir_doc.clone(), false, None, code_theme)
+}
+
+/// Serialize a run of events, such as a single table, to an HTML fragment
+pub(crate) fn serialize_events_html(events: &[Event]) -> Result<String, FormatError> {
+    serialize_dom(&build_html_dom(events, false)?)
+}
+
+fn ir_to_body_html(
+    mut ir_doc: IrDocument,
+    source_lines: bool,
+    toc: Option<TocOptions>,
+    code_theme: Option<CodeTheme>,
+) -> Result<String, FormatError> {
+    // Sessions get ids, and references to them become links to those ids
+    resolve_references(&mut ir_doc);
+    // The notes session is rendered as a footnote list instead
+    let notes = take_notes(&mut ir_doc);
+    fill_toc(&mut ir_doc, toc);
 
     // Step 2: IR → Events
     let events = tree_to_events(&DocNode::Document(ir_doc));
 
     // Step 3: Events → RcDom (HTML DOM tree)
-    let dom = build_html_dom(&events)?;
+    let dom = build_html_dom(&events, source_lines)?;
+    if !notes.is_empty() {
+        append_footnotes(&dom, &notes, source_lines)?;
+    }
+    highlight_dom(&dom.document, code_theme);
 
     // Step 4: RcDom → HTML string
-    let html_string = serialize_dom(&dom)?;
-
-    // Step 5: Wrap in complete HTML document with CSS
-    let complete_html = wrap_in_document(&html_string, &title, &options)?;
-
-    Ok(complete_html)
+    serialize_dom(&dom)
 }
 
 /// Build an HTML DOM tree from IR events
-fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
+///
+/// With `source_lines`, the element opened for each node that carries a source
+/// span gets a 1-based `data-lex-line` attribute.
+fn build_html_dom(events: &[Event], source_lines: bool) -> Result<RcDom, FormatError> {
     let dom = RcDom::default();
 
     // Create document container
@@ -91,12 +192,28 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
     let mut in_verbatim = false;
     let mut verbatim_language: Option<String> = None;
     let mut verbatim_content = String::new();
+    // DOT graphs rendered so far, numbering the ids inside each SVG
+    let mut diagrams = 0;
 
     // State for heading context
     let mut current_heading: Option<Handle> = None;
 
+    // Source line of the node opened by the next event
+    let mut pending_line: Option<usize> = None;
+    let mut verbatim_line: Option<usize> = None;
+
+    // Id of the section opened by the next heading
+    let mut pending_anchor: Option<String> = None;
+
     for event in events {
+        let source_line = pending_line.take();
         match event {
+            Event::Source(span) => {
+                if source_lines {
+                    pending_line = Some(span.start.line + 1);
+                }
+            }
+
             Event::StartDocument => {
                 // Already created doc_container
             }
@@ -105,10 +222,22 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 // Done
             }
 
+            Event::Anchor(id) => {
+                pending_anchor = Some(id.clone());
+                // The source line belongs to the heading that follows
+                pending_line = source_line;
+            }
+
             Event::StartHeading(level) => {
                 // Create section wrapper for this session
                 let class = format!("lex-session lex-session-{level}");
-                let section = create_element("section", vec![("class", &class)]);
+                let mut attrs = vec![("class", class.as_str())];
+                let anchor = pending_anchor.take();
+                if let Some(id) = &anchor {
+                    attrs.push(("id", id.as_str()));
+                }
+                let section = create_element("section", attrs);
+                set_source_line(&section, source_line);
                 current_parent.children.borrow_mut().push(section.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = section;
@@ -154,6 +283,7 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
             Event::StartParagraph => {
                 current_heading = None;
                 let para = create_element("p", vec![("class", "lex-paragraph")]);
+                set_source_line(&para, source_line);
                 current_parent.children.borrow_mut().push(para.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = para;
@@ -184,6 +314,7 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                     }
                     _ => create_element(tag, vec![("class", "lex-list")]),
                 };
+                set_source_line(&list, source_line);
                 current_parent.children.borrow_mut().push(list.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = list;
@@ -195,9 +326,22 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 })?;
             }
 
-            Event::StartListItem => {
+            Event::StartListItem { checked } => {
                 current_heading = None;
-                let item = create_element("li", vec![("class", "lex-list-item")]);
+                let item = match checked {
+                    Some(_) => create_element("li", vec![("class", "lex-list-item lex-task-item")]),
+                    None => create_element("li", vec![("class", "lex-list-item")]),
+                };
+                set_source_line(&item, source_line);
+                if let Some(checked) = checked {
+                    let mut attrs = vec![("type", "checkbox"), ("disabled", "")];
+                    if *checked {
+                        attrs.push(("checked", ""));
+                    }
+                    item.children
+                        .borrow_mut()
+                        .push(create_element("input", attrs));
+                }
                 current_parent.children.borrow_mut().push(item.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = item;
@@ -213,6 +357,7 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 current_heading = None;
                 in_verbatim = true;
                 verbatim_language = language.clone();
+                verbatim_line = source_line;
                 verbatim_content.clear();
 
                 // Render subject as a caption before the code block
@@ -240,6 +385,43 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                     }
                 }
 
+                // Display math becomes MathML unless it does not convert
+                if verbatim_language.as_deref() == Some(MATH_LABEL) {
+                    if let Ok(math) = mathml(&verbatim_content, true) {
+                        let div = create_element("div", vec![("class", "lex-math-display")]);
+                        set_source_line(&div, verbatim_line.take());
+                        div.children.borrow_mut().push(math);
+                        current_parent.children.borrow_mut().push(div);
+
+                        in_verbatim = false;
+                        verbatim_language = None;
+                        verbatim_content.clear();
+                        continue;
+                    }
+                }
+
+                // DOT graphs become inline SVG unless they do not render
+                let is_dot = verbatim_language
+                    .as_deref()
+                    .is_some_and(|lang| DOT_LABELS.contains(&lang));
+                if is_dot {
+                    let prefix = format!("lex-diagram-{}-", diagrams + 1);
+                    let svg = render_dot(&verbatim_content, &prefix)
+                        .and_then(|svg| create_foreign(&svg, ns!(svg)));
+                    if let Ok(svg) = svg {
+                        diagrams += 1;
+                        let figure = create_element("figure", vec![("class", "lex-diagram")]);
+                        set_source_line(&figure, verbatim_line.take());
+                        figure.children.borrow_mut().push(svg);
+                        current_parent.children.borrow_mut().push(figure);
+
+                        in_verbatim = false;
+                        verbatim_language = None;
+                        verbatim_content.clear();
+                        continue;
+                    }
+                }
+
                 // Create pre + code block with highlight.js-compatible classes
                 let normalized_lang;
                 let mut pre_attrs = vec![("class", "lex-verbatim")];
@@ -253,6 +435,7 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 }
 
                 let pre = create_element("pre", pre_attrs);
+                set_source_line(&pre, verbatim_line.take());
                 let code_attrs = match normalized_lang {
                     Some(ref class) => vec![("class", class.as_str())],
                     None => vec![],
@@ -271,6 +454,7 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
             Event::StartDefinition => {
                 current_heading = None;
                 let dl = create_element("dl", vec![("class", "lex-definition")]);
+                set_source_line(&dl, source_line);
                 current_parent.children.borrow_mut().push(dl.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = dl;
@@ -310,9 +494,17 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 })?;
             }
 
-            Event::StartTable => {
+            Event::StartTable { caption } => {
                 current_heading = None;
                 let table = create_element("table", vec![("class", "lex-table")]);
+                set_source_line(&table, source_line);
+                if let Some(caption) = caption {
+                    let caption_el = create_element("caption", vec![]);
+                    for inline in caption {
+                        add_inline_to_node(&caption_el, inline)?;
+                    }
+                    table.children.borrow_mut().push(caption_el);
+                }
                 current_parent.children.borrow_mut().push(table.clone());
                 parent_stack.push(current_parent.clone());
                 current_parent = table;
@@ -337,7 +529,12 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 })?;
             }
 
-            Event::StartTableCell { header, align } => {
+            Event::StartTableCell {
+                header,
+                align,
+                colspan,
+                rowspan,
+            } => {
                 let tag = if *header { "th" } else { "td" };
                 let mut attrs = vec![];
                 match align {
@@ -346,6 +543,14 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                     TableCellAlignment::Center => attrs.push(("style", "text-align: center")),
                     TableCellAlignment::None => {}
                 }
+                let colspan = colspan.to_string();
+                let rowspan = rowspan.to_string();
+                if colspan != "1" {
+                    attrs.push(("colspan", &colspan));
+                }
+                if rowspan != "1" {
+                    attrs.push(("rowspan", &rowspan));
+                }
 
                 let cell = create_element(tag, attrs);
                 current_parent.children.borrow_mut().push(cell.clone());
@@ -374,6 +579,25 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 }
             }
 
+            // A filled table of contents is navigation, not a hidden note
+            Event::StartAnnotation { label, .. } if label == TOC_LABEL => {
+                current_heading = None;
+                let nav = create_element(
+                    "nav",
+                    vec![("class", "lex-toc"), ("aria-label", "Table of contents")],
+                );
+                set_source_line(&nav, source_line);
+                current_parent.children.borrow_mut().push(nav.clone());
+                parent_stack.push(current_parent.clone());
+                current_parent = nav;
+            }
+
+            Event::EndAnnotation { label } if label == TOC_LABEL => {
+                current_parent = parent_stack.pop().ok_or_else(|| {
+                    FormatError::SerializationError("Unbalanced table of contents end".to_string())
+                })?;
+            }
+
             Event::StartAnnotation { label, parameters } => {
                 current_heading = None;
                 // Create HTML comment
@@ -393,6 +617,27 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
                 current_parent.children.borrow_mut().push(comment_node);
             }
 
+            Event::StartBlockQuote => {
+                current_heading = None;
+                let quote = create_element("blockquote", vec![("class", "lex-blockquote")]);
+                set_source_line(&quote, source_line);
+                current_parent.children.borrow_mut().push(quote.clone());
+                parent_stack.push(current_parent.clone());
+                current_parent = quote;
+            }
+
+            Event::EndBlockQuote => {
+                current_parent = parent_stack.pop().ok_or_else(|| {
+                    FormatError::SerializationError("Unbalanced block quote end".to_string())
+                })?;
+            }
+
+            Event::ThematicBreak => {
+                current_heading = None;
+                let rule = create_element("hr", vec![("class", "lex-thematic-break")]);
+                current_parent.children.borrow_mut().push(rule);
+            }
+
             Event::Image(image) => {
                 let figure = create_element("figure", vec![("class", "lex-image")]);
                 current_parent.children.borrow_mut().push(figure.clone());
@@ -447,6 +692,101 @@ fn build_html_dom(events: &[Event]) -> Result<RcDom, FormatError> {
     Ok(dom)
 }
 
+/// Append the notes as a footnote list at the end of the document
+///
+/// Each note links back to the first reference to it, which gets the id
+/// `fnref-<label>` for that.
+fn append_footnotes(
+    dom: &RcDom,
+    notes: &[Footnote],
+    source_lines: bool,
+) -> Result<(), FormatError> {
+    let container = dom
+        .document
+        .children
+        .borrow()
+        .first()
+        .cloned()
+        .ok_or_else(|| FormatError::SerializationError("Empty document".to_string()))?;
+    let mut referenced = HashSet::new();
+    mark_footnote_refs(&container, &mut referenced);
+
+    let section = create_element("section", vec![("class", "lex-footnotes")]);
+    let list = create_element("ol", vec![("class", "lex-footnote-list")]);
+    for note in notes {
+        let id = format!("fn-{}", note.label);
+        let item = create_element("li", vec![("id", &id), ("value", &note.label)]);
+
+        let events: Vec<Event> = note.content.iter().flat_map(tree_to_events).collect();
+        let rendered = build_html_dom(&events, source_lines)?;
+        if let Some(body) = rendered.document.children.borrow().first() {
+            item.children.borrow_mut().extend(body.children.take());
+        }
+
+        if referenced.contains(&note.label) {
+            let href = format!("#fnref-{}", note.label);
+            let back = create_element(
+                "a",
+                vec![
+                    ("href", &href),
+                    ("class", "lex-footnote-backref"),
+                    ("aria-label", "Back to the text"),
+                ],
+            );
+            back.children.borrow_mut().push(create_text("↩"));
+            item.children.borrow_mut().push(back);
+        }
+        list.children.borrow_mut().push(item);
+    }
+    section.children.borrow_mut().push(list);
+    container.children.borrow_mut().push(section);
+    Ok(())
+}
+
+/// Give the first footnote reference to each label the id `fnref-<label>`,
+/// collecting the labels seen
+fn mark_footnote_refs(node: &Handle, seen: &mut HashSet<String>) {
+    for child in node.children.borrow().iter() {
+        let is_ref = match &child.data {
+            NodeData::Element { name, .. } => {
+                &*name.local == "sup"
+                    && attribute(child, "class").as_deref() == Some("lex-footnote-ref")
+            }
+            _ => false,
+        };
+        if !is_ref {
+            mark_footnote_refs(child, seen);
+            continue;
+        }
+
+        let label = child
+            .children
+            .borrow()
+            .first()
+            .and_then(|anchor| attribute(anchor, "href"))
+            .and_then(|href| href.strip_prefix("#fn-").map(str::to_string));
+        if let (Some(label), NodeData::Element { attrs, .. }) = (label, &child.data) {
+            if seen.insert(label.clone()) {
+                attrs.borrow_mut().push(Attribute {
+                    name: QualName::new(None, ns!(), LocalName::from("id")),
+                    value: format!("fnref-{label}").into(),
+                });
+            }
+        }
+    }
+}
+
+fn attribute(handle: &Handle, name: &str) -> Option<String> {
+    match &handle.data {
+        NodeData::Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attr| &*attr.name.local == name)
+            .map(|attr| attr.value.to_string()),
+        _ => None,
+    }
+}
+
 /// Add inline content to an HTML node, handling references → anchors conversion
 fn add_inline_to_node(parent: &Handle, inline: &InlineContent) -> Result<(), FormatError> {
     match inline {
@@ -479,14 +819,19 @@ fn add_inline_to_node(parent: &Handle, inline: &InlineContent) -> Result<(), For
         }
 
         InlineContent::Math(math_text) => {
-            // Math rendered in a span with class
+            // Math rendered in a span with class, as MathML or else as TeX
             let math_span = create_element("span", vec![("class", "lex-math")]);
-            let dollar_open = create_text("$");
-            let math_content = create_text(math_text);
-            let dollar_close = create_text("$");
-            math_span.children.borrow_mut().push(dollar_open);
-            math_span.children.borrow_mut().push(math_content);
-            math_span.children.borrow_mut().push(dollar_close);
+            match mathml(math_text, false) {
+                Ok(math) => math_span.children.borrow_mut().push(math),
+                Err(_) => {
+                    let dollar_open = create_text("$");
+                    let math_content = create_text(math_text);
+                    let dollar_close = create_text("$");
+                    math_span.children.borrow_mut().push(dollar_open);
+                    math_span.children.borrow_mut().push(math_content);
+                    math_span.children.borrow_mut().push(dollar_close);
+                }
+            }
             parent.children.borrow_mut().push(math_span);
         }
 
@@ -520,13 +865,42 @@ fn add_inline_to_node(parent: &Handle, inline: &InlineContent) -> Result<(), For
             let img = create_element("img", attrs);
             parent.children.borrow_mut().push(img);
         }
+
+        InlineContent::Strikethrough(children) => {
+            let del = create_element("del", vec![]);
+            parent.children.borrow_mut().push(del.clone());
+            for child in children {
+                add_inline_to_node(&del, child)?;
+            }
+        }
+
+        InlineContent::Link(link) => {
+            let mut attrs = vec![("href", link.url.as_str())];
+            if let Some(title) = &link.title {
+                attrs.push(("title", title.as_str()));
+            }
+            let anchor = create_element("a", attrs);
+            parent.children.borrow_mut().push(anchor.clone());
+            for child in &link.content {
+                add_inline_to_node(&anchor, child)?;
+            }
+        }
+
+        InlineContent::FootnoteRef(label) => {
+            let sup = create_element("sup", vec![("class", "lex-footnote-ref")]);
+            let href = format!("#fn-{label}");
+            let anchor = create_element("a", vec![("href", &href)]);
+            anchor.children.borrow_mut().push(create_text(label));
+            sup.children.borrow_mut().push(anchor);
+            parent.children.borrow_mut().push(sup);
+        }
     }
 
     Ok(())
 }
 
 /// Create an HTML element with attributes
-fn create_element(tag: &str, attrs: Vec<(&str, &str)>) -> Handle {
+pub(super) fn create_element(tag: &str, attrs: Vec<(&str, &str)>) -> Handle {
     let qual_name = QualName::new(None, ns!(html), LocalName::from(tag));
     let attributes = attrs
         .into_iter()
@@ -548,8 +922,69 @@ fn create_element(tag: &str, attrs: Vec<(&str, &str)>) -> Handle {
     })
 }
 
+/// Parse SVG or MathML markup into a DOM element
+///
+/// Elements that do not declare a namespace are put in `ns`.
+pub(super) fn create_foreign(markup: &str, ns: Namespace) -> Result<Handle, String> {
+    let xml = roxmltree::Document::parse(markup).map_err(|err| err.to_string())?;
+    Ok(foreign_element(xml.root_element(), &ns))
+}
+
+fn foreign_element(node: roxmltree::Node, inherited: &Namespace) -> Handle {
+    let ns = node
+        .tag_name()
+        .namespace()
+        .map_or_else(|| inherited.clone(), Namespace::from);
+    let attributes = node
+        .attributes()
+        .map(|attr| Attribute {
+            name: QualName::new(
+                None,
+                attr.namespace().map_or(ns!(), Namespace::from),
+                LocalName::from(attr.name()),
+            ),
+            value: attr.value().to_string().into(),
+        })
+        .collect();
+    let handle = Rc::new(Node {
+        parent: Cell::new(None),
+        children: RefCell::new(Vec::new()),
+        data: NodeData::Element {
+            name: QualName::new(None, ns.clone(), LocalName::from(node.tag_name().name())),
+            attrs: RefCell::new(attributes),
+            template_contents: Default::default(),
+            mathml_annotation_xml_integration_point: false,
+        },
+    });
+    for child in node.children() {
+        if child.is_element() {
+            handle
+                .children
+                .borrow_mut()
+                .push(foreign_element(child, &ns));
+        } else if child.is_text() {
+            let text = child.text().unwrap_or_default();
+            handle.children.borrow_mut().push(create_text(text));
+        }
+    }
+    handle
+}
+
+/// Add a `data-lex-line` attribute to an element, when the line is known
+fn set_source_line(handle: &Handle, line: Option<usize>) {
+    let Some(line) = line else {
+        return;
+    };
+    if let NodeData::Element { attrs, .. } = &handle.data {
+        attrs.borrow_mut().push(Attribute {
+            name: QualName::new(None, ns!(), LocalName::from("data-lex-line")),
+            value: line.to_string().into(),
+        });
+    }
+}
+
 /// Create a text node
-fn create_text(text: &str) -> Handle {
+pub(super) fn create_text(text: &str) -> Handle {
     Rc::new(Node {
         parent: Cell::new(None),
         children: RefCell::new(Vec::new()),
@@ -605,6 +1040,7 @@ fn serialize_dom(dom: &RcDom) -> Result<String, FormatError> {
 fn wrap_in_document(
     body_html: &str,
     title: &str,
+    metadata: &Metadata,
     options: &HtmlOptions,
 ) -> Result<String, FormatError> {
     let baseline_css = include_str!("../../../css/baseline.css");
@@ -618,6 +1054,17 @@ fn wrap_in_document(
 
     // Escape HTML entities in title for safety
     let escaped_title = html_escape(title);
+    let meta_tags = metadata_meta_tags(metadata);
+
+    // Code highlighted here needs no script; otherwise highlight.js does it
+    let (highlight_css, highlight_script) = if highlights(options.code_theme) {
+        ("", "")
+    } else {
+        (
+            "  <link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.11.1/styles/github.min.css\">\n",
+            "  <script src=\"https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.11.1/highlight.min.js\"></script>\n  <script>hljs.highlightAll();</script>\n",
+        )
+    };
 
     let html = format!(
         r#"<!DOCTYPE html>
@@ -626,16 +1073,13 @@ fn wrap_in_document(
   <meta charset="UTF-8">
   <meta name="viewport" content="width=device-width, initial-scale=1.0">
   <meta name="generator" content="lex-babel">
-  <title>{escaped_title}</title>
-  <link rel="stylesheet" href="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.11.1/styles/github.min.css">
-  <style>
+{meta_tags}  <title>{escaped_title}</title>
+{highlight_css}  <style>
 {baseline_css}
 {theme_css}
 {custom_css}
   </style>
-  <script src="https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.11.1/highlight.min.js"></script>
-  <script>hljs.highlightAll();</script>
-</head>
+{highlight_script}</head>
 <body>
 <div class="lex-document">
 {body_html}
@@ -647,6 +1091,35 @@ fn wrap_in_document(
     Ok(html)
 }
 
+/// `<meta>` tags for the document metadata (one line each, indented)
+///
+/// PDF export renders this page, so the same values end up there.
+fn metadata_meta_tags(metadata: &Metadata) -> String {
+    let mut tags = Vec::new();
+    for author in metadata.authors() {
+        tags.push(("author", author));
+    }
+    if let Some(date) = metadata.date() {
+        tags.push(("date", date));
+    }
+    let keywords = metadata.tags();
+    if !keywords.is_empty() {
+        tags.push(("keywords", keywords.join(", ")));
+    }
+    if let Some(description) = metadata.get_text("description") {
+        tags.push(("description", description));
+    }
+
+    tags.iter()
+        .map(|(name, content)| {
+            format!(
+                "  <meta name=\"{name}\" content=\"{}\">\n",
+                html_escape(content)
+            )
+        })
+        .collect()
+}
+
 /// Map common language aliases to highlight.js class names
 fn normalize_language(lang: &str) -> &str {
     match lang {
@@ -698,7 +1171,7 @@ mod tests {
 
         let html = serialize_to_html(&lex_doc, HtmlTheme::Modern).unwrap();
 
-        assert!(html.contains("<section class=\"lex-session lex-session-2\">"));
+        assert!(html.contains("<section class=\"lex-session lex-session-2\""));
         assert!(html.contains("<h2>"));
         assert!(html.contains("Introduction"));
     }
@@ -0,0 +1,131 @@
+//! Jupyter notebook format implementation
+//!
+//! This module implements bidirectional conversion between Lex and Jupyter
+//! notebooks (nbformat 4, `.ipynb`).
+//!
+//! # Element Mapping Table
+//!
+//! | Lex Element                 | Notebook Equivalent      | Notes                                       |
+//! |-----------------------------|--------------------------|---------------------------------------------|
+//! | Prose (sessions, lists, ..) | Markdown cell            | Consecutive blocks share one cell           |
+//! | Verbatim (kernel language)  | Code cell                | Other verbatims stay in Markdown cells      |
+//! | `:: output ::` annotation   | Cell outputs             | Verbatim → stream, data-URI image → display |
+//! | Document metadata           | `metadata.lex`           | Key/value pairs, restored on import         |
+//! | —                           | Raw cell                 | Imported as a `text` verbatim               |
+//!
+//! On import, markdown cells are parsed with the Markdown importer, code cells
+//! become verbatim blocks labelled with the cell (or kernel) language, and text
+//! or image outputs are collected into an `output` annotation after the block.
+//! Execution counts and cell ids are not preserved.
+
+pub mod parser;
+pub mod serializer;
+
+use crate::error::FormatError;
+use crate::format::{reject_options, Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::Document as IrDocument;
+use lex_core::lex::ast::Document;
+use std::collections::HashMap;
+
+/// Annotation label used for code cell outputs
+pub(crate) const OUTPUT_LABEL: &str = "output";
+
+/// Verbatim labels that export as code cells rather than Markdown code blocks
+const KERNEL_LANGUAGES: &[&str] = &[
+    "python",
+    "python3",
+    "r",
+    "julia",
+    "javascript",
+    "typescript",
+    "scala",
+    "rust",
+    "bash",
+    "sh",
+    "ruby",
+    "go",
+    "cpp",
+    "c++",
+    "java",
+    "kotlin",
+    "haskell",
+    "matlab",
+    "octave",
+    "sql",
+    "csharp",
+    "fsharp",
+    "powershell",
+];
+
+pub(crate) fn is_kernel_language(language: &str) -> bool {
+    KERNEL_LANGUAGES
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(language.trim()))
+}
+
+/// Format implementation for Jupyter notebooks
+pub struct IpynbFormat;
+
+impl Format for IpynbFormat {
+    fn name(&self) -> &str {
+        "ipynb"
+    }
+
+    fn description(&self) -> &str {
+        "Jupyter notebook (nbformat 4)"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["ipynb"]
+    }
+
+    fn supports_parsing(&self) -> bool {
+        true
+    }
+
+    fn supports_serialization(&self) -> bool {
+        true
+    }
+
+    fn parse(&self, source: &str) -> Result<Document, FormatError> {
+        parser::parse_from_ipynb(source)
+    }
+
+    fn parse_with_context(
+        &self,
+        source: &str,
+        ctx: &ConversionContext,
+    ) -> Result<Document, FormatError> {
+        let ir_doc = parser::parse_ipynb_to_ir(source)?;
+        Ok(crate::from_ir_with_context(&ir_doc, ctx))
+    }
+
+    fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
+        serializer::serialize_to_ipynb(doc)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        reject_options(self.name(), options)?;
+        serializer::serialize_ir_to_ipynb(ir, document_title(source)).map(SerializedDocument::Text)
+    }
+}
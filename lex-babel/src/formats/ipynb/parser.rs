@@ -0,0 +1,230 @@
+//! Jupyter notebook parsing (.ipynb → Lex import)
+//!
+//! Pipeline: notebook JSON → per-cell IR events → IR → Lex AST
+//!
+//! Markdown cells go through the Markdown importer and are spliced into one event
+//! stream, so headings in later cells still nest the cells that follow them. Code
+//! cells become verbatim blocks labelled with their language, and their outputs
+//! are kept in an `output` annotation placed after the block.
+
+use super::OUTPUT_LABEL;
+use crate::common::flat_to_nested::events_to_tree;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use crate::formats::markdown::parser::markdown_to_events;
+use crate::ir::events::Event;
+use crate::ir::nodes::{
+    Document as IrDocument, Image, InlineContent, Metadata, MetadataValue, SourcePosition,
+};
+use lex_core::lex::ast::Document;
+use serde_json::Value;
+
+/// Parse a Jupyter notebook to a Lex document
+pub fn parse_from_ipynb(source: &str) -> Result<Document, FormatError> {
+    Ok(crate::from_ir(&parse_ipynb_to_ir(source)?))
+}
+
+/// Parse a Jupyter notebook to an IR document
+pub fn parse_ipynb_to_ir(source: &str) -> Result<IrDocument, FormatError> {
+    let notebook: Value = serde_json::from_str(source).map_err(|e| {
+        // serde_json counts both from 1
+        let position =
+            SourcePosition::new(e.line().saturating_sub(1), e.column().saturating_sub(1));
+        ErrorDetail::new(
+            ErrorKind::Parse,
+            "json-syntax",
+            format!("Invalid notebook JSON: {e}"),
+        )
+        .in_format("ipynb")
+        .at(position)
+        .caused_by(e)
+    })?;
+
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| FormatError::ParseError("Notebook has no 'cells' array".to_string()))?;
+
+    let kernel_language = notebook_language(&notebook);
+
+    let mut events = vec![Event::StartDocument];
+
+    let mut first_markdown = true;
+    for cell in cells {
+        let source = join_text(cell.get("source"));
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => {
+                let cell_events = markdown_to_events(&source, first_markdown)?;
+                first_markdown = false;
+                events.extend(
+                    cell_events.into_iter().filter(|event| {
+                        !matches!(event, Event::StartDocument | Event::EndDocument)
+                    }),
+                );
+            }
+            Some("code") => {
+                let language = cell
+                    .pointer("/metadata/language")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .or_else(|| kernel_language.clone());
+                let subject = cell
+                    .pointer("/metadata/lex/subject")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                events.push(Event::StartVerbatim { language, subject });
+                events.push(Event::Inline(InlineContent::Text(source)));
+                events.push(Event::EndVerbatim);
+                push_outputs(cell, &mut events);
+            }
+            Some("raw") => push_verbatim(&mut events, Some("text".to_string()), source),
+            Some(other) => {
+                return Err(FormatError::ParseError(format!(
+                    "Unknown notebook cell type '{other}'"
+                )))
+            }
+            None => {
+                return Err(FormatError::ParseError(
+                    "Notebook cell is missing 'cell_type'".to_string(),
+                ))
+            }
+        }
+    }
+
+    events.push(Event::EndDocument);
+
+    let mut ir_doc = events_to_tree(&events).map_err(|e| {
+        FormatError::ParseError(format!("Failed to build IR tree from events: {e}"))
+    })?;
+    ir_doc.metadata = notebook_metadata(&notebook);
+
+    Ok(ir_doc)
+}
+
+/// Kernel language declared in the notebook metadata
+fn notebook_language(notebook: &Value) -> Option<String> {
+    notebook
+        .pointer("/metadata/language_info/name")
+        .or_else(|| notebook.pointer("/metadata/kernelspec/language"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Restore document metadata written by the exporter under `metadata.lex`
+fn notebook_metadata(notebook: &Value) -> Metadata {
+    let mut metadata = Metadata::default();
+    let Some(fields) = notebook.pointer("/metadata/lex").and_then(Value::as_object) else {
+        return metadata;
+    };
+
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    for (key, value) in fields {
+        let value = match value {
+            Value::Array(items) => MetadataValue::List(items.iter().map(text).collect()),
+            Value::Object(fields) => MetadataValue::Fields(
+                fields
+                    .iter()
+                    .map(|(field, value)| (field.clone(), text(value)))
+                    .collect(),
+            ),
+            other => MetadataValue::Text(text(other)),
+        };
+        metadata.push(key.as_str(), value);
+    }
+    metadata
+}
+
+fn push_verbatim(events: &mut Vec<Event>, language: Option<String>, content: String) {
+    events.push(Event::StartVerbatim {
+        language,
+        subject: None,
+    });
+    events.push(Event::Inline(InlineContent::Text(content)));
+    events.push(Event::EndVerbatim);
+}
+
+/// Attach a code cell's outputs as an `output` annotation
+///
+/// Text results (streams, `text/plain` data and tracebacks) become plain-text
+/// verbatim blocks; base64 PNG/JPEG data becomes an image with a data URI.
+fn push_outputs(cell: &Value, events: &mut Vec<Event>) {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return;
+    };
+
+    let mut body = Vec::new();
+    for output in outputs {
+        match output.get("output_type").and_then(Value::as_str) {
+            Some("stream") => {
+                push_text_output(&mut body, join_text(output.get("text")));
+            }
+            Some("execute_result") | Some("display_data") => {
+                let Some(data) = output.get("data") else {
+                    continue;
+                };
+                let plain = data.get("text/plain").map(|text| join_text(Some(text)));
+                match ["image/png", "image/jpeg"]
+                    .iter()
+                    .find_map(|mime| data.get(*mime).map(|payload| (mime, payload)))
+                {
+                    Some((mime, payload)) => {
+                        let payload: String = join_text(Some(payload))
+                            .chars()
+                            .filter(|c| !c.is_whitespace())
+                            .collect();
+                        body.push(Event::Image(Image {
+                            src: format!("data:{mime};base64,{payload}"),
+                            alt: plain.unwrap_or_else(|| OUTPUT_LABEL.to_string()),
+                            title: None,
+                        }));
+                    }
+                    None => {
+                        if let Some(text) = plain {
+                            push_text_output(&mut body, text);
+                        }
+                    }
+                }
+            }
+            Some("error") => {
+                let name = output
+                    .get("ename")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Error");
+                let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+                push_text_output(&mut body, format!("{name}: {value}"));
+            }
+            _ => {}
+        }
+    }
+
+    if body.is_empty() {
+        return;
+    }
+
+    events.push(Event::StartAnnotation {
+        label: OUTPUT_LABEL.to_string(),
+        parameters: vec![],
+    });
+    events.extend(body);
+    events.push(Event::EndAnnotation {
+        label: OUTPUT_LABEL.to_string(),
+    });
+}
+
+fn push_text_output(events: &mut Vec<Event>, text: String) {
+    let text = text.trim_end_matches('\n').to_string();
+    if !text.is_empty() {
+        push_verbatim(events, Some("text".to_string()), text);
+    }
+}
+
+/// Notebook text fields are either a string or an array of lines
+fn join_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,276 @@
+//! Jupyter notebook serialization (Lex → .ipynb export)
+//!
+//! Pipeline: Lex AST → IR → cell grouping → notebook JSON
+//!
+//! Consecutive prose blocks are collected into a group and rendered through the
+//! Markdown serializer as a single markdown cell. A verbatim block whose label is
+//! a known kernel language closes the current group and becomes a code cell; an
+//! `output` annotation directly after it becomes that cell's outputs.
+
+use super::{is_kernel_language, OUTPUT_LABEL};
+use crate::common::text::plain_text;
+use crate::error::FormatError;
+use crate::formats::markdown::serializer::serialize_ir_to_markdown;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{
+    DocNode, Document as IrDocument, Heading, Metadata, MetadataValue, Verbatim,
+};
+use lex_core::lex::ast::Document;
+use serde_json::{json, Map, Value};
+
+/// Serialize a Lex document to a Jupyter notebook (nbformat 4)
+pub fn serialize_to_ipynb(doc: &Document) -> Result<String, FormatError> {
+    serialize_ir_to_ipynb(&crate::to_ir(doc), document_title(doc))
+}
+
+/// Serialize an IR document to a Jupyter notebook under the given title
+pub(crate) fn serialize_ir_to_ipynb(
+    ir_doc: &IrDocument,
+    title: &str,
+) -> Result<String, FormatError> {
+    let title = if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    };
+
+    let mut builder = NotebookBuilder::new(title);
+    builder.metadata = metadata_to_json(&ir_doc.metadata);
+    builder.walk(&ir_doc.children)?;
+    builder.flush_markdown()?;
+
+    let notebook = builder.finish();
+    let mut output = serde_json::to_string_pretty(&notebook).map_err(|e| {
+        FormatError::SerializationError(format!("Notebook JSON serialization failed: {e}"))
+    })?;
+    output.push('\n');
+    Ok(output)
+}
+
+/// Document metadata as the `metadata.lex` object; repeated keys become arrays
+fn metadata_to_json(metadata: &Metadata) -> Map<String, Value> {
+    let mut object = Map::new();
+    for key in metadata.keys() {
+        let values: Vec<&MetadataValue> = metadata
+            .entries
+            .iter()
+            .filter(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+            .collect();
+        let value = match values.as_slice() {
+            [MetadataValue::Text(text)] => Value::String(text.clone()),
+            [MetadataValue::Fields(fields)] => Value::Object(
+                fields
+                    .iter()
+                    .map(|(field, value)| (field.clone(), Value::String(value.clone())))
+                    .collect(),
+            ),
+            values => Value::Array(
+                values
+                    .iter()
+                    .flat_map(|value| value.items())
+                    .map(Value::String)
+                    .collect(),
+            ),
+        };
+        object.insert(key.to_string(), value);
+    }
+    object
+}
+
+struct NotebookBuilder {
+    cells: Vec<Value>,
+    pending: Vec<DocNode>,
+    title: Option<String>,
+    metadata: Map<String, Value>,
+    kernel_language: Option<String>,
+    attached_outputs: Vec<Value>,
+}
+
+impl NotebookBuilder {
+    fn new(title: Option<String>) -> Self {
+        Self {
+            cells: Vec::new(),
+            pending: Vec::new(),
+            title,
+            metadata: Map::new(),
+            kernel_language: None,
+            attached_outputs: Vec::new(),
+        }
+    }
+
+    fn walk(&mut self, nodes: &[DocNode]) -> Result<(), FormatError> {
+        let mut iter = nodes.iter().peekable();
+        while let Some(node) = iter.next() {
+            match node {
+                DocNode::Heading(heading) => {
+                    // Headings are flat in Markdown, so the heading line joins the
+                    // current group and its children are walked in document order.
+                    self.pending.push(DocNode::Heading(Heading {
+                        level: heading.level,
+                        content: heading.content.clone(),
+                        children: Vec::new(),
+                        id: heading.id.clone(),
+                        span: None,
+                    }));
+                    self.walk(&heading.children)?;
+                }
+                DocNode::Verbatim(verbatim) if is_code_cell(node) => {
+                    self.flush_markdown()?;
+                    let mut outputs = std::mem::take(&mut self.attached_outputs);
+                    if let Some(DocNode::Annotation(ann)) = iter.peek() {
+                        if ann.label == OUTPUT_LABEL {
+                            iter.next();
+                            outputs.extend(ann.content.iter().filter_map(output_from_node));
+                        }
+                    }
+                    self.push_code_cell(verbatim, outputs);
+                }
+                // Annotations attached to a block come before it in the IR, so an
+                // output annotation attached to the next code cell is held for it.
+                DocNode::Annotation(ann)
+                    if ann.label == OUTPUT_LABEL
+                        && iter.peek().is_some_and(|next| is_code_cell(next)) =>
+                {
+                    self.attached_outputs
+                        .extend(ann.content.iter().filter_map(output_from_node));
+                }
+                DocNode::Verbatim(verbatim) => self.pending.push(DocNode::Verbatim(Verbatim {
+                    content: verbatim_text(verbatim).to_string(),
+                    ..verbatim.clone()
+                })),
+                other => self.pending.push(other.clone()),
+            }
+        }
+        Ok(())
+    }
+
+    fn push_code_cell(&mut self, verbatim: &Verbatim, outputs: Vec<Value>) {
+        let language = verbatim
+            .language
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        let mut metadata = Map::new();
+        if let Some(subject) = verbatim.subject.as_deref().filter(|s| !s.trim().is_empty()) {
+            metadata.insert("lex".to_string(), json!({ "subject": subject }));
+        }
+        match &self.kernel_language {
+            None => self.kernel_language = Some(language),
+            Some(kernel) if *kernel != language => {
+                metadata.insert("language".to_string(), Value::String(language));
+            }
+            Some(_) => {}
+        }
+
+        self.cells.push(json!({
+            "cell_type": "code",
+            "execution_count": null,
+            "metadata": metadata,
+            "outputs": outputs,
+            "source": source_lines(verbatim_text(verbatim)),
+        }));
+    }
+
+    /// Render the pending prose group as one markdown cell
+    fn flush_markdown(&mut self) -> Result<(), FormatError> {
+        if self.pending.is_empty() && self.title.is_none() {
+            return Ok(());
+        }
+
+        let children = std::mem::take(&mut self.pending);
+        let markdown = serialize_ir_to_markdown(
+            IrDocument {
+                children,
+                ..Default::default()
+            },
+            self.title.take(),
+            None,
+        )?;
+        let markdown = markdown.trim();
+        if !markdown.is_empty() {
+            self.cells.push(json!({
+                "cell_type": "markdown",
+                "metadata": {},
+                "source": source_lines(markdown),
+            }));
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        let mut metadata = Map::new();
+        if let Some(language) = &self.kernel_language {
+            metadata.insert("language_info".to_string(), json!({ "name": language }));
+        }
+        if !self.metadata.is_empty() {
+            metadata.insert("lex".to_string(), Value::Object(self.metadata));
+        }
+
+        json!({
+            "cells": self.cells,
+            "metadata": metadata,
+            "nbformat": 4,
+            "nbformat_minor": 4,
+        })
+    }
+}
+
+fn is_code_cell(node: &DocNode) -> bool {
+    matches!(
+        node,
+        DocNode::Verbatim(Verbatim { language: Some(language), .. }) if is_kernel_language(language)
+    )
+}
+
+/// Convert a node from an `output` annotation into a notebook output
+fn output_from_node(node: &DocNode) -> Option<Value> {
+    let output = match node {
+        DocNode::Image(image) => match image
+            .src
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+        {
+            Some((mime, data)) => json!({
+                "output_type": "display_data",
+                "metadata": {},
+                "data": {
+                    (mime): data,
+                    "text/plain": [image.alt.clone()],
+                },
+            }),
+            None => display_text(&image.src),
+        },
+        DocNode::Verbatim(verbatim) => json!({
+            "output_type": "stream",
+            "name": "stdout",
+            "text": source_lines(verbatim_text(verbatim)),
+        }),
+        DocNode::Paragraph(paragraph) => display_text(&plain_text(&paragraph.content)),
+        _ => return None,
+    };
+    Some(output)
+}
+
+fn display_text(text: &str) -> Value {
+    json!({
+        "output_type": "display_data",
+        "metadata": {},
+        "data": { "text/plain": source_lines(text) },
+    })
+}
+
+/// A verbatim block's text without the newlines its separating blank lines add
+///
+/// A blank line after the subject and one before the closing marker each
+/// leave a single `\n`; further blank lines are the author's and stay.
+fn verbatim_text(verbatim: &Verbatim) -> &str {
+    let text = verbatim.content.as_str();
+    let text = text.strip_prefix('\n').unwrap_or(text);
+    text.strip_suffix('\n').unwrap_or(text)
+}
+
+/// Split text into the line array notebooks use for `source` and `text`
+fn source_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(str::to_string).collect()
+}
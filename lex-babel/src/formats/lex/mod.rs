@@ -4,7 +4,7 @@
 //! as just another format in the system. This creates a uniform API where
 //! Lex can be converted to/from other formats using the same interface.
 
-use crate::error::FormatError;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
 use crate::format::Format;
 use lex_core::lex::ast::Document;
 use lex_core::lex::transforms::standard::STRING_TO_AST;
@@ -50,9 +50,11 @@ impl Format for LexFormat {
     }
 
     fn parse(&self, source: &str) -> Result<Document, FormatError> {
-        STRING_TO_AST
-            .run(source.to_string())
-            .map_err(|e| FormatError::ParseError(e.to_string()))
+        STRING_TO_AST.run(source.to_string()).map_err(|e| {
+            ErrorDetail::new(ErrorKind::Parse, "lex-syntax", e.to_string())
+                .in_format("lex")
+                .into()
+        })
     }
 
     fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
@@ -61,6 +63,11 @@ impl Format for LexFormat {
             .serialize(doc)
             .map_err(FormatError::SerializationError)
     }
+
+    // Verbatim blocks and includes are written back as they are
+    fn resolves_sources(&self) -> bool {
+        false
+    }
 }
 
 /// Convenience function to export a Document AST into a Lex string
@@ -1,11 +1,13 @@
 use super::formatting_rules::FormattingRules;
+use crate::ir::from_lex::{follows_session, is_file_reference, is_short_form, is_trailing};
 use lex_core::lex::ast::{
     elements::{
         blank_line_group::BlankLineGroup, paragraph::TextLine, sequence_marker::Form,
         verbatim::VerbatimGroupItemRef, VerbatimLine,
     },
     traits::{AstNode, Visitor},
-    Annotation, Definition, Document, List, ListItem, Paragraph, Session, Verbatim,
+    Annotation, ContentItem, Data, Definition, Document, List, ListItem, Paragraph, Session,
+    Verbatim,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -89,6 +91,7 @@ pub struct LexSerializer {
     verbatim_registry: VerbatimRegistry,
     skip_verbatim_lines: bool,
     formatted_verbatim_content: Option<String>,
+    title_references: Vec<Annotation>,
 }
 
 impl LexSerializer {
@@ -102,14 +105,63 @@ impl LexSerializer {
             verbatim_registry: VerbatimRegistry::default_with_standard(),
             skip_verbatim_lines: false,
             formatted_verbatim_content: None,
+            title_references: Vec::new(),
         }
     }
 
     pub fn serialize(mut self, doc: &Document) -> Result<String, String> {
+        // The document title is written first, so references after it wait
+        let title = &doc.root.title;
+        let title_end = title
+            .location
+            .as_ref()
+            .filter(|_| !title.as_string().is_empty())
+            .map(|range| range.span.end);
+        let (after_title, leading): (Vec<_>, Vec<_>) = doc
+            .annotations
+            .iter()
+            .filter(|annotation| is_file_reference(annotation))
+            .filter(|annotation| !is_trailing(annotation, &doc.root.children))
+            .partition(|annotation| {
+                title_end.is_some_and(|end| annotation.data.label.location.span.start > end)
+            });
+        for annotation in leading {
+            self.write_file_reference(annotation, false);
+        }
+        self.title_references = after_title.into_iter().cloned().collect();
         doc.root.accept(&mut self);
+        self.write_file_references(&doc.annotations, &doc.root.children, true);
         Ok(self.output)
     }
 
+    /// Write the file references (`:: doc.include src=... ::`) attached to an
+    /// element: those before it, or (`trailing`) those after its `children`
+    ///
+    /// The AST visitor does not walk attached annotations. Other attached
+    /// annotations are left out, but dropping a reference would drop the
+    /// content it stands for.
+    fn write_file_references(
+        &mut self,
+        annotations: &[Annotation],
+        children: &[ContentItem],
+        trailing: bool,
+    ) {
+        for annotation in annotations {
+            if is_file_reference(annotation) && is_trailing(annotation, children) == trailing {
+                self.write_file_reference(annotation, trailing);
+            }
+        }
+    }
+
+    fn write_file_reference(&mut self, annotation: &Annotation, trailing: bool) {
+        // Set off by blank lines so it attaches as it did when parsed
+        self.ensure_blank_lines(1);
+        self.write_line(&format!("{} ::", data_header(&annotation.data)));
+        if !trailing {
+            self.ensure_blank_lines(1);
+        }
+    }
+
     fn indent(&self) -> String {
         self.rules.indent_string.repeat(self.indent_level)
     }
@@ -150,24 +202,60 @@ impl LexSerializer {
     }
 }
 
+fn is_one_line(annotation: &Annotation) -> bool {
+    annotation.children.is_empty() || is_file_reference(annotation)
+}
+
+/// `:: label` and its parameters, values as written (quotes included)
+///
+/// Parameters are separated by commas; a space alone would run the next
+/// parameter into the previous value.
+fn data_header(data: &Data) -> String {
+    let mut header = format!(":: {}", data.label.value);
+    for (i, param) in data.parameters.iter().enumerate() {
+        header.push_str(if i == 0 { " " } else { ", " });
+        header.push_str(&param.key);
+        header.push('=');
+        header.push_str(&param.value);
+    }
+    header
+}
+
 impl Visitor for LexSerializer {
     fn visit_session(&mut self, session: &Session) {
         let title = session.title.as_string();
         if !title.is_empty() {
             self.ensure_blank_lines(self.rules.session_blank_lines_before);
+            self.write_file_references(session.annotations(), &session.children, false);
             self.write_line(title);
             self.ensure_blank_lines(self.rules.session_blank_lines_after);
             self.indent_level += 1;
+            for annotation in std::mem::take(&mut self.title_references) {
+                self.write_file_reference(&annotation, false);
+            }
         }
     }
 
     fn leave_session(&mut self, session: &Session) {
         if !session.title.as_string().is_empty() {
+            let (after, closing): (Vec<_>, Vec<_>) = session
+                .annotations()
+                .iter()
+                .filter(|annotation| is_file_reference(annotation))
+                .filter(|annotation| is_trailing(annotation, &session.children))
+                .partition(|annotation| follows_session(annotation, session));
+            for annotation in closing {
+                self.write_file_reference(annotation, true);
+            }
             self.indent_level -= 1;
+            for annotation in after {
+                self.write_file_reference(annotation, true);
+            }
         }
     }
 
-    fn visit_paragraph(&mut self, _paragraph: &Paragraph) {
+    fn visit_paragraph(&mut self, paragraph: &Paragraph) {
+        self.write_file_references(paragraph.annotations(), &[], false);
         // Paragraphs are handled by visiting TextLines
         // TODO: Investigate why some paragraphs are skipped during traversal when indentation is mixed.
         // See: https://github.com/lex-project/lex/issues/new?title=Parser+drops+paragraphs+with+mixed+indentation
@@ -192,6 +280,7 @@ impl Visitor for LexSerializer {
     }
 
     fn visit_list(&mut self, list: &List) {
+        self.write_file_references(list.annotations(), &[], false);
         // Use the SequenceMarker to determine marker type
         let marker_type = if let Some(marker) = &list.marker {
             use lex_core::lex::ast::elements::DecorationStyle;
@@ -227,6 +316,7 @@ impl Visitor for LexSerializer {
     }
 
     fn visit_list_item(&mut self, list_item: &ListItem) {
+        self.write_file_references(list_item.annotations(), &[], false);
         let is_extended = self
             .list_stack
             .iter()
@@ -281,6 +371,7 @@ impl Visitor for LexSerializer {
     }
 
     fn visit_definition(&mut self, definition: &Definition) {
+        self.write_file_references(definition.annotations(), &[], false);
         let subject = definition.subject.as_string();
         self.write_line(&format!("{subject}:"));
         self.indent_level += 1;
@@ -291,39 +382,30 @@ impl Visitor for LexSerializer {
     }
 
     fn visit_annotation(&mut self, annotation: &Annotation) {
-        let label = &annotation.data.label.value;
-        let params = &annotation.data.parameters;
-
-        let mut header = format!(":: {label}");
-        if !params.is_empty() {
-            for param in params {
-                header.push(' ');
-                header.push_str(&param.key);
-                header.push('=');
-                header.push_str(&param.value);
-            }
-        }
+        let mut header = data_header(&annotation.data);
 
-        // Only add closing :: for short-form annotations (no children)
-        if annotation.children.is_empty() {
+        // Short-form annotations (no children) and file references keep
+        // their one-line form; content needs the closing :: to read back
+        if is_one_line(annotation) || !is_short_form(annotation) {
             header.push_str(" ::");
         }
 
         self.write_line(&header);
 
-        if !annotation.children.is_empty() {
+        if !is_one_line(annotation) {
             self.indent_level += 1;
         }
     }
 
     fn leave_annotation(&mut self, annotation: &Annotation) {
-        if !annotation.children.is_empty() {
+        if !is_one_line(annotation) {
             self.indent_level -= 1;
             self.write_line("::");
         }
     }
 
     fn visit_verbatim_block(&mut self, verbatim: &Verbatim) {
+        self.write_file_references(verbatim.annotations(), &[], false);
         let label = &verbatim.closing_data.label.value;
 
         // Try to get formatted content from handler
@@ -368,17 +450,7 @@ impl Visitor for LexSerializer {
             }
         }
 
-        let label = &verbatim.closing_data.label.value;
-        let mut footer = format!(":: {label}");
-        if !verbatim.closing_data.parameters.is_empty() {
-            for param in &verbatim.closing_data.parameters {
-                footer.push(' ');
-                footer.push_str(&param.key);
-                footer.push('=');
-                footer.push_str(&param.value);
-            }
-        }
-        footer.push_str(" ::");
+        let footer = format!("{} ::", data_header(&verbatim.closing_data));
         self.write_line(&footer);
     }
 }
@@ -599,13 +671,44 @@ mod tests {
         // Document-level annotations should be preserved
         assert_eq!(
             formatted,
-            ":: note\n    This is an important note that requires a detailed explanation.\n::\n"
+            ":: note ::\n    This is an important note that requires a detailed explanation.\n::\n"
+        );
+    }
+
+    #[test]
+    fn test_file_reference_keeps_its_line() {
+        let source = "Guide\n\n    Intro.\n\n    :: doc.include src=\"setup.lex\" ::\n";
+        let format = super::super::LexFormat::default();
+        let doc = format.parse(source).unwrap();
+        let formatted = format.serialize(&doc).unwrap();
+        assert!(
+            formatted.contains("    Intro.\n\n    :: doc.include src=\"setup.lex\" ::\n"),
+            "{formatted}"
         );
     }
 
     // ==== Round-trip Tests ====
     // Format → parse → format should be idempotent
 
+    #[test]
+    fn test_round_trip_file_references() {
+        let source = "Intro.\n\n:: doc.include src=\"a.lex\" ::\n\nGuide\n\n    Read on.\n\n    :: doc.include src=\"b.lex\" ::\n";
+        let format = super::super::LexFormat::default();
+        let formatted = format.serialize(&format.parse(source).unwrap()).unwrap();
+        let formatted_again = format
+            .serialize(&format.parse(&formatted).unwrap())
+            .unwrap();
+        assert_text_eq(&formatted, &formatted_again);
+        assert!(
+            formatted.contains(":: doc.include src=\"a.lex\" ::"),
+            "{formatted}"
+        );
+        assert!(
+            formatted.contains(":: doc.include src=\"b.lex\" ::"),
+            "{formatted}"
+        );
+    }
+
     #[test]
     fn test_round_trip_paragraph_01() {
         let source = Lexplore::load(ElementType::Paragraph, 1).source();
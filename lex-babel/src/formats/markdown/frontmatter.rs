@@ -0,0 +1,197 @@
+//! YAML front matter ⇄ document metadata
+//!
+//! Only the subset of YAML that front matter uses in practice is handled:
+//! `key: value` scalars, flow lists (`[a, b]`), block lists (`- a`) and one
+//! level of nested mapping (`author:` followed by indented `name: ..` lines).
+//! Repeated keys are merged into a list on export, since YAML mappings cannot
+//! repeat a key.
+
+use crate::common::text::unquote;
+use crate::ir::nodes::{Metadata, MetadataValue};
+
+/// Parse the YAML between the `---` delimiters into metadata entries
+pub(crate) fn parse_front_matter(yaml: &str) -> Metadata {
+    let mut metadata = Metadata::default();
+    let lines: Vec<&str> = yaml
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .collect();
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+
+        if !value.is_empty() {
+            let value = if value.starts_with('[') {
+                MetadataValue::from_text(key, value)
+            } else {
+                MetadataValue::from_text(key, unquote(value))
+            };
+            metadata.push(key, value);
+            continue;
+        }
+
+        // Block value: indented list items or `field: value` pairs
+        let mut items = Vec::new();
+        let mut fields = Vec::new();
+        while index < lines.len() && lines[index].starts_with([' ', '\t']) {
+            let nested = lines[index].trim();
+            index += 1;
+            if let Some(item) = nested.strip_prefix('-') {
+                items.push(unquote(item.trim()).to_string());
+            } else if let Some((field, value)) = nested.split_once(':') {
+                fields.push((field.trim().to_string(), unquote(value.trim()).to_string()));
+            }
+        }
+
+        if !fields.is_empty() {
+            metadata.push(key, MetadataValue::Fields(fields));
+        } else if !items.is_empty() {
+            metadata.push(key, MetadataValue::List(items));
+        } else {
+            metadata.push(key, MetadataValue::Text(String::new()));
+        }
+    }
+    metadata
+}
+
+/// Render metadata as a front matter block, including the `---` delimiters
+///
+/// Returns an empty string for empty metadata.
+pub(crate) fn write_front_matter(metadata: &Metadata) -> String {
+    if metadata.is_empty() {
+        return String::new();
+    }
+
+    let mut yaml = String::from("---\n");
+    for key in metadata.keys() {
+        let values: Vec<&MetadataValue> = metadata
+            .entries
+            .iter()
+            .filter(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+            .collect();
+
+        match values.as_slice() {
+            [MetadataValue::Text(text)] => {
+                yaml.push_str(&format!("{key}: {}\n", yaml_scalar(text, false)));
+            }
+            [MetadataValue::Fields(fields)] => {
+                yaml.push_str(&format!("{key}:\n"));
+                for (field, value) in fields {
+                    yaml.push_str(&format!("  {field}: {}\n", yaml_scalar(value, false)));
+                }
+            }
+            values if values.iter().all(|v| matches!(v, MetadataValue::Fields(_))) => {
+                yaml.push_str(&format!("{key}:\n"));
+                for value in values {
+                    let MetadataValue::Fields(fields) = value else {
+                        continue;
+                    };
+                    for (position, (field, value)) in fields.iter().enumerate() {
+                        let bullet = if position == 0 { "-" } else { " " };
+                        yaml.push_str(&format!(
+                            "  {bullet} {field}: {}\n",
+                            yaml_scalar(value, false)
+                        ));
+                    }
+                }
+            }
+            values => {
+                let items: Vec<String> = values
+                    .iter()
+                    .flat_map(|value| value.items())
+                    .map(|item| yaml_scalar(&item, true))
+                    .collect();
+                yaml.push_str(&format!("{key}: [{}]\n", items.join(", ")));
+            }
+        }
+    }
+    yaml.push_str("---\n\n");
+    yaml
+}
+
+/// Quote a scalar when plain YAML would read it differently
+fn yaml_scalar(value: &str, in_flow: bool) -> String {
+    let needs_quotes = value.is_empty()
+        || value != value.trim()
+        || value.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.contains('\n')
+        || (in_flow && value.contains([',', '[', ']', '{', '}']))
+        || matches!(
+            value.to_ascii_lowercase().as_str(),
+            "true" | "false" | "yes" | "no" | "null" | "~"
+        );
+
+    if needs_quotes {
+        format!(
+            "\"{}\"",
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        )
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scalars_lists_and_fields() {
+        let metadata = parse_front_matter(
+            "title: \"My: Doc\"\ntags: [a, b]\nauthors:\n  - Ana\n  - Bo\nauthor:\n  name: Ana\n  email: ana@example.org\n",
+        );
+
+        assert_eq!(metadata.title().as_deref(), Some("My: Doc"));
+        assert_eq!(metadata.tags(), vec!["a", "b"]);
+        assert_eq!(
+            metadata.get("authors"),
+            Some(&MetadataValue::List(vec!["Ana".into(), "Bo".into()]))
+        );
+        assert_eq!(
+            metadata.get("author"),
+            Some(&MetadataValue::Fields(vec![
+                ("name".into(), "Ana".into()),
+                ("email".into(), "ana@example.org".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_write_merges_repeated_keys_and_quotes() {
+        let mut metadata = Metadata::default();
+        metadata.push("title", MetadataValue::Text("Notes: draft".into()));
+        metadata.push("author", MetadataValue::Text("Ana".into()));
+        metadata.push("author", MetadataValue::Text("Bo".into()));
+
+        assert_eq!(
+            write_front_matter(&metadata),
+            "---\ntitle: \"Notes: draft\"\nauthor: [Ana, Bo]\n---\n\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let yaml = "title: Report\ntags: [x, y]\nauthor:\n  name: Ana\n  email: ana@example.org\n";
+        let metadata = parse_front_matter(yaml);
+        assert_eq!(write_front_matter(&metadata), format!("---\n{yaml}---\n\n"));
+    }
+}
@@ -23,6 +23,9 @@
 //! | Definition       | **Term**: Description   | Bold term + colon + content            | Parse bold + colon pattern            |
 //! | Verbatim         | Code block (```)        | Language → info string                 | Info string → language                |
 //! | Annotation       | HTML comment            | `<!-- lex:label key=val -->` format    | Not implemented (annotations lost)    |
+//! | `:: toc ::`      | Nested link list        | Between `lex:toc` comments; `toc` option adds one | Plain list                 |
+//! | Table            | GFM pipe table          | HTML `<table>` if spans/caption/2+ header rows | Single header row, no spans   |
+//! | `doc.dot`        | Image                   | SVG file in `asset-dir`; code block without one | Code block                   |
 //! | InlineContent:   |                         |                                        |                                       |
 //! |   Text           | Plain text              | Direct                                 | Direct                                |
 //! |   Bold           | **bold** or __bold__    | Use **                                 | Parse both                            |
@@ -30,6 +33,8 @@
 //! |   Code           | `code`                  | Direct                                 | Direct                                |
 //! |   Math           | $math$ or $$math$$      | Use $...$                              | Parse if extension enabled            |
 //! |   Reference      | \[text\]                | Plain text (Lex refs are citations)    | Parse link/reference syntax           |
+//! |   Session ref    | \[#2.1\](#id)           | Linked heading gets `<a id>` anchor    | Plain link                            |
+//! |   Footnote ref   | \[^1\]                  | Notes session → `[^1]: ...` definitions | Definitions → Notes session, renumbered |
 //!
 //! # Lossy Conversions
 //!
@@ -38,9 +43,22 @@
 //! - Lex annotations → HTML comments (exported but not parsed on import)
 //! - Lex definition structure → bold text pattern (not native Markdown)
 //! - Lex references → plain text (citations, not URLs)
+//! - Tables with cell spans, captions or several header rows → raw HTML tables
 //! - Multiple blank lines → single blank line (Markdown normalization)
 //! - Verbatim post-wall indentation → lost (see issue #276)
 //!
+//! # Diagrams
+//!
+//! With the `asset-dir` option, `doc.dot` graphs are rendered to SVG files in
+//! that directory and linked by file name, so it should be the directory the
+//! Markdown is written to; the CLI passes the output file's. Without it, or
+//! when a graph does not render, the DOT source stays a code block (see
+//! [`crate::common::verbatim::dot`]).
+//!
+//! Deep sessions, unlinkable references and dropped raw HTML are also reported
+//! as warnings by [`crate::FormatRegistry::serialize_with_report`] and
+//! [`crate::FormatRegistry::parse_with_report`].
+//!
 //! # Architecture Notes
 //!
 //! There is a fundamental mismatch between Markdown's flat model and Lex's hierarchical structure.
@@ -76,16 +94,36 @@
 //!   - [x] Annotations (HTML comment parsing)
 //!   - [x] Definitions (pattern matching)
 
+mod frontmatter;
 pub mod parser;
 pub mod serializer;
 
+use crate::common::verbatim::dot::{report_dot_fallbacks, write_diagrams};
 use crate::error::FormatError;
-use crate::format::Format;
+use crate::format::{Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::references::resolve_references;
+use crate::ir::toc::{TocOptions, TOC_OPTIONS};
+use crate::options::{FormatOption, OptionType};
+use crate::report::{ConversionReport, IrLossCheck};
 use lex_core::lex::ast::Document;
+use std::path::Path;
 
 /// Format implementation for Markdown
 pub struct MarkdownFormat;
 
+const MARKDOWN_OPTIONS: &[FormatOption] = &[
+    TOC_OPTIONS[0],
+    TOC_OPTIONS[1],
+    TOC_OPTIONS[2],
+    FormatOption::new(
+        "asset-dir",
+        OptionType::Path,
+        "Directory to write rendered diagrams to, next to the output (default: keep their source)",
+    ),
+];
+
 impl Format for MarkdownFormat {
     fn name(&self) -> &str {
         "markdown"
@@ -99,6 +137,10 @@ impl Format for MarkdownFormat {
         &["md", "markdown"]
     }
 
+    fn options(&self) -> &[FormatOption] {
+        MARKDOWN_OPTIONS
+    }
+
     fn supports_parsing(&self) -> bool {
         true
     }
@@ -111,7 +153,66 @@ impl Format for MarkdownFormat {
         parser::parse_from_markdown(source)
     }
 
+    fn parse_with_context(
+        &self,
+        source: &str,
+        ctx: &ConversionContext,
+    ) -> Result<Document, FormatError> {
+        let ir_doc = parser::parse_markdown_to_ir(source)?;
+        Ok(crate::from_ir_with_context(&ir_doc, ctx))
+    }
+
     fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
         serializer::serialize_to_markdown(doc)
     }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &std::collections::HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &crate::ir::nodes::Document,
+        options: &std::collections::HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let toc = TocOptions::from_format_options(self.name(), options)?;
+        let title = document_title(source);
+        let title = (!title.is_empty()).then(|| title.to_string());
+        let mut ir = ir.clone();
+        if let Some(dir) = options.get("asset-dir") {
+            write_diagrams(&mut ir, Path::new(dir))?;
+        }
+        serializer::serialize_ir_to_markdown(ir, title, toc).map(SerializedDocument::Text)
+    }
+
+    fn report_parse_losses(&self, source: &str, report: &mut ConversionReport) {
+        parser::report_dropped_html(source, report);
+    }
+
+    fn report_serialize_losses(
+        &self,
+        ir: &crate::ir::nodes::Document,
+        report: &mut ConversionReport,
+    ) {
+        // Checked as serialized: references to sessions become links first
+        let mut resolved = ir.clone();
+        resolve_references(&mut resolved);
+        IrLossCheck {
+            format: "Markdown",
+            max_heading_level: Some(6),
+            linkable: Some(|target| serializer::reference_url(target).is_some()),
+        }
+        .run(&resolved, report);
+        report_dot_fallbacks(ir, report);
+    }
 }
@@ -3,32 +3,240 @@
 //! Converts CommonMark Markdown to Lex documents.
 //! Pipeline: Markdown string → Comrak AST → Events → IR → Lex AST
 
+use super::frontmatter::parse_front_matter;
 use crate::common::flat_to_nested::events_to_tree;
 use crate::error::FormatError;
 use crate::ir::events::Event;
-use crate::ir::nodes::{InlineContent, TableCellAlignment};
-use comrak::nodes::{AstNode, NodeValue, TableAlignment};
+use crate::ir::footnotes::{notes_session, Footnote};
+use crate::ir::nodes::{
+    DocNode, Document as IrDocument, InlineContent, Link, Metadata, SourcePosition, SourceSpan,
+    TableCellAlignment,
+};
+use crate::ir::visit::{walk_document_mut, walk_inline_mut, VisitorMut};
+use crate::report::{ConversionReport, ConversionWarning, WarningKind};
+use comrak::nodes::{Ast, AstNode, NodeLink, NodeValue, TableAlignment};
 use comrak::{parse_document, Arena, ComrakOptions};
 use lex_core::lex::ast::Document;
+use std::collections::HashMap;
 
 /// Parse Markdown string to Lex document
 pub fn parse_from_markdown(source: &str) -> Result<Document, FormatError> {
-    // Step 1: Parse Markdown string to Comrak AST
-    let arena = Arena::new();
-    let options = default_comrak_options();
-    let root = parse_document(&arena, source, &options);
+    let ir_doc = parse_markdown_to_ir(source)?;
 
-    // Step 2: Convert Comrak AST to IR events
-    let events = comrak_ast_to_events(root)?;
+    // Step 4: Convert IR to Lex AST
+    let lex_doc = crate::from_ir(&ir_doc);
+    Ok(lex_doc)
+}
+
+/// Parse Markdown string to an IR document
+pub fn parse_markdown_to_ir(source: &str) -> Result<IrDocument, FormatError> {
+    // Steps 1-2: Markdown string → Comrak AST → IR events (+ front matter)
+    let (events, metadata) = parse_markdown(source, true)?;
 
     // Step 3: Convert events to IR tree
-    let ir_doc = events_to_tree(&events).map_err(|e| {
+    let mut ir_doc = events_to_tree(&events).map_err(|e| {
         FormatError::ParseError(format!("Failed to build IR tree from events: {e}"))
     })?;
+    ir_doc.metadata = metadata;
+    collect_footnotes(&mut ir_doc);
+    Ok(ir_doc)
+}
 
-    // Step 4: Convert IR to Lex AST
-    let lex_doc = crate::from_ir(&ir_doc);
-    Ok(lex_doc)
+/// Move footnote definitions into a trailing Notes session
+///
+/// Comrak puts the definitions, as `footnote` annotations, at the end of the
+/// document in the order they are first cited; after nesting they sit under
+/// the last heading. Lex notes are numbered, so named footnotes (`[^why]`)
+/// are renumbered in that order and their references follow.
+fn collect_footnotes(doc: &mut IrDocument) {
+    let mut notes = Vec::new();
+    let mut numbers = HashMap::new();
+    take_footnotes(&mut doc.children, &mut notes, &mut numbers);
+    if notes.is_empty() {
+        return;
+    }
+
+    walk_document_mut(&mut Renumber { numbers: &numbers }, doc);
+    let level = doc
+        .children
+        .iter()
+        .filter_map(|node| match node {
+            DocNode::Heading(heading) => Some(heading.level),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(1);
+    doc.children.push(notes_session(level, notes));
+}
+
+/// Remove `footnote` annotations from `nodes` and the headings below them
+fn take_footnotes(
+    nodes: &mut Vec<DocNode>,
+    notes: &mut Vec<Footnote>,
+    numbers: &mut HashMap<String, String>,
+) {
+    nodes.retain_mut(|node| match node {
+        DocNode::Annotation(annotation) if annotation.label == "footnote" => {
+            let name = annotation
+                .parameters
+                .iter()
+                .find(|(key, _)| key == "id")
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            let label = (notes.len() + 1).to_string();
+            numbers.insert(name, label.clone());
+            notes.push(Footnote {
+                label,
+                content: annotation.content.clone(),
+                span: annotation.span.clone(),
+            });
+            false
+        }
+        DocNode::Heading(heading) => {
+            take_footnotes(&mut heading.children, notes, numbers);
+            true
+        }
+        _ => true,
+    });
+}
+
+struct Renumber<'a> {
+    numbers: &'a HashMap<String, String>,
+}
+
+impl VisitorMut for Renumber<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        if let InlineContent::FootnoteRef(label) = inline {
+            if let Some(number) = self.numbers.get(label.as_str()) {
+                *label = number.clone();
+            }
+        }
+        walk_inline_mut(self, inline);
+    }
+}
+
+/// Parse a Markdown string into a flat IR event stream
+///
+/// When `extract_title` is set, a leading H1 is treated as the document title
+/// (as in a full Markdown import). Formats that splice several Markdown
+/// fragments into one document only want this for the first fragment. Front
+/// matter is not part of the event stream, and neither are source spans, as
+/// they would point into the fragment rather than the spliced document.
+pub(crate) fn markdown_to_events(
+    source: &str,
+    extract_title: bool,
+) -> Result<Vec<Event>, FormatError> {
+    parse_markdown(source, extract_title).map(|(events, _)| {
+        events
+            .into_iter()
+            .filter(|event| !matches!(event, Event::Source(_)))
+            .collect()
+    })
+}
+
+fn parse_markdown(
+    source: &str,
+    extract_title: bool,
+) -> Result<(Vec<Event>, Metadata), FormatError> {
+    let arena = Arena::new();
+    let options = default_comrak_options();
+    let root = parse_document(&arena, source, &options);
+
+    let metadata = root
+        .children()
+        .find_map(|child| match &child.data.borrow().value {
+            NodeValue::FrontMatter(content) => {
+                // Comrak keeps the delimiters in the literal
+                let yaml = content
+                    .trim()
+                    .trim_start_matches("---")
+                    .trim_end_matches("---");
+                Some(parse_front_matter(yaml))
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let mut events = comrak_ast_to_events(root, extract_title)?;
+    resolve_byte_offsets(&mut events, source);
+    Ok((events, metadata))
+}
+
+/// Source span of a block, from comrak's 1-based, end-inclusive positions
+///
+/// Comrak only reports lines and columns; the byte range is filled in by
+/// [`resolve_byte_offsets`].
+fn source_span(node: &Ast) -> SourceSpan {
+    let pos = node.sourcepos;
+    SourceSpan {
+        bytes: 0..0,
+        start: SourcePosition::new(
+            pos.start.line.saturating_sub(1),
+            pos.start.column.saturating_sub(1),
+        ),
+        end: SourcePosition::new(pos.end.line.saturating_sub(1), pos.end.column),
+    }
+}
+
+fn resolve_byte_offsets(events: &mut [Event], source: &str) {
+    let line_starts = line_starts(source);
+    for event in events {
+        if let Event::Source(span) = event {
+            resolve_span_bytes(span, &line_starts, source.len());
+        }
+    }
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+fn resolve_span_bytes(span: &mut SourceSpan, line_starts: &[usize], len: usize) {
+    let offset = |pos: SourcePosition| {
+        line_starts
+            .get(pos.line)
+            .map_or(len, |start| (start + pos.column).min(len))
+    };
+    span.bytes = offset(span.start)..offset(span.end);
+}
+
+/// Report raw HTML the import drops
+///
+/// Lex annotation comments (`<!-- lex:... -->`) are imported; any other HTML
+/// block or inline tag has no Lex equivalent.
+pub(crate) fn report_dropped_html(source: &str, report: &mut ConversionReport) {
+    let arena = Arena::new();
+    let options = default_comrak_options();
+    let root = parse_document(&arena, source, &options);
+    let line_starts = line_starts(source);
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let (html, what) = match &data.value {
+            NodeValue::HtmlBlock(html) => {
+                if parse_lex_annotation(&html.literal).is_some()
+                    || parse_lex_annotation_close(&html.literal).is_some()
+                {
+                    continue;
+                }
+                (html.literal.as_str(), "HTML block")
+            }
+            NodeValue::HtmlInline(html) => (html.as_str(), "Inline HTML"),
+            _ => continue,
+        };
+        let mut span = source_span(&data);
+        resolve_span_bytes(&mut span, &line_starts, source.len());
+        let snippet: String = html.trim().chars().take(40).collect();
+        report.push(
+            ConversionWarning::new(
+                WarningKind::ContentDropped,
+                format!("{what} `{snippet}` has no Lex equivalent and is dropped"),
+            )
+            .with_span(Some(span)),
+        );
+    }
 }
 
 fn default_comrak_options() -> ComrakOptions<'static> {
@@ -38,6 +246,7 @@ fn default_comrak_options() -> ComrakOptions<'static> {
     options.extension.autolink = true;
     options.extension.tasklist = true;
     options.extension.superscript = true;
+    options.extension.footnotes = true;
     options.extension.front_matter_delimiter = Some("---".to_string());
     options
 }
@@ -45,14 +254,20 @@ fn default_comrak_options() -> ComrakOptions<'static> {
 type DefinitionPieces = Option<(Vec<InlineContent>, Vec<InlineContent>)>;
 
 /// Convert Comrak AST to IR events
-fn comrak_ast_to_events<'a>(root: &'a AstNode<'a>) -> Result<Vec<Event>, FormatError> {
+fn comrak_ast_to_events<'a>(
+    root: &'a AstNode<'a>,
+    extract_title: bool,
+) -> Result<Vec<Event>, FormatError> {
     let mut events = vec![Event::StartDocument];
 
     // Check if first child is an H1 heading - if so, treat it as document title
-    let mut children_iter = root.children().peekable();
+    let mut children_iter = root
+        .children()
+        .filter(|child| !matches!(child.data.borrow().value, NodeValue::FrontMatter(_)))
+        .peekable();
     let mut document_title: Option<String> = None;
 
-    if let Some(first_child) = children_iter.peek() {
+    if let Some(first_child) = children_iter.peek().filter(|_| extract_title) {
         if let NodeValue::Heading(heading) = &first_child.data.borrow().value {
             if heading.level == 1 {
                 // Extract H1 text as document title
@@ -103,6 +318,21 @@ fn collect_events_from_node<'a>(
 ) -> Result<(), FormatError> {
     let node_data = node.data.borrow();
 
+    if matches!(
+        node_data.value,
+        NodeValue::Heading(_)
+            | NodeValue::Paragraph
+            | NodeValue::List(_)
+            | NodeValue::Item(_)
+            | NodeValue::TaskItem(_)
+            | NodeValue::CodeBlock(_)
+            | NodeValue::BlockQuote
+            | NodeValue::FootnoteDefinition(_)
+            | NodeValue::Table(_)
+    ) {
+        events.push(Event::Source(source_span(&node_data)));
+    }
+
     match &node_data.value {
         NodeValue::Document => {
             // Skip document wrapper, process children
@@ -156,8 +386,12 @@ fn collect_events_from_node<'a>(
             events.push(Event::EndList);
         }
 
-        NodeValue::Item(_) => {
-            events.push(Event::StartListItem);
+        NodeValue::Item(_) | NodeValue::TaskItem(_) => {
+            let checked = match &node_data.value {
+                NodeValue::TaskItem(symbol) => Some(symbol.is_some()),
+                _ => None,
+            };
+            events.push(Event::StartListItem { checked });
 
             // Process list item content
             collect_children_with_definitions(node.children(), events)?;
@@ -204,61 +438,33 @@ fn collect_events_from_node<'a>(
             // Otherwise skip HTML blocks
         }
 
-        NodeValue::FrontMatter(content) => {
-            // Parse YAML frontmatter
-            // We'll treat it as a special annotation with label "frontmatter"
-            // and parameters derived from the YAML content (flattened)
-
-            let yaml_str = content.trim();
-            // Remove delimiters if present (Comrak might include them or not depending on version/options)
-            let yaml_str = yaml_str
-                .trim_start_matches("---")
-                .trim_end_matches("---")
-                .trim();
-
-            let mut parameters = vec![];
-
-            // Simple YAML parsing: key: value
-            // For a real implementation, we should use serde_yaml, but for now we'll do basic parsing
-            // to avoid adding a dependency if possible, or we can just store the raw content
-            // as a special parameter if we want to be safe.
-            // Let's try to parse simple key-value pairs.
-
-            for line in yaml_str.lines() {
-                if let Some((key, value)) = line.split_once(':') {
-                    let key = key.trim().to_string();
-                    let value = value.trim().to_string();
-                    // Remove quotes if present
-                    let value = value.trim_matches('"').trim_matches('\'').to_string();
-
-                    // Handle arrays like [a, b] -> just store as string for now
-                    parameters.push((key, value));
-                }
-            }
-
-            events.push(Event::StartAnnotation {
-                label: "frontmatter".to_string(),
-                parameters,
-            });
-            events.push(Event::EndAnnotation {
-                label: "frontmatter".to_string(),
-            });
+        NodeValue::FrontMatter(_) => {
+            // Read into document metadata by `parse_markdown`
         }
 
         NodeValue::ThematicBreak => {
-            // Thematic breaks (---) don't have direct Lex equivalent, skip
+            events.push(Event::ThematicBreak);
         }
 
         NodeValue::BlockQuote => {
-            // Block quotes don't have direct Lex equivalent
-            // Process children as regular content
-            for child in node.children() {
-                collect_events_from_node(child, events)?;
-            }
+            events.push(Event::StartBlockQuote);
+            collect_children_with_definitions(node.children(), events)?;
+            events.push(Event::EndBlockQuote);
+        }
+
+        NodeValue::FootnoteDefinition(footnote) => {
+            // Becomes a note of the Notes session in `collect_footnotes`
+            let label = "footnote".to_string();
+            events.push(Event::StartAnnotation {
+                label: label.clone(),
+                parameters: vec![("id".to_string(), footnote.name.clone())],
+            });
+            collect_children_with_definitions(node.children(), events)?;
+            events.push(Event::EndAnnotation { label });
         }
 
         NodeValue::Table(_) => {
-            events.push(Event::StartTable);
+            events.push(Event::StartTable { caption: None });
             for child in node.children() {
                 collect_events_from_node(child, events)?;
             }
@@ -275,7 +481,12 @@ fn collect_events_from_node<'a>(
 
         NodeValue::TableCell => {
             let (header, align) = get_table_cell_info(node);
-            events.push(Event::StartTableCell { header, align });
+            events.push(Event::StartTableCell {
+                header,
+                align,
+                colspan: 1,
+                rowspan: 1,
+            });
 
             events.push(Event::StartParagraph);
             for child in node.children() {
@@ -370,8 +581,10 @@ fn collect_inline_events<'a>(
             events.push(Event::Inline(InlineContent::Code(code.literal.clone())));
         }
 
-        NodeValue::Link(link) => {
-            events.push(Event::Inline(InlineContent::Reference(link.url.clone())));
+        NodeValue::Link(_) | NodeValue::Strikethrough | NodeValue::FootnoteReference(_) => {
+            let mut content = vec![];
+            collect_inline_content(node, &mut content)?;
+            events.extend(content.into_iter().map(Event::Inline));
         }
 
         NodeValue::SoftBreak | NodeValue::LineBreak => {
@@ -441,8 +654,24 @@ fn collect_inline_content<'a>(
             content.push(InlineContent::Code(code.literal.clone()));
         }
 
+        NodeValue::Strikethrough => {
+            let mut children = vec![];
+            for child in node.children() {
+                collect_inline_content(child, &mut children)?;
+            }
+            content.push(InlineContent::Strikethrough(children));
+        }
+
         NodeValue::Link(link) => {
-            content.push(InlineContent::Reference(link.url.clone()));
+            let mut children = vec![];
+            for child in node.children() {
+                collect_inline_content(child, &mut children)?;
+            }
+            content.push(link_content(link, children));
+        }
+
+        NodeValue::FootnoteReference(footnote) => {
+            content.push(InlineContent::FootnoteRef(footnote.name.clone()));
         }
 
         NodeValue::SoftBreak | NodeValue::LineBreak => {
@@ -455,6 +684,28 @@ fn collect_inline_content<'a>(
     Ok(())
 }
 
+/// A link whose text is just its URL (autolinks, `<https://..>`) stays a bare
+/// reference; anything else keeps its text.
+fn link_content(link: &NodeLink, children: Vec<InlineContent>) -> InlineContent {
+    let is_bare = match children.as_slice() {
+        [] => true,
+        [InlineContent::Text(text)] => {
+            *text == link.url || link.url.strip_prefix("mailto:") == Some(text.as_str())
+        }
+        _ => false,
+    };
+
+    if is_bare {
+        InlineContent::Reference(link.url.clone())
+    } else {
+        InlineContent::Link(Link {
+            url: link.url.clone(),
+            content: children,
+            title: (!link.title.is_empty()).then(|| link.title.clone()),
+        })
+    }
+}
+
 /// Determine if a node is a heading (used to know when to stop collecting
 /// definition description siblings).
 fn is_heading_node(node: &AstNode<'_>) -> bool {
@@ -511,8 +762,10 @@ fn try_parse_definition_term<'a>(node: &'a AstNode<'a>) -> Result<DefinitionPiec
             // Only collect additional inline nodes after we have seen the colon
             NodeValue::Strong
             | NodeValue::Emph
+            | NodeValue::Strikethrough
             | NodeValue::Code(_)
             | NodeValue::Link(_)
+            | NodeValue::FootnoteReference(_)
             | NodeValue::SoftBreak
             | NodeValue::LineBreak => {
                 if !saw_colon {
@@ -722,4 +975,23 @@ mod tests {
         });
         assert!(has_table, "Document should contain an aligned table");
     }
+
+    #[test]
+    fn test_block_source_spans() {
+        let md = "Intro line.\n\n- one\n- two\n";
+        let (events, _) = parse_markdown(md, false).unwrap();
+        let spans: Vec<&SourceSpan> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Source(span) => Some(span),
+                _ => None,
+            })
+            .collect();
+
+        // Paragraph, list, two items and their paragraphs
+        assert_eq!(spans.len(), 6);
+        assert_eq!(&md[spans[0].bytes.clone()], "Intro line.");
+        assert_eq!(spans[1].start, SourcePosition::new(2, 0));
+        assert_eq!(&md[spans[2].bytes.clone()], "- one");
+    }
 }
@@ -3,36 +3,78 @@
 //! Converts Lex documents to CommonMark Markdown.
 //! Pipeline: Lex AST → IR → Events → Comrak AST → Markdown string
 
+use super::frontmatter::write_front_matter;
 use crate::common::nested_to_flat::tree_to_events;
 use crate::error::FormatError;
 use crate::ir::events::Event;
-use crate::ir::nodes::{DocNode, InlineContent, TableCellAlignment};
+use crate::ir::footnotes::{take_notes, unlink_missing_notes, Footnote};
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{DocNode, Document as IrDocument, InlineContent, TableCellAlignment};
+use crate::ir::references::resolve_references;
+use crate::ir::toc::{fill_toc, TocOptions};
 use comrak::nodes::{Ast, AstNode, ListDelimType, ListType, NodeTable, NodeValue, TableAlignment};
 use comrak::{format_commonmark, Arena, ComrakOptions};
 use lex_core::lex::ast::Document;
 use std::cell::RefCell;
+use std::collections::HashSet;
 
 /// Serialize a Lex document to Markdown
 pub fn serialize_to_markdown(doc: &Document) -> Result<String, FormatError> {
     // Extract document title before IR conversion (which loses it)
-    let document_title = doc.root.title.as_string();
-    let document_title = if document_title.is_empty() {
+    let title = document_title(doc);
+    let document_title = if title.is_empty() {
         None
     } else {
-        Some(document_title.to_string())
+        Some(title.to_string())
     };
 
     // Step 1: Lex AST → IR
     let ir_doc = crate::to_ir(doc);
 
+    serialize_ir_to_markdown(ir_doc, document_title, None)
+}
+
+/// Serialize an IR document to Markdown
+///
+/// Shared by formats that embed Markdown fragments (e.g. notebook cells), so
+/// they go through the same Events → Comrak pipeline as a full export.
+/// `toc` inserts a table of contents when there is no `:: toc ::` placeholder.
+pub(crate) fn serialize_ir_to_markdown(
+    mut ir_doc: IrDocument,
+    document_title: Option<String>,
+    toc: Option<TocOptions>,
+) -> Result<String, FormatError> {
+    let front_matter = write_front_matter(&ir_doc.metadata);
+
+    // Sessions get ids, and references to them become links to those ids
+    resolve_references(&mut ir_doc);
+    // The notes session becomes GFM footnote definitions
+    let notes = take_notes(&mut ir_doc);
+    unlink_missing_notes(&mut ir_doc, &notes);
+    fill_toc(&mut ir_doc, toc);
+
     // Step 2: IR → Events
     let events = tree_to_events(&DocNode::Document(ir_doc));
 
-    // Step 3: Events → Comrak AST
+    // Steps 3 and 4: Events → Comrak AST → Markdown string
+    let mut cleaned = events_to_markdown(&events)?;
+    for note in &notes {
+        cleaned.push('\n');
+        cleaned.push_str(&footnote_definition(note)?);
+    }
+
+    // Prepend document title as H1 heading if present
+    let with_title = prepend_title_as_h1(&cleaned, document_title);
+
+    // Metadata goes first as YAML front matter
+    Ok(format!("{front_matter}{with_title}"))
+}
+
+/// Render events as Markdown through a Comrak AST
+fn events_to_markdown(events: &[Event]) -> Result<String, FormatError> {
     let arena = Arena::new();
-    let root = build_comrak_ast(&arena, &events)?;
+    let root = build_comrak_ast(&arena, events)?;
 
-    // Step 4: Comrak AST → Markdown string (using comrak's serializer)
     let mut output = Vec::new();
     let options = default_comrak_options();
     format_commonmark(root, &options, &mut output).map_err(|e| {
@@ -43,12 +85,33 @@ pub fn serialize_to_markdown(doc: &Document) -> Result<String, FormatError> {
         .map_err(|e| FormatError::SerializationError(format!("UTF-8 conversion failed: {e}")))?;
 
     // Remove Comrak's "end list" HTML comments which appear between consecutive lists
-    let cleaned = markdown.replace("<!-- end list -->\n\n", "");
-
-    // Prepend document title as H1 heading if present
-    let with_title = prepend_title_as_h1(&cleaned, document_title);
+    Ok(markdown.replace("<!-- end list -->\n\n", ""))
+}
 
-    Ok(with_title)
+/// A GFM footnote definition: `[^1]: text`, with any further blocks indented
+fn footnote_definition(note: &Footnote) -> Result<String, FormatError> {
+    let events: Vec<Event> = note.content.iter().flat_map(tree_to_events).collect();
+    let body = events_to_markdown(&events)?;
+
+    let mut definition = format!("[^{}]:", note.label);
+    for (i, line) in body.trim_end().lines().enumerate() {
+        match (i, line.is_empty()) {
+            (_, true) => {}
+            (0, false) => {
+                definition.push(' ');
+                definition.push_str(line);
+            }
+            (_, false) => {
+                definition.push_str("    ");
+                definition.push_str(line);
+            }
+        }
+        definition.push('\n');
+    }
+    if !definition.ends_with('\n') {
+        definition.push('\n');
+    }
+    Ok(definition)
 }
 
 /// Prepend document title as an H1 heading
@@ -107,7 +170,23 @@ fn build_comrak_ast<'a>(
     // State for handling table cells (flatten paragraphs inside cells)
     let mut in_table_cell = false;
 
-    for event in events {
+    // Set while skipping the events of a table already written as HTML
+    let mut skipped_table_depth = 0usize;
+
+    // Heading ids that some link points at; only those get an explicit anchor
+    let linked_ids = internal_link_targets(events);
+    let mut pending_anchor: Option<&String> = None;
+
+    for (index, event) in events.iter().enumerate() {
+        if skipped_table_depth > 0 {
+            match event {
+                Event::StartTable { .. } => skipped_table_depth += 1,
+                Event::EndTable => skipped_table_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
         match event {
             Event::StartDocument => {
                 // Already created root
@@ -117,6 +196,10 @@ fn build_comrak_ast<'a>(
                 // Done
             }
 
+            Event::Anchor(id) => {
+                pending_anchor = Some(id);
+            }
+
             Event::StartHeading(level) => {
                 // Headings can only contain inline content, not block elements
                 // Create heading and set it as target for inline content
@@ -128,6 +211,15 @@ fn build_comrak_ast<'a>(
                     (0, 0).into(),
                 ))));
                 current_parent.append(heading_node);
+                // Renderers derive their own heading slugs, which need not
+                // match ours, so linked headings carry the anchor themselves
+                if let Some(id) = pending_anchor.take().filter(|id| linked_ids.contains(*id)) {
+                    let anchor = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                        NodeValue::HtmlInline(format!("<a id=\"{id}\"></a>")),
+                        (0, 0).into(),
+                    ))));
+                    heading_node.append(anchor);
+                }
                 current_heading = Some(heading_node);
                 // Note: We do NOT change current_parent or push to parent_stack
                 // Block content after this heading will be siblings at document level
@@ -207,11 +299,13 @@ fn build_comrak_ast<'a>(
                 })?;
             }
 
-            Event::StartListItem => {
+            Event::StartListItem { checked } => {
                 current_heading = None;
 
-                let item_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
-                    NodeValue::Item(comrak::nodes::NodeList {
+                let value = match checked {
+                    // Task items take their marker from the enclosing list
+                    Some(checked) => NodeValue::TaskItem(checked.then_some('x')),
+                    None => NodeValue::Item(comrak::nodes::NodeList {
                         list_type: ListType::Bullet,
                         marker_offset: 0,
                         padding: 0,
@@ -220,8 +314,9 @@ fn build_comrak_ast<'a>(
                         bullet_char: b'-',
                         tight: true, // Tight items don't add extra spacing
                     }),
-                    (0, 0).into(),
-                ))));
+                };
+                let item_node =
+                    arena.alloc(AstNode::new(RefCell::new(Ast::new(value, (0, 0).into()))));
                 current_parent.append(item_node);
                 parent_stack.push(current_parent);
                 current_parent = item_node;
@@ -409,24 +504,6 @@ fn build_comrak_ast<'a>(
                 }
             }
 
-            Event::StartAnnotation { label, parameters } if label == "frontmatter" => {
-                // Serialize as YAML frontmatter
-                let mut yaml = String::from("---\n");
-                for (key, value) in parameters {
-                    // Simple YAML serialization
-                    // If value contains special chars, we might need quoting, but for now simple string
-                    yaml.push_str(&format!("{key}: {value}\n"));
-                }
-                yaml.push_str("---\n\n");
-
-                let frontmatter_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
-                    NodeValue::FrontMatter(yaml),
-                    (0, 0).into(),
-                ))));
-                current_parent.append(frontmatter_node);
-                // No need to push to stack or change current_parent as FrontMatter is a leaf block
-            }
-
             Event::StartAnnotation { label, parameters } => {
                 current_heading = None;
 
@@ -521,10 +598,6 @@ fn build_comrak_ast<'a>(
                 current_parent.append(html_node);
             }
 
-            Event::EndAnnotation { label } if label == "frontmatter" => {
-                // Nothing to do, FrontMatter node is self-contained
-            }
-
             Event::EndAnnotation { label } => {
                 // Closing annotation comment with label-specific tag
                 let closing_tag = format!("<!-- /lex:{label} -->");
@@ -599,8 +672,22 @@ fn build_comrak_ast<'a>(
                 // Nothing needed
             }
 
-            Event::StartTable => {
+            Event::StartTable { .. } => {
                 current_heading = None;
+
+                if let Some(html) = html_table_fallback(&events[index..])? {
+                    let html_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                        NodeValue::HtmlBlock(comrak::nodes::NodeHtmlBlock {
+                            block_type: 6,
+                            literal: html,
+                        }),
+                        (0, 0).into(),
+                    ))));
+                    current_parent.append(html_node);
+                    skipped_table_depth = 1;
+                    continue;
+                }
+
                 let table_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
                     NodeValue::Table(NodeTable {
                         alignments: vec![],
@@ -637,7 +724,7 @@ fn build_comrak_ast<'a>(
                 })?;
             }
 
-            Event::StartTableCell { header: _, align } => {
+            Event::StartTableCell { align, .. } => {
                 let cell_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
                     NodeValue::TableCell,
                     (0, 0).into(),
@@ -679,6 +766,36 @@ fn build_comrak_ast<'a>(
                 })?;
             }
 
+            Event::StartBlockQuote => {
+                current_heading = None;
+                let quote_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                    NodeValue::BlockQuote,
+                    (0, 0).into(),
+                ))));
+                current_parent.append(quote_node);
+                parent_stack.push(current_parent);
+                current_parent = quote_node;
+            }
+
+            Event::EndBlockQuote => {
+                current_parent = parent_stack.pop().ok_or_else(|| {
+                    FormatError::SerializationError("Unbalanced block quote end".to_string())
+                })?;
+            }
+
+            Event::ThematicBreak => {
+                current_heading = None;
+                let rule_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                    NodeValue::ThematicBreak,
+                    (0, 0).into(),
+                ))));
+                current_parent.append(rule_node);
+            }
+
+            Event::Source(_) => {
+                // Markdown has nowhere to record source positions
+            }
+
             Event::Image(image) => {
                 // Render as paragraph with image
                 let para_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
@@ -748,6 +865,43 @@ fn build_comrak_ast<'a>(
 }
 
 /// Add inline content to a comrak node
+/// Render the table starting at `events[0]` as HTML when GFM cannot express it
+///
+/// GFM tables have exactly one header row and no spans or captions; tables
+/// using any of those are written as an HTML block instead.
+fn html_table_fallback(events: &[Event]) -> Result<Option<String>, FormatError> {
+    let mut depth = 0;
+    let mut end = events.len();
+    let mut header_rows = 0;
+    let mut needs_html = false;
+
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::StartTable { caption } => {
+                depth += 1;
+                needs_html |= caption.is_some();
+            }
+            Event::EndTable => {
+                depth -= 1;
+                if depth == 0 {
+                    end = index + 1;
+                    break;
+                }
+            }
+            Event::StartTableRow { header: true } if depth == 1 => header_rows += 1,
+            Event::StartTableCell {
+                colspan, rowspan, ..
+            } => needs_html |= *colspan > 1 || *rowspan > 1,
+            _ => {}
+        }
+    }
+
+    if !needs_html && header_rows <= 1 {
+        return Ok(None);
+    }
+    crate::formats::html::serialize_events_html(&events[..end]).map(Some)
+}
+
 fn add_inline_to_node<'a>(
     arena: &'a Arena<AstNode<'a>>,
     parent: &'a AstNode<'a>,
@@ -800,21 +954,7 @@ fn add_inline_to_node<'a>(
         }
 
         InlineContent::Reference(ref_text) => {
-            // Lex references can be URLs, anchors, citations, or placeholders.
-            // Try to convert known types to Markdown links.
-            let url = if ref_text.starts_with("http")
-                || ref_text.starts_with('/')
-                || ref_text.starts_with("./")
-                || ref_text.starts_with('#')
-            {
-                Some(ref_text.clone())
-            } else {
-                ref_text
-                    .strip_prefix('@')
-                    .map(|citation| format!("#ref-{citation}"))
-            };
-
-            if let Some(url) = url {
+            if let Some(url) = reference_url(ref_text) {
                 let link_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
                     NodeValue::Link(comrak::nodes::NodeLink {
                         url,
@@ -901,11 +1041,96 @@ fn add_inline_to_node<'a>(
             ))));
             parent.append(text_node);
         }
+
+        InlineContent::Strikethrough(children) => {
+            let strike_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                NodeValue::Strikethrough,
+                (0, 0).into(),
+            ))));
+            parent.append(strike_node);
+            for child in children {
+                add_inline_to_node(arena, strike_node, child)?;
+            }
+        }
+
+        InlineContent::Link(link) => {
+            let link_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                NodeValue::Link(comrak::nodes::NodeLink {
+                    url: link.url.clone(),
+                    title: link.title.clone().unwrap_or_default(),
+                }),
+                (0, 0).into(),
+            ))));
+            parent.append(link_node);
+            for child in &link.content {
+                add_inline_to_node(arena, link_node, child)?;
+            }
+        }
+
+        InlineContent::FootnoteRef(label) => {
+            // GFM footnote syntax; written raw so the brackets are not escaped
+            let footnote_node = arena.alloc(AstNode::new(RefCell::new(Ast::new(
+                NodeValue::HtmlInline(format!("[^{label}]")),
+                (0, 0).into(),
+            ))));
+            parent.append(footnote_node);
+        }
     }
 
     Ok(())
 }
 
+/// Ids that links in `events` point at with `#id`
+fn internal_link_targets(events: &[Event]) -> HashSet<String> {
+    fn collect(inline: &InlineContent, ids: &mut HashSet<String>) {
+        match inline {
+            InlineContent::Link(link) => {
+                if let Some(id) = link.url.strip_prefix('#') {
+                    ids.insert(id.to_string());
+                }
+                link.content.iter().for_each(|child| collect(child, ids));
+            }
+            InlineContent::Bold(children)
+            | InlineContent::Italic(children)
+            | InlineContent::Strikethrough(children) => {
+                children.iter().for_each(|child| collect(child, ids));
+            }
+            _ => {}
+        }
+    }
+
+    let mut ids = HashSet::new();
+    for event in events {
+        match event {
+            Event::Inline(inline) => collect(inline, &mut ids),
+            Event::StartTable {
+                caption: Some(caption),
+            } => caption.iter().for_each(|inline| collect(inline, &mut ids)),
+            _ => {}
+        }
+    }
+    ids
+}
+
+/// Link target for a Lex reference, if Markdown can link it
+///
+/// Lex references can be URLs, anchors, citations, or placeholders. URLs,
+/// paths and anchors link as-is and citations link to `#ref-<key>`; anything
+/// else is written as bracketed text.
+pub(crate) fn reference_url(ref_text: &str) -> Option<String> {
+    if ref_text.starts_with("http")
+        || ref_text.starts_with('/')
+        || ref_text.starts_with("./")
+        || ref_text.starts_with('#')
+    {
+        Some(ref_text.to_string())
+    } else {
+        ref_text
+            .strip_prefix('@')
+            .map(|citation| format!("#ref-{citation}"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
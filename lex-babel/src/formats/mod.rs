@@ -4,8 +4,12 @@
 //! Lex AST and various text representations.
 
 pub mod common;
+pub mod confluence;
+pub mod eml;
+pub mod gemtext;
 pub mod html;
 pub mod icons;
+pub mod ipynb;
 pub mod lex;
 pub mod linetreeviz;
 pub mod markdown;
@@ -15,11 +19,16 @@ pub mod pandoc;
 pub mod pdf;
 #[cfg(feature = "native-export")]
 pub mod png;
+pub mod rfc_text;
 pub mod rfc_xml;
 pub mod tag;
 pub mod treeviz;
 
+pub use confluence::ConfluenceFormat;
+pub use eml::EmlFormat;
+pub use gemtext::GemtextFormat;
 pub use html::{get_default_css, HtmlFormat, HtmlOptions, HtmlTheme};
+pub use ipynb::IpynbFormat;
 pub use lex::LexFormat;
 pub use linetreeviz::LinetreevizFormat;
 pub use markdown::MarkdownFormat;
@@ -27,6 +36,7 @@ pub use markdown::MarkdownFormat;
 pub use pdf::PdfFormat;
 #[cfg(feature = "native-export")]
 pub use png::PngFormat;
+pub use rfc_text::RfcTextFormat;
 pub use rfc_xml::RfcXmlFormat;
 pub use tag::TagFormat;
 pub use treeviz::TreevizFormat;
@@ -1,3 +1,4 @@
+use crate::options::parse_bool;
 use lex_core::lex::ast::elements::annotation::Annotation;
 use lex_core::lex::ast::elements::blank_line_group::BlankLineGroup;
 use lex_core::lex::ast::elements::definition::Definition;
@@ -20,19 +21,19 @@ pub fn to_nodemap_str_with_params(
 ) -> String {
     let use_color = params
         .get("color")
-        .map(|v| v.to_lowercase() == "true")
+        .and_then(|v| parse_bool(v))
         .unwrap_or(false);
 
     let use_color_char = params
         .get("colorchar")
         .or(params.get("color-char"))
-        .map(|v| v.to_lowercase() == "true")
+        .and_then(|v| parse_bool(v))
         .unwrap_or(false);
 
     let include_summary = params
         .get("nodesummary")
         .or(params.get("node-summary"))
-        .map(|v| v.to_lowercase() == "true")
+        .and_then(|v| parse_bool(v))
         .unwrap_or(false);
 
     let render_mode = if use_color_char {
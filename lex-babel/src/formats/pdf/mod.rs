@@ -2,11 +2,24 @@
 //!
 //! The implementation renders Lex documents to HTML using the existing HTML
 //! format, injects page-size specific CSS, then shells out to a Chrome/Chromium
-//! binary running in headless mode to print the page to PDF.
+//! binary running in headless mode to print the page to PDF. Document metadata
+//! reaches the PDF info dictionary through the `<title>` and `<meta>` tags of
+//! the HTML page.
+//!
+//! Chrome turns the section headings into the PDF's outline (bookmarks). The
+//! `toc` options are handed to the HTML serializer, which prints a table of
+//! contents on the first page, and so is `code-theme`: code is highlighted
+//! with inline colours that Chrome is told to print.
 
-use crate::error::FormatError;
+use crate::common::verbatim::dot::report_dot_fallbacks;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
 use crate::format::{Format, SerializedDocument};
-use crate::formats::html::HtmlFormat;
+use crate::formats::html::{report_math_fallbacks, HtmlFormat, CODE_THEME_OPTION};
+use crate::ir::context::ConversionContext;
+use crate::ir::nodes::Document as IrDocument;
+use crate::ir::toc::TOC_OPTIONS;
+use crate::options::{bool_option, FormatOption, OptionType};
+use crate::report::ConversionReport;
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
 use std::env;
@@ -32,6 +45,25 @@ impl PdfFormat {
     }
 }
 
+const PDF_OPTIONS: &[FormatOption] = &[
+    FormatOption::new(
+        "size-lexed",
+        OptionType::Bool,
+        "A4 pages sized like the Lex editor (the default)",
+    )
+    .with_default("true"),
+    FormatOption::new(
+        "size-mobile",
+        OptionType::Bool,
+        "Narrow 90x160mm pages for reading on phones",
+    )
+    .with_default("false"),
+    TOC_OPTIONS[0],
+    TOC_OPTIONS[1],
+    TOC_OPTIONS[2],
+    CODE_THEME_OPTION,
+];
+
 impl Format for PdfFormat {
     fn name(&self) -> &str {
         "pdf"
@@ -49,6 +81,10 @@ impl Format for PdfFormat {
         true
     }
 
+    fn options(&self) -> &[FormatOption] {
+        PDF_OPTIONS
+    }
+
     fn serialize(&self, _doc: &Document) -> Result<String, FormatError> {
         Err(FormatError::NotSupported(
             "PDF serialization produces binary output".to_string(),
@@ -62,10 +98,59 @@ impl Format for PdfFormat {
     ) -> Result<SerializedDocument, FormatError> {
         let profile = PdfSizeProfile::from_options(options)?;
         let html = self.html.serialize(doc)?;
-        let final_html = inject_page_css(&html, profile.print_css());
-        let pdf_bytes = render_html_to_pdf(&final_html, profile)?;
-        Ok(SerializedDocument::Binary(pdf_bytes))
+        render_pdf(&html, profile)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
     }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let profile = PdfSizeProfile::from_options(options)?;
+        // The table of contents and code colours come from the HTML serializer
+        let html_options: HashMap<String, String> = options
+            .iter()
+            .filter(|(key, _)| {
+                TOC_OPTIONS.iter().any(|option| option.matches(key))
+                    || CODE_THEME_OPTION.matches(key)
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        match self
+            .html
+            .serialize_ir_with_options(source, ir, &html_options)?
+        {
+            SerializedDocument::Text(html) => render_pdf(&html, profile),
+            SerializedDocument::Binary(_) => Err(FormatError::SerializationError(
+                "HTML serializer produced binary output".to_string(),
+            )),
+        }
+    }
+
+    fn report_serialize_losses(&self, ir: &IrDocument, report: &mut ConversionReport) {
+        report_math_fallbacks(ir, report);
+        report_dot_fallbacks(ir, report);
+    }
+}
+
+fn render_pdf(html: &str, profile: PdfSizeProfile) -> Result<SerializedDocument, FormatError> {
+    let final_html = inject_page_css(html, profile.print_css());
+    let pdf_bytes = render_html_to_pdf(&final_html, profile)?;
+    Ok(SerializedDocument::Binary(pdf_bytes))
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -76,8 +161,8 @@ enum PdfSizeProfile {
 
 impl PdfSizeProfile {
     fn from_options(options: &HashMap<String, String>) -> Result<Self, FormatError> {
-        let mobile = parse_bool_flag(options, "size-mobile", false)?;
-        let lexed = parse_bool_flag(options, "size-lexed", !mobile)?;
+        let mobile = bool_option("pdf", options, "size-mobile")?.unwrap_or(false);
+        let lexed = bool_option("pdf", options, "size-lexed")?.unwrap_or(!mobile);
 
         if mobile && lexed {
             return Err(FormatError::SerializationError(
@@ -109,27 +194,6 @@ impl PdfSizeProfile {
     }
 }
 
-fn parse_bool_flag(
-    options: &HashMap<String, String>,
-    key: &str,
-    default: bool,
-) -> Result<bool, FormatError> {
-    if let Some(value) = options.get(key) {
-        if value.is_empty() {
-            return Ok(true);
-        }
-        match value.to_lowercase().as_str() {
-            "true" | "1" | "yes" | "y" => Ok(true),
-            "false" | "0" | "no" | "n" => Ok(false),
-            other => Err(FormatError::SerializationError(format!(
-                "Invalid boolean value '{other}' for --extra-{key}"
-            ))),
-        }
-    } else {
-        Ok(default)
-    }
-}
-
 fn inject_page_css(html: &str, css: &str) -> String {
     let style_tag = format!("<style data-lex-pdf>\n{css}\n</style>");
     if let Some(idx) = html.find("</head>") {
@@ -173,22 +237,29 @@ fn render_html_to_pdf(html: &str, profile: PdfSizeProfile) -> Result<Vec<u8>, Fo
         .arg("--no-sandbox")
         .arg("--disable-dev-shm-usage")
         .arg("--no-pdf-header-footer")
+        .arg("--generate-pdf-document-outline")
         .arg(pdf_arg)
         .arg(window_arg)
         .arg(file_url.as_str())
         .status()
         .map_err(|e| {
-            FormatError::SerializationError(format!(
-                "Failed to launch Chrome ({}): {}",
-                chrome.display(),
-                e
-            ))
+            ErrorDetail::new(
+                ErrorKind::Serialization,
+                "chrome-launch",
+                format!("Failed to launch Chrome ({}): {}", chrome.display(), e),
+            )
+            .in_format("pdf")
+            .caused_by(e)
         })?;
 
     if !status.success() {
-        return Err(FormatError::SerializationError(format!(
-            "Chrome exited with status {status}"
-        )));
+        return Err(ErrorDetail::new(
+            ErrorKind::Serialization,
+            "chrome-failed",
+            format!("Chrome exited with status {status}"),
+        )
+        .in_format("pdf")
+        .into());
     }
 
     fs::read(&pdf_path).map_err(|e| FormatError::SerializationError(e.to_string()))
@@ -1,11 +1,16 @@
 //! PNG export built on top of the HTML serializer + headless Chrome.
 //!
 //! Similar to PDF export, this renders Lex documents to HTML and uses
-//! Chrome's screenshot capability to generate a PNG image.
+//! Chrome's screenshot capability to generate a PNG image. The `code-theme`
+//! option is handed to the HTML serializer.
 
-use crate::error::FormatError;
+use crate::common::verbatim::dot::report_dot_fallbacks;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
 use crate::format::{Format, SerializedDocument};
-use crate::formats::html::HtmlFormat;
+use crate::formats::html::{report_math_fallbacks, HtmlFormat, CODE_THEME_OPTION};
+use crate::ir::nodes::Document as IrDocument;
+use crate::options::{bool_option, FormatOption, OptionType};
+use crate::report::ConversionReport;
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
 use std::env;
@@ -31,6 +36,24 @@ impl PngFormat {
     }
 }
 
+const PNG_OPTIONS: &[FormatOption] = &[
+    FormatOption::new(
+        "quicklook",
+        OptionType::Bool,
+        "Small preview sized for Finder Quick Look",
+    )
+    .with_default("false"),
+    FormatOption::new("size-mobile", OptionType::Bool, "Phone-width screenshot")
+        .with_default("false"),
+    FormatOption::new(
+        "size-lexed",
+        OptionType::Bool,
+        "Screenshot sized like the Lex editor (the default)",
+    )
+    .with_default("false"),
+    CODE_THEME_OPTION,
+];
+
 impl Format for PngFormat {
     fn name(&self) -> &str {
         "png"
@@ -48,6 +71,10 @@ impl Format for PngFormat {
         true
     }
 
+    fn options(&self) -> &[FormatOption] {
+        PNG_OPTIONS
+    }
+
     fn serialize(&self, _doc: &Document) -> Result<String, FormatError> {
         Err(FormatError::NotSupported(
             "PNG serialization produces binary output".to_string(),
@@ -60,11 +87,28 @@ impl Format for PngFormat {
         options: &HashMap<String, String>,
     ) -> Result<SerializedDocument, FormatError> {
         let profile = PngSizeProfile::from_options(options)?;
-        let html = self.html.serialize(doc)?;
+        let html_options: HashMap<String, String> = options
+            .iter()
+            .filter(|(key, _)| CODE_THEME_OPTION.matches(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let html = match self.html.serialize_with_options(doc, &html_options)? {
+            SerializedDocument::Text(html) => html,
+            SerializedDocument::Binary(_) => {
+                return Err(FormatError::SerializationError(
+                    "HTML serializer produced binary output".to_string(),
+                ))
+            }
+        };
         let final_html = inject_screenshot_css(&html, profile.css());
         let png_bytes = render_html_to_png(&final_html, profile)?;
         Ok(SerializedDocument::Binary(png_bytes))
     }
+
+    fn report_serialize_losses(&self, ir: &IrDocument, report: &mut ConversionReport) {
+        report_math_fallbacks(ir, report);
+        report_dot_fallbacks(ir, report);
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -76,9 +120,9 @@ pub enum PngSizeProfile {
 
 impl PngSizeProfile {
     fn from_options(options: &HashMap<String, String>) -> Result<Self, FormatError> {
-        let quicklook = parse_bool_flag(options, "quicklook", false)?;
-        let mobile = parse_bool_flag(options, "size-mobile", false)?;
-        let lexed = parse_bool_flag(options, "size-lexed", false)?;
+        let quicklook = bool_option("png", options, "quicklook")?.unwrap_or(false);
+        let mobile = bool_option("png", options, "size-mobile")?.unwrap_or(false);
+        let lexed = bool_option("png", options, "size-lexed")?.unwrap_or(false);
 
         let count = [quicklook, mobile, lexed].iter().filter(|&&x| x).count();
         if count > 1 {
@@ -135,27 +179,6 @@ impl PngSizeProfile {
     }
 }
 
-fn parse_bool_flag(
-    options: &HashMap<String, String>,
-    key: &str,
-    default: bool,
-) -> Result<bool, FormatError> {
-    if let Some(value) = options.get(key) {
-        if value.is_empty() {
-            return Ok(true);
-        }
-        match value.to_lowercase().as_str() {
-            "true" | "1" | "yes" | "y" => Ok(true),
-            "false" | "0" | "no" | "n" => Ok(false),
-            other => Err(FormatError::SerializationError(format!(
-                "Invalid boolean value '{other}' for --extra-{key}"
-            ))),
-        }
-    } else {
-        Ok(default)
-    }
-}
-
 fn inject_screenshot_css(html: &str, css: &str) -> String {
     let style_tag = format!("<style data-lex-png>\n{css}\n</style>");
     if let Some(idx) = html.find("</head>") {
@@ -204,17 +227,23 @@ fn render_html_to_png(html: &str, profile: PngSizeProfile) -> Result<Vec<u8>, Fo
         .arg(file_url.as_str())
         .status()
         .map_err(|e| {
-            FormatError::SerializationError(format!(
-                "Failed to launch Chrome ({}): {}",
-                chrome.display(),
-                e
-            ))
+            ErrorDetail::new(
+                ErrorKind::Serialization,
+                "chrome-launch",
+                format!("Failed to launch Chrome ({}): {}", chrome.display(), e),
+            )
+            .in_format("png")
+            .caused_by(e)
         })?;
 
     if !status.success() {
-        return Err(FormatError::SerializationError(format!(
-            "Chrome exited with status {status}"
-        )));
+        return Err(ErrorDetail::new(
+            ErrorKind::Serialization,
+            "chrome-failed",
+            format!("Chrome exited with status {status}"),
+        )
+        .in_format("png")
+        .into());
     }
 
     fs::read(&png_path).map_err(|e| FormatError::SerializationError(e.to_string()))
@@ -0,0 +1,88 @@
+//! RFC-style plain text implementation
+//!
+//! This module implements export from Lex to paginated plain text in the
+//! layout produced by `xml2rfc --text`: 72-column lines, numbered sections, a
+//! generated table of contents and form-feed separated pages with running
+//! headers and footers.
+//!
+//! # Element Mapping Table
+//!
+//! | Lex Element        | RFC Text                               | Notes                                      |
+//! |--------------------|----------------------------------------|--------------------------------------------|
+//! | Session            | `1.2.  Title` at column 0              | Numbers are computed from nesting          |
+//! | Paragraph          | Wrapped at 72 columns, indented 3      |                                            |
+//! | List / ListItem    | `*  ` or the ordered marker, hanging   | Nested lists indent under the item text    |
+//! | Definition         | Term, description indented 3 more      |                                            |
+//! | Verbatim           | Artwork, copied line for line          | Never split across pages when it fits      |
+//! | Verbatim subject   | `Figure N: subject`, centered          |                                            |
+//! | Table              | ASCII table with `+---+` rules         | Caption becomes `Table N: caption`         |
+//! | Bold / Italic      | `*text*` / `_text_`                    |                                            |
+//! | Reference          | `[text]`                               |                                            |
+//!
+//! # Pages
+//!
+//! Each page is 58 lines long and pages are separated by a form feed on a line
+//! of its own. Pages after the first carry a header line (`Internet-Draft`,
+//! title, month and year) and every page ends with a footer (author and
+//! `[Page N]`). Title, author and date come from the document title and the
+//! `author` / `date` (or `publishing-date`) metadata annotations.
+
+pub mod serializer;
+
+use crate::error::FormatError;
+use crate::format::{reject_options, Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::Document as IrDocument;
+use lex_core::lex::ast::Document;
+use std::collections::HashMap;
+
+/// Format implementation for RFC-style plain text
+#[derive(Default)]
+pub struct RfcTextFormat;
+
+impl Format for RfcTextFormat {
+    fn name(&self) -> &str {
+        "rfc_text"
+    }
+
+    fn description(&self) -> &str {
+        "RFC-style paginated plain text (xml2rfc text layout)"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["rfctxt"]
+    }
+
+    fn supports_serialization(&self) -> bool {
+        true
+    }
+
+    fn serialize(&self, doc: &Document) -> Result<String, FormatError> {
+        serializer::serialize_to_rfc_text(doc)
+    }
+
+    fn serialize_with_context(
+        &self,
+        doc: &Document,
+        options: &HashMap<String, String>,
+        ctx: &ConversionContext,
+    ) -> Result<SerializedDocument, FormatError> {
+        self.serialize_ir_with_options(doc, &crate::to_ir_with_context(doc, ctx), options)
+    }
+
+    fn serializes_ir(&self) -> bool {
+        true
+    }
+
+    fn serialize_ir_with_options(
+        &self,
+        source: &Document,
+        ir: &IrDocument,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        reject_options(self.name(), options)?;
+        serializer::serialize_ir_to_rfc_text(ir, document_title(source))
+            .map(SerializedDocument::Text)
+    }
+}
@@ -0,0 +1,864 @@
+//! RFC plain-text serialization (Lex → xml2rfc-style text)
+//!
+//! Pipeline: Lex AST → IR → blocks of wrapped lines → pages
+//!
+//! Rendering happens in two steps. First the IR is turned into a list of
+//! [`Block`]s, each a run of lines already wrapped to [`LINE_WIDTH`]. Then the
+//! blocks are flowed onto pages of [`PAGE_LENGTH`] lines, honouring the
+//! keep-together flag of artwork and tables and keeping headings with the text
+//! that follows them. The table of contents is laid out with placeholder lines
+//! (one per entry, so its height is known up front) and filled in once the page
+//! of every section is known.
+
+use crate::common::text::strip_marker;
+use crate::error::FormatError;
+use crate::ir::from_lex::document_title;
+use crate::ir::nodes::{
+    DocNode, Document as IrDocument, Heading, InlineContent, List, ListItem, Metadata, Table,
+    TableCellAlignment, Verbatim,
+};
+use lex_core::lex::ast::Document;
+
+/// Maximum line width in columns
+pub const LINE_WIDTH: usize = 72;
+
+/// Lines per page, including header and footer
+pub const PAGE_LENGTH: usize = 58;
+
+/// Header line plus two blank lines
+const HEADER_LINES: usize = 3;
+
+/// Two blank lines plus footer line
+const FOOTER_LINES: usize = 3;
+
+/// Lines available for content on each page
+const BODY_LINES: usize = PAGE_LENGTH - HEADER_LINES - FOOTER_LINES;
+
+/// Indentation of body text relative to section headings
+const BODY_INDENT: usize = 3;
+
+/// Sessions deeper than this are numbered but left out of the table of contents
+const TOC_DEPTH: usize = 3;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Serialize a Lex document to RFC-style paginated plain text
+pub fn serialize_to_rfc_text(doc: &Document) -> Result<String, FormatError> {
+    serialize_ir_to_rfc_text(&crate::to_ir(doc), document_title(doc))
+}
+
+/// Serialize an IR document to RFC-style text under the given document title
+pub(crate) fn serialize_ir_to_rfc_text(
+    ir_doc: &IrDocument,
+    title: &str,
+) -> Result<String, FormatError> {
+    let front = FrontMatter::new(title, &ir_doc.metadata);
+
+    let mut renderer = Renderer::default();
+    renderer.render_nodes(&ir_doc.children, BODY_INDENT);
+
+    let mut blocks = front.title_blocks();
+    if !renderer.sections.is_empty() {
+        blocks.push(Block::heading("Table of Contents".to_string(), None));
+        blocks.push(Block {
+            // Entry lengths do not depend on the page numbers filled in later
+            lines: renderer
+                .toc_entries()
+                .enumerate()
+                .flat_map(|(index, section)| {
+                    (0..toc_lines(section, 0).len()).map(move |line| Line::toc(index, line))
+                })
+                .collect(),
+            keep_together: false,
+            keep_with_next: false,
+        });
+    }
+    blocks.append(&mut renderer.blocks);
+
+    let mut pages = paginate(&blocks);
+    fill_toc(&mut pages, &renderer.sections);
+
+    Ok(render_pages(&pages, &front))
+}
+
+/// Render the document body as wrapped text without title block, table of
+/// contents or pagination
+///
+/// Used for the plain-text alternative of email exports.
+pub(crate) fn render_wrapped_text(ir_doc: &IrDocument) -> String {
+    let mut renderer = Renderer::default();
+    renderer.render_nodes(&ir_doc.children, BODY_INDENT);
+
+    let blocks: Vec<String> = renderer
+        .blocks
+        .iter()
+        .filter(|block| !block.lines.is_empty())
+        .map(|block| {
+            let lines: Vec<&str> = block.lines.iter().map(|line| line.text.as_str()).collect();
+            lines.join("\n")
+        })
+        .collect();
+
+    let mut text = blocks.join("\n\n");
+    text.push('\n');
+    text
+}
+
+/// Values used for the title block and running header/footer
+struct FrontMatter {
+    title: String,
+    author: String,
+    date: String,
+}
+
+impl FrontMatter {
+    fn new(title: &str, metadata: &Metadata) -> Self {
+        let mut title = title.trim().to_string();
+        if title.is_empty() {
+            title = metadata.title().unwrap_or_default();
+        }
+
+        // xml2rfc style: "Doe & Roe", or "Doe, et al." for three or more
+        let author = match metadata.authors().as_slice() {
+            [] => String::new(),
+            [one] => one.clone(),
+            [first, second] => format!("{first} & {second}"),
+            [first, ..] => format!("{first}, et al."),
+        };
+
+        Self {
+            title,
+            author,
+            date: metadata
+                .date()
+                .map(|date| month_year(&date))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Author and date flush right, then the centered title
+    fn title_blocks(&self) -> Vec<Block> {
+        let mut lines = Vec::new();
+        for value in [&self.author, &self.date] {
+            if !value.is_empty() {
+                lines.push(Line::text(format!("{value:>LINE_WIDTH$}")));
+            }
+        }
+        if !self.title.is_empty() {
+            if !lines.is_empty() {
+                lines.push(Line::text(String::new()));
+                lines.push(Line::text(String::new()));
+            }
+            for title_line in wrap(&self.title, "", "", LINE_WIDTH - 8) {
+                lines.push(Line::text(center(&title_line.text)));
+            }
+        }
+
+        if lines.is_empty() {
+            Vec::new()
+        } else {
+            vec![Block {
+                lines,
+                keep_together: true,
+                keep_with_next: false,
+            }]
+        }
+    }
+}
+
+/// Turn an ISO-ish date (`2025-03-14`, `2025-03`) into `March 2025`
+fn month_year(value: &str) -> String {
+    let value = value.trim();
+    let mut parts = value.split('-');
+    let year = parts
+        .next()
+        .filter(|y| y.len() == 4 && y.parse::<u32>().is_ok());
+    let month = parts
+        .next()
+        .and_then(|m| m.parse::<usize>().ok())
+        .filter(|m| (1..=12).contains(m));
+
+    match (year, month) {
+        (Some(year), Some(month)) => format!("{} {year}", MONTHS[month - 1]),
+        _ => value.to_string(),
+    }
+}
+
+/// A rendered output line
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    /// Set on the heading line of a section (index into `Renderer::sections`)
+    section: Option<usize>,
+    /// Placeholder for a line of a table of contents entry: the entry and
+    /// the line within it
+    toc: Option<(usize, usize)>,
+}
+
+impl Line {
+    fn text(text: String) -> Self {
+        Self {
+            text,
+            section: None,
+            toc: None,
+        }
+    }
+
+    fn toc(index: usize, line: usize) -> Self {
+        Self {
+            text: String::new(),
+            section: None,
+            toc: Some((index, line)),
+        }
+    }
+}
+
+/// A run of lines laid out as a unit, separated from its neighbours by a blank line
+#[derive(Debug, Clone)]
+struct Block {
+    lines: Vec<Line>,
+    /// Move the whole block to the next page rather than splitting it
+    keep_together: bool,
+    /// Do not leave the block as the last thing on a page
+    keep_with_next: bool,
+}
+
+impl Block {
+    fn text(lines: Vec<Line>) -> Self {
+        Self {
+            lines,
+            keep_together: false,
+            keep_with_next: false,
+        }
+    }
+
+    fn heading(text: String, section: Option<usize>) -> Self {
+        Self {
+            lines: vec![Line {
+                text,
+                section,
+                toc: None,
+            }],
+            keep_together: true,
+            keep_with_next: true,
+        }
+    }
+}
+
+struct Section {
+    number: String,
+    title: String,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct Renderer {
+    blocks: Vec<Block>,
+    sections: Vec<Section>,
+    counters: Vec<usize>,
+    figures: usize,
+    tables: usize,
+}
+
+impl Renderer {
+    fn toc_entries(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter().filter(|s| s.depth <= TOC_DEPTH)
+    }
+
+    fn render_nodes(&mut self, nodes: &[DocNode], indent: usize) {
+        for node in nodes {
+            self.render_node(node, indent);
+        }
+    }
+
+    fn render_node(&mut self, node: &DocNode, indent: usize) {
+        match node {
+            DocNode::Document(doc) => self.render_nodes(&doc.children, indent),
+            DocNode::Heading(heading) => self.render_heading(heading),
+            DocNode::Paragraph(paragraph) => {
+                let text = render_inlines(&paragraph.content);
+                let prefix = " ".repeat(indent);
+                self.blocks
+                    .push(Block::text(wrap(&text, &prefix, &prefix, LINE_WIDTH)));
+            }
+            DocNode::List(list) => {
+                let mut lines = Vec::new();
+                self.render_list(list, indent, &mut lines);
+                self.push_lines(lines);
+            }
+            DocNode::ListItem(item) => {
+                let mut lines = Vec::new();
+                self.render_list_item(item, false, indent, &mut lines);
+                self.push_lines(lines);
+            }
+            DocNode::Definition(definition) => {
+                let term = render_inlines(&definition.term);
+                let term = term.trim().trim_end_matches(':');
+                let prefix = " ".repeat(indent);
+                let mut term_lines = wrap(term, &prefix, &prefix, LINE_WIDTH);
+
+                let start = self.blocks.len();
+                self.render_nodes(&definition.description, indent + BODY_INDENT);
+                if start < self.blocks.len() {
+                    // The term sits directly above its first description block
+                    term_lines.append(&mut self.blocks[start].lines);
+                    self.blocks[start].lines = term_lines;
+                } else {
+                    self.blocks.push(Block::text(term_lines));
+                }
+            }
+            DocNode::Verbatim(verbatim) => self.render_artwork(verbatim, indent),
+            // Annotations contribute their content only
+            DocNode::Annotation(annotation) => self.render_nodes(&annotation.content, indent),
+            DocNode::Table(table) => self.render_table(table, indent),
+            DocNode::Image(image) => {
+                let label = if image.alt.is_empty() {
+                    &image.src
+                } else {
+                    &image.alt
+                };
+                self.push_placeholder("Image", label, indent);
+            }
+            DocNode::Video(video) => {
+                let label = video.title.as_deref().unwrap_or(&video.src);
+                self.push_placeholder("Video", label, indent);
+            }
+            DocNode::Audio(audio) => {
+                let label = audio.title.as_deref().unwrap_or(&audio.src);
+                self.push_placeholder("Audio", label, indent);
+            }
+            DocNode::Inline(inline) => {
+                let text = render_inlines(std::slice::from_ref(inline));
+                let prefix = " ".repeat(indent);
+                self.blocks
+                    .push(Block::text(wrap(&text, &prefix, &prefix, LINE_WIDTH)));
+            }
+            DocNode::BlockQuote(quote) => self.render_nodes(&quote.children, indent + BODY_INDENT),
+            // Section breaks are carried by the blank lines between blocks
+            DocNode::ThematicBreak => {}
+        }
+    }
+
+    /// Sections are numbered from their nesting (`1.`, `1.1.`); any marker the
+    /// author wrote is replaced by the computed number.
+    fn render_heading(&mut self, heading: &Heading) {
+        // Top-level sessions are IR level 2
+        let depth = heading.level.saturating_sub(1).max(1);
+        self.counters.resize(depth, 0);
+        if let Some(last) = self.counters.last_mut() {
+            *last += 1;
+        }
+        let number: String = self
+            .counters
+            .iter()
+            .map(|n| format!("{}.", n.max(&1)))
+            .collect();
+
+        let title = render_inlines(strip_marker(&heading.content));
+        let title = title.trim().to_string();
+        let section = self.sections.len();
+        self.sections.push(Section {
+            number: number.clone(),
+            title: title.clone(),
+            depth,
+        });
+
+        let first = format!("{number}  ");
+        let rest = " ".repeat(first.chars().count());
+        let mut lines = wrap(&title, &first, &rest, LINE_WIDTH);
+        if let Some(line) = lines.first_mut() {
+            line.section = Some(section);
+        }
+        self.blocks.push(Block {
+            lines,
+            keep_together: true,
+            keep_with_next: true,
+        });
+
+        self.render_nodes(&heading.children, BODY_INDENT);
+    }
+
+    /// Verbatim blocks are artwork: copied line for line and kept on one page
+    /// when they fit. A subject becomes a centered figure caption.
+    fn render_artwork(&mut self, verbatim: &Verbatim, indent: usize) {
+        let prefix = " ".repeat(indent);
+        let mut lines: Vec<Line> = verbatim
+            .content
+            .lines()
+            .map(|line| Line::text(format!("{prefix}{line}").trim_end().to_string()))
+            .collect();
+
+        if let Some(subject) = verbatim
+            .subject
+            .as_deref()
+            .map(|s| s.trim().trim_end_matches(':'))
+            .filter(|s| !s.is_empty())
+        {
+            self.figures += 1;
+            lines.push(Line::text(String::new()));
+            lines.push(Line::text(center(&format!(
+                "Figure {}: {subject}",
+                self.figures
+            ))));
+        }
+
+        self.blocks.push(Block {
+            lines,
+            keep_together: true,
+            keep_with_next: false,
+        });
+    }
+
+    /// Tables are drawn with ASCII rules in the xml2rfc v3 style
+    fn render_table(&mut self, table: &Table, indent: usize) {
+        let rows: Vec<Vec<(String, TableCellAlignment)>> = table
+            .header
+            .iter()
+            .chain(table.rows.iter())
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| {
+                        let text: Vec<String> = cell
+                            .content
+                            .iter()
+                            .map(|node| match node {
+                                DocNode::Paragraph(p) => render_inlines(&p.content),
+                                DocNode::Inline(inline) => {
+                                    render_inlines(std::slice::from_ref(inline))
+                                }
+                                _ => String::new(),
+                            })
+                            .collect();
+                        (text.join(" ").trim().to_string(), cell.align)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                rows.iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|(text, _)| text.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let prefix = " ".repeat(indent);
+        let rule = |fill: &str| {
+            let segments: Vec<String> = widths.iter().map(|w| fill.repeat(w + 2)).collect();
+            Line::text(format!("{prefix}+{}+", segments.join("+")))
+        };
+
+        let mut lines = vec![rule(if table.header.is_empty() { "-" } else { "=" })];
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(col, width)| {
+                    let (text, align) = row
+                        .get(col)
+                        .map(|(text, align)| (text.as_str(), *align))
+                        .unwrap_or(("", TableCellAlignment::None));
+                    match align {
+                        TableCellAlignment::Right => format!(" {text:>width$} "),
+                        TableCellAlignment::Center => format!(" {text:^width$} "),
+                        _ => format!(" {text:<width$} "),
+                    }
+                })
+                .collect();
+            lines.push(Line::text(
+                format!("{prefix}|{}|", cells.join("|"))
+                    .trim_end()
+                    .to_string(),
+            ));
+            if index + 1 == table.header.len() {
+                lines.push(rule("="));
+            } else {
+                lines.push(rule("-"));
+            }
+        }
+
+        if let Some(caption) = &table.caption {
+            self.tables += 1;
+            lines.push(Line::text(String::new()));
+            lines.push(Line::text(center(&format!(
+                "Table {}: {}",
+                self.tables,
+                render_inlines(caption).trim()
+            ))));
+        }
+
+        self.blocks.push(Block {
+            lines,
+            keep_together: true,
+            keep_with_next: false,
+        });
+    }
+
+    /// List lines collect in `lines` until an item holds a block of its own
+    fn render_list(&mut self, list: &List, indent: usize, lines: &mut Vec<Line>) {
+        for item in &list.items {
+            self.render_list_item(item, list.ordered, indent, lines);
+        }
+    }
+
+    /// Bullets are `*`, ordered items keep their Lex marker; continuation lines
+    /// and nested blocks hang under the item text.
+    fn render_list_item(
+        &mut self,
+        item: &ListItem,
+        ordered: bool,
+        indent: usize,
+        lines: &mut Vec<Line>,
+    ) {
+        let marker = match item.content.first() {
+            Some(InlineContent::Marker(marker)) if ordered => marker.clone(),
+            _ => "*".to_string(),
+        };
+        let first = format!("{}{marker:<3}", " ".repeat(indent));
+        let first = if first.ends_with(' ') {
+            first
+        } else {
+            format!("{first} ")
+        };
+        let hang = first.chars().count();
+        let rest = " ".repeat(hang);
+
+        let mut text = render_inlines(strip_marker(&item.content));
+        match item.checked {
+            Some(true) => text.insert_str(0, "[x] "),
+            Some(false) => text.insert_str(0, "[ ] "),
+            None => {}
+        }
+        lines.extend(wrap(text.trim(), &first, &rest, LINE_WIDTH));
+
+        for child in &item.children {
+            match child {
+                DocNode::List(nested) => self.render_list(nested, hang, lines),
+                DocNode::Paragraph(paragraph) => {
+                    let text = render_inlines(&paragraph.content);
+                    lines.extend(wrap(&text, &rest, &rest, LINE_WIDTH));
+                }
+                _ => {
+                    self.push_lines(std::mem::take(lines));
+                    self.render_node(child, hang);
+                }
+            }
+        }
+    }
+
+    /// Close a run of list lines as a block, unless it is empty
+    fn push_lines(&mut self, lines: Vec<Line>) {
+        if !lines.is_empty() {
+            self.blocks.push(Block::text(lines));
+        }
+    }
+
+    fn push_placeholder(&mut self, kind: &str, label: &str, indent: usize) {
+        let prefix = " ".repeat(indent);
+        self.blocks.push(Block::text(wrap(
+            &format!("[{kind}: {label}]"),
+            &prefix,
+            &prefix,
+            LINE_WIDTH,
+        )));
+    }
+}
+
+/// Plain-text inline rendering, following xml2rfc: `*strong*`, `_em_`
+fn render_inlines(content: &[InlineContent]) -> String {
+    let mut text = String::new();
+    for inline in content {
+        match inline {
+            InlineContent::Text(t) => text.push_str(t),
+            InlineContent::Bold(children) => {
+                text.push('*');
+                text.push_str(&render_inlines(children));
+                text.push('*');
+            }
+            InlineContent::Italic(children) => {
+                text.push('_');
+                text.push_str(&render_inlines(children));
+                text.push('_');
+            }
+            InlineContent::Code(code) | InlineContent::Math(code) => text.push_str(code),
+            InlineContent::Marker(marker) => text.push_str(marker),
+            InlineContent::Reference(reference) => {
+                text.push('[');
+                text.push_str(reference);
+                text.push(']');
+            }
+            InlineContent::Image(image) => {
+                text.push('[');
+                text.push_str(&image.alt);
+                text.push(']');
+            }
+            InlineContent::Strikethrough(children) => text.push_str(&render_inlines(children)),
+            // URIs in angle brackets, as in RFC 7322
+            InlineContent::Link(link) => {
+                text.push_str(&render_inlines(&link.content));
+                text.push_str(" <");
+                text.push_str(&link.url);
+                text.push('>');
+            }
+            InlineContent::FootnoteRef(label) => {
+                text.push('[');
+                text.push_str(label);
+                text.push(']');
+            }
+        }
+    }
+    text
+}
+
+/// Greedy word wrap; words longer than the line are left unbroken
+fn wrap(text: &str, first_prefix: &str, rest_prefix: &str, width: usize) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut current = first_prefix.to_string();
+    let mut current_len = first_prefix.chars().count();
+    let mut has_word = false;
+
+    for word in text.split_whitespace() {
+        let word_len = word.chars().count();
+        if has_word && current_len + 1 + word_len > width {
+            lines.push(Line::text(current));
+            current = rest_prefix.to_string();
+            current_len = rest_prefix.chars().count();
+            has_word = false;
+        }
+        if has_word {
+            current.push(' ');
+            current_len += 1;
+        }
+        current.push_str(word);
+        current_len += word_len;
+        has_word = true;
+    }
+
+    if has_word || lines.is_empty() {
+        lines.push(Line::text(current.trim_end().to_string()));
+    }
+    lines
+}
+
+fn center(text: &str) -> String {
+    let len = text.chars().count();
+    if len >= LINE_WIDTH {
+        return text.to_string();
+    }
+    format!("{}{text}", " ".repeat((LINE_WIDTH - len) / 2))
+}
+
+/// Lay out `left`, `center` and `right` on one line of `LINE_WIDTH` columns
+fn three_part(left: &str, middle: &str, right: &str) -> String {
+    let mut line = left.to_string();
+
+    if !middle.is_empty() {
+        let middle_len = middle.chars().count();
+        let start = LINE_WIDTH.saturating_sub(middle_len) / 2;
+        let len = line.chars().count();
+        if len < start {
+            line.push_str(&" ".repeat(start - len));
+        } else if len > 0 {
+            line.push(' ');
+        }
+        line.push_str(middle);
+    }
+
+    if !right.is_empty() {
+        let right_len = right.chars().count();
+        let start = LINE_WIDTH.saturating_sub(right_len);
+        let len = line.chars().count();
+        if len < start {
+            line.push_str(&" ".repeat(start - len));
+        } else if len > 0 {
+            line.push(' ');
+        }
+        line.push_str(right);
+    }
+
+    line.trim_end().to_string()
+}
+
+/// Flow blocks onto pages of `BODY_LINES` lines
+fn paginate(blocks: &[Block]) -> Vec<Vec<Line>> {
+    let mut pages: Vec<Vec<Line>> = Vec::new();
+    let mut page: Vec<Line> = Vec::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        if block.lines.is_empty() {
+            continue;
+        }
+
+        // Blank line between blocks, but never at the top of a page
+        if !page.is_empty() {
+            if page.len() + 1 >= BODY_LINES {
+                pages.push(std::mem::take(&mut page));
+            } else {
+                page.push(Line::text(String::new()));
+            }
+        }
+
+        let remaining = BODY_LINES - page.len();
+        let needed = block.lines.len();
+        let mut breaks = block.keep_together && needed <= BODY_LINES && needed > remaining;
+
+        if block.keep_with_next && !breaks {
+            // Keep the heading with at least two lines of what follows it
+            let next = blocks[index + 1..]
+                .iter()
+                .find(|b| !b.lines.is_empty())
+                .map(|b| b.lines.len().min(2) + 1)
+                .unwrap_or(0);
+            breaks = needed + next > remaining && needed + next <= BODY_LINES;
+        }
+
+        if breaks && !page.is_empty() {
+            // Drop the separator blank line left at the bottom
+            if page.last().is_some_and(|line| line.text.is_empty()) {
+                page.pop();
+            }
+            pages.push(std::mem::take(&mut page));
+        }
+
+        for line in &block.lines {
+            if page.len() == BODY_LINES {
+                pages.push(std::mem::take(&mut page));
+            }
+            page.push(line.clone());
+        }
+    }
+
+    if !page.is_empty() || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+/// Record the page of every section and write the TOC entries
+fn fill_toc(pages: &mut [Vec<Line>], sections: &[Section]) {
+    let mut section_pages = vec![0; sections.len()];
+    for (page_index, page) in pages.iter().enumerate() {
+        for line in page {
+            if let Some(section) = line.section {
+                section_pages[section] = page_index + 1;
+            }
+        }
+    }
+
+    let entries: Vec<Vec<String>> = sections
+        .iter()
+        .zip(section_pages)
+        .filter(|(section, _)| section.depth <= TOC_DEPTH)
+        .map(|(section, page_number)| toc_lines(section, page_number))
+        .collect();
+
+    for page in pages.iter_mut() {
+        for line in page.iter_mut() {
+            if let Some((index, part)) = line.toc {
+                if let Some(text) = entries.get(index).and_then(|entry| entry.get(part)) {
+                    line.text = text.clone();
+                }
+            }
+        }
+    }
+}
+
+/// `   1.1.  Title . . . . . . . . .   4`
+///
+/// Long titles wrap onto lines indented under the title text; the dot leader
+/// and page number follow the last of them.
+fn toc_lines(section: &Section, page: usize) -> Vec<String> {
+    let page = format!("{page:>4}");
+    let indent = BODY_INDENT + 2 * (section.depth - 1);
+    let prefix = format!("{}{}  ", " ".repeat(indent), section.number);
+    let rest_prefix = " ".repeat(prefix.chars().count());
+
+    let limit = LINE_WIDTH - page.len();
+    let mut lines: Vec<String> = wrap(&section.title, &prefix, &rest_prefix, limit - 2)
+        .into_iter()
+        .map(|line| line.text)
+        .collect();
+    let mut line = lines.pop().unwrap_or_default();
+
+    if line.chars().count() + 2 <= limit {
+        line.push_str("  ");
+        // Leader dots sit on even columns so adjacent entries line up
+        if line.chars().count() % 2 == 1 {
+            line.push(' ');
+        }
+        while line.chars().count() + 2 <= limit {
+            line.push_str(". ");
+        }
+    }
+    let len = line.chars().count();
+    if len < limit {
+        line.push_str(&" ".repeat(limit - len));
+    }
+    line.push_str(&page);
+    lines.push(line);
+    lines
+}
+
+fn render_pages(pages: &[Vec<Line>], front: &FrontMatter) -> String {
+    let header_left = "Internet-Draft";
+    let header_title = truncate(
+        &front.title,
+        LINE_WIDTH
+            .saturating_sub(header_left.len() + front.date.chars().count() + 4)
+            .max(8),
+    );
+
+    let mut output = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
+        if index > 0 {
+            output.push("\u{c}".to_string());
+            output.push(three_part(header_left, &header_title, &front.date));
+        } else {
+            // The first page carries the title block instead of a running header
+            output.push(String::new());
+        }
+        output.resize(output.len() + HEADER_LINES - 1, String::new());
+
+        output.extend(page.iter().map(|line| line.text.trim_end().to_string()));
+        output.resize(
+            output.len() + BODY_LINES - page.len() + FOOTER_LINES - 1,
+            String::new(),
+        );
+        output.push(three_part(
+            &front.author,
+            "",
+            &format!("[Page {}]", index + 1),
+        ));
+    }
+
+    let mut text = output.join("\n");
+    text.push('\n');
+    text
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(3)).collect();
+    truncated.push_str("...");
+    truncated
+}
@@ -1,5 +1,6 @@
 use crate::error::FormatError;
 use crate::format::{Format, SerializedDocument};
+use crate::ir::context::ConversionContext;
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
 
@@ -36,6 +37,15 @@ impl Format for RfcXmlFormat {
         Ok(crate::from_ir(&ir_doc))
     }
 
+    fn parse_with_context(
+        &self,
+        source: &str,
+        ctx: &ConversionContext,
+    ) -> Result<Document, FormatError> {
+        let ir_doc = parser::parse_to_ir(source)?;
+        Ok(crate::from_ir_with_context(&ir_doc, ctx))
+    }
+
     fn serialize(&self, _doc: &Document) -> Result<String, FormatError> {
         Err(FormatError::NotSupported(
             "RFC XML serialization not implemented".to_string(),
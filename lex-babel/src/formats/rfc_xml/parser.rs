@@ -1,21 +1,36 @@
-use crate::error::FormatError;
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
 use crate::ir::nodes::{
     Definition, DocNode, Document, Heading, InlineContent, List, ListForm, ListItem, ListStyle,
-    Paragraph, Verbatim,
+    Paragraph, SourcePosition, Verbatim,
 };
-use roxmltree::{Node, NodeType};
+use roxmltree::{Node, NodeType, TextPos};
 
 pub fn parse_to_ir(source: &str) -> Result<Document, FormatError> {
-    let doc = roxmltree::Document::parse(source)
-        .map_err(|e| FormatError::ParseError(format!("XML parsing error: {}", e)))?;
+    let doc = roxmltree::Document::parse(source).map_err(|e| {
+        ErrorDetail::new(
+            ErrorKind::Parse,
+            "xml-syntax",
+            format!("XML parsing error: {e}"),
+        )
+        .in_format("rfc_xml")
+        .at(position(e.pos()))
+        .caused_by(e)
+    })?;
 
     let root = doc.root_element();
     // RFC XML root is <rfc> or sometimes <internet-draft> (older?) -> spec says <rfc>
     if root.tag_name().name() != "rfc" {
-        return Err(FormatError::ParseError(format!(
-            "Root element is <{}>, expected <rfc>",
-            root.tag_name().name()
-        )));
+        return Err(ErrorDetail::new(
+            ErrorKind::Parse,
+            "rfc-root",
+            format!(
+                "Root element is <{}>, expected <rfc>",
+                root.tag_name().name()
+            ),
+        )
+        .in_format("rfc_xml")
+        .at(position(doc.text_pos_at(root.range().start)))
+        .into());
     }
 
     let mut doc_children = Vec::new();
@@ -34,6 +49,8 @@ pub fn parse_to_ir(source: &str) -> Result<Document, FormatError> {
                 level: 2,
                 content: vec![InlineContent::Text("Abstract".to_string())],
                 children: process_container_children(abstract_node, 2)?,
+                id: None,
+                span: None,
             }));
         }
     }
@@ -53,13 +70,24 @@ pub fn parse_to_ir(source: &str) -> Result<Document, FormatError> {
         level: 1,
         content: title_content,
         children: doc_children,
+        id: None,
+        span: None,
     });
 
     Ok(Document {
         children: vec![root_heading],
+        ..Default::default()
     })
 }
 
+/// Zero-based position from roxmltree's 1-based row and column
+fn position(pos: TextPos) -> SourcePosition {
+    SourcePosition::new(
+        pos.row.saturating_sub(1) as usize,
+        pos.col.saturating_sub(1) as usize,
+    )
+}
+
 fn process_middle(node: Node) -> Result<Vec<DocNode>, FormatError> {
     // Middle sections are level 2 (under Root)
     process_container_children(node, 1)
@@ -97,11 +125,14 @@ fn process_container_children(
                     level: current_level + 1,
                     content: title_text,
                     children: process_container_children(child, current_level + 1)?,
+                    id: child.attribute("anchor").map(str::to_string),
+                    span: None,
                 }));
             }
             "t" => {
                 nodes.push(DocNode::Paragraph(Paragraph {
                     content: parse_inline_content(child)?,
+                    span: None,
                 }));
             }
             "ul" | "ol" => {
@@ -116,6 +147,7 @@ fn process_container_children(
                     style,
                     form: ListForm::Short,
                     items: process_list_items(child, current_level)?,
+                    span: None,
                 }));
             }
             "list" => {
@@ -132,6 +164,7 @@ fn process_container_children(
                     style,
                     form: ListForm::Short,
                     items: process_list_items(child, current_level)?,
+                    span: None,
                 }));
             }
             "dl" => {
@@ -163,6 +196,8 @@ fn process_container_children(
                     level: current_level + 1,
                     content: title_text,
                     children: process_container_children(child, current_level + 1)?,
+                    id: None,
+                    span: None,
                 }));
             }
             "references" => {
@@ -181,6 +216,8 @@ fn process_container_children(
                     level: current_level + 1,
                     content: title_text,
                     children: process_container_children(child, current_level + 1)?,
+                    id: None,
+                    span: None,
                 }));
             }
             "reference" => {
@@ -196,7 +233,10 @@ fn process_container_children(
                     }
                 }
 
-                nodes.push(DocNode::Paragraph(Paragraph { content }));
+                nodes.push(DocNode::Paragraph(Paragraph {
+                    content,
+                    span: None,
+                }));
             }
             _ => {
                 // Skip unknown
@@ -224,11 +264,18 @@ fn process_list_items(node: Node, level: usize) -> Result<Vec<ListItem>, FormatE
                 }
             }
 
-            items.push(ListItem { content, children });
+            items.push(ListItem {
+                content,
+                children,
+                checked: None,
+                span: None,
+            });
         } else if child.tag_name().name() == "t" {
             items.push(ListItem {
                 content: parse_inline_content(child)?,
                 children: Vec::new(),
+                checked: None,
+                span: None,
             });
         }
     }
@@ -251,6 +298,7 @@ fn process_definition_list(node: Node, level: usize) -> Result<Vec<DocNode>, For
             definitions.push(DocNode::Definition(Definition {
                 term: current_term.clone(),
                 description,
+                span: None,
             }));
             current_term.clear();
         }
@@ -264,6 +312,7 @@ fn parse_verbatim(node: Node) -> Result<DocNode, FormatError> {
         subject: None,
         language: node.attribute("type").map(|s| s.to_string()),
         content: text,
+        span: None,
     }))
 }
 
@@ -22,6 +22,7 @@
 
 use crate::error::FormatError;
 use crate::format::Format;
+use crate::options::parse_bool;
 use lex_core::lex::ast::trait_helpers::try_as_container;
 use lex_core::lex::ast::traits::AstNode;
 use lex_core::lex::ast::{ContentItem, Document};
@@ -254,12 +255,12 @@ pub fn serialize_document_with_params(doc: &Document, params: &HashMap<String, S
     // Check if ast-full parameter is set to true
     let include_all = params
         .get("ast-full")
-        .map(|v| v.to_lowercase() == "true")
+        .and_then(|v| parse_bool(v))
         .unwrap_or(false);
 
     let show_linum = params
         .get("show-linum")
-        .map(|v| v != "false")
+        .map(|v| parse_bool(v).unwrap_or(true))
         .unwrap_or(false);
 
     let mut result = String::new();
@@ -0,0 +1,596 @@
+//! Citations: `[@key]` references resolved against a bibliography.
+//!
+//! A document names its bibliography, a BibTeX or CSL-JSON file relative to
+//! the document, with a `bibliography` metadata entry (`:: bibliography ::
+//! refs.bib`, or `bibliography:` in Markdown front matter), and may pick a
+//! `citation-style`. A [`ConversionContext`] can override both, which is how
+//! `lex convert --bibliography` works.
+//!
+//! A citation names one or more keys, each with an optional locator:
+//! `[@smith2023]`, `[@smith2023, p. 45]`, `[@smith2023; @lee2020]`.
+//! [`process_citations`] replaces every citation whose keys are all in the
+//! bibliography with its in-text form, linked to a generated References
+//! session that lists the cited works. Citations with unknown keys are left
+//! as written.
+//!
+//! | Style         | In text                          | References                     |
+//! |---------------|----------------------------------|--------------------------------|
+//! | `author-year` | `(Smith and Lee 2023, p. 45)`    | Sorted by author, then year    |
+//! | `numeric`     | `[1, p. 45]`                     | Numbered in order of citation  |
+
+use super::context::ConversionContext;
+use super::footnotes::is_notes_session;
+use super::nodes::{
+    DocNode, Document, Heading, InlineContent, Link, List, ListForm, ListItem, ListStyle, Metadata,
+    SourceSpan,
+};
+use super::visit::{
+    walk_document, walk_document_mut, walk_inline, walk_inline_mut, walk_node, Visitor, VisitorMut,
+};
+use crate::common::bibliography::{Bibliography, Entry, Name};
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Id of the generated References session, which citations link to
+pub const REFERENCES_ID: &str = "bibliography";
+
+/// How citations and the reference list are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CitationStyle {
+    /// `(Smith 2023)`, references sorted by author
+    #[default]
+    AuthorYear,
+    /// `[1]`, references numbered in order of first citation
+    Numeric,
+}
+
+impl CitationStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CitationStyle::AuthorYear => "author-year",
+            CitationStyle::Numeric => "numeric",
+        }
+    }
+}
+
+impl fmt::Display for CitationStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CitationStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "author-year" | "authoryear" => Ok(CitationStyle::AuthorYear),
+            "numeric" => Ok(CitationStyle::Numeric),
+            other => Err(format!(
+                "Unknown citation style '{other}' (available: author-year, numeric)"
+            )),
+        }
+    }
+}
+
+/// One cited work of a citation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CiteItem {
+    pub key: String,
+    /// Where in the work, e.g. `p. 45`
+    pub locator: Option<String>,
+}
+
+/// The works a reference (without brackets) cites, or `None` if it is not a
+/// citation
+pub fn parse_citation(raw: &str) -> Option<Vec<CiteItem>> {
+    let mut items: Vec<CiteItem> = Vec::new();
+    for segment in raw.split([';', ',']) {
+        let segment = segment.trim();
+        match segment.strip_prefix('@') {
+            Some(key) => {
+                if key.is_empty() || !key.chars().all(is_key_char) {
+                    return None;
+                }
+                items.push(CiteItem {
+                    key: key.to_string(),
+                    locator: None,
+                });
+            }
+            // Anything else locates the work cited before it
+            None => {
+                let item = items.last_mut()?;
+                if !segment.is_empty() {
+                    let locator = item.locator.get_or_insert_with(String::new);
+                    if !locator.is_empty() {
+                        locator.push_str(", ");
+                    }
+                    locator.push_str(segment);
+                }
+            }
+        }
+    }
+    (!items.is_empty()).then_some(items)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-:./+".contains(c)
+}
+
+/// The bibliography file `metadata` or `ctx` names, and where the metadata
+/// entry came from
+pub fn bibliography_path(
+    metadata: &Metadata,
+    ctx: &ConversionContext,
+) -> Option<(PathBuf, Option<SourceSpan>)> {
+    if let Some(path) = ctx.bibliography {
+        return Some((path.to_path_buf(), None));
+    }
+    let entry = metadata
+        .entries
+        .iter()
+        .find(|entry| entry.key == "bibliography")?;
+    let path = entry.value.as_text();
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    let span = entry.source.as_ref().and_then(|source| source.span.clone());
+    Some((ctx.resolve_path(path), span))
+}
+
+/// Read the bibliography `metadata` or `ctx` names, with the citation style
+///
+/// Returns `None` when there is no bibliography to read.
+pub fn load_bibliography(
+    metadata: &Metadata,
+    ctx: &ConversionContext,
+) -> Result<Option<(Bibliography, CitationStyle)>, FormatError> {
+    let Some((path, span)) = bibliography_path(metadata, ctx) else {
+        return Ok(None);
+    };
+    let at_entry = |err: FormatError| match &span {
+        Some(span) => err.with_position(span.start),
+        None => err,
+    };
+
+    let style = match (ctx.citation_style, metadata.get_text("citation-style")) {
+        (Some(style), _) => style,
+        (None, Some(name)) => name.parse().map_err(|message: String| {
+            at_entry(ErrorDetail::new(ErrorKind::Parse, "invalid-citation-style", message).into())
+        })?,
+        (None, None) => CitationStyle::default(),
+    };
+
+    let text = fs::read_to_string(&path).map_err(|err| {
+        at_entry(
+            ErrorDetail::new(
+                ErrorKind::Parse,
+                "bibliography-source",
+                format!("Cannot read bibliography '{}': {err}", path.display()),
+            )
+            .caused_by(err)
+            .into(),
+        )
+    })?;
+    let bibliography = Bibliography::parse(&text, &path).map_err(|err| {
+        at_entry(
+            ErrorDetail::new(
+                ErrorKind::Parse,
+                "bibliography-syntax",
+                format!("Invalid bibliography '{}', {err}", path.display()),
+            )
+            .caused_by(err)
+            .into(),
+        )
+    })?;
+    Ok(Some((bibliography, style)))
+}
+
+/// Replace known citations with their in-text form and add a References
+/// session listing the cited works
+///
+/// The session goes at the end of the document, before a trailing notes
+/// session. Nothing is added when no citation resolves.
+pub fn process_citations(doc: &mut Document, bibliography: &Bibliography, style: CitationStyle) {
+    let mut citer = Citer {
+        bibliography,
+        style,
+        cited: Vec::new(),
+    };
+    walk_document_mut(&mut citer, doc);
+    if citer.cited.is_empty() {
+        return;
+    }
+
+    let mut cited: Vec<&Entry> = citer.cited;
+    let list = match style {
+        CitationStyle::AuthorYear => {
+            cited.sort_by_cached_key(|entry| {
+                (
+                    entry.authors.first().map(|name| name.family.to_lowercase()),
+                    entry.year.clone(),
+                    entry.title.clone(),
+                )
+            });
+            reference_list(&cited, ListStyle::Bullet, |_| "-".to_string())
+        }
+        CitationStyle::Numeric => reference_list(&cited, ListStyle::Numeric, |n| format!("{n}.")),
+    };
+
+    let level = doc
+        .children
+        .iter()
+        .filter_map(|node| match node {
+            DocNode::Heading(heading) => Some(heading.level),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(1);
+    let references = DocNode::Heading(Heading {
+        level,
+        content: vec![InlineContent::Text("References".to_string())],
+        children: vec![list],
+        id: Some(REFERENCES_ID.to_string()),
+        span: None,
+    });
+    let at = match doc.children.last() {
+        Some(last) if is_notes_session(last) => doc.children.len() - 1,
+        _ => doc.children.len(),
+    };
+    doc.children.insert(at, references);
+}
+
+/// Citations in `doc` that name a key the bibliography lacks, in document order
+pub fn unknown_citations(doc: &Document) -> Vec<(String, Option<SourceSpan>)> {
+    let mut finder = CitationFinder::default();
+    walk_document(&mut finder, doc);
+    finder.found
+}
+
+struct Citer<'a> {
+    bibliography: &'a Bibliography,
+    style: CitationStyle,
+    /// Cited entries in order of first citation
+    cited: Vec<&'a Entry>,
+}
+
+impl VisitorMut for Citer<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        let InlineContent::Reference(raw) = inline else {
+            walk_inline_mut(self, inline);
+            return;
+        };
+        let Some(items) = parse_citation(raw) else {
+            return;
+        };
+        let bibliography = self.bibliography;
+        let Some(entries) = items
+            .iter()
+            .map(|item| bibliography.get(&item.key))
+            .collect::<Option<Vec<&Entry>>>()
+        else {
+            return;
+        };
+
+        let mut parts = Vec::new();
+        for (item, &entry) in items.iter().zip(&entries) {
+            let number = match self.cited.iter().position(|cited| cited.key == entry.key) {
+                Some(index) => index + 1,
+                None => {
+                    self.cited.push(entry);
+                    self.cited.len()
+                }
+            };
+            let mut part = match self.style {
+                CitationStyle::AuthorYear => {
+                    format!("{} {}", short_authors(entry), year(entry))
+                }
+                CitationStyle::Numeric => number.to_string(),
+            };
+            if let Some(locator) = &item.locator {
+                part.push_str(", ");
+                part.push_str(locator);
+            }
+            parts.push(part);
+        }
+
+        let text = match self.style {
+            CitationStyle::AuthorYear => format!("({})", parts.join("; ")),
+            CitationStyle::Numeric => {
+                let separator = if items.iter().any(|item| item.locator.is_some()) {
+                    "; "
+                } else {
+                    ", "
+                };
+                format!("[{}]", parts.join(separator))
+            }
+        };
+        let titles: Vec<&str> = entries
+            .iter()
+            .filter_map(|entry| entry.title.as_deref())
+            .collect();
+        *inline = InlineContent::Link(Link {
+            url: format!("#{REFERENCES_ID}"),
+            content: vec![InlineContent::Text(text)],
+            title: (!titles.is_empty()).then(|| titles.join("; ")),
+        });
+    }
+}
+
+/// `Smith`, `Smith and Lee` or `Smith et al.`; the title when there are no
+/// authors
+fn short_authors(entry: &Entry) -> String {
+    match entry.authors.as_slice() {
+        [] => entry.title.clone().unwrap_or_else(|| entry.key.clone()),
+        [one] => one.family.clone(),
+        [first, second] => format!("{} and {}", first.family, second.family),
+        [first, ..] => format!("{} et al.", first.family),
+    }
+}
+
+fn year(entry: &Entry) -> &str {
+    entry.year.as_deref().unwrap_or("n.d.")
+}
+
+fn reference_list(
+    entries: &[&Entry],
+    style: ListStyle,
+    marker: impl Fn(usize) -> String,
+) -> DocNode {
+    let items = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mut content = vec![
+                InlineContent::Marker(marker(i + 1)),
+                InlineContent::Text(" ".to_string()),
+            ];
+            content.extend(reference_text(entry));
+            ListItem {
+                content,
+                children: Vec::new(),
+                checked: None,
+                span: None,
+            }
+        })
+        .collect();
+    DocNode::List(List {
+        items,
+        ordered: style != ListStyle::Bullet,
+        style,
+        form: ListForm::Short,
+        span: None,
+    })
+}
+
+/// `Smith, J. and Lee, A. (2023). Title. *Journal*, 12, 1–10. Publisher. link`
+fn reference_text(entry: &Entry) -> Vec<InlineContent> {
+    let mut text = String::new();
+    if !entry.authors.is_empty() {
+        text.push_str(&full_authors(&entry.authors));
+        text.push(' ');
+    }
+    text.push_str(&format!("({}).", year(entry)));
+    if let Some(title) = &entry.title {
+        text.push(' ');
+        text.push_str(title.trim_end_matches('.'));
+        text.push('.');
+    }
+
+    let mut content = vec![InlineContent::Text(text)];
+    if let Some(container) = &entry.container {
+        content.push(InlineContent::Text(" ".to_string()));
+        content.push(InlineContent::Italic(vec![InlineContent::Text(
+            container.clone(),
+        )]));
+        let mut rest = String::new();
+        for detail in [&entry.volume, &entry.pages].into_iter().flatten() {
+            rest.push_str(", ");
+            rest.push_str(detail);
+        }
+        rest.push('.');
+        content.push(InlineContent::Text(rest));
+    }
+    if let Some(publisher) = &entry.publisher {
+        content.push(InlineContent::Text(format!(" {publisher}.")));
+    }
+
+    let link = match (&entry.doi, &entry.url) {
+        (Some(doi), _) if doi.starts_with("http") => Some(doi.clone()),
+        (Some(doi), _) => Some(format!("https://doi.org/{doi}")),
+        (None, Some(url)) => Some(url.clone()),
+        (None, None) => None,
+    };
+    if let Some(url) = link {
+        content.push(InlineContent::Text(" ".to_string()));
+        content.push(InlineContent::Link(Link {
+            content: vec![InlineContent::Text(url.clone())],
+            url,
+            title: None,
+        }));
+    }
+    content
+}
+
+/// `Smith, J.`, `Smith, J. and Lee, A.`, `Smith, J., Lee, A. and Park, S.`
+fn full_authors(names: &[Name]) -> String {
+    let names: Vec<String> = names.iter().map(full_name).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+        _ => names.join(""),
+    }
+}
+
+fn full_name(name: &Name) -> String {
+    let initials: Vec<String> = name
+        .given
+        .iter()
+        .flat_map(|given| given.split([' ', '-']))
+        .filter_map(|part| part.chars().next())
+        .map(|initial| format!("{initial}."))
+        .collect();
+    if initials.is_empty() {
+        name.family.clone()
+    } else {
+        format!("{}, {}", name.family, initials.join(" "))
+    }
+}
+
+#[derive(Default)]
+struct CitationFinder {
+    /// Span of the innermost enclosing node that has one
+    span: Option<SourceSpan>,
+    found: Vec<(String, Option<SourceSpan>)>,
+}
+
+impl Visitor for CitationFinder {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+        walk_node(self, node);
+        self.span = outer;
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        // Resolved citations are links by now
+        if let InlineContent::Reference(raw) = inline {
+            if parse_citation(raw).is_some() {
+                self.found.push((raw.clone(), self.span.clone()));
+            }
+        }
+        walk_inline(self, inline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::bibliography::parse_bibtex;
+    use crate::ir::nodes::Paragraph;
+
+    const BIB: &str = r#"
+@article{smith2023, author = {Smith, John and Lee, Ann}, title = {Rivers},
+  journal = {Hydrology}, volume = 12, pages = {1--10}, year = 2023, doi = {10.1/x}}
+@book{adams2020, author = {Adams, Zoe}, title = {Lakes}, publisher = {Acme}, year = 2020}
+"#;
+
+    fn document(references: &[&str]) -> Document {
+        Document {
+            children: vec![DocNode::Paragraph(Paragraph {
+                content: references
+                    .iter()
+                    .map(|raw| InlineContent::Reference(raw.to_string()))
+                    .collect(),
+                span: None,
+            })],
+            metadata: Default::default(),
+        }
+    }
+
+    fn link_texts(doc: &Document) -> Vec<String> {
+        let DocNode::Paragraph(paragraph) = &doc.children[0] else {
+            panic!("expected a paragraph");
+        };
+        paragraph
+            .content
+            .iter()
+            .map(|inline| match inline {
+                InlineContent::Link(link) => format!("{:?}", link.content),
+                other => format!("{other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_citation() {
+        assert_eq!(
+            parse_citation("@smith2023, p. 45; @lee"),
+            Some(vec![
+                CiteItem {
+                    key: "smith2023".to_string(),
+                    locator: Some("p. 45".to_string()),
+                },
+                CiteItem {
+                    key: "lee".to_string(),
+                    locator: None,
+                },
+            ])
+        );
+        assert_eq!(parse_citation("https://example.com"), None);
+        assert_eq!(parse_citation("#2.1"), None);
+        assert_eq!(parse_citation("@"), None);
+    }
+
+    #[test]
+    fn test_author_year() {
+        let bib = parse_bibtex(BIB).unwrap();
+        let mut doc = document(&["@smith2023, p. 4", "@adams2020; @smith2023", "@nobody"]);
+        process_citations(&mut doc, &bib, CitationStyle::AuthorYear);
+
+        let texts = link_texts(&doc);
+        assert!(texts[0].contains("(Smith and Lee 2023, p. 4)"), "{texts:?}");
+        assert!(
+            texts[1].contains("(Adams 2020; Smith and Lee 2023)"),
+            "{texts:?}"
+        );
+        assert!(texts[2].contains("Reference(\"@nobody\")"), "{texts:?}");
+
+        let DocNode::Heading(references) = &doc.children[1] else {
+            panic!("expected the references session");
+        };
+        assert_eq!(references.id.as_deref(), Some(REFERENCES_ID));
+        let DocNode::List(list) = &references.children[0] else {
+            panic!("expected a list");
+        };
+        // Sorted by author
+        let first = format!("{:?}", list.items[0].content);
+        assert!(first.contains("Adams, Z. (2020). Lakes."), "{first}");
+        assert!(first.contains(" Acme."), "{first}");
+        let second = format!("{:?}", list.items[1].content);
+        assert!(
+            second.contains("Smith, J. and Lee, A. (2023). Rivers."),
+            "{second}"
+        );
+        assert!(second.contains("https://doi.org/10.1/x"), "{second}");
+
+        assert_eq!(unknown_citations(&doc).len(), 1);
+    }
+
+    #[test]
+    fn test_numeric() {
+        let bib = parse_bibtex(BIB).unwrap();
+        let mut doc = document(&["@smith2023", "@adams2020, ch. 2", "@smith2023; @adams2020"]);
+        process_citations(&mut doc, &bib, CitationStyle::Numeric);
+
+        let texts = link_texts(&doc);
+        assert!(texts[0].contains("[1]"), "{texts:?}");
+        assert!(texts[1].contains("[2, ch. 2]"), "{texts:?}");
+        assert!(texts[2].contains("[1, 2]"), "{texts:?}");
+
+        let DocNode::Heading(references) = &doc.children[1] else {
+            panic!("expected the references session");
+        };
+        let DocNode::List(list) = &references.children[0] else {
+            panic!("expected a list");
+        };
+        // In order of first citation
+        assert_eq!(
+            list.items[0].content[0],
+            InlineContent::Marker("1.".to_string())
+        );
+        assert!(format!("{:?}", list.items[0].content).contains("Rivers"));
+    }
+
+    #[test]
+    fn test_style_names() {
+        assert_eq!("numeric".parse(), Ok(CitationStyle::Numeric));
+        assert_eq!("Author-Year".parse(), Ok(CitationStyle::AuthorYear));
+        assert!("apa".parse::<CitationStyle>().is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Shared settings for converting between Lex and the IR.
+//!
+//! A [`ConversionContext`] borrows the pieces a conversion consults but does
+//! not own, so one [`VerbatimRegistry`] can serve every conversion a
+//! [`crate::FormatRegistry`] runs. Custom handlers registered there (say for
+//! `acme.*` blocks) turn those verbatim blocks into real IR nodes on the way
+//! in and back into verbatim blocks on the way out.
+//!
+//! The context also knows where the document lives, so handlers that read
+//! data from files (`:: doc.table src="./data.csv" ::`) can resolve relative
+//! paths the way the author sees them. The bibliography citations resolve
+//! against comes from the document's metadata unless the context names one.
+//! While a `doc.include` is being converted, the context also carries the
+//! chain of files that led to it, so include cycles can be reported.
+//!
+//! Conversion itself never fails: a block whose handler or `src=` file fails
+//! stays plain verbatim text. A context with a failure list records those
+//! errors as it goes, which is how [`super::from_lex::try_convert_document`]
+//! checks a document in the same pass that converts it.
+
+use super::citations::CitationStyle;
+use super::from_lex::DEFAULT_METADATA_LABELS;
+use crate::common::verbatim::VerbatimRegistry;
+use crate::error::FormatError;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+/// Options threaded through Lex ↔ IR conversion and the serializers built on it.
+#[derive(Clone, Copy)]
+pub struct ConversionContext<'a> {
+    /// Handlers for labelled verbatim blocks
+    pub verbatim: &'a VerbatimRegistry,
+    /// Top-level annotation labels promoted to document metadata
+    pub metadata_labels: &'a [&'a str],
+    /// Directory relative file references are resolved against; the current
+    /// directory when unset
+    pub base_dir: Option<&'a Path>,
+    /// Bibliography file overriding the document's `bibliography` metadata
+    pub bibliography: Option<&'a Path>,
+    /// Citation style overriding the document's `citation-style` metadata
+    pub citation_style: Option<CitationStyle>,
+    /// Files being included, outermost first, while converting a `doc.include`
+    pub includes: &'a [PathBuf],
+    /// Where blocks that fall back to plain verbatim record why
+    pub failures: Option<&'a RefCell<Vec<FormatError>>>,
+}
+
+impl<'a> ConversionContext<'a> {
+    /// A context using `verbatim` and the default metadata labels
+    pub fn new(verbatim: &'a VerbatimRegistry) -> Self {
+        Self {
+            verbatim,
+            metadata_labels: DEFAULT_METADATA_LABELS,
+            base_dir: None,
+            bibliography: None,
+            citation_style: None,
+            includes: &[],
+            failures: None,
+        }
+    }
+
+    /// Replace the labels promoted to document metadata
+    pub fn with_metadata_labels(mut self, labels: &'a [&'a str]) -> Self {
+        self.metadata_labels = labels;
+        self
+    }
+
+    /// Resolve relative file references against `dir`, usually the directory
+    /// of the document being converted
+    pub fn with_base_dir(mut self, dir: Option<&'a Path>) -> Self {
+        self.base_dir = dir;
+        self
+    }
+
+    /// Resolve citations against `path` instead of the bibliography the
+    /// document names
+    pub fn with_bibliography(mut self, path: Option<&'a Path>) -> Self {
+        self.bibliography = path;
+        self
+    }
+
+    /// Write citations in `style` instead of the one the document names
+    pub fn with_citation_style(mut self, style: Option<CitationStyle>) -> Self {
+        self.citation_style = style;
+        self
+    }
+
+    /// Convert with `files` as the chain of includes leading here
+    pub fn with_includes(mut self, files: &'a [PathBuf]) -> Self {
+        self.includes = files;
+        self
+    }
+
+    /// Record the errors of blocks that fall back to plain verbatim in `failures`
+    pub fn with_failures(mut self, failures: Option<&'a RefCell<Vec<FormatError>>>) -> Self {
+        self.failures = failures;
+        self
+    }
+
+    /// Record `err` for the caller checking this conversion, if there is one
+    pub(crate) fn record_failure(&self, err: FormatError) {
+        if let Some(failures) = self.failures {
+            failures.borrow_mut().push(err);
+        }
+    }
+
+    /// `path` as seen from the document
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        match self.base_dir {
+            Some(dir) => dir.join(path),
+            None => Path::new(path).to_path_buf(),
+        }
+    }
+}
+
+impl ConversionContext<'static> {
+    /// The standard verbatim handlers and default metadata labels
+    pub fn standard() -> Self {
+        Self::new(VerbatimRegistry::standard())
+    }
+}
+
+impl Default for ConversionContext<'static> {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
@@ -24,7 +24,9 @@ pub enum Event {
         form: ListForm,
     },
     EndList,
-    StartListItem,
+    StartListItem {
+        checked: Option<bool>,
+    },
     EndListItem,
     StartDefinition,
     EndDefinition,
@@ -44,7 +46,9 @@ pub enum Event {
     EndAnnotation {
         label: String,
     },
-    StartTable,
+    StartTable {
+        caption: Option<Vec<crate::ir::nodes::InlineContent>>,
+    },
     EndTable,
     StartTableRow {
         header: bool,
@@ -53,8 +57,22 @@ pub enum Event {
     StartTableCell {
         header: bool,
         align: crate::ir::nodes::TableCellAlignment,
+        colspan: usize,
+        rowspan: usize,
     },
     EndTableCell,
+    StartBlockQuote,
+    EndBlockQuote,
+    ThematicBreak,
+    /// Source span of the node opened by the next event
+    ///
+    /// Only emitted for nodes that carry a span. Consumers that do not track
+    /// provenance can ignore it.
+    Source(crate::ir::nodes::SourceSpan),
+    /// Id of the heading opened by the next event, for cross-references
+    ///
+    /// Follows any `Source` event. Consumers without anchors can ignore it.
+    Anchor(String),
     Image(crate::ir::nodes::Image),
     Video(crate::ir::nodes::Video),
     Audio(crate::ir::nodes::Audio),
@@ -0,0 +1,476 @@
+//! Footnotes: numbered references and the Notes session they point to.
+//!
+//! A Lex document keeps its footnotes in a trailing `Notes` (or `Footnotes`)
+//! session, as a numbered list or, in the older form, as numbered child
+//! sessions; `[1]` in the text refers to the note numbered `1`. A single
+//! note is a numbered paragraph, since a list needs two items:
+//!
+//! ```text
+//! Rivers flood in spring [1].
+//!
+//! Notes
+//!
+//!     1. Snow melt, mostly.
+//! ```
+//!
+//! [`take_notes`] lifts that session out of an IR document so a serializer
+//! can render the notes in its own footnote form, and [`footnote_issues`]
+//! lists references without a note and notes nothing refers to;
+//! [`unlink_missing_notes`] keeps the former as the `[n]` text they were.
+//! [`notes_session`] goes the other way for importers.
+
+use super::nodes::{
+    DocNode, Document, Heading, InlineContent, List, ListForm, ListItem, ListStyle, Paragraph,
+    SourceSpan,
+};
+use super::references::split_title;
+use super::visit::{
+    walk_document, walk_document_mut, walk_inline, walk_inline_mut, walk_node, Visitor, VisitorMut,
+};
+use std::collections::HashSet;
+
+/// Titles that mark the notes session, compared case-insensitively
+pub const NOTES_TITLES: &[&str] = &["Notes", "Footnotes"];
+
+/// One note of the notes session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footnote {
+    /// The number references use, e.g. `1`
+    pub label: String,
+    pub content: Vec<DocNode>,
+    pub span: Option<SourceSpan>,
+}
+
+/// A footnote reference without a note, or a note without a reference
+#[derive(Debug, Clone, PartialEq)]
+pub enum FootnoteIssue {
+    Missing {
+        label: String,
+        span: Option<SourceSpan>,
+    },
+    Unused {
+        label: String,
+        span: Option<SourceSpan>,
+    },
+}
+
+/// Remove the notes session from the end of `doc` and return its notes
+///
+/// Returns nothing, and leaves `doc` alone, when the last top-level node is
+/// not a notes session or holds anything besides numbered notes.
+pub fn take_notes(doc: &mut Document) -> Vec<Footnote> {
+    let notes = match doc.children.last() {
+        Some(DocNode::Heading(heading)) if is_notes_title(&heading.content) => {
+            notes_of(&heading.children)
+        }
+        _ => None,
+    };
+    match notes {
+        Some(notes) => {
+            doc.children.pop();
+            notes
+        }
+        None => Vec::new(),
+    }
+}
+
+/// References without a note and notes without a reference, in document order
+pub fn footnote_issues(doc: &Document) -> Vec<FootnoteIssue> {
+    let mut doc = doc.clone();
+    let notes = take_notes(&mut doc);
+
+    let mut finder = RefFinder::default();
+    walk_document(&mut finder, &doc);
+    // Notes may cite other notes
+    for note in &notes {
+        for node in &note.content {
+            finder.visit_node(node);
+        }
+    }
+
+    let mut issues = Vec::new();
+    let mut reported = HashSet::new();
+    for (label, span) in &finder.refs {
+        let has_note = notes.iter().any(|note| &note.label == label);
+        if !has_note && reported.insert(label) {
+            issues.push(FootnoteIssue::Missing {
+                label: label.clone(),
+                span: span.clone(),
+            });
+        }
+    }
+    for note in &notes {
+        if !finder.refs.iter().any(|(label, _)| label == &note.label) {
+            issues.push(FootnoteIssue::Unused {
+                label: note.label.clone(),
+                span: note.span.clone(),
+            });
+        }
+    }
+    issues
+}
+
+/// Turn references to notes missing from `notes` back into `[n]` text
+///
+/// For serializers that link references to their notes, so a reference
+/// with nothing to link to reads as written.
+pub fn unlink_missing_notes(doc: &mut Document, notes: &[Footnote]) {
+    walk_document_mut(&mut Unlinker { notes }, doc);
+}
+
+/// A `Notes` session at `level` holding `notes` as a numbered list
+///
+/// A list needs two items, so a single note is written as Lex parses it
+/// back: a numbered paragraph, or a numbered session when it runs longer.
+pub fn notes_session(level: usize, mut notes: Vec<Footnote>) -> DocNode {
+    let children = match notes.len() {
+        1 => {
+            let mut note = notes.remove(0);
+            match &mut note.content[..] {
+                [] | [DocNode::Paragraph(_)] => {
+                    let mut content = vec![InlineContent::Text(format!("{}. ", note.label))];
+                    if let Some(DocNode::Paragraph(text)) = note.content.pop() {
+                        content.extend(text.content);
+                    }
+                    vec![DocNode::Paragraph(Paragraph {
+                        content,
+                        span: note.span,
+                    })]
+                }
+                _ => {
+                    let item = note_item(note);
+                    vec![DocNode::Heading(Heading {
+                        level: level + 1,
+                        content: item.content,
+                        children: item.children,
+                        id: None,
+                        span: item.span,
+                    })]
+                }
+            }
+        }
+        _ => vec![DocNode::List(List {
+            items: notes.into_iter().map(note_item).collect(),
+            ordered: true,
+            style: ListStyle::Numeric,
+            form: ListForm::Short,
+            span: None,
+        })],
+    };
+
+    DocNode::Heading(Heading {
+        level,
+        content: vec![InlineContent::Text(NOTES_TITLES[0].to_string())],
+        children,
+        id: None,
+        span: None,
+    })
+}
+
+/// A note as a numbered list item
+fn note_item(note: Footnote) -> ListItem {
+    let mut content = vec![
+        InlineContent::Marker(format!("{}.", note.label)),
+        InlineContent::Text(" ".to_string()),
+    ];
+    // The first paragraph becomes the item's own text
+    let mut children = note.content;
+    if matches!(children.first(), Some(DocNode::Paragraph(_))) {
+        if let DocNode::Paragraph(first) = children.remove(0) {
+            content.extend(first.content);
+        }
+    }
+    ListItem {
+        content,
+        children,
+        checked: None,
+        span: note.span,
+    }
+}
+
+/// Whether `node` is a notes session [`take_notes`] would lift out
+pub(super) fn is_notes_session(node: &DocNode) -> bool {
+    match node {
+        DocNode::Heading(heading) => {
+            is_notes_title(&heading.content) && notes_of(&heading.children).is_some()
+        }
+        _ => false,
+    }
+}
+
+fn is_notes_title(content: &[InlineContent]) -> bool {
+    let (_, title) = split_title(content);
+    NOTES_TITLES
+        .iter()
+        .any(|notes| title.eq_ignore_ascii_case(notes))
+}
+
+/// The notes in a notes session's children, or `None` if anything else is there
+fn notes_of(children: &[DocNode]) -> Option<Vec<Footnote>> {
+    let mut notes = Vec::new();
+    for child in children {
+        match child {
+            DocNode::List(list) => {
+                for item in &list.items {
+                    let (label, text) = split_label(&item.content)?;
+                    let mut content = Vec::new();
+                    if !text.is_empty() {
+                        content.push(DocNode::Paragraph(Paragraph {
+                            content: text,
+                            span: item.span.clone(),
+                        }));
+                    }
+                    content.extend(item.children.iter().cloned());
+                    notes.push(Footnote {
+                        label,
+                        content,
+                        span: item.span.clone(),
+                    });
+                }
+            }
+            // A single one-paragraph note, which parses as a paragraph
+            DocNode::Paragraph(paragraph) if children.len() == 1 => {
+                let (label, text) = split_numbered_text(&paragraph.content)?;
+                notes.push(Footnote {
+                    label,
+                    content: vec![DocNode::Paragraph(Paragraph {
+                        content: text,
+                        span: paragraph.span.clone(),
+                    })],
+                    span: paragraph.span.clone(),
+                });
+            }
+            // The older form: `1. First note` as a session of its own
+            DocNode::Heading(heading) => {
+                let (label, text) = split_label(&heading.content)?;
+                let mut content = vec![DocNode::Paragraph(Paragraph {
+                    content: text,
+                    span: heading.span.clone(),
+                })];
+                content.extend(heading.children.iter().cloned());
+                notes.push(Footnote {
+                    label,
+                    content,
+                    span: heading.span.clone(),
+                });
+            }
+            _ => return None,
+        }
+    }
+    (!notes.is_empty()).then_some(notes)
+}
+
+/// Split a paragraph's `1. Text` content, where the number is plain text
+fn split_numbered_text(content: &[InlineContent]) -> Option<(String, Vec<InlineContent>)> {
+    let [InlineContent::Text(first), rest @ ..] = content else {
+        return None;
+    };
+    let (number, text) = first.split_once(['.', ')'])?;
+    // `1.5 m` is not a note
+    if !text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut content = vec![
+        InlineContent::Marker(format!("{number}.")),
+        InlineContent::Text(text.to_string()),
+    ];
+    content.extend_from_slice(rest);
+    split_label(&content)
+}
+
+/// Split `1. Text` content into the number and the text after it
+fn split_label(content: &[InlineContent]) -> Option<(String, Vec<InlineContent>)> {
+    let [InlineContent::Marker(marker), rest @ ..] = content else {
+        return None;
+    };
+    let label = marker.trim().trim_end_matches(['.', ')']);
+    if label.is_empty() || !label.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut rest = rest.to_vec();
+    if let Some(InlineContent::Text(first)) = rest.first_mut() {
+        *first = first.trim_start().to_string();
+        if first.is_empty() {
+            rest.remove(0);
+        }
+    }
+    Some((label.to_string(), rest))
+}
+
+#[derive(Default)]
+struct RefFinder {
+    /// Every footnote reference with the span of its enclosing block
+    refs: Vec<(String, Option<SourceSpan>)>,
+    span: Option<SourceSpan>,
+}
+
+impl Visitor for RefFinder {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+        walk_node(self, node);
+        self.span = outer;
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        if let InlineContent::FootnoteRef(label) = inline {
+            self.refs.push((label.clone(), self.span.clone()));
+        }
+        walk_inline(self, inline);
+    }
+}
+
+struct Unlinker<'a> {
+    notes: &'a [Footnote],
+}
+
+impl VisitorMut for Unlinker<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        if let InlineContent::FootnoteRef(label) = inline {
+            if !self.notes.iter().any(|note| &note.label == label) {
+                *inline = InlineContent::Text(format!("[{label}]"));
+            }
+            return;
+        }
+        walk_inline_mut(self, inline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> InlineContent {
+        InlineContent::Text(value.to_string())
+    }
+
+    fn paragraph(content: Vec<InlineContent>) -> DocNode {
+        DocNode::Paragraph(Paragraph {
+            content,
+            span: None,
+        })
+    }
+
+    fn sample() -> Document {
+        let notes = vec![
+            Footnote {
+                label: "1".to_string(),
+                content: vec![paragraph(vec![text("Snow melt.")])],
+                span: None,
+            },
+            Footnote {
+                label: "2".to_string(),
+                content: vec![paragraph(vec![text("Unused.")])],
+                span: None,
+            },
+        ];
+        Document {
+            children: vec![
+                paragraph(vec![
+                    text("Rivers flood"),
+                    InlineContent::FootnoteRef("1".to_string()),
+                    InlineContent::FootnoteRef("3".to_string()),
+                    InlineContent::FootnoteRef("3".to_string()),
+                ]),
+                notes_session(2, notes),
+            ],
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_take_notes_round_trips_notes_session() {
+        let mut doc = sample();
+        let notes = take_notes(&mut doc);
+
+        assert_eq!(doc.children.len(), 1);
+        let labels: Vec<&str> = notes.iter().map(|note| note.label.as_str()).collect();
+        assert_eq!(labels, vec!["1", "2"]);
+        assert_eq!(notes[0].content, vec![paragraph(vec![text("Snow melt.")])]);
+    }
+
+    #[test]
+    fn test_take_notes_ignores_other_sessions() {
+        let mut doc = sample();
+        doc.children.push(paragraph(vec![text("After the notes")]));
+        assert!(take_notes(&mut doc).is_empty());
+        assert_eq!(doc.children.len(), 3);
+    }
+
+    #[test]
+    fn test_footnote_issues() {
+        let issues = footnote_issues(&sample());
+        assert_eq!(
+            issues,
+            vec![
+                FootnoteIssue::Missing {
+                    label: "3".to_string(),
+                    span: None
+                },
+                FootnoteIssue::Unused {
+                    label: "2".to_string(),
+                    span: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_note_is_a_numbered_paragraph() {
+        let note = Footnote {
+            label: "1".to_string(),
+            content: vec![paragraph(vec![text("Snow melt.")])],
+            span: None,
+        };
+        let session = notes_session(2, vec![note]);
+        let DocNode::Heading(heading) = &session else {
+            panic!("expected a session: {session:?}");
+        };
+        assert_eq!(
+            heading.children,
+            vec![paragraph(vec![text("1. "), text("Snow melt.")])]
+        );
+
+        // As Lex parses it back
+        let mut doc = Document {
+            children: vec![DocNode::Heading(Heading {
+                children: vec![paragraph(vec![text("1. Snow melt.")])],
+                ..heading.clone()
+            })],
+            metadata: Default::default(),
+        };
+        let notes = take_notes(&mut doc);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].label, "1");
+        assert_eq!(notes[0].content, vec![paragraph(vec![text("Snow melt.")])]);
+        assert!(doc.children.is_empty());
+    }
+
+    #[test]
+    fn test_unnumbered_paragraph_is_not_a_note() {
+        let mut doc = sample();
+        let Some(DocNode::Heading(heading)) = doc.children.last_mut() else {
+            panic!("expected the notes session");
+        };
+        heading.children = vec![paragraph(vec![text("1.5 metres of snow.")])];
+        assert!(take_notes(&mut doc).is_empty());
+    }
+
+    #[test]
+    fn test_unlink_missing_notes() {
+        let mut doc = sample();
+        let notes = take_notes(&mut doc);
+        unlink_missing_notes(&mut doc, &notes);
+        assert_eq!(
+            doc.children,
+            vec![paragraph(vec![
+                text("Rivers flood"),
+                InlineContent::FootnoteRef("1".to_string()),
+                text("[3]"),
+                text("[3]"),
+            ])]
+        );
+    }
+}
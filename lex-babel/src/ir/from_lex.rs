@@ -4,128 +4,429 @@ use lex_core::lex::ast::elements::{
     Paragraph as LexParagraph, Session as LexSession, TextLine as LexTextLine,
     Verbatim as LexVerbatim, VerbatimLine as LexVerbatimLine,
 };
-use lex_core::lex::ast::TextContent;
+use lex_core::lex::ast::traits::AstNode;
+use lex_core::lex::ast::{Data, Range, TextContent};
 
+use super::citations::{load_bibliography, process_citations};
+use super::context::ConversionContext;
 use super::nodes::{
-    Annotation, Definition, DocNode, Document, Heading, InlineContent, List, ListForm, ListItem,
-    ListStyle, Paragraph, Table, TableCell, TableCellAlignment, TableRow, Verbatim,
+    Annotation, BlockQuote, Definition, DocNode, Document, Heading, InlineContent, List, ListForm,
+    ListItem, ListStyle, Metadata, MetadataEntry, MetadataValue, Paragraph, SourcePosition,
+    SourceSpan, Table, TableCell, TableCellAlignment, TableRow, Verbatim,
 };
+use super::passes::ShiftHeadings;
+use super::visit::Pass;
+use crate::common::text::{plain_text, strip_marker, unquote};
+use crate::common::verbatim::param;
+use crate::common::verbatim::source::load_source;
+use crate::error::FormatError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Annotation labels that [`from_lex_document`] promotes to document metadata.
+pub const DEFAULT_METADATA_LABELS: &[&str] = &[
+    "author",
+    "authors",
+    "publishing-date",
+    "title",
+    "date",
+    "tags",
+    "keywords",
+    "category",
+    "template",
+    "subject",
+    "from",
+    "to",
+    "cc",
+    "frontmatter",
+    "front-matter",
+    "bibliography",
+    "citation-style",
+];
+
+/// Labels whose parameters are each a separate metadata entry, as written
+/// back by `to_lex` for imported metadata (`:: frontmatter title=.. ::`)
+const METADATA_CONTAINER_LABELS: &[&str] = &["frontmatter", "front-matter"];
+
+/// Annotations that mark up the paragraph they attach to (see `apply_markup`)
+const MARKUP_LABELS: &[&str] = &["strikethrough", "list"];
 
 /// Converts a lex document to the IR.
 pub fn from_lex_document(doc: &LexDocument) -> Document {
-    let mut children = convert_children(&doc.root.children, 2);
-
-    let mut parameters = Vec::new();
-
-    // 1. Process document-level annotations
-    for ann in &doc.annotations {
-        let key = ann.data.label.value.clone();
-        let value = if !ann.children.is_empty() {
-            let mut text = String::new();
-            for child in &ann.children {
-                if let LexContentItem::Paragraph(p) = child {
-                    text.push_str(&p.text());
-                }
-            }
-            text
-        } else {
-            String::new()
-        };
+    from_lex_document_with_context(doc, &ConversionContext::standard())
+}
+
+/// Converts a lex document to the IR, treating top-level annotations labelled
+/// with one of `labels` as document metadata.
+pub fn from_lex_document_with_metadata_labels(doc: &LexDocument, labels: &[&str]) -> Document {
+    from_lex_document_with_context(
+        doc,
+        &ConversionContext::standard().with_metadata_labels(labels),
+    )
+}
+
+/// Converts a lex document to the IR using the context's verbatim handlers
+/// and metadata labels.
+///
+/// Document-level and top-level annotations with one of the context's
+/// metadata labels are promoted: they are removed from the children and kept
+/// as the entries' source. Other document-level annotations (like a `toc`
+/// placeholder) stay in the body.
+///
+/// Citations are resolved when the document (or the context) names a
+/// bibliography that can be read; [`check_bibliography`] reports one that
+/// cannot.
+pub fn from_lex_document_with_context(doc: &LexDocument, ctx: &ConversionContext) -> Document {
+    let mut document = convert_document(doc, ctx);
+    if let Ok(Some((bibliography, style))) = load_bibliography(&document.metadata, ctx) {
+        process_citations(&mut document, &bibliography, style);
+    }
+    document
+}
+
+/// Converts like [`from_lex_document_with_context`], failing where that falls
+/// back to plain verbatim text
+///
+/// A verbatim block whose handler or `src=` file fails, or a bibliography
+/// that cannot be read, is an error; blocks are checked in the same pass
+/// that converts them.
+pub fn try_from_lex_document_with_context(
+    doc: &LexDocument,
+    ctx: &ConversionContext,
+) -> Result<Document, FormatError> {
+    let mut document = try_convert_document(doc, ctx)?;
+    if let Some((bibliography, style)) = load_bibliography(&document.metadata, ctx)? {
+        process_citations(&mut document, &bibliography, style);
+    }
+    Ok(document)
+}
 
-        if !value.is_empty() {
-            parameters.push((key, value));
-        } else {
-            for param in &ann.data.parameters {
-                parameters.push((format!("{}.{}", key, param.key), param.value.clone()));
+/// Converts a document without resolving citations, failing on the first
+/// block that falls back to plain verbatim text
+pub(crate) fn try_convert_document(
+    doc: &LexDocument,
+    ctx: &ConversionContext,
+) -> Result<Document, FormatError> {
+    let failures = RefCell::new(Vec::new());
+    let document = convert_document(doc, &ctx.with_failures(Some(&failures)));
+    match failures.into_inner().into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(document),
+    }
+}
+
+/// Converts a document without resolving citations
+pub(crate) fn convert_document(doc: &LexDocument, ctx: &ConversionContext) -> Document {
+    let labels = ctx.metadata_labels;
+    let mut children = convert_children(&doc.root.children, 2, ctx);
+    if title_is_paragraph(doc) {
+        children.insert(
+            0,
+            DocNode::Paragraph(Paragraph {
+                content: convert_inline_content(&doc.root.title),
+                span: doc.root.title.location.as_ref().and_then(source_span),
+            }),
+        );
+    }
+    let mut metadata = Metadata::default();
+    let mut remaining = Vec::with_capacity(children.len());
+
+    // 1. Process document-level annotations, keeping those after the
+    // content (like a closing include) for the end
+    let (trailing, leading): (Vec<_>, Vec<_>) = doc
+        .annotations
+        .iter()
+        .partition(|ann| is_trailing(ann, &doc.root.children));
+    for ann in leading {
+        push_document_annotation(&mut remaining, &mut metadata, ann, ctx);
+    }
+
+    // 2. Promote top-level metadata annotations (e.g. attached to first element)
+    for child in children {
+        match child {
+            DocNode::Annotation(annotation) if labels.contains(&annotation.label.as_str()) => {
+                push_metadata_annotation(&mut metadata, annotation);
             }
+            other => remaining.push(other),
         }
     }
 
-    // 2. Scan children for metadata annotations (e.g. attached to first element)
-    let mut indices_to_remove = Vec::new();
+    for ann in trailing {
+        push_document_annotation(&mut remaining, &mut metadata, ann, ctx);
+    }
 
-    // Whitelist of labels to treat as frontmatter
-    let metadata_labels = [
-        "author",
-        "publishing-date",
-        "title",
-        "date",
-        "tags",
-        "category",
-        "template",
-        "front-matter",
-    ];
+    Document {
+        children: remaining,
+        metadata,
+    }
+}
 
-    for (i, child) in children.iter().enumerate() {
-        if let DocNode::Annotation(ann) = child {
-            if metadata_labels.contains(&ann.label.as_str()) {
-                // It's metadata!
-                let key = ann.label.clone();
-                // Extract value (content or params)
-                let value = if !ann.content.is_empty() {
-                    // Flatten content
-                    let mut text = String::new();
-                    for c in &ann.content {
-                        if let DocNode::Paragraph(p) = c {
-                            for ic in &p.content {
-                                if let InlineContent::Text(t) = ic {
-                                    text.push_str(t);
-                                }
-                            }
-                        }
-                    }
-                    text
-                } else {
-                    String::new()
-                };
+/// The document's title, empty when it has none
+///
+/// Lex promotes a first paragraph set off by a blank line to the title. One
+/// that holds a reference (say to a footnote) stays the first paragraph
+/// instead, so the reference resolves like any other.
+pub fn document_title(doc: &LexDocument) -> &str {
+    if title_is_paragraph(doc) {
+        ""
+    } else {
+        doc.root.title.as_string()
+    }
+}
 
-                if !value.is_empty() {
-                    parameters.push((key, value));
-                } else {
-                    for (k, v) in &ann.parameters {
-                        parameters.push((format!("{key}.{k}"), v.clone()));
-                    }
-                }
+fn title_is_paragraph(doc: &LexDocument) -> bool {
+    convert_inline_content(&doc.root.title)
+        .iter()
+        .any(|inline| {
+            matches!(
+                inline,
+                InlineContent::Reference(_) | InlineContent::FootnoteRef(_)
+            )
+        })
+}
 
-                indices_to_remove.push(i);
+/// Add a document-level annotation: one with a metadata label is metadata,
+/// anything else goes to the body
+fn push_document_annotation(
+    body: &mut Vec<DocNode>,
+    metadata: &mut Metadata,
+    ann: &LexAnnotation,
+    ctx: &ConversionContext,
+) {
+    match from_lex_annotation(ann, 2, ctx) {
+        DocNode::Annotation(annotation)
+            if ctx.metadata_labels.contains(&annotation.label.as_str()) =>
+        {
+            push_metadata_annotation(metadata, annotation)
+        }
+        node => push_converted(body, node, 2),
+    }
+}
+
+/// Checks that the bibliography the document or context names can be read
+pub fn check_bibliography(doc: &LexDocument, ctx: &ConversionContext) -> Result<(), FormatError> {
+    let metadata = convert_document(doc, ctx).metadata;
+    load_bibliography(&metadata, ctx).map(|_| ())
+}
+
+/// Checks that every verbatim block with a handler converts cleanly
+///
+/// Conversion keeps a block its handler rejects (say a `doc.table` whose
+/// `src=` file is missing) as plain verbatim text, and likewise keeps the
+/// inline copy of a code sample whose `src=` file cannot be read. This
+/// converts the document once and returns the first such failure instead.
+/// The error points at the block's `src` parameter when it has one, else at
+/// the block.
+pub fn check_verbatim_blocks(
+    doc: &LexDocument,
+    ctx: &ConversionContext,
+) -> Result<(), FormatError> {
+    try_convert_document(doc, ctx).map(|_| ())
+}
+
+/// A code sample whose inline copy no longer matches its `src=` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceDrift {
+    /// The `src=` parameter as written
+    pub src: String,
+    pub span: Option<SourceSpan>,
+}
+
+/// Every verbatim block whose inline content differs from what its `src=`
+/// parameters select
+///
+/// Blocks with an empty body, with a handler, or whose source cannot be read
+/// are skipped; [`check_verbatim_blocks`] reports the last kind.
+pub fn source_drift(doc: &LexDocument, ctx: &ConversionContext) -> Vec<SourceDrift> {
+    let mut drifts = Vec::new();
+    visit_verbatims(doc, &mut |verbatim| {
+        let label = &verbatim.closing_data.label.value;
+        let inline = verbatim_content(verbatim);
+        if ctx.verbatim.get(label).is_some() || inline.trim().is_empty() {
+            return;
+        }
+        let params = data_params(&verbatim.closing_data);
+        if let Ok(Some(snippet)) = load_source(label, &params, ctx) {
+            if !snippet.matches(&inline) {
+                drifts.push(SourceDrift {
+                    src: param(&params, "src").unwrap_or_default().to_string(),
+                    span: source_span(verbatim.range()),
+                });
             }
         }
+    });
+    drifts
+}
+
+/// Call `f` on every verbatim block
+fn visit_verbatims(doc: &LexDocument, f: &mut dyn FnMut(&LexVerbatim)) {
+    visit_items(&doc.root.children, f);
+    for annotation in &doc.annotations {
+        visit_items(&annotation.children, f);
+    }
+}
+
+fn visit_items(items: &[LexContentItem], f: &mut dyn FnMut(&LexVerbatim)) {
+    for item in items {
+        for annotation in attached_annotations(item) {
+            visit_items(&annotation.children, f);
+        }
+        match item {
+            LexContentItem::Session(session) => visit_items(&session.children, f),
+            LexContentItem::List(list) => visit_items(&list.items, f),
+            LexContentItem::ListItem(list_item) => visit_items(&list_item.children, f),
+            LexContentItem::Definition(definition) => visit_items(&definition.children, f),
+            LexContentItem::Annotation(annotation) => visit_items(&annotation.children, f),
+            LexContentItem::VerbatimBlock(verbatim) => f(verbatim),
+            _ => {}
+        }
     }
+}
+
+/// Point `err` at the `src` parameter of `data`, else at `range`
+fn locate_error(err: FormatError, data: &Data, range: &Range) -> FormatError {
+    let src = data
+        .parameters
+        .iter()
+        .find(|param| param.key == "src")
+        .and_then(|param| source_span(&param.location));
+    match src.or_else(|| source_span(range)) {
+        Some(span) => err.with_position(span.start),
+        None => err,
+    }
+}
 
-    // Remove promoted annotations (in reverse order to keep indices valid)
-    for i in indices_to_remove.iter().rev() {
-        children.remove(*i);
+/// Converts one metadata annotation into entries
+///
+/// `:: tags :: a, b` gives a list, `:: author name=Ana ::` gives fields, and a
+/// `frontmatter` annotation contributes one entry per parameter.
+fn push_metadata_annotation(metadata: &mut Metadata, annotation: Annotation) {
+    let mut entries: Vec<(String, MetadataValue)> = Vec::new();
+
+    if METADATA_CONTAINER_LABELS.contains(&annotation.label.as_str()) {
+        for (key, value) in &annotation.parameters {
+            let Some((key, field)) = key.split_once('.') else {
+                entries.push((key.clone(), MetadataValue::from_text(key, value)));
+                continue;
+            };
+            // `author.name=.. author.email=..` build up one fields value
+            match entries.last_mut() {
+                Some((last, MetadataValue::Fields(fields))) if last == key => {
+                    fields.push((field.to_string(), value.clone()));
+                }
+                _ => entries.push((
+                    key.to_string(),
+                    MetadataValue::Fields(vec![(field.to_string(), value.clone())]),
+                )),
+            }
+        }
+    } else {
+        let value = match annotation.content.as_slice() {
+            [DocNode::List(list)] => MetadataValue::List(
+                list.items
+                    .iter()
+                    .map(|item| metadata_text(strip_marker(&item.content)))
+                    .collect(),
+            ),
+            [] if !annotation.parameters.is_empty() => {
+                MetadataValue::Fields(annotation.parameters.clone())
+            }
+            content => {
+                let paragraphs: Vec<String> = content
+                    .iter()
+                    .filter_map(|node| match node {
+                        DocNode::Paragraph(p) => Some(metadata_text(&p.content)),
+                        _ => None,
+                    })
+                    .collect();
+                MetadataValue::from_text(&annotation.label, &paragraphs.join("\n"))
+            }
+        };
+        entries.push((annotation.label.clone(), value));
     }
 
-    if !parameters.is_empty() {
-        let frontmatter = DocNode::Annotation(Annotation {
-            label: "frontmatter".to_string(),
-            parameters,
-            content: vec![],
+    for (key, value) in entries {
+        metadata.entries.push(MetadataEntry {
+            key,
+            value,
+            source: Some(annotation.clone()),
         });
-        children.insert(0, frontmatter);
     }
+}
 
-    Document { children }
+/// Flattens inline content to text, with line breaks as spaces
+fn metadata_text(content: &[InlineContent]) -> String {
+    plain_text(content).replace('\n', " ").trim().to_string()
 }
 
 /// Helper: Converts a list of content items, filtering out blank lines
 /// Also extracts annotations attached to each element
-fn convert_children(items: &[LexContentItem], level: usize) -> Vec<DocNode> {
+///
+/// Markup annotations left unattached (lex-core attaches none inside an
+/// annotation's content) apply to the element after them.
+fn convert_children(
+    items: &[LexContentItem],
+    level: usize,
+    ctx: &ConversionContext,
+) -> Vec<DocNode> {
+    let mut markup: Vec<LexAnnotation> = Vec::new();
     items
         .iter()
         .filter(|item| !matches!(item, LexContentItem::BlankLineGroup(_)))
         .flat_map(|item| {
-            let mut nodes = extract_attached_annotations(item, level);
-            nodes.push(from_lex_content_item_with_level(item, level));
+            if let LexContentItem::Annotation(annotation) = item {
+                if is_markup(annotation) {
+                    markup.push(annotation.clone());
+                    return vec![];
+                }
+            }
+            let mut nodes = extract_attached_annotations(item, level, ctx);
+            let mut node = from_lex_content_item_with_level(item, level, ctx);
+            markup.extend(attached_annotations(item).iter().cloned());
+            apply_markup(&mut node, &std::mem::take(&mut markup));
+            node.set_span(source_span(item.range()));
+            push_converted(&mut nodes, node, level);
+            if let LexContentItem::Session(session) = item {
+                for annotation in session.annotations() {
+                    if follows_session(annotation, session) {
+                        let mut node = from_lex_annotation(annotation, level, ctx);
+                        node.set_span(source_span(annotation.range()));
+                        push_converted(&mut nodes, node, level);
+                    }
+                }
+            }
             nodes
         })
         .collect()
 }
 
-/// Extracts annotations attached to a content item and converts them to IR nodes
-fn extract_attached_annotations(item: &LexContentItem, level: usize) -> Vec<DocNode> {
-    let annotations = match item {
+/// Add a converted node to `nodes`
+///
+/// A document (what `doc.include` gives) adds its children instead, with its
+/// top-level sessions moved to `level`.
+fn push_converted(nodes: &mut Vec<DocNode>, node: DocNode, level: usize) {
+    match node {
+        DocNode::Document(mut included) => {
+            let top = included
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    DocNode::Heading(heading) => Some(heading.level),
+                    _ => None,
+                })
+                .min();
+            if let Some(top) = top {
+                ShiftHeadings::new(level as isize - top as isize).run(&mut included);
+            }
+            nodes.extend(included.children);
+        }
+        node => nodes.push(node),
+    }
+}
+
+/// Annotations attached to a content item
+fn attached_annotations(item: &LexContentItem) -> &[LexAnnotation] {
+    match item {
         LexContentItem::Session(session) => session.annotations(),
         LexContentItem::Paragraph(paragraph) => paragraph.annotations(),
         LexContentItem::List(list) => list.annotations(),
@@ -133,12 +434,170 @@ fn extract_attached_annotations(item: &LexContentItem, level: usize) -> Vec<DocN
         LexContentItem::Definition(definition) => definition.annotations(),
         LexContentItem::VerbatimBlock(verbatim) => verbatim.annotations(),
         _ => &[],
+    }
+}
+
+/// Whether an annotation comes after all of `children`
+///
+/// An annotation that closes a session (or the document) attaches to the
+/// session rather than to an element, and belongs after its content.
+pub(crate) fn is_trailing(annotation: &LexAnnotation, children: &[LexContentItem]) -> bool {
+    children
+        .iter()
+        .rfind(|item| !matches!(item, LexContentItem::BlankLineGroup(_)))
+        .is_some_and(|last| annotation.range().end > last.range().end)
+}
+
+/// Whether a session's trailing annotation comes after the session rather
+/// than at the end of its content
+///
+/// lex-core attaches both to the session, so indentation tells them apart:
+/// the `:: ` before the label starts left of the session's content.
+pub(crate) fn follows_session(annotation: &LexAnnotation, session: &LexSession) -> bool {
+    let content_column = session.children.iter().find_map(|item| match item {
+        LexContentItem::BlankLineGroup(_) => None,
+        // Session ranges start at the top of the document, their titles do not
+        LexContentItem::Session(child) => child.title.location.as_ref().map(|r| r.start.column),
+        item => Some(item.range().start.column),
+    });
+    is_trailing(annotation, &session.children)
+        && content_column
+            .is_some_and(|column| annotation.data.label.location.start.column < column + 3)
+}
+
+/// Extracts annotations attached to a content item and converts them to IR nodes
+fn extract_attached_annotations(
+    item: &LexContentItem,
+    level: usize,
+    ctx: &ConversionContext,
+) -> Vec<DocNode> {
+    let children = match item {
+        LexContentItem::Session(session) => &session.children[..],
+        _ => &[],
     };
+    let mut nodes = Vec::new();
+    for anno in attached_annotations(item) {
+        if is_trailing(anno, children) {
+            // Converted with the session's children
+            continue;
+        }
+        if is_markup(anno) {
+            // Applied to the element itself
+            continue;
+        }
+        let mut node = from_lex_annotation(anno, level, ctx);
+        node.set_span(source_span(anno.range()));
+        push_converted(&mut nodes, node, level);
+    }
+    nodes
+}
+
+fn is_markup(annotation: &LexAnnotation) -> bool {
+    MARKUP_LABELS.contains(&annotation.data.label.value.as_str())
+}
 
-    annotations
+/// Applies the `strikethrough` and `list` annotations of a paragraph
+///
+/// They stand for what Lex text has no syntax for: a struck span, named by
+/// its `text` (the first match after the previous span is struck), and a
+/// list of one item, which Lex reads as a paragraph.
+fn apply_markup(node: &mut DocNode, annotations: &[LexAnnotation]) {
+    let DocNode::Paragraph(paragraph) = node else {
+        return;
+    };
+    let mut from = 0;
+    for annotation in annotations {
+        if annotation.data.label.value != "strikethrough" {
+            continue;
+        }
+        if let Some(text) = param(&data_params(&annotation.data), "text") {
+            from = strike(&mut paragraph.content, text, from);
+        }
+    }
+    if annotations
         .iter()
-        .map(|anno| from_lex_annotation(anno, level))
-        .collect()
+        .any(|annotation| annotation.data.label.value == "list")
+    {
+        if let Some(list) = one_item_list(&paragraph.content) {
+            *node = list;
+        }
+    }
+}
+
+/// Wraps the first `struck` text at or after `from` in a strikethrough
+///
+/// Returns where the next search starts.
+fn strike(content: &mut Vec<InlineContent>, struck: &str, from: usize) -> usize {
+    for index in from..content.len() {
+        let InlineContent::Text(text) = &content[index] else {
+            continue;
+        };
+        let Some(start) = text.find(struck) else {
+            continue;
+        };
+        let (before, after) = (&text[..start], &text[start + struck.len()..]);
+        let mut replacement = Vec::new();
+        if !before.is_empty() {
+            replacement.push(InlineContent::Text(before.to_string()));
+        }
+        replacement.push(InlineContent::Strikethrough(vec![InlineContent::Text(
+            struck.to_string(),
+        )]));
+        let next = index + replacement.len();
+        if !after.is_empty() {
+            replacement.push(InlineContent::Text(after.to_string()));
+        }
+        content.splice(index..=index, replacement);
+        return next;
+    }
+    from
+}
+
+/// A list of one item from the `- item` line of a paragraph
+fn one_item_list(content: &[InlineContent]) -> Option<DocNode> {
+    let (InlineContent::Text(first), rest) = content.split_first()? else {
+        return None;
+    };
+    let (marker, text) = first.split_once(' ')?;
+    let style = detect_list_style(&TextContent::from_string(marker.to_string(), None));
+
+    let mut item_content = vec![
+        InlineContent::Marker(marker.to_string()),
+        InlineContent::Text(" ".to_string()),
+    ];
+    if !text.is_empty() {
+        item_content.push(InlineContent::Text(text.to_string()));
+    }
+    item_content.extend(rest.iter().cloned());
+    let checked = take_task_marker(&mut item_content);
+
+    Some(DocNode::List(List {
+        items: vec![ListItem {
+            content: item_content,
+            children: vec![],
+            checked,
+            span: None,
+        }],
+        ordered: style.is_ordered(),
+        style,
+        form: ListForm::Short,
+        span: None,
+    }))
+}
+
+/// Converts a Lex range to an IR source span
+///
+/// Elements built in code rather than parsed have an empty range and get no
+/// span.
+fn source_span(range: &Range) -> Option<SourceSpan> {
+    if range.span.is_empty() {
+        return None;
+    }
+    Some(SourceSpan {
+        bytes: range.span.clone(),
+        start: SourcePosition::new(range.start.line, range.start.column),
+        end: SourcePosition::new(range.end.line, range.end.column),
+    })
 }
 
 /// Converts TextContent to IR InlineContent
@@ -166,31 +625,50 @@ fn convert_inline_node(node: &InlineNode) -> InlineContent {
         }
         InlineNode::Code { text, .. } => InlineContent::Code(text.clone()),
         InlineNode::Math { text, .. } => InlineContent::Math(text.clone()),
-        InlineNode::Reference { data, .. } => InlineContent::Reference(data.raw.clone()),
+        InlineNode::Reference { data, .. } => match footnote_label(&data.raw) {
+            Some(label) => InlineContent::FootnoteRef(label.to_string()),
+            None => InlineContent::Reference(data.raw.clone()),
+        },
+    }
+}
+
+/// Footnote label of a reference: `[1]` (numbered) or `[^note]` (labelled)
+fn footnote_label(raw: &str) -> Option<&str> {
+    if !raw.is_empty() && raw.chars().all(|c| c.is_ascii_digit()) {
+        Some(raw)
+    } else {
+        raw.strip_prefix('^').filter(|label| !label.is_empty())
     }
 }
 
 /// Converts a lex content item to an IR node with a given level.
-fn from_lex_content_item_with_level(item: &LexContentItem, level: usize) -> DocNode {
+fn from_lex_content_item_with_level(
+    item: &LexContentItem,
+    level: usize,
+    ctx: &ConversionContext,
+) -> DocNode {
     match item {
-        LexContentItem::Session(session) => from_lex_session(session, level),
+        LexContentItem::Session(session) => from_lex_session(session, level, ctx),
         LexContentItem::Paragraph(paragraph) => from_lex_paragraph(paragraph),
-        LexContentItem::List(list) => from_lex_list(list, level),
-        LexContentItem::ListItem(list_item) => from_lex_list_item(list_item, level),
-        LexContentItem::Definition(definition) => from_lex_definition(definition, level),
-        LexContentItem::VerbatimBlock(verbatim) => from_lex_verbatim(verbatim),
-        LexContentItem::Annotation(annotation) => from_lex_annotation(annotation, level),
+        LexContentItem::List(list) => from_lex_list(list, level, ctx),
+        LexContentItem::ListItem(list_item) => from_lex_list_item(list_item, level, ctx),
+        LexContentItem::Definition(definition) => from_lex_definition(definition, level, ctx),
+        LexContentItem::VerbatimBlock(verbatim) => from_lex_verbatim(verbatim, ctx),
+        LexContentItem::Annotation(annotation) => from_lex_annotation(annotation, level, ctx),
         LexContentItem::TextLine(text_line) => from_lex_text_line(text_line),
         LexContentItem::VerbatimLine(verbatim_line) => from_lex_verbatim_line(verbatim_line),
         LexContentItem::BlankLineGroup(_) => {
             // Blank lines are filtered out by convert_children, but handle gracefully if encountered
-            DocNode::Paragraph(Paragraph { content: vec![] })
+            DocNode::Paragraph(Paragraph {
+                content: vec![],
+                span: None,
+            })
         }
     }
 }
 
 /// Converts a lex session to an IR heading.
-fn from_lex_session(session: &LexSession, level: usize) -> DocNode {
+fn from_lex_session(session: &LexSession, level: usize, ctx: &ConversionContext) -> DocNode {
     // Preserve the original session title (including any sequence marker)
     let mut content = Vec::new();
 
@@ -210,11 +688,20 @@ fn from_lex_session(session: &LexSession, level: usize) -> DocNode {
         content.extend(convert_inline_content(&session.title));
     }
 
-    let children = convert_children(&session.children, level + 1);
+    let mut children = convert_children(&session.children, level + 1, ctx);
+    for annotation in session.annotations() {
+        if is_trailing(annotation, &session.children) && !follows_session(annotation, session) {
+            let mut node = from_lex_annotation(annotation, level + 1, ctx);
+            node.set_span(source_span(annotation.range()));
+            push_converted(&mut children, node, level + 1);
+        }
+    }
     DocNode::Heading(Heading {
         level,
         content,
         children,
+        id: None,
+        span: None,
     })
 }
 
@@ -240,17 +727,20 @@ fn from_lex_paragraph(paragraph: &LexParagraph) -> DocNode {
             }
         }
     }
-    DocNode::Paragraph(Paragraph { content })
+    DocNode::Paragraph(Paragraph {
+        content,
+        span: None,
+    })
 }
 
 /// Converts a lex list to an IR list.
-fn from_lex_list(list: &LexList, level: usize) -> DocNode {
+fn from_lex_list(list: &LexList, level: usize, ctx: &ConversionContext) -> DocNode {
     let items: Vec<ListItem> = list
         .items
         .iter()
         .filter_map(|item| {
             if let LexContentItem::ListItem(li) = item {
-                Some(convert_list_item(li, level))
+                Some(convert_list_item(li, level, ctx))
             } else {
                 None
             }
@@ -280,16 +770,17 @@ fn from_lex_list(list: &LexList, level: usize) -> DocNode {
         ordered,
         style,
         form,
+        span: None,
     })
 }
 
 /// Converts a lex list item to an IR list item node.
-fn from_lex_list_item(list_item: &LexListItem, level: usize) -> DocNode {
-    DocNode::ListItem(convert_list_item(list_item, level))
+fn from_lex_list_item(list_item: &LexListItem, level: usize, ctx: &ConversionContext) -> DocNode {
+    DocNode::ListItem(convert_list_item(list_item, level, ctx))
 }
 
 /// Converts a lex list item to an IR list item struct.
-fn convert_list_item(list_item: &LexListItem, level: usize) -> ListItem {
+fn convert_list_item(list_item: &LexListItem, level: usize, ctx: &ConversionContext) -> ListItem {
     // List item has text (Vec<TextContent>) and children
     let mut content = Vec::new();
 
@@ -305,19 +796,72 @@ fn convert_list_item(list_item: &LexListItem, level: usize) -> ListItem {
     for text_content in &list_item.text {
         content.extend(convert_inline_content(text_content));
     }
-    let children = convert_children(&list_item.children, level);
-    ListItem { content, children }
+    let checked = take_task_marker(&mut content);
+    let children = convert_children(&list_item.children, level, ctx);
+    ListItem {
+        content,
+        children,
+        checked,
+        span: source_span(list_item.range()),
+    }
+}
+
+/// Strips a leading `[ ]`/`[x]` task box from list item content
+///
+/// The box follows the marker and its space, either as text or, for `[x]`, as
+/// a reference the inline parser already recognised.
+fn take_task_marker(content: &mut Vec<InlineContent>) -> Option<bool> {
+    let start = match content.as_slice() {
+        [InlineContent::Marker(_), InlineContent::Text(space), ..] if space == " " => 2,
+        _ => return None,
+    };
+
+    let (checked, rest) = match content.get(start)? {
+        InlineContent::Reference(raw) => match raw.as_str() {
+            " " => (false, None),
+            "x" | "X" => (true, None),
+            _ => return None,
+        },
+        InlineContent::Text(text) => match text.get(..3)? {
+            "[ ]" => (false, Some(text[3..].to_string())),
+            "[x]" | "[X]" => (true, Some(text[3..].to_string())),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match rest {
+        Some(rest) => content[start] = InlineContent::Text(rest),
+        None => {
+            content.remove(start);
+        }
+    }
+    if let Some(InlineContent::Text(text)) = content.get_mut(start) {
+        *text = text.trim_start().to_string();
+        if text.is_empty() {
+            content.remove(start);
+        }
+    }
+    Some(checked)
 }
 
 /// Converts a lex definition to an IR definition.
-fn from_lex_definition(definition: &LexDefinition, level: usize) -> DocNode {
+fn from_lex_definition(
+    definition: &LexDefinition,
+    level: usize,
+    ctx: &ConversionContext,
+) -> DocNode {
     let term = convert_inline_content(&definition.subject);
-    let description = convert_children(&definition.children, level);
-    DocNode::Definition(Definition { term, description })
+    let description = convert_children(&definition.children, level, ctx);
+    DocNode::Definition(Definition {
+        term,
+        description,
+        span: None,
+    })
 }
 
 /// Converts a lex verbatim block to an IR verbatim block.
-fn from_lex_verbatim(verbatim: &LexVerbatim) -> DocNode {
+fn from_lex_verbatim(verbatim: &LexVerbatim, ctx: &ConversionContext) -> DocNode {
     let subject_str = verbatim.subject.as_string();
     let subject = if subject_str.is_empty() {
         None
@@ -325,7 +869,42 @@ fn from_lex_verbatim(verbatim: &LexVerbatim) -> DocNode {
         Some(subject_str.to_string())
     };
     let language = Some(verbatim.closing_data.label.value.clone());
-    let content = verbatim
+    let content = verbatim_content(verbatim);
+
+    let params = data_params(&verbatim.closing_data);
+    let fail =
+        |err| ctx.record_failure(locate_error(err, &verbatim.closing_data, verbatim.range()));
+    match ctx.verbatim.get(&verbatim.closing_data.label.value) {
+        Some(handler) => match handler.to_ir_with_context(&content, &params, ctx) {
+            Ok(Some(node)) => return node,
+            Ok(None) => {}
+            Err(err) => fail(err),
+        },
+        None => match load_source(&verbatim.closing_data.label.value, &params, ctx) {
+            Ok(Some(snippet)) => {
+                return DocNode::Verbatim(Verbatim {
+                    subject,
+                    language: Some(snippet.language),
+                    content: snippet.content,
+                    span: None,
+                });
+            }
+            Ok(None) => {}
+            Err(err) => fail(err),
+        },
+    }
+
+    DocNode::Verbatim(Verbatim {
+        subject,
+        language,
+        content,
+        span: None,
+    })
+}
+
+/// The lines of a verbatim block joined into one string
+fn verbatim_content(verbatim: &LexVerbatim) -> String {
+    verbatim
         .children
         .iter()
         .map(|item| {
@@ -336,33 +915,68 @@ fn from_lex_verbatim(verbatim: &LexVerbatim) -> DocNode {
             }
         })
         .collect::<Vec<_>>()
-        .join("\n");
+        .join("\n")
+}
+
+/// The parameters of an annotation or closing marker as a map
+fn data_params(data: &Data) -> HashMap<String, String> {
+    data.parameters
+        .iter()
+        .map(|p| (p.key.clone(), p.value.clone()))
+        .collect()
+}
 
-    let registry = crate::common::verbatim::VerbatimRegistry::default_with_standard();
+/// Whether an annotation is a one-line `:: label ::` with no content
+///
+/// One that closes a container parses with an empty paragraph.
+pub(crate) fn is_short_form(annotation: &LexAnnotation) -> bool {
+    annotation.children.iter().all(|child| match child {
+        LexContentItem::Paragraph(paragraph) => paragraph.lines.is_empty(),
+        LexContentItem::BlankLineGroup(_) => true,
+        _ => false,
+    })
+}
 
-    if let Some(handler) = registry.get(&verbatim.closing_data.label.value) {
-        let params = verbatim
-            .closing_data
+/// Whether an annotation is a one-line `:: doc.table src=.. ::` reference
+///
+/// Such references parse as annotations rather than verbatim blocks, but go
+/// to the verbatim handler for their label all the same.
+pub(crate) fn is_file_reference(annotation: &LexAnnotation) -> bool {
+    is_short_form(annotation)
+        && annotation
+            .data
             .parameters
             .iter()
-            .map(|p| (p.key.clone(), p.value.clone()))
-            .collect();
-        if let Some(node) = handler.to_ir(&content, &params) {
-            return node;
-        }
-    }
-
-    DocNode::Verbatim(Verbatim {
-        subject,
-        language,
-        content,
-    })
+            .any(|param| param.key == "src")
 }
 
 /// Converts a lex annotation to an IR annotation.
-fn from_lex_annotation(annotation: &LexAnnotation, level: usize) -> DocNode {
-    if annotation.data.label.value == "table" {
-        return from_lex_table(annotation, level);
+fn from_lex_annotation(
+    annotation: &LexAnnotation,
+    level: usize,
+    ctx: &ConversionContext,
+) -> DocNode {
+    match annotation.data.label.value.as_str() {
+        "table" => return from_lex_table(annotation, level, ctx),
+        "blockquote" => {
+            return DocNode::BlockQuote(BlockQuote {
+                children: convert_children(&annotation.children, level, ctx),
+                span: None,
+            })
+        }
+        "hr" => return DocNode::ThematicBreak,
+        _ => {}
+    }
+    if is_file_reference(annotation) {
+        if let Some(handler) = ctx.verbatim.get(&annotation.data.label.value) {
+            match handler.to_ir_with_context("", &data_params(&annotation.data), ctx) {
+                Ok(Some(node)) => return node,
+                Ok(None) => {}
+                Err(err) => {
+                    ctx.record_failure(locate_error(err, &annotation.data, annotation.range()))
+                }
+            }
+        }
     }
     let label = annotation.data.label.value.clone();
     let parameters = annotation
@@ -371,15 +985,16 @@ fn from_lex_annotation(annotation: &LexAnnotation, level: usize) -> DocNode {
         .iter()
         .map(|p| (p.key.clone(), p.value.clone()))
         .collect();
-    let content = convert_children(&annotation.children, level);
+    let content = convert_children(&annotation.children, level, ctx);
     DocNode::Annotation(Annotation {
         label,
         parameters,
         content,
+        span: None,
     })
 }
 
-fn from_lex_table(annotation: &LexAnnotation, level: usize) -> DocNode {
+fn from_lex_table(annotation: &LexAnnotation, level: usize, ctx: &ConversionContext) -> DocNode {
     // Parse children to find thead and tbody
     let mut header = Vec::new();
     let mut rows = Vec::new();
@@ -390,7 +1005,7 @@ fn from_lex_table(annotation: &LexAnnotation, level: usize) -> DocNode {
                 for row_item in &ann.children {
                     if let LexContentItem::Annotation(row_ann) = row_item {
                         if row_ann.data.label.value == "tr" {
-                            header.push(from_lex_table_row(row_ann, level));
+                            header.push(from_lex_table_row(row_ann, level, ctx));
                         }
                     }
                 }
@@ -398,7 +1013,7 @@ fn from_lex_table(annotation: &LexAnnotation, level: usize) -> DocNode {
                 for row_item in &ann.children {
                     if let LexContentItem::Annotation(row_ann) = row_item {
                         if row_ann.data.label.value == "tr" {
-                            rows.push(from_lex_table_row(row_ann, level));
+                            rows.push(from_lex_table_row(row_ann, level, ctx));
                         }
                     }
                 }
@@ -406,54 +1021,74 @@ fn from_lex_table(annotation: &LexAnnotation, level: usize) -> DocNode {
         }
     }
 
+    let caption = annotation
+        .data
+        .parameters
+        .iter()
+        .find(|param| param.key == "caption")
+        .map(|param| vec![InlineContent::Text(unquote(&param.value).to_string())]);
+
     DocNode::Table(Table {
         rows,
         header,
-        caption: None,
+        caption,
+        span: None,
     })
 }
 
-fn from_lex_table_row(annotation: &LexAnnotation, level: usize) -> TableRow {
+fn from_lex_table_row(
+    annotation: &LexAnnotation,
+    level: usize,
+    ctx: &ConversionContext,
+) -> TableRow {
     let mut cells = Vec::new();
     for child in &annotation.children {
         if let LexContentItem::Annotation(ann) = child {
             if ann.data.label.value == "th" || ann.data.label.value == "td" {
-                cells.push(from_lex_table_cell(ann, level));
+                cells.push(from_lex_table_cell(ann, level, ctx));
             }
         }
     }
     TableRow { cells }
 }
 
-fn from_lex_table_cell(annotation: &LexAnnotation, level: usize) -> TableCell {
+fn from_lex_table_cell(
+    annotation: &LexAnnotation,
+    level: usize,
+    ctx: &ConversionContext,
+) -> TableCell {
     let header = annotation.data.label.value == "th";
 
-    let mut align = TableCellAlignment::None;
+    let mut cell = TableCell::new(Vec::new(), header, TableCellAlignment::None);
     for param in &annotation.data.parameters {
-        if param.key == "align" {
-            align = match param.value.as_str() {
-                "left" => TableCellAlignment::Left,
-                "center" => TableCellAlignment::Center,
-                "right" => TableCellAlignment::Right,
-                _ => TableCellAlignment::None,
-            };
+        match param.key.as_str() {
+            "align" => {
+                cell.align = match param.value.as_str() {
+                    "left" => TableCellAlignment::Left,
+                    "center" => TableCellAlignment::Center,
+                    "right" => TableCellAlignment::Right,
+                    _ => TableCellAlignment::None,
+                };
+            }
+            // Malformed spans count as 1 rather than dropping the cell
+            "colspan" => cell.colspan = param.value.parse().unwrap_or(1).max(1),
+            "rowspan" => cell.rowspan = param.value.parse().unwrap_or(1).max(1),
+            _ => {}
         }
     }
 
-    let content = convert_children(&annotation.children, level);
-
-    TableCell {
-        content,
-        header,
-        align,
-    }
+    cell.content = convert_children(&annotation.children, level, ctx);
+    cell
 }
 
 /// Converts a standalone TextLine to an IR paragraph.
 /// TextLines are typically parts of paragraphs, but can appear standalone.
 fn from_lex_text_line(text_line: &LexTextLine) -> DocNode {
     let content = convert_inline_content(&text_line.content);
-    DocNode::Paragraph(Paragraph { content })
+    DocNode::Paragraph(Paragraph {
+        content,
+        span: None,
+    })
 }
 
 /// Converts a VerbatimLine to an IR verbatim block.
@@ -464,6 +1099,7 @@ fn from_lex_verbatim_line(verbatim_line: &LexVerbatimLine) -> DocNode {
         subject: None,
         language: None,
         content,
+        span: None,
     })
 }
 
@@ -552,7 +1188,7 @@ mod tests {
     #[test]
     fn test_session_to_heading() {
         let session = LexSession::with_title("Test Section".to_string());
-        let ir_node = from_lex_session(&session, 1);
+        let ir_node = from_lex_session(&session, 1, &ConversionContext::standard());
 
         match ir_node {
             DocNode::Heading(heading) => {
@@ -570,7 +1206,7 @@ mod tests {
         let item2 = LexListItem::new("-".to_string(), "Item 2".to_string());
         let list = LexList::new(vec![item1, item2]);
 
-        let ir_node = from_lex_list(&list, 1);
+        let ir_node = from_lex_list(&list, 1, &ConversionContext::standard());
 
         match ir_node {
             DocNode::List(list) => {
@@ -597,7 +1233,7 @@ mod tests {
             lex_core::lex::ast::elements::verbatim::VerbatimBlockMode::Inflow,
         );
 
-        let ir_node = from_lex_verbatim(&verb);
+        let ir_node = from_lex_verbatim(&verb, &ConversionContext::standard());
 
         match ir_node {
             DocNode::Verbatim(verb) => {
@@ -616,7 +1252,7 @@ mod tests {
             Vec::new(),
         ));
 
-        let children = convert_children(&[para, blank], 1);
+        let children = convert_children(&[para, blank], 1, &ConversionContext::standard());
 
         assert_eq!(children.len(), 1);
     }
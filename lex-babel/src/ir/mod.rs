@@ -4,8 +4,16 @@
 //! designed to facilitate conversion to various output formats like HTML,
 //! Markdown, etc.
 
+pub mod citations;
+pub mod context;
 pub mod events;
+pub mod footnotes;
 pub mod from_lex;
 pub mod nodes;
+pub mod numbering;
+pub mod passes;
+pub mod references;
 pub mod to_events;
 pub mod to_lex;
+pub mod toc;
+pub mod visit;
@@ -16,12 +16,231 @@ pub enum DocNode {
     Image(Image),
     Video(Video),
     Audio(Audio),
+    BlockQuote(BlockQuote),
+    ThematicBreak,
+}
+
+impl DocNode {
+    /// Source span of this node, for the node kinds that carry one.
+    pub fn span(&self) -> Option<&SourceSpan> {
+        match self {
+            DocNode::Heading(node) => node.span.as_ref(),
+            DocNode::Paragraph(node) => node.span.as_ref(),
+            DocNode::List(node) => node.span.as_ref(),
+            DocNode::ListItem(node) => node.span.as_ref(),
+            DocNode::Definition(node) => node.span.as_ref(),
+            DocNode::Verbatim(node) => node.span.as_ref(),
+            DocNode::Annotation(node) => node.span.as_ref(),
+            DocNode::Table(node) => node.span.as_ref(),
+            DocNode::BlockQuote(node) => node.span.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Sets the source span; a no-op for node kinds without one.
+    pub fn set_span(&mut self, span: Option<SourceSpan>) {
+        match self {
+            DocNode::Heading(node) => node.span = span,
+            DocNode::Paragraph(node) => node.span = span,
+            DocNode::List(node) => node.span = span,
+            DocNode::ListItem(node) => node.span = span,
+            DocNode::Definition(node) => node.span = span,
+            DocNode::Verbatim(node) => node.span = span,
+            DocNode::Annotation(node) => node.span = span,
+            DocNode::Table(node) => node.span = span,
+            DocNode::BlockQuote(node) => node.span = span,
+            _ => {}
+        }
+    }
+}
+
+/// The region of the source document a node was read from.
+///
+/// For nodes converted from Lex this is the `lex_core` range of the element;
+/// importers fill it from the positions their parser reports. Positions are
+/// zero-based, and `end` points just past the last character.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// Byte offsets into the source text
+    pub bytes: std::ops::Range<usize>,
+    pub start: SourcePosition,
+    pub end: SourcePosition,
+}
+
+/// A zero-based line and column (in bytes) in the source text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourcePosition {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
 }
 
 /// Represents the root of a document.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Document {
     pub children: Vec<DocNode>,
+    pub metadata: Metadata,
+}
+
+/// Document metadata (title, authors, dates, tags, ...).
+///
+/// Entries keep their source order, and a key may appear more than once, e.g.
+/// one `:: author ::` annotation per author. Serializers should go through the
+/// accessors (`title`, `authors`, `tags`, ...) so every format agrees on which
+/// keys mean what.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub entries: Vec<MetadataEntry>,
+}
+
+/// A single metadata key/value pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataEntry {
+    pub key: String,
+    pub value: MetadataValue,
+    /// The annotation this entry was read from, written back as-is when
+    /// converting to Lex. Several entries can share one source annotation.
+    pub source: Option<Annotation>,
+}
+
+/// The value of a metadata entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Text(String),
+    /// Multi-valued keys such as `tags` or `[a, b]` values
+    List(Vec<String>),
+    /// Named fields, e.g. `:: author name="Ana" email="ana@example.org" ::`
+    Fields(Vec<(String, String)>),
+}
+
+/// Keys whose comma-separated text values are lists.
+pub const METADATA_LIST_KEYS: &[&str] = &["tags", "keywords", "authors", "categories"];
+
+impl MetadataValue {
+    /// Interpret a text value: `[a, b]` and values of [`METADATA_LIST_KEYS`]
+    /// become lists, anything else is text.
+    pub fn from_text(key: &str, text: &str) -> MetadataValue {
+        let text = text.trim();
+        let items = if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            inner
+        } else if METADATA_LIST_KEYS.contains(&key) {
+            text
+        } else {
+            return MetadataValue::Text(text.to_string());
+        };
+
+        MetadataValue::List(
+            items
+                .split(',')
+                .map(|item| item.trim().trim_matches(['"', '\'']).to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Single-line text form: lists are comma-separated, fields use their
+    /// `name` (or first) value.
+    pub fn as_text(&self) -> String {
+        match self {
+            MetadataValue::Text(text) => text.clone(),
+            MetadataValue::List(items) => items.join(", "),
+            MetadataValue::Fields(fields) => fields
+                .iter()
+                .find(|(key, _)| key == "name" || key == "value")
+                .or(fields.first())
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The value as a list of items; text and fields are a single item.
+    pub fn items(&self) -> Vec<String> {
+        match self {
+            MetadataValue::List(items) => items.clone(),
+            other => vec![other.as_text()],
+        }
+    }
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append an entry without a source annotation.
+    pub fn push(&mut self, key: impl Into<String>, value: MetadataValue) {
+        self.entries.push(MetadataEntry {
+            key: key.into(),
+            value,
+            source: None,
+        });
+    }
+
+    /// The first value for `key`.
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| &entry.value)
+    }
+
+    /// The first value for `key` as trimmed, non-empty text.
+    pub fn get_text(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .map(|value| value.as_text().trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Every value for `key`, with lists flattened, in source order.
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.key == key)
+            .flat_map(|entry| entry.value.items())
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Distinct keys in order of first appearance.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for entry in &self.entries {
+            if !keys.contains(&entry.key.as_str()) {
+                keys.push(&entry.key);
+            }
+        }
+        keys
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.get_text("title")
+    }
+
+    /// Values of `author` and `authors`.
+    pub fn authors(&self) -> Vec<String> {
+        let mut authors = self.get_all("author");
+        authors.extend(self.get_all("authors"));
+        authors
+    }
+
+    /// The `date`, falling back to `publishing-date`.
+    pub fn date(&self) -> Option<String> {
+        self.get_text("date")
+            .or_else(|| self.get_text("publishing-date"))
+    }
+
+    /// Values of `tags` and `keywords`.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = self.get_all("tags");
+        tags.extend(self.get_all("keywords"));
+        tags
+    }
 }
 
 /// Represents a heading with a specific level.
@@ -30,12 +249,19 @@ pub struct Heading {
     pub level: usize,
     pub content: Vec<InlineContent>,
     pub children: Vec<DocNode>,
+    /// Anchor cross-references link to, e.g. `getting-started`; assigned by
+    /// [`crate::ir::references::resolve_references`] when not set by the importer
+    pub id: Option<String>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents a paragraph of text.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Paragraph {
     pub content: Vec<InlineContent>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Decoration style for ordered lists.
@@ -77,6 +303,8 @@ pub struct List {
     pub ordered: bool,
     pub style: ListStyle,
     pub form: ListForm,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents an item in a list.
@@ -84,6 +312,10 @@ pub struct List {
 pub struct ListItem {
     pub content: Vec<InlineContent>,
     pub children: Vec<DocNode>,
+    /// Task list state: `Some(true)` for `[x]`, `Some(false)` for `[ ]`
+    pub checked: Option<bool>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents a definition of a term.
@@ -91,6 +323,8 @@ pub struct ListItem {
 pub struct Definition {
     pub term: Vec<InlineContent>,
     pub description: Vec<DocNode>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents a block of verbatim text.
@@ -99,6 +333,8 @@ pub struct Verbatim {
     pub subject: Option<String>,
     pub language: Option<String>,
     pub content: String,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents an annotation.
@@ -107,14 +343,30 @@ pub struct Annotation {
     pub label: String,
     pub parameters: Vec<(String, String)>,
     pub content: Vec<DocNode>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
+}
+
+/// Represents a block quotation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockQuote {
+    pub children: Vec<DocNode>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents a table.
+///
+/// `header` may hold several rows. Cells spanning more than one row or column
+/// appear once, in the row and column where they start; the positions they
+/// cover have no cell of their own.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Table {
     pub rows: Vec<TableRow>,
     pub header: Vec<TableRow>,
     pub caption: Option<Vec<InlineContent>>,
+    /// Where this node came from, when the producer tracked it
+    pub span: Option<SourceSpan>,
 }
 
 /// Represents a table row.
@@ -129,6 +381,38 @@ pub struct TableCell {
     pub content: Vec<DocNode>,
     pub header: bool,
     pub align: TableCellAlignment,
+    /// Number of columns the cell covers, at least 1
+    pub colspan: usize,
+    /// Number of rows the cell covers, at least 1
+    pub rowspan: usize,
+}
+
+impl TableCell {
+    /// A cell covering a single row and column
+    pub fn new(content: Vec<DocNode>, header: bool, align: TableCellAlignment) -> Self {
+        Self {
+            content,
+            header,
+            align,
+            colspan: 1,
+            rowspan: 1,
+        }
+    }
+
+    pub fn is_spanning(&self) -> bool {
+        self.colspan > 1 || self.rowspan > 1
+    }
+}
+
+impl Table {
+    /// Whether any cell spans more than one row or column
+    pub fn has_spans(&self) -> bool {
+        self.header
+            .iter()
+            .chain(&self.rows)
+            .flat_map(|row| &row.cells)
+            .any(TableCell::is_spanning)
+    }
 }
 
 /// Alignment of a table cell.
@@ -151,6 +435,20 @@ pub enum InlineContent {
     Reference(String),
     Marker(String),
     Image(Image),
+    Strikethrough(Vec<InlineContent>),
+    Link(Link),
+    /// Reference to a footnote by label (`1`, `note`), without brackets
+    FootnoteRef(String),
+}
+
+/// Represents a hyperlink with its own text.
+///
+/// Bare URLs (`[https://..]` in Lex) stay [`InlineContent::Reference`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub url: String,
+    pub content: Vec<InlineContent>,
+    pub title: Option<String>,
 }
 
 /// Represents an image.
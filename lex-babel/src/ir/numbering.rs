@@ -0,0 +1,614 @@
+//! Session numbers: `1.2.3`, `I.A.1` and lettered appendices.
+//!
+//! [`number_sessions`] prefixes every heading with its position in the
+//! session tree, counted in a [`NumberingStyle`]: `1.2.3` (the default)
+//! gives `1.`, `1.1`, `1.1.1`; `I.A.1` gives `I.`, `I.A`, `I.A.1`. Levels
+//! deeper than the style reuse its last counter. With an appendix counter,
+//! the top-level sessions from the first one titled `Appendix ...` onwards
+//! are counted separately (`A.`, `A.1`, `B.`).
+//!
+//! Documents whose titles already carry numbers keep them: the author's
+//! numbering wins, and [`numbering_gaps`] points out where it skips or
+//! repeats. Session references by number (`[#2.1]`) are rewritten to the
+//! new numbers, so they keep pointing at the same sessions. The Notes session
+//! and the generated References session stay unnumbered.
+
+use super::citations::REFERENCES_ID;
+use super::footnotes::is_notes_session;
+use super::nodes::{DocNode, Document, Heading, InlineContent, SourceSpan};
+use super::references::{assign_heading_ids, renumber_references, split_title};
+use super::visit::{walk_document_mut, walk_node_mut, VisitorMut};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// One way of counting sessions at a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// `1`, `2`, `3`
+    Arabic,
+    /// `I`, `II`, `III`
+    UpperRoman,
+    /// `i`, `ii`, `iii`
+    LowerRoman,
+    /// `A`, `B`, ..., `Z`, `AA`
+    UpperAlpha,
+    /// `a`, `b`, ..., `z`, `aa`
+    LowerAlpha,
+}
+
+impl Counter {
+    /// The counter a style component such as `1`, `I`, `iv` or `a` stands for
+    ///
+    /// Any digits mean Arabic numbers, roman numerals mean roman numbers and
+    /// other letters mean letters, each in the case they are written in.
+    pub fn from_symbol(symbol: &str) -> Option<Counter> {
+        let upper = symbol.chars().all(|c| c.is_ascii_uppercase());
+        let lower = symbol.chars().all(|c| c.is_ascii_lowercase());
+        let roman = symbol
+            .chars()
+            .all(|c| "IVXLCDM".contains(c.to_ascii_uppercase()));
+        match symbol {
+            "" => None,
+            _ if symbol.chars().all(|c| c.is_ascii_digit()) => Some(Counter::Arabic),
+            _ if roman && upper => Some(Counter::UpperRoman),
+            _ if roman && lower => Some(Counter::LowerRoman),
+            _ if upper => Some(Counter::UpperAlpha),
+            _ if lower => Some(Counter::LowerAlpha),
+            _ => None,
+        }
+    }
+
+    /// Write `n` (counting from 1) with this counter
+    pub fn format(self, n: usize) -> String {
+        match self {
+            Counter::Arabic => n.to_string(),
+            Counter::UpperRoman => to_roman(n),
+            Counter::LowerRoman => to_roman(n).to_lowercase(),
+            Counter::UpperAlpha => to_alpha(n),
+            Counter::LowerAlpha => to_alpha(n).to_lowercase(),
+        }
+    }
+
+    /// Read a number written with this counter
+    pub fn parse(self, text: &str) -> Option<usize> {
+        let n = match self {
+            Counter::Arabic => text.parse().ok(),
+            Counter::UpperRoman | Counter::LowerRoman => from_roman(text),
+            Counter::UpperAlpha | Counter::LowerAlpha => from_alpha(text),
+        }?;
+        // Only the canonical spelling counts: `IIII` and `007` are not numbers
+        (n > 0 && self.format(n) == text).then_some(n)
+    }
+
+    /// The counter a number as written most likely uses
+    fn detect(text: &str) -> Option<Counter> {
+        [
+            Counter::Arabic,
+            Counter::UpperRoman,
+            Counter::LowerRoman,
+            Counter::UpperAlpha,
+            Counter::LowerAlpha,
+        ]
+        .into_iter()
+        .find(|counter| counter.parse(text).is_some())
+    }
+}
+
+fn to_roman(mut n: usize) -> String {
+    const NUMERALS: &[(usize, &str)] = &[
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut roman = String::new();
+    for &(value, numeral) in NUMERALS {
+        while n >= value {
+            roman.push_str(numeral);
+            n -= value;
+        }
+    }
+    roman
+}
+
+fn from_roman(text: &str) -> Option<usize> {
+    let mut total = 0;
+    let mut previous = 0;
+    for c in text.chars().rev() {
+        let value = match c.to_ascii_uppercase() {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            'D' => 500,
+            'M' => 1000,
+            _ => return None,
+        };
+        if value < previous {
+            total -= value;
+        } else {
+            total += value;
+            previous = value;
+        }
+    }
+    Some(total)
+}
+
+/// `A`..`Z`, then `AA`, `AB`, ... like spreadsheet columns
+fn to_alpha(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b'A' + (n % 26) as u8));
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn from_alpha(text: &str) -> Option<usize> {
+    let mut n = 0usize;
+    for c in text.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        n = n
+            .checked_mul(26)?
+            .checked_add(usize::from(c.to_ascii_uppercase() as u8 - b'A' + 1))?;
+    }
+    Some(n)
+}
+
+/// Counters for each nesting level, written like the numbers they produce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberingStyle {
+    levels: Vec<Counter>,
+}
+
+impl NumberingStyle {
+    /// The counter for sessions at `depth` (0 for top-level sessions)
+    pub fn counter(&self, depth: usize) -> Counter {
+        self.levels
+            .get(depth)
+            .or(self.levels.last())
+            .copied()
+            .unwrap_or(Counter::Arabic)
+    }
+}
+
+impl Default for NumberingStyle {
+    fn default() -> Self {
+        Self {
+            levels: vec![Counter::Arabic],
+        }
+    }
+}
+
+impl FromStr for NumberingStyle {
+    type Err = String;
+
+    /// Parse a style such as `1.2.3` or `I.A.1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let levels = s
+            .trim()
+            .trim_end_matches('.')
+            .split('.')
+            .map(|symbol| {
+                Counter::from_symbol(symbol.trim())
+                    .ok_or_else(|| format!("Unknown numbering style '{s}' (try 1.2.3 or I.A.1)"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { levels })
+    }
+}
+
+/// How [`number_sessions`] numbers a document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionNumbering {
+    pub style: NumberingStyle,
+    /// Counter for trailing `Appendix ...` sessions, if they are set apart
+    pub appendix: Option<Counter>,
+}
+
+/// A sequence marker that does not follow the one before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberingGap {
+    /// The marker as written, e.g. `2.4`
+    pub marker: String,
+    /// The sibling marker before it, if any
+    pub previous: Option<String>,
+    /// The number expected instead, e.g. `2.3`
+    pub expected: String,
+    pub span: Option<SourceSpan>,
+}
+
+/// Number every session of `doc`, unless the titles are numbered already
+pub fn number_sessions(doc: &mut Document, numbering: &SessionNumbering) {
+    if has_own_numbers(&doc.children) {
+        return;
+    }
+
+    assign_heading_ids(doc);
+    let mut planner = Planner {
+        numbering,
+        markers: HashMap::new(),
+        path: Vec::new(),
+        count: 0,
+        appendices: None,
+        in_appendix: false,
+    };
+    planner.visit(&doc.children);
+    let markers = planner.markers;
+
+    // References still name sessions by their old numbers here
+    let numbers = markers
+        .iter()
+        .map(|(id, marker)| (id.clone(), marker.trim_end_matches('.').to_string()))
+        .collect();
+    renumber_references(doc, &numbers);
+    walk_document_mut(&mut MarkerWriter { markers: &markers }, doc);
+}
+
+/// Places where the numbers authors gave their sessions skip or repeat
+///
+/// Siblings are compared by the last part of their markers, so `2.4` after
+/// `2.2` is a gap. A switch of counter (`3.` then `A.`) starts over, which
+/// allows for lettered appendices. Sessions without a marker are ignored.
+pub fn numbering_gaps(doc: &Document) -> Vec<NumberingGap> {
+    let mut gaps = Vec::new();
+    find_gaps(&doc.children, &mut gaps);
+    gaps
+}
+
+fn find_gaps(nodes: &[DocNode], gaps: &mut Vec<NumberingGap>) {
+    let mut siblings = Siblings::default();
+    for heading in sessions(nodes) {
+        if let Some(InlineContent::Marker(marker)) = heading.content.first() {
+            siblings.check(marker, heading.span.as_ref(), gaps);
+        }
+        find_gaps(&heading.children, gaps);
+    }
+}
+
+/// The numbering of a run of sibling sessions so far
+#[derive(Default)]
+struct Siblings {
+    counter: Option<Counter>,
+    /// The last marker seen, and its value
+    previous: Option<(String, usize)>,
+}
+
+impl Siblings {
+    fn check(&mut self, marker: &str, span: Option<&SourceSpan>, gaps: &mut Vec<NumberingGap>) {
+        let marker = marker.trim().trim_end_matches('.');
+        let (parent, last) = match marker.rsplit_once('.') {
+            Some((parent, last)) => (Some(parent), last),
+            None => (None, marker),
+        };
+
+        let (counter, value) = match self
+            .counter
+            .and_then(|counter| Some((counter, counter.parse(last)?)))
+        {
+            Some(found) => found,
+            None => {
+                let Some(counter) = Counter::detect(last) else {
+                    return;
+                };
+                self.previous = None;
+                (counter, counter.parse(last).unwrap_or(1))
+            }
+        };
+        self.counter = Some(counter);
+
+        let expected = self.previous.as_ref().map_or(1, |(_, n)| n + 1);
+        if value != expected {
+            let expected = counter.format(expected);
+            gaps.push(NumberingGap {
+                marker: marker.to_string(),
+                previous: self.previous.as_ref().map(|(marker, _)| marker.clone()),
+                expected: match parent {
+                    Some(parent) => format!("{parent}.{expected}"),
+                    None => expected,
+                },
+                span: span.cloned(),
+            });
+        }
+        self.previous = Some((marker.to_string(), value));
+    }
+}
+
+/// The headings among `nodes` that get numbers, looking into containers
+fn sessions(nodes: &[DocNode]) -> Vec<&Heading> {
+    let mut found = Vec::new();
+    for node in nodes {
+        match node {
+            DocNode::Heading(heading) if !is_back_matter(node) => found.push(heading),
+            DocNode::Annotation(annotation) => found.extend(sessions(&annotation.content)),
+            DocNode::BlockQuote(quote) => found.extend(sessions(&quote.children)),
+            _ => {}
+        }
+    }
+    found
+}
+
+/// The Notes session and the generated References session stay unnumbered
+fn is_back_matter(node: &DocNode) -> bool {
+    match node {
+        DocNode::Heading(heading) => {
+            heading.id.as_deref() == Some(REFERENCES_ID) || is_notes_session(node)
+        }
+        _ => false,
+    }
+}
+
+/// Whether every numbered session already starts with a marker
+fn has_own_numbers(nodes: &[DocNode]) -> bool {
+    fn all_marked(nodes: &[DocNode]) -> bool {
+        sessions(nodes).into_iter().all(|heading| {
+            matches!(heading.content.first(), Some(InlineContent::Marker(_)))
+                && all_marked(&heading.children)
+        })
+    }
+    !sessions(nodes).is_empty() && all_marked(nodes)
+}
+
+fn is_appendix(heading: &Heading) -> bool {
+    let (_, title) = split_title(&heading.content);
+    title
+        .get(..8)
+        .is_some_and(|start| start.eq_ignore_ascii_case("appendix"))
+}
+
+/// Works out the new marker of every session, keyed by heading id
+struct Planner<'a> {
+    numbering: &'a SessionNumbering,
+    markers: HashMap<String, String>,
+    /// Numbers of the enclosing sessions, as written
+    path: Vec<String>,
+    /// Sessions seen so far at the current depth
+    count: usize,
+    /// Top-level appendices seen so far, once the first one is found
+    appendices: Option<usize>,
+    /// Whether the sessions being numbered are inside an appendix
+    in_appendix: bool,
+}
+
+impl Planner<'_> {
+    fn visit(&mut self, nodes: &[DocNode]) {
+        for heading in sessions(nodes) {
+            let depth = self.path.len();
+            let number = match self.numbering.appendix {
+                Some(counter)
+                    if depth == 0 && (self.appendices.is_some() || is_appendix(heading)) =>
+                {
+                    let n = self.appendices.unwrap_or(0) + 1;
+                    self.appendices = Some(n);
+                    self.in_appendix = true;
+                    counter.format(n)
+                }
+                _ => {
+                    self.count += 1;
+                    // Appendix sections are numbered A.1, A.2 whatever the style
+                    let counter = if self.in_appendix {
+                        Counter::Arabic
+                    } else {
+                        self.numbering.style.counter(depth)
+                    };
+                    counter.format(self.count)
+                }
+            };
+            self.path.push(number);
+
+            let marker = match self.path.as_slice() {
+                [top] => format!("{top}."),
+                path => path.join("."),
+            };
+            if let Some(id) = &heading.id {
+                self.markers.insert(id.clone(), marker);
+            }
+
+            let siblings = std::mem::take(&mut self.count);
+            self.visit(&heading.children);
+            self.count = siblings;
+            self.path.pop();
+        }
+    }
+}
+
+struct MarkerWriter<'a> {
+    markers: &'a HashMap<String, String>,
+}
+
+impl VisitorMut for MarkerWriter<'_> {
+    fn visit_node_mut(&mut self, node: &mut DocNode) {
+        if let DocNode::Heading(heading) = node {
+            if let Some(marker) = heading.id.as_ref().and_then(|id| self.markers.get(id)) {
+                set_marker(&mut heading.content, marker.clone());
+            }
+        }
+        walk_node_mut(self, node);
+    }
+}
+
+/// Replace the leading marker of a title, or insert one
+fn set_marker(content: &mut Vec<InlineContent>, marker: String) {
+    if let Some(InlineContent::Marker(existing)) = content.first_mut() {
+        *existing = marker;
+        return;
+    }
+    if !content.is_empty() {
+        content.insert(0, InlineContent::Text(" ".to_string()));
+    }
+    content.insert(0, InlineContent::Marker(marker));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::Paragraph;
+
+    fn heading(title: &str, children: Vec<DocNode>) -> DocNode {
+        let content = match title.split_once(' ') {
+            Some((marker, rest)) if marker.ends_with('.') || marker.contains('.') => vec![
+                InlineContent::Marker(marker.to_string()),
+                InlineContent::Text(format!(" {rest}")),
+            ],
+            _ => vec![InlineContent::Text(title.to_string())],
+        };
+        DocNode::Heading(Heading {
+            level: 2,
+            content,
+            children,
+            id: None,
+            span: None,
+        })
+    }
+
+    fn markers(nodes: &[DocNode], out: &mut Vec<String>) {
+        for node in nodes {
+            if let DocNode::Heading(heading) = node {
+                match heading.content.first() {
+                    Some(InlineContent::Marker(marker)) => out.push(marker.clone()),
+                    _ => out.push(String::new()),
+                }
+                markers(&heading.children, out);
+            }
+        }
+    }
+
+    fn doc(children: Vec<DocNode>) -> Document {
+        Document {
+            children,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_counters() {
+        assert_eq!(Counter::UpperRoman.format(14), "XIV");
+        assert_eq!(Counter::LowerAlpha.format(28), "ab");
+        assert_eq!(Counter::UpperRoman.parse("XIV"), Some(14));
+        assert_eq!(Counter::UpperRoman.parse("IIII"), None);
+        assert_eq!(Counter::UpperAlpha.parse("AB"), Some(28));
+        assert_eq!(Counter::detect("iv"), Some(Counter::LowerRoman));
+        assert_eq!(Counter::detect("C"), Some(Counter::UpperRoman));
+        assert_eq!(Counter::detect("B"), Some(Counter::UpperAlpha));
+    }
+
+    #[test]
+    fn test_styles_and_appendices() {
+        let mut document = doc(vec![
+            heading(
+                "Intro",
+                vec![heading("Scope", vec![heading("Terms", vec![])])],
+            ),
+            heading("Usage", vec![]),
+            heading("Appendix: Data", vec![heading("Tables", vec![])]),
+            heading("Appendix: Code", vec![]),
+        ]);
+        let numbering = SessionNumbering {
+            style: "I.A.1".parse().unwrap(),
+            appendix: Some(Counter::UpperAlpha),
+        };
+        number_sessions(&mut document, &numbering);
+
+        let mut out = Vec::new();
+        markers(&document.children, &mut out);
+        assert_eq!(out, vec!["I.", "I.A", "I.A.1", "II.", "A.", "A.1", "B."]);
+        assert!("1.x2".parse::<NumberingStyle>().is_err());
+    }
+
+    #[test]
+    fn test_style_components_name_counter_kinds() {
+        let style: NumberingStyle = "1.2.3".parse().unwrap();
+        assert!((0..4).all(|depth| style.counter(depth) == Counter::Arabic));
+        let style: NumberingStyle = "IV.B.ii.e".parse().unwrap();
+        assert_eq!(
+            (0..4).map(|depth| style.counter(depth)).collect::<Vec<_>>(),
+            vec![
+                Counter::UpperRoman,
+                Counter::UpperAlpha,
+                Counter::LowerRoman,
+                Counter::LowerAlpha,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references_follow_new_numbers() {
+        let mut document = doc(vec![
+            heading("Intro", vec![heading("Scope", vec![])]),
+            heading(
+                "Usage",
+                vec![DocNode::Paragraph(Paragraph {
+                    content: vec![
+                        InlineContent::Reference("#1.1".to_string()),
+                        InlineContent::Reference("#Usage".to_string()),
+                    ],
+                    span: None,
+                })],
+            ),
+        ]);
+        let numbering = SessionNumbering {
+            style: "I.A".parse().unwrap(),
+            appendix: None,
+        };
+        number_sessions(&mut document, &numbering);
+
+        let DocNode::Heading(usage) = &document.children[1] else {
+            panic!("expected a heading");
+        };
+        let DocNode::Paragraph(paragraph) = &usage.children[0] else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(
+            paragraph.content,
+            vec![
+                InlineContent::Reference("#I.A".to_string()),
+                InlineContent::Reference("#Usage".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_own_numbers_are_kept_and_checked() {
+        let mut document = doc(vec![
+            heading("1. Intro", vec![heading("1.1 Scope", vec![])]),
+            heading(
+                "2. Usage",
+                vec![heading("2.1 Install", vec![]), heading("2.3 Run", vec![])],
+            ),
+            heading("4. Limits", vec![]),
+            heading("A. Data", vec![]),
+        ]);
+        let before = document.clone();
+        number_sessions(&mut document, &SessionNumbering::default());
+        assert_eq!(document, before);
+
+        let gaps: Vec<(String, Option<String>, String)> = numbering_gaps(&document)
+            .into_iter()
+            .map(|gap| (gap.marker, gap.previous, gap.expected))
+            .collect();
+        assert_eq!(
+            gaps,
+            vec![
+                (
+                    "2.3".to_string(),
+                    Some("2.1".to_string()),
+                    "2.2".to_string()
+                ),
+                ("4".to_string(), Some("2".to_string()), "3".to_string()),
+            ]
+        );
+    }
+}
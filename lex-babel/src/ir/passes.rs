@@ -0,0 +1,401 @@
+//! Built-in IR passes.
+//!
+//! Each pass is selected by a spec string, `name` or `name=argument`, so the
+//! CLI can take them as `--pass` values:
+//!
+//! - `shift-headings=N`: add `N` (which may be negative) to every heading
+//!   level, never going below 1.
+//! - `number-sessions` or `number-sessions=STYLE[,appendix=A]`: prefix each
+//!   heading with its position in the session tree, `1.`, `1.1`, `1.1.1` by
+//!   default or in a style such as `I.A.1`; `appendix=A` letters the trailing
+//!   `Appendix ...` sessions. Titles that are all numbered already are kept
+//!   (see [`super::numbering`]).
+//! - `strip-annotations=label,...`: remove annotations with any of the given
+//!   labels, along with the metadata read from them.
+//! - `toc` or `toc=N`: fill `:: toc ::` placeholders, or insert a table of
+//!   contents at the top, listing sessions `N` levels deep (default 3).
+
+use super::nodes::{DocNode, Document};
+use super::numbering::{number_sessions, Counter, SessionNumbering};
+use super::toc::{fill_toc, TocOptions};
+use super::visit::{
+    fold_document, fold_node_children, walk_document_mut, walk_node_mut, Fold, Pass, VisitorMut,
+};
+use crate::error::FormatError;
+
+/// Names of the built-in passes, as accepted by [`builtin_pass`]
+pub const BUILTIN_PASSES: &[&str] = &[
+    "shift-headings",
+    "number-sessions",
+    "strip-annotations",
+    "toc",
+];
+
+/// Build a built-in pass from its spec, e.g. `shift-headings=1`
+pub fn builtin_pass(spec: &str) -> Result<Box<dyn Pass>, FormatError> {
+    let (name, argument) = match spec.split_once('=') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (spec.trim(), None),
+    };
+
+    match name {
+        "shift-headings" => {
+            let argument = argument.ok_or_else(|| {
+                FormatError::ParseError("Pass 'shift-headings' needs an offset".to_string())
+            })?;
+            let offset = argument.parse::<isize>().map_err(|_| {
+                FormatError::ParseError(format!(
+                    "Invalid heading offset '{argument}' for pass 'shift-headings'"
+                ))
+            })?;
+            Ok(Box::new(ShiftHeadings::new(offset)))
+        }
+        "number-sessions" => {
+            let mut numbering = SessionNumbering::default();
+            for part in argument.unwrap_or_default().split(',').map(str::trim) {
+                match part.split_once('=') {
+                    _ if part.is_empty() => {}
+                    Some(("appendix", symbol)) => {
+                        let counter = Counter::from_symbol(symbol.trim()).ok_or_else(|| {
+                            FormatError::ParseError(format!(
+                                "Invalid appendix numbering '{symbol}' for pass 'number-sessions' (try A)"
+                            ))
+                        })?;
+                        numbering.appendix = Some(counter);
+                    }
+                    Some(_) => {
+                        return Err(FormatError::ParseError(format!(
+                            "Unknown setting '{part}' for pass 'number-sessions'"
+                        )))
+                    }
+                    None => numbering.style = part.parse().map_err(FormatError::ParseError)?,
+                }
+            }
+            Ok(Box::new(NumberSessions::new(numbering)))
+        }
+        "strip-annotations" => {
+            let labels: Vec<String> = argument
+                .unwrap_or_default()
+                .split(',')
+                .map(|label| label.trim().to_string())
+                .filter(|label| !label.is_empty())
+                .collect();
+            if labels.is_empty() {
+                return Err(FormatError::ParseError(
+                    "Pass 'strip-annotations' needs at least one label".to_string(),
+                ));
+            }
+            Ok(Box::new(StripAnnotations::new(labels)))
+        }
+        "toc" => {
+            let mut options = TocOptions::default();
+            if let Some(argument) = argument {
+                options.depth = argument
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|depth| *depth > 0)
+                    .ok_or_else(|| {
+                        FormatError::ParseError(format!(
+                            "Invalid depth '{argument}' for pass 'toc'"
+                        ))
+                    })?;
+            }
+            Ok(Box::new(TableOfContents::new(options)))
+        }
+        _ => Err(FormatError::NotSupported(format!(
+            "Unknown pass '{name}' (available: {})",
+            BUILTIN_PASSES.join(", ")
+        ))),
+    }
+}
+
+/// Moves every heading up or down by a fixed number of levels.
+pub struct ShiftHeadings {
+    offset: isize,
+}
+
+impl ShiftHeadings {
+    pub fn new(offset: isize) -> Self {
+        Self { offset }
+    }
+}
+
+impl Pass for ShiftHeadings {
+    fn name(&self) -> &str {
+        "shift-headings"
+    }
+
+    fn run(&self, doc: &mut Document) {
+        walk_document_mut(&mut HeadingShifter(self.offset), doc);
+    }
+}
+
+struct HeadingShifter(isize);
+
+impl VisitorMut for HeadingShifter {
+    fn visit_node_mut(&mut self, node: &mut DocNode) {
+        if let DocNode::Heading(heading) = node {
+            heading.level = (heading.level as isize + self.0).max(1) as usize;
+        }
+        walk_node_mut(self, node);
+    }
+}
+
+/// Numbers headings by their position in the session tree.
+pub struct NumberSessions {
+    numbering: SessionNumbering,
+}
+
+impl NumberSessions {
+    pub fn new(numbering: SessionNumbering) -> Self {
+        Self { numbering }
+    }
+}
+
+impl Pass for NumberSessions {
+    fn name(&self) -> &str {
+        "number-sessions"
+    }
+
+    fn run(&self, doc: &mut Document) {
+        number_sessions(doc, &self.numbering);
+    }
+}
+
+/// Removes annotations by label.
+pub struct StripAnnotations {
+    labels: Vec<String>,
+}
+
+impl StripAnnotations {
+    pub fn new(labels: Vec<String>) -> Self {
+        Self { labels }
+    }
+}
+
+impl Pass for StripAnnotations {
+    fn name(&self) -> &str {
+        "strip-annotations"
+    }
+
+    fn run(&self, doc: &mut Document) {
+        let mut stripper = AnnotationStripper(&self.labels);
+        *doc = fold_document(&mut stripper, std::mem::take(doc));
+        doc.metadata.entries.retain(|entry| {
+            entry
+                .source
+                .as_ref()
+                .is_none_or(|annotation| !self.labels.contains(&annotation.label))
+        });
+    }
+}
+
+struct AnnotationStripper<'a>(&'a [String]);
+
+impl Fold for AnnotationStripper<'_> {
+    fn fold_node(&mut self, node: DocNode) -> Option<DocNode> {
+        match node {
+            DocNode::Annotation(annotation) if self.0.contains(&annotation.label) => None,
+            other => Some(fold_node_children(self, other)),
+        }
+    }
+}
+
+/// Fills table of contents placeholders, adding one when there is none.
+pub struct TableOfContents {
+    options: TocOptions,
+}
+
+impl TableOfContents {
+    pub fn new(options: TocOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Pass for TableOfContents {
+    fn name(&self) -> &str {
+        "toc"
+    }
+
+    fn run(&self, doc: &mut Document) {
+        fill_toc(doc, Some(self.options));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::{
+        Annotation, Heading, InlineContent, MetadataEntry, MetadataValue, Paragraph,
+    };
+    use crate::ir::visit::run_passes;
+
+    fn heading(level: usize, title: &str, children: Vec<DocNode>) -> DocNode {
+        DocNode::Heading(Heading {
+            level,
+            content: vec![InlineContent::Text(title.to_string())],
+            children,
+            id: None,
+            span: None,
+        })
+    }
+
+    fn note(label: &str) -> DocNode {
+        DocNode::Annotation(Annotation {
+            label: label.to_string(),
+            parameters: vec![],
+            content: vec![DocNode::Paragraph(Paragraph {
+                content: vec![InlineContent::Text("body".to_string())],
+                span: None,
+            })],
+            span: None,
+        })
+    }
+
+    fn sample() -> Document {
+        Document {
+            children: vec![
+                heading(
+                    2,
+                    "Intro",
+                    vec![heading(3, "Scope", vec![]), heading(3, "Terms", vec![])],
+                ),
+                heading(2, "Usage", vec![note("todo"), note("warning")]),
+            ],
+            metadata: Default::default(),
+        }
+    }
+
+    fn titles(nodes: &[DocNode], out: &mut Vec<(usize, Vec<InlineContent>)>) {
+        for node in nodes {
+            if let DocNode::Heading(heading) = node {
+                out.push((heading.level, heading.content.clone()));
+                titles(&heading.children, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shift_headings_clamps_at_one() {
+        let mut doc = sample();
+        builtin_pass("shift-headings=-2").unwrap().run(&mut doc);
+        let mut out = Vec::new();
+        titles(&doc.children, &mut out);
+        let levels: Vec<usize> = out.iter().map(|(level, _)| *level).collect();
+        assert_eq!(levels, vec![1, 1, 1, 1]);
+
+        let mut doc = sample();
+        builtin_pass("shift-headings=1").unwrap().run(&mut doc);
+        let mut out = Vec::new();
+        titles(&doc.children, &mut out);
+        let levels: Vec<usize> = out.iter().map(|(level, _)| *level).collect();
+        assert_eq!(levels, vec![3, 4, 4, 3]);
+    }
+
+    #[test]
+    fn test_number_sessions() {
+        let mut doc = sample();
+        if let DocNode::Heading(heading) = &mut doc.children[1] {
+            heading.content = vec![
+                InlineContent::Marker("7.".to_string()),
+                InlineContent::Text(" ".to_string()),
+                InlineContent::Text("Usage".to_string()),
+            ];
+        }
+        builtin_pass("number-sessions").unwrap().run(&mut doc);
+
+        let mut out = Vec::new();
+        titles(&doc.children, &mut out);
+        let markers: Vec<&InlineContent> = out.iter().map(|(_, content)| &content[0]).collect();
+        assert_eq!(
+            markers,
+            vec![
+                &InlineContent::Marker("1.".to_string()),
+                &InlineContent::Marker("1.1".to_string()),
+                &InlineContent::Marker("1.2".to_string()),
+                &InlineContent::Marker("2.".to_string()),
+            ]
+        );
+        assert_eq!(
+            out[3].1.len(),
+            3,
+            "existing marker is replaced, not doubled"
+        );
+        assert_eq!(out[0].1[1], InlineContent::Text(" ".to_string()));
+    }
+
+    #[test]
+    fn test_strip_annotations_by_label() {
+        let mut doc = sample();
+        let DocNode::Annotation(author) = note("author") else {
+            unreachable!()
+        };
+        doc.metadata.entries.push(MetadataEntry {
+            key: "author".to_string(),
+            value: MetadataValue::Text("body".to_string()),
+            source: Some(author),
+        });
+
+        let passes = vec![builtin_pass("strip-annotations=todo, author").unwrap()];
+        run_passes(&mut doc, &passes);
+
+        let DocNode::Heading(usage) = &doc.children[1] else {
+            panic!("expected heading");
+        };
+        assert_eq!(usage.children.len(), 1);
+        assert!(matches!(
+            &usage.children[0],
+            DocNode::Annotation(annotation) if annotation.label == "warning"
+        ));
+        assert!(doc.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_toc_inserts_table_of_contents() {
+        let mut doc = sample();
+        builtin_pass("toc=1").unwrap().run(&mut doc);
+
+        let DocNode::Annotation(toc) = &doc.children[0] else {
+            panic!("expected the table of contents first");
+        };
+        assert_eq!(toc.label, "toc");
+        let [DocNode::List(list)] = toc.content.as_slice() else {
+            panic!("expected a single list");
+        };
+        assert_eq!(list.items.len(), 2);
+        assert!(list.items.iter().all(|item| item.children.is_empty()));
+    }
+
+    #[test]
+    fn test_builtin_pass_errors() {
+        assert!(matches!(
+            builtin_pass("shift-headings"),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(matches!(
+            builtin_pass("shift-headings=up"),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(matches!(
+            builtin_pass("strip-annotations="),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(matches!(
+            builtin_pass("number-sessions=1.x2"),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(matches!(
+            builtin_pass("number-sessions=I.A.1,appendix=A1"),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(builtin_pass("number-sessions=I.A.1, appendix=A").is_ok());
+        assert!(matches!(
+            builtin_pass("toc=0"),
+            Err(FormatError::ParseError(_))
+        ));
+        assert!(matches!(
+            builtin_pass("unknown"),
+            Err(FormatError::NotSupported(_))
+        ));
+    }
+}
@@ -0,0 +1,496 @@
+//! Cross-references between sessions.
+//!
+//! Lex points at a session with `[#2.1]` (its number) or `[#Introduction]`
+//! (its title); a plain `[Introduction]` that matches a session title also
+//! counts. Authors never write anchors: [`resolve_references`] gives every
+//! heading a stable [`Heading::id`] and turns each reference it can place
+//! into a [`Link`] to `#id`, which serializers render as their own anchor
+//! syntax (`<section id>` in HTML, heading anchors in Markdown).
+//!
+//! Ids are slugs of the title (`Getting Started` → `getting-started`), so
+//! they survive reordering; a title with no letters or digits falls back to
+//! the session number (`section-2-1`). Repeated slugs get `-2`, `-3`, ...
+//! in document order. A `#` reference that matches no session is left as
+//! it is and reported by [`unresolved_references`].
+//!
+//! Numbers are looked up by the marker the author wrote (`2.1. Scope`)
+//! first and by position in the session tree second, so `[#2.1]` works
+//! whether or not the titles are numbered.
+
+use super::nodes::{DocNode, Document, Heading, InlineContent, Link, SourceSpan};
+use super::visit::{
+    walk_document, walk_document_mut, walk_inline, walk_inline_mut, walk_node, Visitor, VisitorMut,
+};
+use crate::common::text::plain_text;
+use std::collections::{HashMap, HashSet};
+
+/// A `#` reference no session answers to
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedReference {
+    /// The reference as written, without brackets
+    pub target: String,
+    /// Span of the innermost block containing the reference, when known
+    pub span: Option<SourceSpan>,
+}
+
+/// Assign heading ids and link every session reference that resolves
+pub fn resolve_references(doc: &mut Document) {
+    let targets = assign_ids(doc);
+    walk_document_mut(&mut Linker { targets: &targets }, doc);
+}
+
+/// Give every heading without an id one, as [`resolve_references`] does
+pub fn assign_heading_ids(doc: &mut Document) {
+    assign_ids(doc);
+}
+
+/// Point `#` references that name a session by number at its new number
+///
+/// `numbers` maps heading ids to the numbers the sessions are about to get
+/// (without the trailing dot); references by title or id are left alone.
+pub(super) fn renumber_references(doc: &mut Document, numbers: &HashMap<String, String>) {
+    let targets = assign_ids(doc);
+    walk_document_mut(
+        &mut Renumberer {
+            targets: &targets,
+            numbers,
+        },
+        doc,
+    );
+}
+
+/// A copy of the session `name` points at, as `[#name]` would: by number,
+/// title or id
+///
+/// The copy keeps the ids its headings had in `doc`, usually none.
+pub(crate) fn find_session(doc: &Document, name: &str) -> Option<Heading> {
+    let mut indexed = doc.clone();
+    let targets = assign_ids(&mut indexed);
+    let id = &targets.lookup(&format!("#{name}"))?.id;
+    let path = heading_path(&indexed.children, id)?;
+
+    let mut nodes = &doc.children;
+    let mut found = None;
+    for index in path {
+        let heading = match &nodes[index] {
+            DocNode::Heading(heading) => heading,
+            _ => return None,
+        };
+        nodes = &heading.children;
+        found = Some(heading);
+    }
+    found.cloned()
+}
+
+/// Indices leading through nested headings to the heading with `id`
+fn heading_path(nodes: &[DocNode], id: &str) -> Option<Vec<usize>> {
+    nodes
+        .iter()
+        .enumerate()
+        .find_map(|(index, node)| match node {
+            DocNode::Heading(heading) if heading.id.as_deref() == Some(id) => Some(vec![index]),
+            DocNode::Heading(heading) => {
+                let mut path = heading_path(&heading.children, id)?;
+                path.insert(0, index);
+                Some(path)
+            }
+            _ => None,
+        })
+}
+
+/// The `#` references in `doc` that match no session
+///
+/// Run on a document before [`resolve_references`], or after it: resolved
+/// references are links by then and no longer show up.
+pub fn unresolved_references(doc: &Document) -> Vec<UnresolvedReference> {
+    let mut doc = doc.clone();
+    let targets = assign_ids(&mut doc);
+    let mut finder = UnresolvedFinder {
+        targets: &targets,
+        span: None,
+        found: Vec::new(),
+    };
+    walk_document(&mut finder, &doc);
+    finder.found
+}
+
+/// Turn a title into an id: lowercase letters and digits, words joined by `-`
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    slug
+}
+
+/// Where each way of naming a session leads
+#[derive(Default)]
+struct Targets {
+    /// Numbers from the authors' own markers
+    by_marker: HashMap<String, Target>,
+    /// Numbers from the position in the session tree
+    by_position: HashMap<String, Target>,
+    /// Normalized titles
+    by_title: HashMap<String, Target>,
+    by_id: HashMap<String, Target>,
+}
+
+#[derive(Clone)]
+struct Target {
+    id: String,
+    title: String,
+}
+
+impl Targets {
+    /// The session `raw` (a reference without brackets) points at, if any
+    ///
+    /// `#` references may name a number, title or id; plain ones only
+    /// match a title exactly.
+    fn lookup(&self, raw: &str) -> Option<&Target> {
+        match raw.strip_prefix('#') {
+            Some(name) => self
+                .lookup_number(name)
+                .or_else(|| self.by_title.get(&normalize_title(name)))
+                .or_else(|| self.by_id.get(name.trim())),
+            None => self.by_title.get(&normalize_title(raw)),
+        }
+    }
+
+    /// The session numbered `number` by its marker or, failing that, by its
+    /// position
+    fn lookup_number(&self, number: &str) -> Option<&Target> {
+        let number = number.trim().trim_end_matches('.');
+        self.by_marker
+            .get(number)
+            .or_else(|| self.by_position.get(number))
+    }
+}
+
+/// Give every heading without an id one, and index the headings by name
+fn assign_ids(doc: &mut Document) -> Targets {
+    let mut assigner = IdAssigner::default();
+    for child in &mut doc.children {
+        assigner.visit(child);
+    }
+    assigner.targets
+}
+
+#[derive(Default)]
+struct IdAssigner {
+    targets: Targets,
+    used: HashSet<String>,
+    /// Numbers of the enclosing headings
+    path: Vec<usize>,
+    /// Headings seen so far at the current depth
+    count: usize,
+}
+
+impl IdAssigner {
+    fn visit(&mut self, node: &mut DocNode) {
+        let DocNode::Heading(heading) = node else {
+            for child in child_nodes(node) {
+                self.visit(child);
+            }
+            return;
+        };
+
+        self.count += 1;
+        self.path.push(self.count);
+        self.register(heading);
+
+        let siblings = std::mem::take(&mut self.count);
+        for child in &mut heading.children {
+            self.visit(child);
+        }
+        self.count = siblings;
+        self.path.pop();
+    }
+
+    fn register(&mut self, heading: &mut Heading) {
+        let position = self
+            .path
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        let (marker, title) = split_title(&heading.content);
+
+        let id = match &heading.id {
+            Some(id) => id.clone(),
+            None => {
+                let base = match slugify(&title) {
+                    slug if slug.is_empty() => format!("section-{}", position.replace('.', "-")),
+                    slug => slug,
+                };
+                let mut id = base.clone();
+                let mut n = 1;
+                while self.used.contains(&id) {
+                    n += 1;
+                    id = format!("{base}-{n}");
+                }
+                heading.id = Some(id.clone());
+                id
+            }
+        };
+        self.used.insert(id.clone());
+
+        let target = Target {
+            id: id.clone(),
+            title: title.clone(),
+        };
+        // The first session to claim a name keeps it
+        if let Some(marker) = marker {
+            self.targets
+                .by_marker
+                .entry(marker)
+                .or_insert_with(|| target.clone());
+        }
+        self.targets
+            .by_position
+            .entry(position)
+            .or_insert_with(|| target.clone());
+        if !title.is_empty() {
+            self.targets
+                .by_title
+                .entry(normalize_title(&title))
+                .or_insert_with(|| target.clone());
+        }
+        self.targets.by_id.entry(id).or_insert(target);
+    }
+}
+
+/// Nodes below a non-heading node that may contain headings
+fn child_nodes(node: &mut DocNode) -> Vec<&mut DocNode> {
+    match node {
+        DocNode::Document(doc) => doc.children.iter_mut().collect(),
+        DocNode::Annotation(annotation) => annotation.content.iter_mut().collect(),
+        DocNode::BlockQuote(quote) => quote.children.iter_mut().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Split a heading title into its number marker (without the trailing dot)
+/// and the plain text after it
+pub(super) fn split_title(content: &[InlineContent]) -> (Option<String>, String) {
+    let (marker, rest) = match content {
+        [InlineContent::Marker(marker), rest @ ..] => {
+            (Some(marker.trim().trim_end_matches('.').to_string()), rest)
+        }
+        _ => (None, content),
+    };
+    (marker, plain_text(rest).trim().to_string())
+}
+
+/// Case- and whitespace-insensitive form of a title, for lookups
+fn normalize_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+struct Linker<'a> {
+    targets: &'a Targets,
+}
+
+impl VisitorMut for Linker<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        if let InlineContent::Reference(raw) = inline {
+            if let Some(target) = self.targets.lookup(raw) {
+                *inline = InlineContent::Link(Link {
+                    url: format!("#{}", target.id),
+                    content: vec![InlineContent::Text(raw.clone())],
+                    title: (!target.title.is_empty()).then(|| target.title.clone()),
+                });
+                return;
+            }
+        }
+        walk_inline_mut(self, inline);
+    }
+}
+
+struct Renumberer<'a> {
+    targets: &'a Targets,
+    numbers: &'a HashMap<String, String>,
+}
+
+impl VisitorMut for Renumberer<'_> {
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        if let InlineContent::Reference(raw) = inline {
+            let renumbered = raw
+                .strip_prefix('#')
+                .and_then(|name| self.targets.lookup_number(name))
+                .and_then(|target| self.numbers.get(&target.id));
+            if let Some(number) = renumbered {
+                *raw = format!("#{number}");
+            }
+            return;
+        }
+        walk_inline_mut(self, inline);
+    }
+}
+
+struct UnresolvedFinder<'a> {
+    targets: &'a Targets,
+    /// Span of the innermost enclosing node that has one
+    span: Option<SourceSpan>,
+    found: Vec<UnresolvedReference>,
+}
+
+impl Visitor for UnresolvedFinder<'_> {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+        walk_node(self, node);
+        self.span = outer;
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        if let InlineContent::Reference(raw) = inline {
+            if raw.starts_with('#') && self.targets.lookup(raw).is_none() {
+                self.found.push(UnresolvedReference {
+                    target: raw.clone(),
+                    span: self.span.clone(),
+                });
+            }
+        }
+        walk_inline(self, inline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::Paragraph;
+
+    fn heading(title: &[InlineContent], children: Vec<DocNode>) -> DocNode {
+        DocNode::Heading(Heading {
+            level: 2,
+            content: title.to_vec(),
+            children,
+            id: None,
+            span: None,
+        })
+    }
+
+    fn text(value: &str) -> InlineContent {
+        InlineContent::Text(value.to_string())
+    }
+
+    fn paragraph(references: &[&str]) -> DocNode {
+        DocNode::Paragraph(Paragraph {
+            content: references
+                .iter()
+                .map(|raw| InlineContent::Reference(raw.to_string()))
+                .collect(),
+            span: None,
+        })
+    }
+
+    fn sample() -> Document {
+        Document {
+            children: vec![
+                heading(
+                    &[text("Getting Started")],
+                    vec![
+                        heading(&[text("Scope")], vec![]),
+                        heading(&[text("Scope")], vec![]),
+                    ],
+                ),
+                heading(
+                    &[
+                        InlineContent::Marker("7.".to_string()),
+                        text(" "),
+                        text("Usage"),
+                    ],
+                    vec![paragraph(&[
+                        "#1.2",
+                        "#7",
+                        "#getting started",
+                        "Usage",
+                        "#9.9",
+                        "https://example.com",
+                    ])],
+                ),
+                heading(&[text("!!")], vec![]),
+            ],
+            metadata: Default::default(),
+        }
+    }
+
+    fn ids(nodes: &[DocNode], out: &mut Vec<String>) {
+        for node in nodes {
+            if let DocNode::Heading(heading) = node {
+                out.push(heading.id.clone().unwrap_or_default());
+                ids(&heading.children, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ids_are_unique_slugs() {
+        let mut doc = sample();
+        resolve_references(&mut doc);
+        let mut out = Vec::new();
+        ids(&doc.children, &mut out);
+        assert_eq!(
+            out,
+            vec!["getting-started", "scope", "scope-2", "usage", "section-3"]
+        );
+    }
+
+    #[test]
+    fn test_references_become_links() {
+        let mut doc = sample();
+        resolve_references(&mut doc);
+        let DocNode::Heading(usage) = &doc.children[1] else {
+            panic!("expected a heading");
+        };
+        let DocNode::Paragraph(paragraph) = &usage.children[0] else {
+            panic!("expected a paragraph");
+        };
+
+        let urls: Vec<Option<&str>> = paragraph
+            .content
+            .iter()
+            .map(|inline| match inline {
+                InlineContent::Link(link) => Some(link.url.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                Some("#scope-2"),
+                Some("#usage"),
+                Some("#getting-started"),
+                Some("#usage"),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_references() {
+        let unresolved = unresolved_references(&sample());
+        let targets: Vec<&str> = unresolved.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(targets, vec!["#9.9"]);
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("  Über  die Lage (2024) "), "über-die-lage-2024");
+        assert_eq!(slugify("C++ & Rust"), "c-rust");
+        assert_eq!(slugify("--"), "");
+    }
+}
@@ -2,51 +2,181 @@
 //!
 //! This module provides functions to convert from the Intermediate Representation
 //! back to Lex AST structures.
+//!
+//! Nodes Lex has no syntax for are written as annotations, which `from_lex`
+//! reads back:
+//!
+//! - block quote: a `:: blockquote ::` annotation holding the quoted content
+//! - thematic break: `:: hr ::`
+//! - strikethrough: the text undecorated, with a `:: strikethrough text=".." ::`
+//!   line per struck span before the paragraph
+//! - list of one item: its `- item` line, after a `:: list ::` line
+//! - task item: a `[x]` or `[ ]` box at the start of the item's text
 
 use lex_core::lex::ast::elements::{
-    typed_content, verbatim::VerbatimBlockMode, Annotation as LexAnnotation, ContentElement,
-    ContentItem as LexContentItem, Definition as LexDefinition, Label, List as LexList,
-    ListItem as LexListItem, Paragraph as LexParagraph, Session as LexSession,
-    Verbatim as LexVerbatim, VerbatimContent, VerbatimLine as LexVerbatimLine,
+    typed_content, verbatim::VerbatimBlockMode, Annotation as LexAnnotation, BlankLineGroup,
+    ContentElement, ContentItem as LexContentItem, Definition as LexDefinition, Label,
+    List as LexList, ListItem as LexListItem, Paragraph as LexParagraph, SequenceMarker,
+    Session as LexSession, Verbatim as LexVerbatim, VerbatimContent,
+    VerbatimLine as LexVerbatimLine,
 };
 use lex_core::lex::ast::range::Position;
 use lex_core::lex::ast::{Data, Document as LexDocument, Parameter, Range, TextContent};
 
+use super::context::ConversionContext;
 use super::nodes::{
-    Annotation, Definition, DocNode, Document, Heading, InlineContent, List, ListItem, Paragraph,
-    Table, TableCell, TableRow, Verbatim,
+    Annotation, Definition, DocNode, Document, Heading, InlineContent, List, ListItem, Metadata,
+    MetadataValue, Paragraph, Table, TableCell, TableRow, Verbatim,
 };
+use crate::common::text::quote_param;
 
 /// Converts an IR document to a Lex document.
 pub fn to_lex_document(doc: &Document) -> LexDocument {
+    to_lex_document_with_context(doc, &ConversionContext::standard())
+}
+
+/// Converts an IR document to a Lex document, writing tables and media back
+/// through the context's verbatim handlers.
+pub fn to_lex_document_with_context(doc: &Document, ctx: &ConversionContext) -> LexDocument {
     let mut children = Vec::new();
 
-    for node in &doc.children {
-        children.extend(to_lex_content_items(node, 1));
+    for annotation in metadata_annotations(&doc.metadata) {
+        children.push(to_lex_annotation(&annotation, 1, ctx));
+    }
+    // A blank line keeps the metadata on the document
+    if !children.is_empty() {
+        children.push(blank_line());
     }
 
+    children.extend(to_lex_blocks(&doc.children, 1, ctx));
+
     LexDocument::with_content(children)
 }
 
+/// Converts sibling nodes, with a blank line between them
+///
+/// Lex needs one before a list, and without one a paragraph runs on into
+/// the next. An annotation gets none after it, so that it attaches to the
+/// element that follows rather than the one before, and sessions are set
+/// apart by the serializer.
+fn to_lex_blocks(nodes: &[DocNode], level: usize, ctx: &ConversionContext) -> Vec<LexContentItem> {
+    let mut items: Vec<LexContentItem> = Vec::new();
+    for node in nodes {
+        let converted = to_lex_content_items(node, level, ctx);
+        let Some(first) = converted.first() else {
+            continue;
+        };
+        if !matches!(first, LexContentItem::Session(_))
+            && items
+                .last()
+                .is_some_and(|last| !matches!(last, LexContentItem::Annotation(_)))
+        {
+            items.push(blank_line());
+        }
+        items.extend(converted);
+    }
+    items
+}
+
+fn blank_line() -> LexContentItem {
+    LexContentItem::BlankLineGroup(BlankLineGroup::new(1, vec![]))
+}
+
+/// Annotations that carry the document metadata.
+///
+/// Entries read from Lex are written back as their source annotation. Entries
+/// without one (e.g. imported from YAML front matter) are collected into a
+/// single leading `frontmatter` annotation with one parameter per key; lists
+/// are written as `[a, b]` and fields as `key.field` parameters.
+fn metadata_annotations(metadata: &Metadata) -> Vec<Annotation> {
+    let mut annotations: Vec<Annotation> = Vec::new();
+    let mut parameters = Vec::new();
+
+    for entry in &metadata.entries {
+        match (&entry.source, &entry.value) {
+            (Some(source), _) => {
+                if annotations.last() != Some(source) {
+                    annotations.push(source.clone());
+                }
+            }
+            (None, MetadataValue::Fields(fields)) => {
+                for (field, value) in fields {
+                    parameters.push((format!("{}.{field}", entry.key), value.clone()));
+                }
+            }
+            (None, MetadataValue::List(items)) => {
+                parameters.push((entry.key.clone(), format!("[{}]", items.join(", "))));
+            }
+            (None, MetadataValue::Text(text)) => {
+                parameters.push((entry.key.clone(), text.clone()));
+            }
+        }
+    }
+
+    if !parameters.is_empty() {
+        annotations.insert(
+            0,
+            Annotation {
+                label: "frontmatter".to_string(),
+                parameters,
+                content: vec![],
+                span: None,
+            },
+        );
+    }
+    annotations
+}
+
 /// Converts an IR DocNode to one or more Lex ContentItems.
 ///
 /// Some IR nodes may expand to multiple ContentItems (e.g., a Heading with children
 /// becomes a Session with nested content).
-fn to_lex_content_items(node: &DocNode, level: usize) -> Vec<LexContentItem> {
+fn to_lex_content_items(
+    node: &DocNode,
+    level: usize,
+    ctx: &ConversionContext,
+) -> Vec<LexContentItem> {
     match node {
         DocNode::Document(_) => {
             // Document should only appear at root, not recursively
             vec![]
         }
-        DocNode::Heading(heading) => vec![to_lex_session(heading, level)],
-        DocNode::Paragraph(para) => vec![to_lex_paragraph(para)],
-        DocNode::List(list) => vec![to_lex_list(list)],
-        DocNode::ListItem(item) => vec![to_lex_list_item(item)],
-        DocNode::Definition(def) => vec![to_lex_definition(def)],
+        DocNode::Heading(heading) => vec![to_lex_session(heading, level, ctx)],
+        DocNode::Paragraph(para) => {
+            let mut items = strikethrough_annotations(&para.content);
+            items.push(to_lex_paragraph(para));
+            items
+        }
+        DocNode::List(list) => match list.items.as_slice() {
+            [item] if item_text(item).1.is_empty() => one_item_list(item, ctx),
+            _ => vec![to_lex_list(list, ctx)],
+        },
+        DocNode::ListItem(item) => vec![to_lex_list_item(item, ctx)],
+        DocNode::Definition(def) => vec![to_lex_definition(def, ctx)],
         DocNode::Verbatim(verb) => vec![to_lex_verbatim(verb)],
-        DocNode::Annotation(ann) => vec![to_lex_annotation(ann, level)],
-        DocNode::Table(table) => vec![to_lex_table(table, level)],
-        DocNode::Image(_) | DocNode::Video(_) | DocNode::Audio(_) => vec![to_lex_media(node)],
+        DocNode::Annotation(ann) => vec![to_lex_annotation(ann, level, ctx)],
+        DocNode::Table(table) => vec![to_lex_table(table, level, ctx)],
+        DocNode::Image(_) | DocNode::Video(_) | DocNode::Audio(_) => vec![to_lex_media(node, ctx)],
+        DocNode::BlockQuote(quote) => vec![to_lex_annotation(
+            &Annotation {
+                label: "blockquote".to_string(),
+                parameters: vec![],
+                content: quote.children.clone(),
+                span: None,
+            },
+            level,
+            ctx,
+        )],
+        DocNode::ThematicBreak => vec![to_lex_annotation(
+            &Annotation {
+                label: "hr".to_string(),
+                parameters: vec![],
+                content: vec![],
+                span: None,
+            },
+            level,
+            ctx,
+        )],
         DocNode::Inline(_) => {
             // Inline content should not appear at block level
             vec![]
@@ -54,14 +184,11 @@ fn to_lex_content_items(node: &DocNode, level: usize) -> Vec<LexContentItem> {
     }
 }
 
-fn to_lex_session(heading: &Heading, level: usize) -> LexContentItem {
+fn to_lex_session(heading: &Heading, level: usize, ctx: &ConversionContext) -> LexContentItem {
     let title_text = inline_content_to_text(&heading.content);
     let title = TextContent::from_string(title_text, None);
 
-    let mut children = Vec::new();
-    for child in &heading.children {
-        children.extend(to_lex_content_items(child, level + 1));
-    }
+    let children = to_lex_blocks(&heading.children, level + 1, ctx);
 
     // Convert ContentItem to SessionContent
     let session_children = typed_content::into_session_contents(children);
@@ -70,11 +197,10 @@ fn to_lex_session(heading: &Heading, level: usize) -> LexContentItem {
 }
 
 /// Converts an IR Table to a Lex Annotation (nested).
-fn to_lex_table(table: &Table, level: usize) -> LexContentItem {
-    let registry = crate::common::verbatim::VerbatimRegistry::default_with_standard();
+fn to_lex_table(table: &Table, level: usize, ctx: &ConversionContext) -> LexContentItem {
     let node = DocNode::Table(table.clone());
 
-    if let Some(handler) = registry.get("doc.table") {
+    if let Some(handler) = ctx.verbatim.get("doc.table") {
         if let Some((content, params)) = handler.convert_from_ir(&node) {
             let label = Label::new("doc.table".to_string());
             let parameters = params
@@ -105,7 +231,16 @@ fn to_lex_table(table: &Table, level: usize) -> LexContentItem {
 
     // Fallback to annotation if registry fails (though TableHandler should handle it)
     let label = Label::new("table".to_string());
-    let parameters = Vec::new(); // Could add caption here if needed
+    let parameters = table
+        .caption
+        .as_ref()
+        .map(|caption| Parameter {
+            key: "caption".to_string(),
+            value: quote_param(&inline_content_to_text(caption)),
+            location: default_range(),
+        })
+        .into_iter()
+        .collect();
 
     let mut children = Vec::new();
 
@@ -114,7 +249,7 @@ fn to_lex_table(table: &Table, level: usize) -> LexContentItem {
         let thead_label = Label::new("thead".to_string());
         let mut thead_rows = Vec::new();
         for row in &table.header {
-            thead_rows.push(to_lex_table_row(row, level + 1));
+            thead_rows.push(to_lex_table_row(row, level + 1, ctx));
         }
         let thead = LexContentItem::Annotation(LexAnnotation::new(
             thead_label,
@@ -128,7 +263,7 @@ fn to_lex_table(table: &Table, level: usize) -> LexContentItem {
     let tbody_label = Label::new("tbody".to_string());
     let mut tbody_rows = Vec::new();
     for row in &table.rows {
-        tbody_rows.push(to_lex_table_row(row, level + 1));
+        tbody_rows.push(to_lex_table_row(row, level + 1, ctx));
     }
     let tbody = LexContentItem::Annotation(LexAnnotation::new(
         tbody_label,
@@ -144,11 +279,11 @@ fn to_lex_table(table: &Table, level: usize) -> LexContentItem {
     ))
 }
 
-fn to_lex_table_row(row: &TableRow, level: usize) -> LexContentItem {
+fn to_lex_table_row(row: &TableRow, level: usize, ctx: &ConversionContext) -> LexContentItem {
     let label = Label::new("tr".to_string());
     let mut cells = Vec::new();
     for cell in &row.cells {
-        cells.push(to_lex_table_cell(cell, level + 1));
+        cells.push(to_lex_table_cell(cell, level + 1, ctx));
     }
     LexContentItem::Annotation(LexAnnotation::new(
         label,
@@ -157,7 +292,7 @@ fn to_lex_table_row(row: &TableRow, level: usize) -> LexContentItem {
     ))
 }
 
-fn to_lex_table_cell(cell: &TableCell, level: usize) -> LexContentItem {
+fn to_lex_table_cell(cell: &TableCell, level: usize, ctx: &ConversionContext) -> LexContentItem {
     let label_str = if cell.header { "th" } else { "td" };
     let label = Label::new(label_str.to_string());
 
@@ -176,10 +311,19 @@ fn to_lex_table_cell(cell: &TableCell, level: usize) -> LexContentItem {
             location: default_range(),
         });
     }
+    for (key, span) in [("colspan", cell.colspan), ("rowspan", cell.rowspan)] {
+        if span > 1 {
+            parameters.push(Parameter {
+                key: key.to_string(),
+                value: span.to_string(),
+                location: default_range(),
+            });
+        }
+    }
 
     let mut content = Vec::new();
     for child in &cell.content {
-        content.extend(to_lex_content_items(child, level + 1));
+        content.extend(to_lex_content_items(child, level + 1, ctx));
     }
 
     LexContentItem::Annotation(LexAnnotation::new(
@@ -195,19 +339,90 @@ fn to_lex_paragraph(para: &Paragraph) -> LexContentItem {
     LexContentItem::Paragraph(LexParagraph::from_line(text))
 }
 
+/// `strikethrough` annotations for the struck spans of a paragraph
+///
+/// Lex has no strikethrough, so the paragraph keeps the text undecorated and
+/// each span is named in a `:: strikethrough text=".." ::` line before it.
+/// Only spans of plain text are named, and only in paragraphs; elsewhere the
+/// decoration is dropped.
+fn strikethrough_annotations(content: &[InlineContent]) -> Vec<LexContentItem> {
+    content
+        .iter()
+        .filter_map(|inline| match inline {
+            InlineContent::Strikethrough(children)
+                if children
+                    .iter()
+                    .all(|child| matches!(child, InlineContent::Text(_))) =>
+            {
+                Some(inline_content_to_text(children))
+            }
+            _ => None,
+        })
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| {
+            LexContentItem::Annotation(LexAnnotation::new(
+                Label::new("strikethrough".to_string()),
+                vec![Parameter {
+                    key: "text".to_string(),
+                    value: quote_param(&text),
+                    location: default_range(),
+                }],
+                vec![],
+            ))
+        })
+        .collect()
+}
+
+/// A list of one item, as its line marked by a `:: list ::` annotation
+///
+/// Lex reads a list from two items up, so a single `- item` line would come
+/// back as a paragraph.
+fn one_item_list(item: &ListItem, ctx: &ConversionContext) -> Vec<LexContentItem> {
+    let lex_item = to_lex_list_item_struct(item, ctx);
+    let text = lex_item
+        .text
+        .iter()
+        .map(|text| text.as_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let line = Paragraph {
+        content: vec![InlineContent::Text(format!("{} {text}", lex_item.marker()))],
+        span: None,
+    };
+
+    let mut items = vec![LexContentItem::Annotation(LexAnnotation::new(
+        Label::new("list".to_string()),
+        vec![],
+        vec![],
+    ))];
+    items.extend(strikethrough_annotations(item_text(item).0));
+    items.push(to_lex_paragraph(&line));
+    items
+}
+
 /// Converts an IR List to a Lex List.
-fn to_lex_list(list: &List) -> LexContentItem {
-    let items = list.items.iter().map(to_lex_list_item_struct).collect();
-    LexContentItem::List(LexList::new(items))
+fn to_lex_list(list: &List, ctx: &ConversionContext) -> LexContentItem {
+    let items = list
+        .items
+        .iter()
+        .map(|item| to_lex_list_item_struct(item, ctx))
+        .collect::<Vec<_>>();
+    // The first marker sets the list's style, as when parsing
+    let marker = items
+        .first()
+        .and_then(|item| SequenceMarker::parse(item.marker(), None));
+    let mut lex_list = LexList::new(items);
+    lex_list.marker = marker;
+    LexContentItem::List(lex_list)
 }
 
 /// Converts an IR ListItem to a ContentItem::ListItem.
-fn to_lex_list_item(item: &ListItem) -> LexContentItem {
-    LexContentItem::ListItem(to_lex_list_item_struct(item))
+fn to_lex_list_item(item: &ListItem, ctx: &ConversionContext) -> LexContentItem {
+    LexContentItem::ListItem(to_lex_list_item_struct(item, ctx))
 }
 
 /// Converts an IR ListItem to a Lex ListItem struct.
-fn to_lex_list_item_struct(item: &ListItem) -> LexListItem {
+fn to_lex_list_item_struct(item: &ListItem, ctx: &ConversionContext) -> LexListItem {
     // Extract marker from inline content if present
     let marker = item
         .content
@@ -222,28 +437,39 @@ fn to_lex_list_item_struct(item: &ListItem) -> LexListItem {
         .unwrap_or_else(|| "-".to_string());
 
     // Build text from non-marker inline content
-    let text = inline_content_to_text_skip_marker(&item.content);
-
-    let mut child_items = Vec::new();
-    for child in &item.children {
-        child_items.extend(to_lex_content_items(child, 1));
+    let (content, children) = item_text(item);
+    let mut text = inline_content_to_text_skip_marker(content);
+    match item.checked {
+        Some(true) => text.insert_str(0, "[x] "),
+        Some(false) => text.insert_str(0, "[ ] "),
+        None => {}
     }
 
-    let children = to_content_elements(child_items);
+    let children = to_content_elements(to_lex_blocks(children, 1, ctx));
     LexListItem::with_content(marker, text, children)
 }
 
+/// A list item's text and the children after it
+///
+/// Items imported from Markdown hold their text in a leading paragraph,
+/// which becomes the item's line.
+fn item_text(item: &ListItem) -> (&[InlineContent], &[DocNode]) {
+    match item.children.as_slice() {
+        [DocNode::Paragraph(para), rest @ ..]
+            if inline_content_to_text_skip_marker(&item.content).is_empty() =>
+        {
+            (&para.content, rest)
+        }
+        children => (&item.content, children),
+    }
+}
+
 /// Converts an IR Definition to a Lex Definition.
-fn to_lex_definition(def: &Definition) -> LexContentItem {
+fn to_lex_definition(def: &Definition, ctx: &ConversionContext) -> LexContentItem {
     let term_text = inline_content_to_text(&def.term);
     let term = TextContent::from_string(term_text, None);
 
-    let mut child_items = Vec::new();
-    for child in &def.description {
-        child_items.extend(to_lex_content_items(child, 1));
-    }
-
-    let children = to_content_elements(child_items);
+    let children = to_content_elements(to_lex_blocks(&def.description, 1, ctx));
     LexContentItem::Definition(LexDefinition::new(term, children))
 }
 
@@ -273,7 +499,7 @@ fn to_lex_verbatim(verb: &Verbatim) -> LexContentItem {
 }
 
 /// Converts an IR Annotation to a Lex Annotation.
-fn to_lex_annotation(ann: &Annotation, level: usize) -> LexContentItem {
+fn to_lex_annotation(ann: &Annotation, level: usize, ctx: &ConversionContext) -> LexContentItem {
     let label = Label::new(ann.label.clone());
     let parameters: Vec<Parameter> = ann
         .parameters
@@ -285,12 +511,7 @@ fn to_lex_annotation(ann: &Annotation, level: usize) -> LexContentItem {
         })
         .collect();
 
-    let mut child_items = Vec::new();
-    for child in &ann.content {
-        child_items.extend(to_lex_content_items(child, level));
-    }
-
-    let children = to_content_elements(child_items);
+    let children = to_content_elements(to_lex_blocks(&ann.content, level, ctx));
     LexContentItem::Annotation(LexAnnotation::new(label, parameters, children))
 }
 
@@ -337,6 +558,21 @@ fn inline_to_text(inline: &InlineContent) -> String {
             }
             text
         }
+        // Lex has no strikethrough; the text is kept undecorated
+        InlineContent::Strikethrough(children) => inline_content_to_text(children),
+        // Lex links are the text followed by the URL reference
+        InlineContent::Link(link) => {
+            let text = inline_content_to_text(&link.content);
+            if text.is_empty() || text == link.url {
+                format!("[{}]", link.url)
+            } else {
+                format!("{text} [{}]", link.url)
+            }
+        }
+        InlineContent::FootnoteRef(label) if label.chars().all(|c| c.is_ascii_digit()) => {
+            format!("[{label}]")
+        }
+        InlineContent::FootnoteRef(label) => format!("[^{label}]"),
     }
 }
 
@@ -344,29 +580,18 @@ fn inline_to_text(inline: &InlineContent) -> String {
 ///
 /// This is a lossy conversion that flattens all inline formatting.
 fn inline_content_to_text(content: &[InlineContent]) -> String {
-    content
-        .iter()
-        .map(|inline| match inline {
-            InlineContent::Text(text) => text.clone(),
-            InlineContent::Bold(children) => {
-                format!("*{}*", inline_content_to_text(children))
-            }
-            InlineContent::Italic(children) => {
-                format!("_{}_", inline_content_to_text(children))
-            }
-            InlineContent::Code(code) => format!("`{code}`"),
-            InlineContent::Math(math) => format!("#{math}#"),
-            InlineContent::Reference(ref_text) => format!("[{ref_text}]"),
-            InlineContent::Marker(marker) => marker.clone(),
-            InlineContent::Image(image) => {
-                let mut text = format!("![{}]({})", image.alt, image.src);
-                if let Some(title) = &image.title {
-                    text.push_str(&format!(" \"{title}\""));
-                }
-                text
-            }
-        })
-        .collect()
+    let mut text = String::new();
+    for inline in content {
+        // Lex reads `[1]` as a footnote only after a space, where Markdown
+        // puts `[^1]` right after the word
+        if matches!(inline, InlineContent::FootnoteRef(_))
+            && text.ends_with(|c: char| !c.is_whitespace())
+        {
+            text.push(' ');
+        }
+        text.push_str(&inline_to_text(inline));
+    }
+    text
 }
 
 /// Converts ContentItem to ContentElement, filtering out Sessions and ListItems
@@ -382,9 +607,7 @@ fn default_range() -> Range {
     Range::new(0..0, Position::new(0, 0), Position::new(0, 0))
 }
 
-fn to_lex_media(node: &DocNode) -> LexContentItem {
-    let registry = crate::common::verbatim::VerbatimRegistry::default_with_standard();
-
+fn to_lex_media(node: &DocNode, ctx: &ConversionContext) -> LexContentItem {
     let label = match node {
         DocNode::Image(_) => "doc.image",
         DocNode::Video(_) => "doc.video",
@@ -392,7 +615,7 @@ fn to_lex_media(node: &DocNode) -> LexContentItem {
         _ => return LexContentItem::Paragraph(LexParagraph::new(vec![])),
     };
 
-    if let Some(handler) = registry.get(label) {
+    if let Some(handler) = ctx.verbatim.get(label) {
         if let Some((content, params)) = handler.convert_from_ir(node) {
             let label = Label::new(label.to_string());
             let parameters = params
@@ -433,6 +656,7 @@ mod tests {
     fn test_paragraph_to_lex() {
         let ir_para = Paragraph {
             content: vec![InlineContent::Text("Hello world".to_string())],
+            span: None,
         };
 
         let lex_item = to_lex_paragraph(&ir_para);
@@ -451,9 +675,11 @@ mod tests {
             level: 1,
             content: vec![InlineContent::Text("Test".to_string())],
             children: vec![],
+            id: None,
+            span: None,
         };
 
-        let lex_item = to_lex_session(&ir_heading, 1);
+        let lex_item = to_lex_session(&ir_heading, 1, &ConversionContext::standard());
 
         match lex_item {
             LexContentItem::Session(session) => {
@@ -470,18 +696,23 @@ mod tests {
                 ListItem {
                     content: vec![InlineContent::Text("Item 1".to_string())],
                     children: vec![],
+                    checked: None,
+                    span: None,
                 },
                 ListItem {
                     content: vec![InlineContent::Text("Item 2".to_string())],
                     children: vec![],
+                    checked: None,
+                    span: None,
                 },
             ],
             ordered: false,
             style: ListStyle::Bullet,
             form: ListForm::Short,
+            span: None,
         };
 
-        let lex_item = to_lex_list(&ir_list);
+        let lex_item = to_lex_list(&ir_list, &ConversionContext::standard());
 
         match lex_item {
             LexContentItem::List(list) => {
@@ -498,6 +729,7 @@ mod tests {
             subject: None,
             language: Some("rust".to_string()),
             content: "fn main() {}\nlet x = 1;".to_string(),
+            span: None,
         };
 
         let lex_item = to_lex_verbatim(&ir_verb);
@@ -558,11 +790,14 @@ mod tests {
             children: vec![
                 DocNode::Paragraph(Paragraph {
                     content: vec![InlineContent::Text("First paragraph".to_string())],
+                    span: None,
                 }),
                 DocNode::Paragraph(Paragraph {
                     content: vec![InlineContent::Text("Second paragraph".to_string())],
+                    span: None,
                 }),
             ],
+            ..Default::default()
         };
 
         let lex_doc = to_lex_document(&ir_doc);
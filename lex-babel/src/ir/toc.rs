@@ -0,0 +1,404 @@
+//! Tables of contents generated from the session tree.
+//!
+//! A `:: toc ::` annotation marks where a table of contents goes, and
+//! [`fill_toc`] replaces its content with a nested list linking to every
+//! session. Serializers can also insert one at the top of a document that has
+//! no placeholder (the `toc` format option). Parameters on the placeholder
+//! win over the format options:
+//!
+//! ```text
+//! :: toc depth=2 numbered=true ::
+//! ```
+//!
+//! - `depth=N`: list sessions down to nesting level `N` (default 3)
+//! - `numbered=true`: prefix each title with its session number (`2.1`)
+//!
+//! Numbers come from the marker the author wrote (`2.1. Scope`) and otherwise
+//! from the position in the session tree, matching `[#2.1]` references. The
+//! filled placeholder stays an annotation labelled `toc`, so each format
+//! decides how to frame the list (`<nav class="lex-toc">` in HTML).
+
+use super::nodes::{
+    Annotation, DocNode, Document, InlineContent, Link, List, ListForm, ListItem, ListStyle,
+};
+use super::references::{assign_heading_ids, split_title};
+use crate::error::FormatError;
+use crate::options::{bool_option, invalid_value, parse_bool, FormatOption, OptionType};
+use std::collections::HashMap;
+
+/// Label of the placeholder annotation
+pub const TOC_LABEL: &str = "toc";
+
+/// The format options that control the table of contents, for
+/// [`crate::Format::options`] lists
+pub const TOC_OPTIONS: [FormatOption; 3] = [
+    FormatOption::new(
+        "toc",
+        OptionType::Bool,
+        "Insert a table of contents at the top when there is no :: toc :: placeholder",
+    )
+    .with_default("false"),
+    FormatOption::new(
+        "toc-depth",
+        OptionType::Integer,
+        "Deepest session level listed in the table of contents",
+    )
+    .with_default("3"),
+    FormatOption::new(
+        "toc-numbered",
+        OptionType::Bool,
+        "Prefix table of contents entries with their session numbers",
+    )
+    .with_default("false"),
+];
+
+/// How a table of contents is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TocOptions {
+    /// Deepest session nesting level listed, 1 for top-level sessions only
+    pub depth: usize,
+    /// Prefix entries with their session numbers
+    pub numbered: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            depth: 3,
+            numbered: false,
+        }
+    }
+}
+
+impl TocOptions {
+    /// Read the [`TOC_OPTIONS`] out of a format's options
+    ///
+    /// Returns `None` unless `toc` is on. `toc-depth` and `toc-numbered` also
+    /// apply to placeholders, so they are checked either way.
+    pub fn from_format_options(
+        format: &str,
+        options: &HashMap<String, String>,
+    ) -> Result<Option<TocOptions>, FormatError> {
+        let mut toc = TocOptions::default();
+        if let Some(value) = options.get("toc-depth") {
+            toc.depth = parse_depth(value)
+                .ok_or_else(|| invalid_value(format, "toc-depth", value, "a positive number"))?;
+        }
+        if let Some(numbered) = bool_option(format, options, "toc-numbered")? {
+            toc.numbered = numbered;
+        }
+
+        let enabled = bool_option(format, options, "toc")?.unwrap_or(false);
+        Ok(enabled.then_some(toc))
+    }
+
+    /// These options with a placeholder's `depth=` and `numbered=` applied
+    ///
+    /// Values that do not parse are ignored.
+    fn with_parameters(mut self, parameters: &[(String, String)]) -> Self {
+        for (key, value) in parameters {
+            match key.as_str() {
+                "depth" => {
+                    if let Some(depth) = parse_depth(value) {
+                        self.depth = depth;
+                    }
+                }
+                "numbered" => {
+                    if let Some(numbered) = parse_bool(value) {
+                        self.numbered = numbered;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self
+    }
+}
+
+fn parse_depth(value: &str) -> Option<usize> {
+    value.trim().parse().ok().filter(|depth| *depth > 0)
+}
+
+/// Fill every `toc` placeholder in `doc`, giving headings ids to link to
+///
+/// Placeholders start from `options`, or the defaults when it is `None`.
+/// With `options` given and no placeholder in the document, a table of
+/// contents is inserted before the first node.
+pub fn fill_toc(doc: &mut Document, options: Option<TocOptions>) {
+    assign_heading_ids(doc);
+    let mut outline = Vec::new();
+    collect_outline(&doc.children, &mut Vec::new(), &mut outline);
+
+    let base = options.unwrap_or_default();
+    let found = fill_placeholders(&mut doc.children, &outline, base);
+    if !found && options.is_some() && !outline.is_empty() {
+        doc.children.insert(
+            0,
+            DocNode::Annotation(Annotation {
+                label: TOC_LABEL.to_string(),
+                parameters: Vec::new(),
+                content: toc_list(&outline, base, 1).into_iter().collect(),
+                span: None,
+            }),
+        );
+    }
+}
+
+/// One session in the outline
+struct Entry {
+    number: String,
+    title: String,
+    id: String,
+    children: Vec<Entry>,
+}
+
+fn collect_outline(nodes: &[DocNode], path: &mut Vec<usize>, out: &mut Vec<Entry>) {
+    for node in nodes {
+        match node {
+            DocNode::Heading(heading) => {
+                path.push(out.len() + 1);
+                let (marker, title) = split_title(&heading.content);
+                let mut entry = Entry {
+                    number: marker.unwrap_or_else(|| {
+                        path.iter()
+                            .map(usize::to_string)
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    }),
+                    title,
+                    id: heading.id.clone().unwrap_or_default(),
+                    children: Vec::new(),
+                };
+                collect_outline(&heading.children, path, &mut entry.children);
+                path.pop();
+                out.push(entry);
+            }
+            DocNode::BlockQuote(quote) => collect_outline(&quote.children, path, out),
+            _ => {}
+        }
+    }
+}
+
+/// Replace the content of each placeholder under `nodes`; true if any was found
+fn fill_placeholders(nodes: &mut [DocNode], outline: &[Entry], base: TocOptions) -> bool {
+    let mut found = false;
+    for node in nodes {
+        match node {
+            DocNode::Annotation(annotation) if annotation.label == TOC_LABEL => {
+                let options = base.with_parameters(&annotation.parameters);
+                annotation.content = toc_list(outline, options, 1).into_iter().collect();
+                found = true;
+            }
+            DocNode::Annotation(annotation) => {
+                found |= fill_placeholders(&mut annotation.content, outline, base);
+            }
+            DocNode::Heading(heading) => {
+                found |= fill_placeholders(&mut heading.children, outline, base);
+            }
+            DocNode::BlockQuote(quote) => {
+                found |= fill_placeholders(&mut quote.children, outline, base);
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// The nested list for `entries` at nesting `level`, if within the depth
+fn toc_list(entries: &[Entry], options: TocOptions, level: usize) -> Option<DocNode> {
+    if entries.is_empty() || level > options.depth {
+        return None;
+    }
+
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let text = if entry.title.is_empty() {
+                entry.number.clone()
+            } else if options.numbered {
+                format!("{} {}", entry.number, entry.title)
+            } else {
+                entry.title.clone()
+            };
+            ListItem {
+                content: vec![InlineContent::Link(Link {
+                    url: format!("#{}", entry.id),
+                    content: vec![InlineContent::Text(text)],
+                    title: None,
+                })],
+                children: toc_list(&entry.children, options, level + 1)
+                    .into_iter()
+                    .collect(),
+                checked: None,
+                span: None,
+            }
+        })
+        .collect();
+
+    Some(DocNode::List(List {
+        items,
+        ordered: false,
+        style: ListStyle::Bullet,
+        form: ListForm::Short,
+        span: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::{Heading, Paragraph};
+
+    fn heading(title: &[InlineContent], children: Vec<DocNode>) -> DocNode {
+        DocNode::Heading(Heading {
+            level: 2,
+            content: title.to_vec(),
+            children,
+            id: None,
+            span: None,
+        })
+    }
+
+    fn text(value: &str) -> InlineContent {
+        InlineContent::Text(value.to_string())
+    }
+
+    fn placeholder(parameters: &[(&str, &str)]) -> DocNode {
+        DocNode::Annotation(Annotation {
+            label: TOC_LABEL.to_string(),
+            parameters: parameters
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            content: Vec::new(),
+            span: None,
+        })
+    }
+
+    fn sample(first: Option<DocNode>) -> Document {
+        let mut children: Vec<DocNode> = first.into_iter().collect();
+        children.push(heading(
+            &[text("Introduction")],
+            vec![heading(
+                &[text("Scope")],
+                vec![heading(&[text("Limits")], vec![])],
+            )],
+        ));
+        children.push(heading(
+            &[
+                InlineContent::Marker("7.".to_string()),
+                text(" "),
+                text("Usage"),
+            ],
+            vec![DocNode::Paragraph(Paragraph {
+                content: vec![text("Run it.")],
+                span: None,
+            })],
+        ));
+        Document {
+            children,
+            metadata: Default::default(),
+        }
+    }
+
+    /// The entries of a table of contents as `(text, url, depth)`
+    fn entries(node: &DocNode) -> Vec<(String, String, usize)> {
+        fn walk(node: &DocNode, depth: usize, out: &mut Vec<(String, String, usize)>) {
+            let DocNode::List(list) = node else {
+                panic!("expected a list");
+            };
+            for item in &list.items {
+                let [InlineContent::Link(link)] = item.content.as_slice() else {
+                    panic!("expected a single link");
+                };
+                let [InlineContent::Text(text)] = link.content.as_slice() else {
+                    panic!("expected link text");
+                };
+                out.push((text.clone(), link.url.clone(), depth));
+                for child in &item.children {
+                    walk(child, depth + 1, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(node, 1, &mut out);
+        out
+    }
+
+    fn toc_of(doc: &Document) -> &Annotation {
+        match &doc.children[0] {
+            DocNode::Annotation(annotation) if annotation.label == TOC_LABEL => annotation,
+            other => panic!("expected the toc first, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_placeholder_is_filled_with_nested_links() {
+        let mut doc = sample(Some(placeholder(&[])));
+        fill_toc(&mut doc, None);
+
+        let toc = toc_of(&doc);
+        assert_eq!(
+            entries(&toc.content[0]),
+            vec![
+                ("Introduction".to_string(), "#introduction".to_string(), 1),
+                ("Scope".to_string(), "#scope".to_string(), 2),
+                ("Limits".to_string(), "#limits".to_string(), 3),
+                ("Usage".to_string(), "#usage".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_placeholder_parameters_limit_depth_and_number_entries() {
+        let mut doc = sample(Some(placeholder(&[("depth", "2"), ("numbered", "true")])));
+        fill_toc(&mut doc, None);
+
+        let texts: Vec<String> = entries(&toc_of(&doc).content[0])
+            .into_iter()
+            .map(|(text, _, _)| text)
+            .collect();
+        assert_eq!(texts, vec!["1 Introduction", "1.1 Scope", "7 Usage"]);
+    }
+
+    #[test]
+    fn test_option_inserts_toc_without_placeholder() {
+        let mut doc = sample(None);
+        fill_toc(&mut doc, None);
+        assert!(matches!(doc.children[0], DocNode::Heading(_)));
+
+        fill_toc(
+            &mut doc,
+            Some(TocOptions {
+                depth: 1,
+                numbered: false,
+            }),
+        );
+        assert_eq!(entries(&toc_of(&doc).content[0]).len(), 2);
+    }
+
+    #[test]
+    fn test_format_options() {
+        let options: HashMap<String, String> = [("toc-depth", "2"), ("toc", "")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(
+            TocOptions::from_format_options("html", &options).unwrap(),
+            Some(TocOptions {
+                depth: 2,
+                numbered: false
+            })
+        );
+
+        let options = HashMap::from([("toc-depth".to_string(), "zero".to_string())]);
+        let err = TocOptions::from_format_options("html", &options).unwrap_err();
+        assert_eq!(err.code(), "invalid-option-value");
+
+        // Registries reject it before serializing, from the option type
+        let depth = TOC_OPTIONS.iter().find(|o| o.name == "toc-depth").unwrap();
+        assert_eq!(depth.kind, OptionType::Integer);
+        let err = crate::options::validate_options("html", &TOC_OPTIONS, &options).unwrap_err();
+        assert_eq!(err.code(), "invalid-option-value");
+    }
+}
@@ -0,0 +1,469 @@
+//! Traversal and rewriting of IR trees.
+//!
+//! Three traits cover the usual ways of walking a [`Document`]:
+//!
+//! - [`Visitor`] reads the tree through shared references.
+//! - [`VisitorMut`] edits nodes in place.
+//! - [`Fold`] takes nodes by value and rebuilds the tree, which lets it drop
+//!   or replace nodes.
+//!
+//! Every trait method has a default that recurses into the node's children
+//! through the matching free function (`walk_node`, `walk_node_mut`,
+//! `fold_node_children`, ...). An override that still wants to descend calls
+//! that function itself, before or after its own work.
+//!
+//! A [`Pass`] packages a rewrite so it can run between parsing and
+//! serialization; see [`crate::ir::passes`] for the built-in ones and
+//! [`crate::FormatRegistry::serialize_with_passes`] for the pipeline.
+//!
+//! Metadata entries are not visited: passes that care about them read
+//! [`Document::metadata`] directly.
+
+use super::nodes::{
+    Annotation, BlockQuote, Definition, DocNode, Document, Heading, InlineContent, Link, List,
+    ListItem, Paragraph, Table, TableCell, TableRow,
+};
+
+/// Read-only traversal over IR nodes.
+pub trait Visitor {
+    fn visit_node(&mut self, node: &DocNode) {
+        walk_node(self, node);
+    }
+
+    fn visit_list_item(&mut self, item: &ListItem) {
+        walk_list_item(self, item);
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        walk_inline(self, inline);
+    }
+}
+
+/// Visits every top-level node of `doc`.
+pub fn walk_document<V: Visitor + ?Sized>(visitor: &mut V, doc: &Document) {
+    for child in &doc.children {
+        visitor.visit_node(child);
+    }
+}
+
+/// Visits the inline content and child nodes of `node`.
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &DocNode) {
+    match node {
+        DocNode::Document(doc) => walk_document(visitor, doc),
+        DocNode::Heading(heading) => {
+            walk_inlines(visitor, &heading.content);
+            walk_nodes(visitor, &heading.children);
+        }
+        DocNode::Paragraph(paragraph) => walk_inlines(visitor, &paragraph.content),
+        DocNode::List(list) => {
+            for item in &list.items {
+                visitor.visit_list_item(item);
+            }
+        }
+        DocNode::ListItem(item) => visitor.visit_list_item(item),
+        DocNode::Definition(definition) => {
+            walk_inlines(visitor, &definition.term);
+            walk_nodes(visitor, &definition.description);
+        }
+        DocNode::Annotation(annotation) => walk_nodes(visitor, &annotation.content),
+        DocNode::Inline(inline) => visitor.visit_inline(inline),
+        DocNode::Table(table) => {
+            if let Some(caption) = &table.caption {
+                walk_inlines(visitor, caption);
+            }
+            for row in table.header.iter().chain(&table.rows) {
+                for cell in &row.cells {
+                    walk_nodes(visitor, &cell.content);
+                }
+            }
+        }
+        DocNode::BlockQuote(quote) => walk_nodes(visitor, &quote.children),
+        DocNode::Verbatim(_)
+        | DocNode::Image(_)
+        | DocNode::Video(_)
+        | DocNode::Audio(_)
+        | DocNode::ThematicBreak => {}
+    }
+}
+
+/// Visits the inline content and child nodes of a list item.
+pub fn walk_list_item<V: Visitor + ?Sized>(visitor: &mut V, item: &ListItem) {
+    walk_inlines(visitor, &item.content);
+    walk_nodes(visitor, &item.children);
+}
+
+/// Visits the nested inline content of `inline`.
+pub fn walk_inline<V: Visitor + ?Sized>(visitor: &mut V, inline: &InlineContent) {
+    match inline {
+        InlineContent::Bold(children)
+        | InlineContent::Italic(children)
+        | InlineContent::Strikethrough(children) => walk_inlines(visitor, children),
+        InlineContent::Link(link) => walk_inlines(visitor, &link.content),
+        InlineContent::Text(_)
+        | InlineContent::Code(_)
+        | InlineContent::Math(_)
+        | InlineContent::Reference(_)
+        | InlineContent::Marker(_)
+        | InlineContent::Image(_)
+        | InlineContent::FootnoteRef(_) => {}
+    }
+}
+
+fn walk_nodes<V: Visitor + ?Sized>(visitor: &mut V, nodes: &[DocNode]) {
+    for node in nodes {
+        visitor.visit_node(node);
+    }
+}
+
+fn walk_inlines<V: Visitor + ?Sized>(visitor: &mut V, inlines: &[InlineContent]) {
+    for inline in inlines {
+        visitor.visit_inline(inline);
+    }
+}
+
+/// In-place traversal over IR nodes.
+pub trait VisitorMut {
+    fn visit_node_mut(&mut self, node: &mut DocNode) {
+        walk_node_mut(self, node);
+    }
+
+    fn visit_list_item_mut(&mut self, item: &mut ListItem) {
+        walk_list_item_mut(self, item);
+    }
+
+    fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+        walk_inline_mut(self, inline);
+    }
+}
+
+/// Visits every top-level node of `doc` mutably.
+pub fn walk_document_mut<V: VisitorMut + ?Sized>(visitor: &mut V, doc: &mut Document) {
+    for child in &mut doc.children {
+        visitor.visit_node_mut(child);
+    }
+}
+
+/// Mutable counterpart of [`walk_node`].
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut DocNode) {
+    match node {
+        DocNode::Document(doc) => walk_document_mut(visitor, doc),
+        DocNode::Heading(heading) => {
+            walk_inlines_mut(visitor, &mut heading.content);
+            walk_nodes_mut(visitor, &mut heading.children);
+        }
+        DocNode::Paragraph(paragraph) => walk_inlines_mut(visitor, &mut paragraph.content),
+        DocNode::List(list) => {
+            for item in &mut list.items {
+                visitor.visit_list_item_mut(item);
+            }
+        }
+        DocNode::ListItem(item) => visitor.visit_list_item_mut(item),
+        DocNode::Definition(definition) => {
+            walk_inlines_mut(visitor, &mut definition.term);
+            walk_nodes_mut(visitor, &mut definition.description);
+        }
+        DocNode::Annotation(annotation) => walk_nodes_mut(visitor, &mut annotation.content),
+        DocNode::Inline(inline) => visitor.visit_inline_mut(inline),
+        DocNode::Table(table) => {
+            if let Some(caption) = &mut table.caption {
+                walk_inlines_mut(visitor, caption);
+            }
+            for row in table.header.iter_mut().chain(&mut table.rows) {
+                for cell in &mut row.cells {
+                    walk_nodes_mut(visitor, &mut cell.content);
+                }
+            }
+        }
+        DocNode::BlockQuote(quote) => walk_nodes_mut(visitor, &mut quote.children),
+        DocNode::Verbatim(_)
+        | DocNode::Image(_)
+        | DocNode::Video(_)
+        | DocNode::Audio(_)
+        | DocNode::ThematicBreak => {}
+    }
+}
+
+/// Mutable counterpart of [`walk_list_item`].
+pub fn walk_list_item_mut<V: VisitorMut + ?Sized>(visitor: &mut V, item: &mut ListItem) {
+    walk_inlines_mut(visitor, &mut item.content);
+    walk_nodes_mut(visitor, &mut item.children);
+}
+
+/// Mutable counterpart of [`walk_inline`].
+pub fn walk_inline_mut<V: VisitorMut + ?Sized>(visitor: &mut V, inline: &mut InlineContent) {
+    match inline {
+        InlineContent::Bold(children)
+        | InlineContent::Italic(children)
+        | InlineContent::Strikethrough(children) => walk_inlines_mut(visitor, children),
+        InlineContent::Link(link) => walk_inlines_mut(visitor, &mut link.content),
+        InlineContent::Text(_)
+        | InlineContent::Code(_)
+        | InlineContent::Math(_)
+        | InlineContent::Reference(_)
+        | InlineContent::Marker(_)
+        | InlineContent::Image(_)
+        | InlineContent::FootnoteRef(_) => {}
+    }
+}
+
+fn walk_nodes_mut<V: VisitorMut + ?Sized>(visitor: &mut V, nodes: &mut [DocNode]) {
+    for node in nodes {
+        visitor.visit_node_mut(node);
+    }
+}
+
+fn walk_inlines_mut<V: VisitorMut + ?Sized>(visitor: &mut V, inlines: &mut [InlineContent]) {
+    for inline in inlines {
+        visitor.visit_inline_mut(inline);
+    }
+}
+
+/// Rebuilding traversal over IR nodes.
+///
+/// Returning `None` from [`Fold::fold_node`] or [`Fold::fold_list_item`]
+/// removes the node from its parent.
+pub trait Fold {
+    fn fold_node(&mut self, node: DocNode) -> Option<DocNode> {
+        Some(fold_node_children(self, node))
+    }
+
+    fn fold_list_item(&mut self, item: ListItem) -> Option<ListItem> {
+        Some(fold_list_item_children(self, item))
+    }
+
+    fn fold_inline(&mut self, inline: InlineContent) -> InlineContent {
+        fold_inline_children(self, inline)
+    }
+}
+
+/// Folds every top-level node of `doc`, keeping its metadata.
+pub fn fold_document<F: Fold + ?Sized>(folder: &mut F, doc: Document) -> Document {
+    Document {
+        children: fold_nodes(folder, doc.children),
+        metadata: doc.metadata,
+    }
+}
+
+/// Folds a sequence of sibling nodes, dropping those the folder removes.
+pub fn fold_nodes<F: Fold + ?Sized>(folder: &mut F, nodes: Vec<DocNode>) -> Vec<DocNode> {
+    nodes
+        .into_iter()
+        .filter_map(|node| folder.fold_node(node))
+        .collect()
+}
+
+/// Rebuilds `node` from its folded inline content and children.
+pub fn fold_node_children<F: Fold + ?Sized>(folder: &mut F, node: DocNode) -> DocNode {
+    match node {
+        DocNode::Document(doc) => DocNode::Document(fold_document(folder, doc)),
+        DocNode::Heading(heading) => DocNode::Heading(Heading {
+            content: fold_inlines(folder, heading.content),
+            children: fold_nodes(folder, heading.children),
+            ..heading
+        }),
+        DocNode::Paragraph(paragraph) => DocNode::Paragraph(Paragraph {
+            content: fold_inlines(folder, paragraph.content),
+            ..paragraph
+        }),
+        DocNode::List(list) => DocNode::List(List {
+            items: list
+                .items
+                .into_iter()
+                .filter_map(|item| folder.fold_list_item(item))
+                .collect(),
+            ..list
+        }),
+        DocNode::ListItem(item) => DocNode::ListItem(fold_list_item_children(folder, item)),
+        DocNode::Definition(definition) => DocNode::Definition(Definition {
+            term: fold_inlines(folder, definition.term),
+            description: fold_nodes(folder, definition.description),
+            ..definition
+        }),
+        DocNode::Annotation(annotation) => DocNode::Annotation(Annotation {
+            content: fold_nodes(folder, annotation.content),
+            ..annotation
+        }),
+        DocNode::Inline(inline) => DocNode::Inline(folder.fold_inline(inline)),
+        DocNode::Table(table) => DocNode::Table(Table {
+            caption: table.caption.map(|caption| fold_inlines(folder, caption)),
+            header: fold_rows(folder, table.header),
+            rows: fold_rows(folder, table.rows),
+            ..table
+        }),
+        DocNode::BlockQuote(quote) => DocNode::BlockQuote(BlockQuote {
+            children: fold_nodes(folder, quote.children),
+            ..quote
+        }),
+        node @ (DocNode::Verbatim(_)
+        | DocNode::Image(_)
+        | DocNode::Video(_)
+        | DocNode::Audio(_)
+        | DocNode::ThematicBreak) => node,
+    }
+}
+
+/// Rebuilds a list item from its folded inline content and children.
+pub fn fold_list_item_children<F: Fold + ?Sized>(folder: &mut F, item: ListItem) -> ListItem {
+    ListItem {
+        content: fold_inlines(folder, item.content),
+        children: fold_nodes(folder, item.children),
+        ..item
+    }
+}
+
+/// Rebuilds `inline` from its folded nested content.
+pub fn fold_inline_children<F: Fold + ?Sized>(
+    folder: &mut F,
+    inline: InlineContent,
+) -> InlineContent {
+    match inline {
+        InlineContent::Bold(children) => InlineContent::Bold(fold_inlines(folder, children)),
+        InlineContent::Italic(children) => InlineContent::Italic(fold_inlines(folder, children)),
+        InlineContent::Strikethrough(children) => {
+            InlineContent::Strikethrough(fold_inlines(folder, children))
+        }
+        InlineContent::Link(link) => InlineContent::Link(Link {
+            content: fold_inlines(folder, link.content),
+            ..link
+        }),
+        other => other,
+    }
+}
+
+fn fold_inlines<F: Fold + ?Sized>(
+    folder: &mut F,
+    inlines: Vec<InlineContent>,
+) -> Vec<InlineContent> {
+    inlines
+        .into_iter()
+        .map(|inline| folder.fold_inline(inline))
+        .collect()
+}
+
+fn fold_rows<F: Fold + ?Sized>(folder: &mut F, rows: Vec<TableRow>) -> Vec<TableRow> {
+    rows.into_iter()
+        .map(|row| TableRow {
+            cells: row
+                .cells
+                .into_iter()
+                .map(|cell| TableCell {
+                    content: fold_nodes(folder, cell.content),
+                    ..cell
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// A named rewrite of an IR document, run between parsing and serialization.
+pub trait Pass: Send + Sync {
+    /// Name used to select the pass, e.g. on the command line
+    fn name(&self) -> &str;
+
+    /// Rewrite `doc` in place
+    fn run(&self, doc: &mut Document);
+}
+
+/// Runs `passes` over `doc` in order.
+pub fn run_passes(doc: &mut Document, passes: &[Box<dyn Pass>]) {
+    for pass in passes {
+        pass.run(doc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> InlineContent {
+        InlineContent::Text(value.to_string())
+    }
+
+    fn paragraph(content: Vec<InlineContent>) -> DocNode {
+        DocNode::Paragraph(Paragraph {
+            content,
+            span: None,
+        })
+    }
+
+    fn sample() -> Document {
+        Document {
+            children: vec![DocNode::Heading(Heading {
+                level: 2,
+                content: vec![text("Intro")],
+                children: vec![
+                    paragraph(vec![text("a "), InlineContent::Bold(vec![text("b")])]),
+                    DocNode::Annotation(Annotation {
+                        label: "note".to_string(),
+                        parameters: vec![],
+                        content: vec![paragraph(vec![text("c")])],
+                        span: None,
+                    }),
+                ],
+                id: None,
+                span: None,
+            })],
+            metadata: Default::default(),
+        }
+    }
+
+    #[derive(Default)]
+    struct TextCollector(Vec<String>);
+
+    impl Visitor for TextCollector {
+        fn visit_inline(&mut self, inline: &InlineContent) {
+            if let InlineContent::Text(value) = inline {
+                self.0.push(value.clone());
+            }
+            walk_inline(self, inline);
+        }
+    }
+
+    #[test]
+    fn test_visitor_reaches_nested_inlines() {
+        let mut collector = TextCollector::default();
+        walk_document(&mut collector, &sample());
+        assert_eq!(collector.0, vec!["Intro", "a ", "b", "c"]);
+    }
+
+    struct Upper;
+
+    impl VisitorMut for Upper {
+        fn visit_inline_mut(&mut self, inline: &mut InlineContent) {
+            if let InlineContent::Text(value) = inline {
+                *value = value.to_uppercase();
+            }
+            walk_inline_mut(self, inline);
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut_edits_in_place() {
+        let mut doc = sample();
+        walk_document_mut(&mut Upper, &mut doc);
+        let mut collector = TextCollector::default();
+        walk_document(&mut collector, &doc);
+        assert_eq!(collector.0, vec!["INTRO", "A ", "B", "C"]);
+    }
+
+    struct DropAnnotations;
+
+    impl Fold for DropAnnotations {
+        fn fold_node(&mut self, node: DocNode) -> Option<DocNode> {
+            match node {
+                DocNode::Annotation(_) => None,
+                other => Some(fold_node_children(self, other)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_drops_nodes() {
+        let doc = fold_document(&mut DropAnnotations, sample());
+        let DocNode::Heading(heading) = &doc.children[0] else {
+            panic!("expected heading");
+        };
+        assert_eq!(heading.children.len(), 1);
+        assert!(matches!(heading.children[0], DocNode::Paragraph(_)));
+    }
+}
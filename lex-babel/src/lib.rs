@@ -112,15 +112,19 @@ pub mod format;
 pub mod formats;
 pub mod publish;
 pub mod registry;
+pub mod report;
 pub mod templates;
 pub mod transforms;
 
 pub mod common;
 pub mod ir;
+pub mod options;
 
 pub use error::FormatError;
 pub use format::{Format, SerializedDocument};
+pub use ir::context::ConversionContext;
 pub use registry::FormatRegistry;
+pub use report::{ConversionReport, ConversionWarning, WarningKind};
 
 /// Converts a lex document to the Intermediate Representation (IR).
 ///
@@ -129,7 +133,7 @@ pub use registry::FormatRegistry;
 /// The IR is a simplified, semantic representation. The following
 /// Lex information is lost during conversion:
 /// - Blank line grouping (BlankLineGroup nodes)
-/// - Source positions and token information
+/// - Token information (block nodes keep their source range as `span`)
 /// - Comment annotations at document level
 ///
 /// For lossless Lex representation, use the AST directly.
@@ -137,9 +141,37 @@ pub fn to_ir(doc: &lex_core::lex::ast::elements::Document) -> ir::nodes::Documen
     ir::from_lex::from_lex_document(doc)
 }
 
+/// Converts a Lex document to IR, promoting top-level annotations with the
+/// given labels to document metadata.
+///
+/// [`to_ir`] uses [`ir::from_lex::DEFAULT_METADATA_LABELS`].
+pub fn to_ir_with_metadata_labels(
+    doc: &lex_core::lex::ast::elements::Document,
+    labels: &[&str],
+) -> ir::nodes::Document {
+    ir::from_lex::from_lex_document_with_metadata_labels(doc, labels)
+}
+
+/// Converts a Lex document to IR with the verbatim handlers and metadata
+/// labels of `ctx`.
+pub fn to_ir_with_context(
+    doc: &lex_core::lex::ast::elements::Document,
+    ctx: &ConversionContext,
+) -> ir::nodes::Document {
+    ir::from_lex::from_lex_document_with_context(doc, ctx)
+}
+
 /// Converts an IR document back to Lex AST.
 ///
 /// This is useful for round-trip conversions: Format → IR → Lex.
 pub fn from_ir(doc: &ir::nodes::Document) -> lex_core::lex::ast::elements::Document {
     ir::to_lex::to_lex_document(doc)
 }
+
+/// Converts an IR document back to Lex AST with the verbatim handlers of `ctx`.
+pub fn from_ir_with_context(
+    doc: &ir::nodes::Document,
+    ctx: &ConversionContext,
+) -> lex_core::lex::ast::elements::Document {
+    ir::to_lex::to_lex_document_with_context(doc, ctx)
+}
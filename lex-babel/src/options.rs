@@ -0,0 +1,256 @@
+//! Typed descriptions of format options
+//!
+//! Options reach a format as a `HashMap<String, String>` (on the command line,
+//! `--extra-<name> <value>`). Each format lists the options it understands in
+//! [`crate::Format::options`], and [`crate::FormatRegistry`] checks a map
+//! against that list before serializing, so a misspelt key or an unknown theme
+//! fails up front instead of being ignored. The same descriptors drive the
+//! CLI's per-format help and shell completions.
+
+use crate::error::{ErrorDetail, ErrorKind, FormatError};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The kind of value an option takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// `true`/`false` (also `yes`/`no`, `1`/`0`); an empty value means true
+    Bool,
+    /// A positive whole number
+    Integer,
+    /// Free text
+    String,
+    /// A filesystem path
+    Path,
+    /// One of [`FormatOption::allowed_values`]
+    Choice,
+}
+
+impl fmt::Display for OptionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OptionType::Bool => "bool",
+            OptionType::Integer => "integer",
+            OptionType::String => "string",
+            OptionType::Path => "path",
+            OptionType::Choice => "choice",
+        })
+    }
+}
+
+/// Description of one option a format accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOption {
+    pub name: &'static str,
+    /// Other spellings accepted for the same option
+    pub aliases: &'static [&'static str],
+    pub kind: OptionType,
+    /// Value used when the option is not given, if there is a fixed one
+    pub default: Option<&'static str>,
+    /// Accepted values of a [`OptionType::Choice`] option
+    pub allowed_values: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl FormatOption {
+    pub const fn new(name: &'static str, kind: OptionType, help: &'static str) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            kind,
+            default: None,
+            allowed_values: &[],
+            help,
+        }
+    }
+
+    pub const fn with_aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub const fn with_default(mut self, default: &'static str) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub const fn with_allowed_values(mut self, values: &'static [&'static str]) -> Self {
+        self.allowed_values = values;
+        self
+    }
+
+    /// Whether `key` names this option
+    pub fn matches(&self, key: &str) -> bool {
+        self.name == key || self.aliases.contains(&key)
+    }
+
+    fn check_value(&self, format: &str, key: &str, value: &str) -> Result<(), FormatError> {
+        let valid = match self.kind {
+            OptionType::Bool => value.is_empty() || parse_bool(value).is_some(),
+            OptionType::Integer => value.parse::<usize>().is_ok_and(|n| n > 0),
+            OptionType::Choice => self.allowed_values.contains(&value),
+            OptionType::String | OptionType::Path => true,
+        };
+        if valid {
+            return Ok(());
+        }
+
+        let expected = match self.kind {
+            OptionType::Bool => "true or false".to_string(),
+            OptionType::Integer => "a positive number".to_string(),
+            _ => self.allowed_values.join(", "),
+        };
+        Err(invalid_value(format, key, value, &expected))
+    }
+}
+
+/// The error for a `value` of option `key` that is not what it takes
+pub(crate) fn invalid_value(format: &str, key: &str, value: &str, expected: &str) -> FormatError {
+    ErrorDetail::new(
+        ErrorKind::Serialization,
+        "invalid-option-value",
+        format!(
+            "Invalid value '{value}' for option '{key}' of format '{format}' (expected {expected})"
+        ),
+    )
+    .in_format(format)
+    .into()
+}
+
+/// Read a boolean option value, as accepted by [`OptionType::Bool`]
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" => Some(true),
+        "false" | "0" | "no" | "n" => Some(false),
+        _ => None,
+    }
+}
+
+/// Read the [`OptionType::Bool`] option `key`: `None` when it is not given,
+/// true when it is given empty
+///
+/// Values [`parse_bool`] rejects fail as in [`validate_options`], for
+/// callers that did not go through the registry.
+pub fn bool_option(
+    format: &str,
+    options: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<bool>, FormatError> {
+    let Some(value) = options.get(key) else {
+        return Ok(None);
+    };
+    if value.is_empty() {
+        return Ok(Some(true));
+    }
+    parse_bool(value)
+        .map(Some)
+        .ok_or_else(|| invalid_value(format, key, value, "true or false"))
+}
+
+/// Check every key of `options` against `descriptors`
+///
+/// Unknown keys and values of the wrong type are errors. Keys are checked in
+/// sorted order so the error does not depend on hash map iteration.
+pub fn validate_options(
+    format: &str,
+    descriptors: &[FormatOption],
+    options: &HashMap<String, String>,
+) -> Result<(), FormatError> {
+    let mut keys: Vec<&String> = options.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        let Some(descriptor) = descriptors.iter().find(|option| option.matches(key)) else {
+            let message = if descriptors.is_empty() {
+                format!("Format '{format}' does not support extra parameters (got '{key}')")
+            } else {
+                let known: Vec<&str> = descriptors.iter().map(|option| option.name).collect();
+                format!(
+                    "Unknown option '{key}' for format '{format}' (available: {})",
+                    known.join(", ")
+                )
+            };
+            return Err(
+                ErrorDetail::new(ErrorKind::NotSupported, "unknown-option", message)
+                    .in_format(format)
+                    .into(),
+            );
+        };
+        descriptor.check_value(format, key, &options[key])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: &[FormatOption] = &[
+        FormatOption::new("theme", OptionType::Choice, "Theme")
+            .with_default("modern")
+            .with_allowed_values(&["modern", "fancy-serif"]),
+        FormatOption::new("source-lines", OptionType::Bool, "Lines")
+            .with_aliases(&["source_lines"]),
+        FormatOption::new("depth", OptionType::Integer, "Depth"),
+    ];
+
+    fn options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_accepts_known_options_and_aliases() {
+        let given = options(&[("theme", "fancy-serif"), ("source_lines", "yes")]);
+        assert!(validate_options("html", OPTIONS, &given).is_ok());
+        assert!(validate_options("html", OPTIONS, &options(&[("source-lines", "")])).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_keys_and_bad_values() {
+        let err = validate_options("html", OPTIONS, &options(&[("colour", "red")])).unwrap_err();
+        assert_eq!(err.code(), "unknown-option");
+        assert!(err
+            .message()
+            .contains("available: theme, source-lines, depth"));
+
+        let err = validate_options("html", OPTIONS, &options(&[("theme", "neon")])).unwrap_err();
+        assert_eq!(err.code(), "invalid-option-value");
+        assert_eq!(err.kind(), ErrorKind::Serialization);
+        assert!(err.message().contains("modern, fancy-serif"));
+        assert!(!err.to_string().contains("not supported"));
+
+        assert!(validate_options("html", OPTIONS, &options(&[("depth", "2")])).is_ok());
+        let err = validate_options("html", OPTIONS, &options(&[("depth", "0")])).unwrap_err();
+        assert!(err.message().contains("a positive number"));
+
+        let err =
+            validate_options("html", OPTIONS, &options(&[("source-lines", "maybe")])).unwrap_err();
+        assert!(err.message().contains("true or false"));
+
+        let err = validate_options("markdown", &[], &options(&[("theme", "x")])).unwrap_err();
+        assert!(err.message().contains("does not support extra parameters"));
+    }
+
+    #[test]
+    fn test_bool_option() {
+        let given = options(&[("source-lines", "Yes"), ("toc", ""), ("quicklook", "0")]);
+        assert_eq!(
+            bool_option("html", &given, "source-lines").unwrap(),
+            Some(true)
+        );
+        assert_eq!(bool_option("html", &given, "toc").unwrap(), Some(true));
+        assert_eq!(
+            bool_option("png", &given, "quicklook").unwrap(),
+            Some(false)
+        );
+        assert_eq!(bool_option("html", &given, "missing").unwrap(), None);
+
+        let err = bool_option("html", &options(&[("toc", "maybe")]), "toc").unwrap_err();
+        assert_eq!(err.code(), "invalid-option-value");
+        assert!(err.message().contains("true or false"));
+    }
+}
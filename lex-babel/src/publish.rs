@@ -13,6 +13,7 @@
 use crate::error::FormatError;
 use crate::format::SerializedDocument;
 use crate::registry::FormatRegistry;
+use crate::report::ConversionReport;
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
 use std::fs;
@@ -40,6 +41,9 @@ pub struct PublishSpec<'a> {
     pub output: Option<PathBuf>,
     /// Format-specific options (e.g., theme selection, page size).
     pub options: HashMap<String, String>,
+    /// Directory files referenced by the document are resolved against,
+    /// usually the one holding the document.
+    pub base_dir: Option<PathBuf>,
 }
 
 impl<'a> PublishSpec<'a> {
@@ -50,6 +54,7 @@ impl<'a> PublishSpec<'a> {
             format,
             output: None,
             options: HashMap::new(),
+            base_dir: None,
         }
     }
 
@@ -64,6 +69,12 @@ impl<'a> PublishSpec<'a> {
         self.options.insert(key.into(), value.into());
         self
     }
+
+    /// Sets the directory relative `src=` references are resolved against.
+    pub fn with_base_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.base_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
 }
 
 /// The output from a successful publish operation.
@@ -80,6 +91,8 @@ pub enum PublishArtifact {
 pub struct PublishResult {
     /// The published artifact (in-memory content or file path).
     pub artifact: PublishArtifact,
+    /// Content the target format dropped or degraded.
+    pub report: ConversionReport,
 }
 
 /// Publishes a document according to the specification.
@@ -93,41 +106,39 @@ pub struct PublishResult {
 /// Returns [`FormatError`] if:
 /// - The format is not supported
 /// - Serialization fails
+/// - Data the document refers to (e.g. a table's `src=` file) cannot be read
 /// - File I/O fails
 /// - A binary format is requested without an output path
 pub fn publish(spec: PublishSpec<'_>) -> Result<PublishResult, FormatError> {
-    let registry = FormatRegistry::with_defaults();
-    let serialized = registry.serialize_with_options(spec.document, spec.format, &spec.options)?;
-    match serialized {
+    let mut registry = FormatRegistry::with_defaults();
+    registry.set_base_dir(spec.base_dir.as_deref());
+    let (serialized, report) =
+        registry.serialize_with_report(spec.document, spec.format, &spec.options, &[])?;
+    let artifact = match serialized {
         SerializedDocument::Text(text) => write_or_return_text(text, spec.output),
         SerializedDocument::Binary(bytes) => write_binary(bytes, spec.output),
-    }
+    }?;
+    Ok(PublishResult { artifact, report })
 }
 
 fn write_or_return_text(
     text: String,
     output: Option<PathBuf>,
-) -> Result<PublishResult, FormatError> {
+) -> Result<PublishArtifact, FormatError> {
     if let Some(path) = output {
-        write_to_path(path, text.into_bytes()).map(|path| PublishResult {
-            artifact: PublishArtifact::File(path),
-        })
+        write_to_path(path, text.into_bytes()).map(PublishArtifact::File)
     } else {
-        Ok(PublishResult {
-            artifact: PublishArtifact::InMemory(text),
-        })
+        Ok(PublishArtifact::InMemory(text))
     }
 }
 
-fn write_binary(bytes: Vec<u8>, output: Option<PathBuf>) -> Result<PublishResult, FormatError> {
+fn write_binary(bytes: Vec<u8>, output: Option<PathBuf>) -> Result<PublishArtifact, FormatError> {
     let path = output.ok_or_else(|| {
         FormatError::SerializationError(
             "binary formats require an explicit output path".to_string(),
         )
     })?;
-    write_to_path(path, bytes).map(|path| PublishResult {
-        artifact: PublishArtifact::File(path),
-    })
+    write_to_path(path, bytes).map(PublishArtifact::File)
 }
 
 fn write_to_path(path: PathBuf, bytes: Vec<u8>) -> Result<PathBuf, FormatError> {
@@ -158,6 +169,7 @@ mod tests {
             }
             PublishArtifact::File(_) => panic!("expected in-memory artifact"),
         }
+        assert!(result.report.is_empty());
     }
 
     #[test]
@@ -174,4 +186,24 @@ mod tests {
         let contents = fs::read_to_string(path).unwrap();
         assert!(contents.contains("Paragraph text."));
     }
+
+    #[test]
+    fn resolves_table_sources_against_base_dir() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("data.csv"), "Name,Qty\nPear,3\n").unwrap();
+        let doc =
+            parsing::parse_document("Stock:\n:: doc.table src=\"data.csv\", header=true ::\n")
+                .unwrap();
+
+        let result =
+            publish(PublishSpec::new(&doc, "html").with_base_dir(dir.path())).expect("publish");
+        let PublishArtifact::InMemory(content) = result.artifact else {
+            panic!("expected in-memory artifact");
+        };
+        assert!(content.contains("<table"));
+        assert!(content.contains("Pear"));
+
+        let err = publish(PublishSpec::new(&doc, "html")).unwrap_err();
+        assert_eq!(err.code(), "table-source");
+    }
 }
@@ -3,10 +3,21 @@
 //! This module provides a centralized registry for all available formats.
 //! Formats can be registered and retrieved by name.
 
+use crate::common::verbatim::VerbatimRegistry;
 use crate::error::FormatError;
 use crate::format::{Format, SerializedDocument};
+use crate::ir::citations::CitationStyle;
+use crate::ir::context::ConversionContext;
+use crate::ir::from_lex::try_from_lex_document_with_context;
+use crate::ir::visit::{run_passes, Pass};
+use crate::options::validate_options;
+use crate::report::{
+    report_footnote_issues, report_numbering_gaps, report_source_drift, report_unknown_citations,
+    report_unresolved_references, ConversionReport,
+};
 use lex_core::lex::ast::Document;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Registry of document formats
 ///
@@ -24,16 +35,73 @@ use std::collections::HashMap;
 /// ```
 pub struct FormatRegistry {
     formats: HashMap<String, Box<dyn Format>>,
+    verbatim: VerbatimRegistry,
+    base_dir: Option<PathBuf>,
+    bibliography: Option<PathBuf>,
+    citation_style: Option<CitationStyle>,
 }
 
 impl FormatRegistry {
-    /// Create a new empty registry
+    /// Create a new registry with no formats and the standard verbatim handlers
     pub fn new() -> Self {
         FormatRegistry {
             formats: HashMap::new(),
+            verbatim: VerbatimRegistry::default_with_standard(),
+            base_dir: None,
+            bibliography: None,
+            citation_style: None,
         }
     }
 
+    /// Verbatim handlers used by every conversion this registry runs
+    pub fn verbatim_registry(&self) -> &VerbatimRegistry {
+        &self.verbatim
+    }
+
+    /// Mutable access to the verbatim handlers, e.g. to register custom ones
+    ///
+    /// ```ignore
+    /// let mut registry = FormatRegistry::default();
+    /// registry
+    ///     .verbatim_registry_mut()
+    ///     .register_namespace("acme.", Box::new(AcmeHandler));
+    /// ```
+    pub fn verbatim_registry_mut(&mut self) -> &mut VerbatimRegistry {
+        &mut self.verbatim
+    }
+
+    /// Replace the verbatim handlers
+    pub fn set_verbatim_registry(&mut self, verbatim: VerbatimRegistry) {
+        self.verbatim = verbatim;
+    }
+
+    /// Resolve files the document refers to (e.g. `doc.table src=..`) against `dir`
+    ///
+    /// Set this to the directory of the document being converted; without it
+    /// relative paths are taken from the current directory.
+    pub fn set_base_dir(&mut self, dir: Option<&Path>) {
+        self.base_dir = dir.map(Path::to_path_buf);
+    }
+
+    /// Resolve citations against `path` instead of the document's
+    /// `bibliography` metadata
+    pub fn set_bibliography(&mut self, path: Option<&Path>) {
+        self.bibliography = path.map(Path::to_path_buf);
+    }
+
+    /// Write citations in `style` instead of the document's `citation-style`
+    pub fn set_citation_style(&mut self, style: Option<CitationStyle>) {
+        self.citation_style = style;
+    }
+
+    /// Conversion context handed to formats for parsing and serializing
+    pub fn context(&self) -> ConversionContext<'_> {
+        ConversionContext::new(&self.verbatim)
+            .with_base_dir(self.base_dir.as_deref())
+            .with_bibliography(self.bibliography.as_deref())
+            .with_citation_style(self.citation_style)
+    }
+
     /// Register a format
     ///
     /// If a format with the same name already exists, it will be replaced.
@@ -98,7 +166,8 @@ impl FormatRegistry {
                 "Format '{format}' does not support parsing"
             )));
         }
-        fmt.parse(source)
+        fmt.parse_with_context(source, &self.context())
+            .map_err(|e| e.with_format(format))
     }
 
     /// Serialize a document using the specified format
@@ -112,20 +181,140 @@ impl FormatRegistry {
         }
     }
 
-    /// Serialize a document using the specified format and options
-    pub fn serialize_with_options(
+    /// Look up a format for serialization and validate `options` against it
+    fn serializer(
         &self,
-        doc: &Document,
         format: &str,
         options: &HashMap<String, String>,
-    ) -> Result<SerializedDocument, FormatError> {
+    ) -> Result<&dyn Format, FormatError> {
         let fmt = self.get(format)?;
         if !fmt.supports_serialization() {
             return Err(FormatError::NotSupported(format!(
                 "Format '{format}' does not support serialization"
             )));
         }
-        fmt.serialize_with_options(doc, options)
+        validate_options(format, fmt.options(), options)?;
+        Ok(fmt)
+    }
+
+    /// Serialize a document using the specified format and options
+    ///
+    /// Options are checked against [`Format::options`] first.
+    pub fn serialize_with_options(
+        &self,
+        doc: &Document,
+        format: &str,
+        options: &HashMap<String, String>,
+    ) -> Result<SerializedDocument, FormatError> {
+        let fmt = self.serializer(format, options)?;
+        fmt.serialize_with_context(doc, options, &self.context())
+            .map_err(|e| e.with_format(format))
+    }
+
+    /// Serialize a document after running IR passes over it
+    ///
+    /// With no passes this is [`serialize_with_options`](Self::serialize_with_options).
+    /// Otherwise the document is converted to IR, the passes run in order, and the
+    /// result goes to [`Format::serialize_ir_with_options`].
+    pub fn serialize_with_passes(
+        &self,
+        doc: &Document,
+        format: &str,
+        options: &HashMap<String, String>,
+        passes: &[Box<dyn Pass>],
+    ) -> Result<SerializedDocument, FormatError> {
+        if passes.is_empty() {
+            return self.serialize_with_options(doc, format, options);
+        }
+        let fmt = self.serializer(format, options)?;
+        let mut ir = crate::to_ir_with_context(doc, &self.context());
+        run_passes(&mut ir, passes);
+        fmt.serialize_ir_with_options(doc, &ir, options)
+            .map_err(|e| e.with_format(format))
+    }
+
+    /// Parse source text, also reporting what the format could not carry over
+    pub fn parse_with_report(
+        &self,
+        source: &str,
+        format: &str,
+    ) -> Result<(Document, ConversionReport), FormatError> {
+        let doc = self.parse(source, format)?;
+        let mut report = ConversionReport::new();
+        self.get(format)?.report_parse_losses(source, &mut report);
+        Ok((doc, report))
+    }
+
+    /// Serialize like [`serialize_with_passes`](Self::serialize_with_passes),
+    /// also reporting content the target format drops or degrades
+    ///
+    /// The IR is built and passed through `passes` once; the report is taken
+    /// from the same IR the output is written from. For formats that
+    /// [resolve sources](Format::resolves_sources), verbatim blocks that read
+    /// data and the bibliography must resolve, checked as the IR is built.
+    pub fn serialize_with_report(
+        &self,
+        doc: &Document,
+        format: &str,
+        options: &HashMap<String, String>,
+        passes: &[Box<dyn Pass>],
+    ) -> Result<(SerializedDocument, ConversionReport), FormatError> {
+        let fmt = self.serializer(format, options)?;
+        let ctx = self.context();
+        let mut ir = if fmt.resolves_sources() {
+            try_from_lex_document_with_context(doc, &ctx)?
+        } else {
+            crate::to_ir_with_context(doc, &ctx)
+        };
+        let mut report = ConversionReport::new();
+        // Numbering keeps the authors' own numbers, so check them first
+        if passes.iter().any(|pass| pass.name() == "number-sessions") {
+            report_numbering_gaps(&ir, &mut report);
+        }
+        run_passes(&mut ir, passes);
+
+        // Formats that write from the Lex AST only need the IR for the report
+        let output = if passes.is_empty() && !fmt.serializes_ir() {
+            fmt.serialize_with_context(doc, options, &ctx)
+        } else {
+            fmt.serialize_ir_with_options(doc, &ir, options)
+        }
+        .map_err(|e| e.with_format(format))?;
+
+        report_unresolved_references(&ir, &mut report);
+        report_footnote_issues(&ir, &mut report);
+        report_unknown_citations(&ir, &ctx, &mut report);
+        fmt.report_serialize_losses(&ir, &mut report);
+        Ok((output, report))
+    }
+
+    /// Check the files a Lex document refers to, without converting it
+    ///
+    /// Fails where [`serialize_with_report`](Self::serialize_with_report)
+    /// would on data that cannot be read, and reports code samples whose
+    /// inline copy has drifted from their `src=` file.
+    pub fn check(&self, doc: &Document) -> Result<ConversionReport, FormatError> {
+        try_from_lex_document_with_context(doc, &self.context())?;
+        let mut report = ConversionReport::new();
+        report_source_drift(doc, &self.context(), &mut report);
+        Ok(report)
+    }
+
+    /// Convert source text between formats: parse, run passes, serialize
+    ///
+    /// Fails like [`serialize_with_report`](Self::serialize_with_report) on
+    /// data that cannot be read.
+    pub fn convert(
+        &self,
+        source: &str,
+        from: &str,
+        to: &str,
+        options: &HashMap<String, String>,
+        passes: &[Box<dyn Pass>],
+    ) -> Result<SerializedDocument, FormatError> {
+        let doc = self.parse(source, from)?;
+        self.serialize_with_report(&doc, to, options, passes)
+            .map(|(output, _)| output)
     }
 
     /// Create a registry with default formats
@@ -140,6 +329,11 @@ impl FormatRegistry {
         #[cfg(feature = "native-export")]
         registry.register(crate::formats::png::PngFormat::default());
         registry.register(crate::formats::markdown::MarkdownFormat);
+        registry.register(crate::formats::ipynb::IpynbFormat);
+        registry.register(crate::formats::gemtext::GemtextFormat);
+        registry.register(crate::formats::confluence::ConfluenceFormat);
+        registry.register(crate::formats::eml::EmlFormat);
+        registry.register(crate::formats::rfc_text::RfcTextFormat);
         registry.register(crate::formats::rfc_xml::RfcXmlFormat);
         registry.register(crate::formats::tag::TagFormat);
         registry.register(crate::formats::treeviz::TreevizFormat);
@@ -160,6 +354,8 @@ mod tests {
     use super::*;
     use crate::format::Format;
     use lex_core::lex::ast::{ContentItem, Document, Paragraph};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     // Test format
     struct TestFormat;
@@ -368,6 +564,345 @@ mod tests {
         assert_eq!(registry.detect_format_from_filename("doc"), None);
     }
 
+    #[test]
+    fn test_convert_runs_passes_before_serializing() {
+        let registry = FormatRegistry::with_defaults();
+        let source = "Intro\n\n    Some text.\n";
+        let options = HashMap::new();
+
+        let plain = match registry.convert(source, "lex", "html", &options, &[]) {
+            Ok(SerializedDocument::Text(html)) => html,
+            _ => panic!("expected HTML text"),
+        };
+        assert!(plain.contains("<h2"));
+
+        let passes = vec![
+            crate::ir::passes::builtin_pass("shift-headings=1").unwrap(),
+            crate::ir::passes::builtin_pass("number-sessions").unwrap(),
+        ];
+        let shifted = match registry.convert(source, "lex", "html", &options, &passes) {
+            Ok(SerializedDocument::Text(html)) => html,
+            _ => panic!("expected HTML text"),
+        };
+        assert!(shifted.contains("<h3"));
+        assert!(shifted.contains(r#"<span class="seq_marker">1.</span>"#));
+    }
+
+    // Writes verbatim blocks back as they are, like Lex
+    struct PassThroughFormat;
+    impl Format for PassThroughFormat {
+        fn name(&self) -> &str {
+            "pass-through"
+        }
+        fn description(&self) -> &str {
+            "Pass-through format"
+        }
+        fn supports_serialization(&self) -> bool {
+            true
+        }
+        fn serialize(&self, _doc: &Document) -> Result<String, FormatError> {
+            Ok("as written".to_string())
+        }
+        fn resolves_sources(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_sources_are_checked_on_check_and_convert() {
+        let mut registry = FormatRegistry::with_defaults();
+        registry.register(PassThroughFormat);
+        let source = "Stock:\n:: doc.table src=\"missing.csv\" ::\n";
+        let doc = registry.parse(source, "lex").unwrap();
+        let options = HashMap::new();
+
+        // Serializing alone writes what it can
+        assert!(registry.serialize(&doc, "html").is_ok());
+
+        assert_eq!(registry.check(&doc).unwrap_err().code(), "table-source");
+        let err = registry
+            .convert(source, "lex", "html", &options, &[])
+            .unwrap_err();
+        assert_eq!(err.code(), "table-source");
+        let err = registry
+            .serialize_with_report(&doc, "html", &options, &[])
+            .unwrap_err();
+        assert_eq!(err.code(), "table-source");
+
+        // Formats that write the block back never read the file
+        assert!(registry
+            .convert(source, "lex", "lex", &options, &[])
+            .is_ok());
+        assert!(registry
+            .serialize_with_report(&doc, "pass-through", &options, &[])
+            .is_ok());
+    }
+
+    struct ShoutHandler;
+    impl crate::common::verbatim::VerbatimHandler for ShoutHandler {
+        fn label(&self) -> &str {
+            "acme."
+        }
+        fn to_ir(
+            &self,
+            content: &str,
+            _params: &HashMap<String, String>,
+        ) -> Option<crate::ir::nodes::DocNode> {
+            Some(crate::ir::nodes::DocNode::Paragraph(
+                crate::ir::nodes::Paragraph {
+                    content: vec![crate::ir::nodes::InlineContent::Text(
+                        content.trim().to_uppercase(),
+                    )],
+                    span: None,
+                },
+            ))
+        }
+        fn convert_from_ir(
+            &self,
+            _node: &crate::ir::nodes::DocNode,
+        ) -> Option<(String, HashMap<String, String>)> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_registry_verbatim_handlers_apply_to_serialization() {
+        let source = "Shout:\n    hello there\n:: acme.shout ::\n";
+        let doc = crate::formats::lex::LexFormat::default()
+            .parse(source)
+            .unwrap();
+
+        let mut registry = FormatRegistry::with_defaults();
+        let before = registry.serialize(&doc, "markdown").unwrap();
+        assert!(!before.contains("HELLO THERE"));
+
+        registry
+            .verbatim_registry_mut()
+            .register_namespace("acme.", Box::new(ShoutHandler));
+        let markdown = registry.serialize(&doc, "markdown").unwrap();
+        assert!(markdown.contains("HELLO THERE"));
+        let html = match registry.serialize_with_options(&doc, "html", &HashMap::new()) {
+            Ok(SerializedDocument::Text(html)) => html,
+            _ => panic!("expected HTML text"),
+        };
+        assert!(html.contains("HELLO THERE"));
+    }
+
+    #[test]
+    fn test_serialize_with_report_flags_clamped_headings() {
+        let registry = FormatRegistry::with_defaults();
+        let source = "A\n\n    B\n\n        C\n\n            D\n\n                E\n\n                    F\n\n                        Deep text.\n";
+        let doc = registry.parse(source, "lex").unwrap();
+        let options = HashMap::new();
+
+        let (_, report) = registry
+            .serialize_with_report(&doc, "markdown", &options, &[])
+            .unwrap();
+        assert_eq!(report.len(), 1);
+        let warning = &report.warnings()[0];
+        assert_eq!(warning.kind, crate::report::WarningKind::HeadingClamped);
+        assert!(warning.span.is_some());
+
+        let (_, report) = registry
+            .serialize_with_report(&doc, "lex", &options, &[])
+            .unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_serialize_with_report_keeps_session_references_linked() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry
+            .parse(
+                "Method\n\n    Measure twice.\n\nResults\n\n    See [Method] and [https://example.org].\n",
+                "lex",
+            )
+            .unwrap();
+
+        let (output, report) = registry
+            .serialize_with_report(&doc, "markdown", &HashMap::new(), &[])
+            .unwrap();
+        assert!(report.is_empty(), "{:?}", report.warnings());
+        match output {
+            SerializedDocument::Text(md) => assert!(md.contains("[Method](#method"), "{md}"),
+            _ => panic!("expected Markdown text"),
+        }
+    }
+
+    struct CountingPass(Arc<AtomicUsize>);
+    impl Pass for CountingPass {
+        fn name(&self) -> &str {
+            "count"
+        }
+        fn run(&self, _doc: &mut crate::ir::nodes::Document) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_serialize_with_report_runs_passes_once() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry.parse("Intro\n\n    Some text.\n", "lex").unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let passes: Vec<Box<dyn Pass>> = vec![Box::new(CountingPass(runs.clone()))];
+
+        registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &passes)
+            .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_serialize_with_report_flags_unresolved_references() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry
+            .parse("1. Intro\n\n    See [#1] and [#4.2].\n", "lex")
+            .unwrap();
+
+        let (_, report) = registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &[])
+            .unwrap();
+        assert_eq!(report.len(), 1);
+        let warning = &report.warnings()[0];
+        assert_eq!(
+            warning.kind,
+            crate::report::WarningKind::UnresolvedReference
+        );
+        assert!(warning.message.contains("[#4.2]"));
+        assert_eq!(warning.span.as_ref().unwrap().start.line, 2);
+    }
+
+    #[test]
+    fn test_serialize_with_report_flags_math_kept_as_tex() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry
+            .parse("Intro\n\n    Fine #x^2#, broken #x^{2#.\n", "lex")
+            .unwrap();
+
+        let (_, report) = registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &[])
+            .unwrap();
+        assert_eq!(report.len(), 1);
+        let warning = &report.warnings()[0];
+        assert_eq!(warning.kind, crate::report::WarningKind::MathFallback);
+        assert!(warning.message.contains("x^{2"), "{}", warning.message);
+        assert_eq!(warning.span.as_ref().unwrap().start.line, 2);
+    }
+
+    #[test]
+    fn test_serialize_with_report_flags_footnote_issues() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry
+            .parse(
+                "Rivers flood [1] and dry up [3].\n\nNotes\n\n    1. Snow melt.\n    2. Unused.\n",
+                "lex",
+            )
+            .unwrap();
+
+        let (_, report) = registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &[])
+            .unwrap();
+        let kinds: Vec<_> = report.warnings().iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                crate::report::WarningKind::MissingFootnote,
+                crate::report::WarningKind::UnusedFootnote
+            ]
+        );
+        assert!(report.warnings()[0].message.contains("[3]"));
+    }
+
+    #[test]
+    fn test_number_sessions_keeps_own_numbers_and_flags_gaps() {
+        let registry = FormatRegistry::with_defaults();
+        let doc = registry
+            .parse(
+                "1. Intro\n\n    See [#3].\n\n3. Usage\n\n    Text.\n",
+                "lex",
+            )
+            .unwrap();
+        let passes = vec![crate::ir::passes::builtin_pass("number-sessions=I.A").unwrap()];
+
+        let (output, report) = registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &passes)
+            .unwrap();
+        let SerializedDocument::Text(html) = output else {
+            panic!("expected HTML text");
+        };
+        assert!(
+            html.contains(r#"<span class="seq_marker">3.</span>"#),
+            "{html}"
+        );
+        assert_eq!(report.len(), 1);
+        assert_eq!(
+            report.warnings()[0].kind,
+            crate::report::WarningKind::NumberingGap
+        );
+        assert!(report.warnings()[0].message.contains("expected 2"));
+
+        // Without the pass the numbers are the author's business
+        let (_, report) = registry
+            .serialize_with_report(&doc, "html", &HashMap::new(), &[])
+            .unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_code_samples_come_from_src_and_check_flags_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("greet.py"), "print('hi')\nprint('bye')\n").unwrap();
+        let mut registry = FormatRegistry::with_defaults();
+        registry.set_base_dir(Some(dir.path()));
+        let doc = registry
+            .parse(
+                "Greeting:\n    print('hello')\n:: code src=\"greet.py\", lines=\"1\" ::\n",
+                "lex",
+            )
+            .unwrap();
+
+        let markdown = registry.serialize(&doc, "markdown").unwrap();
+        assert!(markdown.contains("``` python"), "{markdown}");
+        assert!(markdown.contains("print('hi')"), "{markdown}");
+        assert!(
+            !markdown.contains("hello") && !markdown.contains("bye"),
+            "{markdown}"
+        );
+
+        let report = registry.check(&doc).unwrap();
+        assert_eq!(report.len(), 1);
+        let warning = &report.warnings()[0];
+        assert_eq!(warning.kind, crate::report::WarningKind::SourceDrift);
+        assert!(warning.message.contains("greet.py"));
+
+        std::fs::remove_file(dir.path().join("greet.py")).unwrap();
+        assert_eq!(registry.check(&doc).unwrap_err().code(), "verbatim-source");
+    }
+
+    #[test]
+    fn test_parse_with_report_flags_raw_html() {
+        let registry = FormatRegistry::with_defaults();
+        let source = "# Title\n\nText.\n\n<div class=\"x\">kept out</div>\n";
+
+        let (_, report) = registry.parse_with_report(source, "markdown").unwrap();
+        assert_eq!(report.len(), 1);
+        let warning = &report.warnings()[0];
+        assert_eq!(warning.kind, crate::report::WarningKind::ContentDropped);
+        assert_eq!(warning.span.as_ref().unwrap().start.line, 4);
+    }
+
+    #[test]
+    fn test_registry_errors_name_the_format() {
+        let registry = FormatRegistry::with_defaults();
+        let err = registry
+            .parse("<rfc>\n  <front>\n</rfc>\n", "rfc_xml")
+            .unwrap_err();
+        assert_eq!(err.code(), "xml-syntax");
+        assert_eq!(err.format(), Some("rfc_xml"));
+        assert_eq!(err.position().map(|pos| pos.line), Some(2));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
     #[test]
     fn test_detect_format_case_sensitive() {
         let registry = FormatRegistry::with_defaults();
@@ -0,0 +1,359 @@
+//! Conversion loss reports
+//!
+//! Most conversions lose something: Markdown has six heading levels, HTML
+//! comments are all that is left of a Lex annotation, raw HTML in Markdown has
+//! no Lex equivalent. Formats record each such place as a
+//! [`ConversionWarning`], and [`crate::FormatRegistry::parse_with_report`] and
+//! [`crate::FormatRegistry::serialize_with_report`] hand the collected
+//! [`ConversionReport`] back next to the output, so callers can show it or
+//! refuse lossy results.
+
+use crate::ir::citations::{bibliography_path, unknown_citations};
+use crate::ir::context::ConversionContext;
+use crate::ir::footnotes::{footnote_issues, FootnoteIssue};
+use crate::ir::from_lex::source_drift;
+use crate::ir::nodes::{DocNode, Document as IrDocument, InlineContent, SourceSpan};
+use crate::ir::numbering::numbering_gaps;
+use crate::ir::references::unresolved_references;
+use crate::ir::visit::{walk_document, walk_inline, walk_node, Visitor};
+use lex_core::lex::ast::Document as LexDocument;
+use std::fmt;
+
+/// What kind of loss a warning describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A heading nested deeper than the format supports was flattened
+    HeadingClamped,
+    /// A reference the format cannot link was written as plain text
+    ReferenceFlattened,
+    /// Content with no representation in the target was dropped
+    ContentDropped,
+    /// A session reference (`[#2.1]`) points at no session
+    UnresolvedReference,
+    /// A footnote reference (`[1]`) has no note in the Notes session
+    MissingFootnote,
+    /// A note of the Notes session is never referenced
+    UnusedFootnote,
+    /// A citation (`[@key]`) names a key the bibliography lacks
+    UnknownCitation,
+    /// The numbers authors gave their sessions skip or repeat
+    NumberingGap,
+    /// The inline copy of a code sample differs from its `src=` file
+    SourceDrift,
+    /// Math that does not convert to the target's notation is kept as TeX
+    MathFallback,
+    /// A diagram that does not render is kept as its source
+    DiagramFallback,
+}
+
+impl WarningKind {
+    /// Stable kebab-case name, e.g. for filtering on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::HeadingClamped => "heading-clamped",
+            WarningKind::ReferenceFlattened => "reference-flattened",
+            WarningKind::ContentDropped => "content-dropped",
+            WarningKind::UnresolvedReference => "unresolved-reference",
+            WarningKind::MissingFootnote => "missing-footnote",
+            WarningKind::UnusedFootnote => "unused-footnote",
+            WarningKind::UnknownCitation => "unknown-citation",
+            WarningKind::NumberingGap => "numbering-gap",
+            WarningKind::SourceDrift => "source-drift",
+            WarningKind::MathFallback => "math-fallback",
+            WarningKind::DiagramFallback => "diagram-fallback",
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One place where a conversion dropped or degraded content
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionWarning {
+    pub kind: WarningKind,
+    pub message: String,
+    /// Where in the input the affected content came from, when known
+    pub span: Option<SourceSpan>,
+}
+
+impl ConversionWarning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Option<SourceSpan>) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl fmt::Display for ConversionWarning {
+    /// `line N: message` with a 1-based line when the span is known
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "line {}: {}", span.start.line + 1, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Warnings collected during one conversion, in the order they were found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    warnings: Vec<ConversionWarning>,
+}
+
+impl ConversionReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, warning: ConversionWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Append every warning of `other`
+    pub fn extend(&mut self, other: ConversionReport) {
+        self.warnings.extend(other.warnings);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn warnings(&self) -> &[ConversionWarning] {
+        &self.warnings
+    }
+}
+
+/// Warn about every session reference in `ir` that matches no session
+pub(crate) fn report_unresolved_references(ir: &IrDocument, report: &mut ConversionReport) {
+    for reference in unresolved_references(ir) {
+        report.push(
+            ConversionWarning::new(
+                WarningKind::UnresolvedReference,
+                format!(
+                    "Reference [{}] matches no session and is kept as written",
+                    reference.target
+                ),
+            )
+            .with_span(reference.span),
+        );
+    }
+}
+
+/// Warn about footnote references without a note, and notes nobody cites
+pub(crate) fn report_footnote_issues(ir: &IrDocument, report: &mut ConversionReport) {
+    for issue in footnote_issues(ir) {
+        let warning = match issue {
+            FootnoteIssue::Missing { label, span } => ConversionWarning::new(
+                WarningKind::MissingFootnote,
+                format!("Footnote [{label}] has no note in the Notes session"),
+            )
+            .with_span(span),
+            FootnoteIssue::Unused { label, span } => ConversionWarning::new(
+                WarningKind::UnusedFootnote,
+                format!("Note {label} is never referenced"),
+            )
+            .with_span(span),
+        };
+        report.push(warning);
+    }
+}
+
+/// Warn about citations left as written because the bibliography lacks a key
+///
+/// Only applies when there is a bibliography; without one every citation is
+/// left as written on purpose.
+pub(crate) fn report_unknown_citations(
+    ir: &IrDocument,
+    ctx: &ConversionContext,
+    report: &mut ConversionReport,
+) {
+    if bibliography_path(&ir.metadata, ctx).is_none() {
+        return;
+    }
+    for (citation, span) in unknown_citations(ir) {
+        report.push(
+            ConversionWarning::new(
+                WarningKind::UnknownCitation,
+                format!("Citation [{citation}] is not in the bibliography and is kept as written"),
+            )
+            .with_span(span),
+        );
+    }
+}
+
+/// Warn where the session numbers written in the titles are not continuous
+pub(crate) fn report_numbering_gaps(ir: &IrDocument, report: &mut ConversionReport) {
+    for gap in numbering_gaps(ir) {
+        let message = match gap.previous {
+            Some(previous) => format!(
+                "Session {} follows {previous}; expected {}",
+                gap.marker, gap.expected
+            ),
+            None => format!(
+                "Session {} is the first of its level; expected {}",
+                gap.marker, gap.expected
+            ),
+        };
+        report.push(ConversionWarning::new(WarningKind::NumberingGap, message).with_span(gap.span));
+    }
+}
+
+/// Warn about code samples whose inline copy is behind their `src=` file
+pub(crate) fn report_source_drift(
+    doc: &LexDocument,
+    ctx: &ConversionContext,
+    report: &mut ConversionReport,
+) {
+    for drift in source_drift(doc, ctx) {
+        report.push(
+            ConversionWarning::new(
+                WarningKind::SourceDrift,
+                format!(
+                    "Verbatim block differs from its source '{}'; exports use the file",
+                    drift.src
+                ),
+            )
+            .with_span(drift.span),
+        );
+    }
+}
+
+/// Loss checks shared by the IR-based serializers
+///
+/// Each field enables one check; `None` means the format has no such limit.
+pub(crate) struct IrLossCheck<'a> {
+    /// Format name used in messages
+    pub format: &'a str,
+    /// Deepest IR heading level the format renders as its own level
+    pub max_heading_level: Option<usize>,
+    /// Whether the format can turn a reference into a link
+    pub linkable: Option<fn(&str) -> bool>,
+}
+
+impl IrLossCheck<'_> {
+    pub(crate) fn run(&self, ir: &IrDocument, report: &mut ConversionReport) {
+        let mut scanner = LossScanner {
+            check: self,
+            span: None,
+            report,
+        };
+        walk_document(&mut scanner, ir);
+    }
+}
+
+struct LossScanner<'a, 'r> {
+    check: &'a IrLossCheck<'a>,
+    /// Span of the innermost enclosing node that has one
+    span: Option<SourceSpan>,
+    report: &'r mut ConversionReport,
+}
+
+impl Visitor for LossScanner<'_, '_> {
+    fn visit_node(&mut self, node: &DocNode) {
+        let outer = self.span.clone();
+        if let Some(span) = node.span() {
+            self.span = Some(span.clone());
+        }
+
+        if let (DocNode::Heading(heading), Some(max)) = (node, self.check.max_heading_level) {
+            if heading.level > max {
+                self.report.push(
+                    ConversionWarning::new(
+                        WarningKind::HeadingClamped,
+                        format!(
+                            "Session at level {} is deeper than {} supports and is rendered at level {max}",
+                            heading.level, self.check.format
+                        ),
+                    )
+                    .with_span(self.span.clone()),
+                );
+            }
+        }
+
+        walk_node(self, node);
+        self.span = outer;
+    }
+
+    fn visit_inline(&mut self, inline: &InlineContent) {
+        if let (InlineContent::Reference(target), Some(linkable)) = (inline, self.check.linkable) {
+            if !linkable(target) {
+                self.report.push(
+                    ConversionWarning::new(
+                        WarningKind::ReferenceFlattened,
+                        format!(
+                            "Reference [{target}] has no {} link form and is kept as text",
+                            self.check.format
+                        ),
+                    )
+                    .with_span(self.span.clone()),
+                );
+            }
+        }
+        walk_inline(self, inline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::nodes::{Heading, Paragraph, SourcePosition};
+
+    fn span(line: usize) -> Option<SourceSpan> {
+        Some(SourceSpan {
+            bytes: 0..0,
+            start: SourcePosition::new(line, 0),
+            end: SourcePosition::new(line, 1),
+        })
+    }
+
+    #[test]
+    fn test_loss_check_reports_deep_headings_and_references() {
+        let doc = IrDocument {
+            children: vec![DocNode::Heading(Heading {
+                level: 7,
+                content: vec![InlineContent::Text("Deep".to_string())],
+                children: vec![DocNode::Paragraph(Paragraph {
+                    content: vec![
+                        InlineContent::Reference("TK".to_string()),
+                        InlineContent::Reference("https://example.com".to_string()),
+                    ],
+                    span: span(4),
+                })],
+                id: None,
+                span: span(2),
+            })],
+            metadata: Default::default(),
+        };
+
+        let mut report = ConversionReport::new();
+        IrLossCheck {
+            format: "Markdown",
+            max_heading_level: Some(6),
+            linkable: Some(|target| target.starts_with("http")),
+        }
+        .run(&doc, &mut report);
+
+        let kinds: Vec<WarningKind> = report.warnings().iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![WarningKind::HeadingClamped, WarningKind::ReferenceFlattened]
+        );
+        assert_eq!(report.warnings()[1].span, span(4));
+        assert!(report.warnings()[1].to_string().starts_with("line 5: "));
+    }
+}
@@ -33,4 +33,6 @@ pub mod verbatim;
 
 pub use asset::{build_asset_snippet, AssetKind, AssetSnippet, AssetSnippetRequest};
 pub(crate) use util::normalize_path;
-pub use verbatim::{build_verbatim_snippet, VerbatimSnippet, VerbatimSnippetRequest};
+pub use verbatim::{
+    build_verbatim_snippet, infer_language, VerbatimSnippet, VerbatimSnippetRequest,
+};
@@ -125,6 +125,25 @@ fn language_label(request: &VerbatimSnippetRequest<'_>) -> String {
         .unwrap_or_else(|| "text".to_string())
 }
 
+/// The language label for a code sample taken from `path`
+///
+/// `label` wins when it names a known language (`rs` becomes `rust`);
+/// otherwise the file extension decides, as for new snippets. Unknown labels
+/// are kept when the extension says nothing either.
+pub fn infer_language(path: &Path, label: Option<&str>) -> String {
+    let known = |token: &str| language_from_token(&token.trim().to_ascii_lowercase());
+    label
+        .and_then(known)
+        .or_else(|| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(known)
+        })
+        .map(str::to_string)
+        .or_else(|| label.map(canonical_language))
+        .unwrap_or_else(|| "text".to_string())
+}
+
 fn canonical_language(value: &str) -> String {
     let token = value.trim().to_ascii_lowercase();
     language_from_token(&token)
@@ -231,6 +250,16 @@ mod tests {
         assert_eq!(snippet.cursor_offset, rules.indent_string.len());
     }
 
+    #[test]
+    fn infer_language_prefers_known_labels() {
+        let path = Path::new("src/main.rs");
+        assert_eq!(infer_language(path, Some("rs")), "rust");
+        assert_eq!(infer_language(path, Some("python")), "python");
+        assert_eq!(infer_language(path, Some("code")), "rust");
+        assert_eq!(infer_language(Path::new("notes"), Some("code")), "code");
+        assert_eq!(infer_language(Path::new("notes"), None), "text");
+    }
+
     #[test]
     fn binary_files_use_media_labels() {
         let dir = tempdir().unwrap();
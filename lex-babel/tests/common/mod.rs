@@ -14,33 +14,44 @@ fn create_test_tree() -> DocNode {
                 children: vec![
                     DocNode::Paragraph(Paragraph {
                         content: vec![InlineContent::Text("Paragraph 1".to_string())],
+                        span: None,
                     }),
                     DocNode::List(List {
                         items: vec![
                             ListItem {
                                 content: vec![InlineContent::Text("Item 1".to_string())],
                                 children: vec![],
+                                checked: None,
+                                span: None,
                             },
                             ListItem {
                                 content: vec![InlineContent::Text("Item 2".to_string())],
                                 children: vec![DocNode::Paragraph(Paragraph {
                                     content: vec![InlineContent::Text("Nested Para".to_string())],
+                                    span: None,
                                 })],
+                                checked: None,
+                                span: None,
                             },
                         ],
                         ordered: false,
                         style: ListStyle::Bullet,
                         form: ListForm::Short,
+                        span: None,
                     }),
                 ],
+                id: None,
+                span: None,
             }),
             DocNode::Paragraph(Paragraph {
                 content: vec![
                     InlineContent::Text("Final ".to_string()),
                     InlineContent::Bold(vec![InlineContent::Text("paragraph".to_string())]),
                 ],
+                span: None,
             }),
         ],
+        ..Default::default()
     })
 }
 
@@ -77,10 +88,10 @@ fn test_event_stream_generation() {
             style: ListStyle::Bullet,
             form: ListForm::Short,
         },
-        Event::StartListItem,
+        Event::StartListItem { checked: None },
         Event::Inline(InlineContent::Text("Item 1".to_string())),
         Event::EndListItem,
-        Event::StartListItem,
+        Event::StartListItem { checked: None },
         Event::Inline(InlineContent::Text("Item 2".to_string())),
         Event::StartContent,
         Event::StartParagraph,
@@ -102,3 +113,50 @@ fn test_event_stream_generation() {
 
     assert_eq!(events, expected_events);
 }
+
+#[test]
+fn test_round_trip_new_node_kinds() {
+    let tree = DocNode::Document(Document {
+        children: vec![
+            DocNode::BlockQuote(BlockQuote {
+                children: vec![DocNode::Paragraph(Paragraph {
+                    content: vec![
+                        InlineContent::Strikethrough(vec![InlineContent::Text("old".to_string())]),
+                        InlineContent::Link(Link {
+                            url: "https://example.org".to_string(),
+                            content: vec![InlineContent::Text("site".to_string())],
+                            title: None,
+                        }),
+                        InlineContent::FootnoteRef("1".to_string()),
+                    ],
+                    span: None,
+                })],
+                span: None,
+            }),
+            DocNode::ThematicBreak,
+            DocNode::List(List {
+                items: vec![ListItem {
+                    content: vec![InlineContent::Text("Task".to_string())],
+                    children: vec![],
+                    checked: Some(true),
+                    span: None,
+                }],
+                ordered: false,
+                style: ListStyle::Bullet,
+                form: ListForm::Short,
+                span: None,
+            }),
+        ],
+        ..Default::default()
+    });
+
+    let events = tree_to_events(&tree);
+    assert!(events.contains(&Event::StartBlockQuote));
+    assert!(events.contains(&Event::ThematicBreak));
+    assert!(events.contains(&Event::StartListItem {
+        checked: Some(true)
+    }));
+
+    let reconstructed = DocNode::Document(events_to_tree(&events).unwrap());
+    assert_eq!(tree, reconstructed);
+}
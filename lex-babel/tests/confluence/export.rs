@@ -0,0 +1,121 @@
+//! Export tests for Confluence storage format (Lex → storage XHTML)
+//!
+//! Whole-document output is compared against golden files in
+//! `tests/fixtures/confluence/`. Run with `UPDATE_GOLDEN=1` to rewrite them
+//! after an intentional change.
+
+use lex_babel::format::{Format, SerializedDocument};
+use lex_babel::formats::confluence::ConfluenceFormat;
+use lex_babel::formats::markdown::MarkdownFormat;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("confluence")
+        .join(name)
+}
+
+fn lex_to_confluence(lex_src: &str, options: &HashMap<String, String>) -> String {
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    match ConfluenceFormat
+        .serialize_with_options(&lex_doc, options)
+        .unwrap()
+    {
+        SerializedDocument::Text(text) => text,
+        SerializedDocument::Binary(_) => panic!("Confluence export should be text"),
+    }
+}
+
+/// Compare the export of `<name>.lex` with the golden `<name>.xml`
+fn assert_golden(name: &str) {
+    let source = std::fs::read_to_string(fixture_path(&format!("{name}.lex"))).unwrap();
+    let actual = lex_to_confluence(&source, &HashMap::new());
+
+    let golden_path = fixture_path(&format!("{name}.xml"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden_path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden_path)
+        .unwrap_or_else(|e| panic!("Failed to read {golden_path:?}: {e}"));
+    assert_eq!(actual, expected, "Golden mismatch for {name}");
+}
+
+#[test]
+fn test_golden_release_notes() {
+    assert_golden("release-notes");
+}
+
+#[test]
+fn test_toc_macro_option() {
+    let lex_src = "1. Intro\n\n    Hello.\n";
+
+    let plain = lex_to_confluence(lex_src, &HashMap::new());
+    assert!(!plain.contains("ac:name=\"toc\""));
+
+    let mut options = HashMap::new();
+    options.insert("toc".to_string(), "true".to_string());
+    let with_toc = lex_to_confluence(lex_src, &options);
+    assert!(with_toc.starts_with("<ac:structured-macro ac:name=\"toc\" />\n<h1>"));
+}
+
+#[test]
+fn test_invalid_toc_value_is_rejected() {
+    let lex_doc = STRING_TO_AST.run("Hello.\n".to_string()).unwrap();
+    let mut options = HashMap::new();
+    options.insert("toc".to_string(), "maybe".to_string());
+
+    assert!(ConfluenceFormat
+        .serialize_with_options(&lex_doc, &options)
+        .is_err());
+}
+
+#[test]
+fn test_warning_annotation_becomes_warning_macro() {
+    let md = "<!-- lex:note type=warning -->\nThis is a warning.\n<!-- /lex:note -->\n";
+    let doc = MarkdownFormat.parse(md).expect("Failed to parse markdown");
+    let output = ConfluenceFormat.serialize(&doc).unwrap();
+
+    assert!(
+        output.contains("<ac:structured-macro ac:name=\"warning\"><ac:rich-text-body>"),
+        "{output}"
+    );
+    assert!(output.contains("<p>This is a warning.</p>"), "{output}");
+}
+
+#[test]
+fn test_code_macro_maps_language_and_escapes_cdata() {
+    let lex_src = "Snippet:\n\n    data = \"]]>\"\n\n:: python ::\n";
+    let output = lex_to_confluence(lex_src, &HashMap::new());
+
+    assert!(output.contains("<ac:parameter ac:name=\"language\">py</ac:parameter>"));
+    assert!(output.contains("<![CDATA[data = \"]]]]><![CDATA[>\"]]>"));
+}
+
+#[test]
+fn test_task_items_with_blocks_stay_a_plain_list() {
+    let lex_src = "- [x] Done\n    Some detail.\n- [ ] Pending\n";
+    let output = lex_to_confluence(lex_src, &HashMap::new());
+
+    assert!(!output.contains("<ac:task-list>"), "{output}");
+    assert!(output.contains("<li>[x] Done"), "{output}");
+    assert!(output.contains("<p>Some detail.</p>"), "{output}");
+
+    let output = lex_to_confluence("- [x] Done\n- [ ] Pending\n", &HashMap::new());
+    assert!(output.contains("<ac:task-list>"), "{output}");
+}
+
+#[test]
+fn test_other_annotations_keep_their_content() {
+    let md = "<!-- lex:aside -->\nAn aside.\n<!-- /lex:aside -->\n";
+    let doc = MarkdownFormat.parse(md).expect("Failed to parse markdown");
+    let output = ConfluenceFormat.serialize(&doc).unwrap();
+
+    assert!(!output.contains("ac:structured-macro"), "{output}");
+    assert!(output.contains("<p>An aside.</p>"), "{output}");
+}
@@ -0,0 +1,5 @@
+//! Confluence storage format tests
+//!
+//! Tests for Lex → Confluence export.
+
+mod export;
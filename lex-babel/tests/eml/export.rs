@@ -0,0 +1,166 @@
+//! Export tests for EML format (Lex → MIME message)
+
+use lex_babel::format::{Format, SerializedDocument};
+use lex_babel::formats::eml::EmlFormat;
+use lex_core::lex::ast::Document;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+use std::collections::HashMap;
+
+fn to_eml(doc: &Document, options: &[(&str, &str)]) -> String {
+    let options: HashMap<String, String> = options
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    match EmlFormat.serialize_with_options(doc, &options).unwrap() {
+        SerializedDocument::Binary(bytes) => String::from_utf8(bytes).unwrap(),
+        SerializedDocument::Text(_) => panic!("EML export should be binary"),
+    }
+}
+
+fn lex_to_eml(lex_src: &str, options: &[(&str, &str)]) -> String {
+    let doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    to_eml(&doc, options)
+}
+
+/// Undo quoted-printable soft line breaks and `=XX` escapes
+fn decode_qp(encoded: &str) -> String {
+    let joined = encoded.replace("=\r\n", "");
+    let mut bytes = Vec::new();
+    let mut rest = joined.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'=' && tail.len() >= 2 {
+            let hex = std::str::from_utf8(&tail[..2]).unwrap();
+            bytes.push(u8::from_str_radix(hex, 16).unwrap());
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Decoded body of the first part with the given content type
+fn part_body(eml: &str, content_type: &str) -> String {
+    let start = eml
+        .find(&format!("Content-Type: {content_type}"))
+        .unwrap_or_else(|| panic!("no {content_type} part in:\n{eml}"));
+    let body_start = start + eml[start..].find("\r\n\r\n").unwrap() + 4;
+    let body_end = body_start + eml[body_start..].find("\r\n--=_lex_").unwrap();
+    decode_qp(&eml[body_start..body_end])
+}
+
+const REPORT: &str = ":: subject ::\n    Weekly status\n::\n\n:: from ::\n    Ana <ana@example.org>\n::\n\n:: to ::\n    team@example.org\n::\n\n1. Done\n\n    Shipped the *exporter* this week.\n";
+
+#[test]
+fn test_headers_come_from_metadata() {
+    let eml = lex_to_eml(REPORT, &[]);
+
+    assert!(eml.contains("Subject: Weekly status\r\n"), "{eml}");
+    assert!(eml.contains("From: Ana <ana@example.org>\r\n"), "{eml}");
+    assert!(eml.contains("To: team@example.org\r\n"), "{eml}");
+    assert!(eml.contains("MIME-Version: 1.0\r\n"), "{eml}");
+    assert!(eml.contains("Content-Type: multipart/alternative;\r\n\tboundary="));
+}
+
+#[test]
+fn test_options_override_metadata() {
+    let eml = lex_to_eml(
+        REPORT,
+        &[("to", "lead@example.org"), ("subject", "Status – week 12")],
+    );
+
+    assert!(eml.contains("To: lead@example.org\r\n"), "{eml}");
+    // Non-ASCII subjects use an RFC 2047 encoded word
+    assert!(eml.contains("Subject: =?UTF-8?B?"), "{eml}");
+}
+
+#[test]
+fn test_plain_text_and_html_alternatives() {
+    let eml = lex_to_eml(REPORT, &[]);
+
+    let text = part_body(&eml, "text/plain");
+    assert!(text.contains("1.  Done"), "{text}");
+    assert!(text.contains("Shipped the *exporter* this week."), "{text}");
+
+    let html = part_body(&eml, "text/html");
+    assert!(html.contains("<strong"), "{html}");
+    assert!(!html.contains("<style"), "{html}");
+    assert!(!html.contains("<script"), "{html}");
+    // CSS is inlined into style attributes
+    assert!(html.contains("<body style=\""), "{html}");
+    assert!(
+        html.contains("<p class=\"lex-paragraph\" style=\""),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_lines_are_within_mime_limits() {
+    let long = "word ".repeat(200);
+    let eml = lex_to_eml(&format!("{long}\n"), &[]);
+
+    for line in eml.split("\r\n") {
+        assert!(line.len() <= 76, "line too long: {line:?}");
+    }
+    assert!(
+        !eml.replace("\r\n", "").contains('\n'),
+        "bare LF in message"
+    );
+}
+
+#[test]
+fn test_local_images_become_related_cid_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("chart.png"), b"\x89PNG\r\n\x1a\nfake").unwrap();
+
+    let eml = lex_to_eml(
+        "Chart:\n:: doc.image src=\"chart.png\", alt=\"Chart\" ::\n\n\
+         Logo:\n:: doc.image src=\"https://example.org/logo.png\", alt=\"Logo\" ::\n",
+        &[("base-dir", dir.path().to_str().unwrap())],
+    );
+
+    assert!(eml.contains("Content-Type: multipart/related;"), "{eml}");
+    assert!(
+        eml.contains("Content-Type: image/png; name=\"chart.png\""),
+        "{eml}"
+    );
+    assert!(eml.contains("Content-ID: <image1@lex>"), "{eml}");
+
+    let html = part_body(&eml, "text/html");
+    assert!(html.contains("src=\"cid:image1@lex\""), "{html}");
+    assert!(
+        html.contains("src=\"https://example.org/logo.png\""),
+        "{html}"
+    );
+}
+
+#[test]
+#[cfg(feature = "highlight")]
+fn test_code_follows_code_theme() {
+    let lex_src = "Example:\n\n    def hello():\n        return 1\n\n:: python ::\n";
+
+    let html = part_body(&lex_to_eml(lex_src, &[]), "text/html");
+    assert!(html.contains(">def</span>"), "{html}");
+
+    let plain = part_body(&lex_to_eml(lex_src, &[("code-theme", "none")]), "text/html");
+    assert!(plain.contains("def hello():"), "{plain}");
+    assert!(!plain.contains("</span>"), "{plain}");
+}
+
+#[test]
+fn test_missing_image_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let doc = STRING_TO_AST
+        .run("Chart:\n:: doc.image src=\"missing.png\" ::\n".to_string())
+        .unwrap();
+    let options = HashMap::from([(
+        "base-dir".to_string(),
+        dir.path().to_str().unwrap().to_string(),
+    )]);
+
+    let err = EmlFormat
+        .serialize_with_options(&doc, &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("missing.png"), "{err}");
+}
@@ -0,0 +1,5 @@
+//! EML format tests
+//!
+//! Tests for Lex → MIME email export.
+
+mod export;
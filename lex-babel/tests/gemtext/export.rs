@@ -0,0 +1,112 @@
+//! Export tests for Gemtext format (Lex → Gemtext)
+
+use lex_babel::format::Format;
+use lex_babel::formats::gemtext::GemtextFormat;
+use lex_babel::report::WarningKind;
+use lex_babel::FormatRegistry;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+use std::collections::HashMap;
+
+fn lex_to_gemtext(lex_src: &str) -> String {
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    GemtextFormat.serialize(&lex_doc).unwrap()
+}
+
+#[test]
+fn test_sessions_become_headings() {
+    let lex_src =
+        "1. Introduction\n\n    Welcome.\n\n    1.1. Background\n\n        Some history.\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(gmi.contains("## 1. Introduction"), "{gmi}");
+    assert!(gmi.contains("### 1.1. Background"), "{gmi}");
+    assert!(gmi.contains("\nWelcome.\n"), "{gmi}");
+}
+
+#[test]
+fn test_deep_sessions_get_numbering_prefix() {
+    let lex_src = "One\n\n    Two\n\n        Three\n\n            Deep text.\n\n        Four\n\n            More text.\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(!gmi.contains("####"), "{gmi}");
+    assert!(gmi.contains("### 1 Three"), "{gmi}");
+    assert!(gmi.contains("### 2 Four"), "{gmi}");
+}
+
+#[test]
+fn test_nested_lists_are_flattened() {
+    let lex_src = "- Fruit\n    - Apple\n    - Pear\n- Vegetables\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(
+        gmi.contains("* Fruit\n* Apple\n* Pear\n* Vegetables"),
+        "{gmi}"
+    );
+}
+
+#[test]
+fn test_list_item_children_follow_their_bullet() {
+    let lex_src = "Intro text.\n\n- First item\n    More about it.\n\n    Example:\n        let x = 1;\n    :: rust ::\n- Second item\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(
+        gmi.contains("* First item\nMore about it.\n```Example\nlet x = 1;\n```\n* Second item"),
+        "{gmi}"
+    );
+}
+
+#[test]
+fn test_verbatim_uses_subject_as_alt_text() {
+    let lex_src = "Hello World:\n\n    print(\"hello\")\n\n:: python ::\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(
+        gmi.contains("```Hello World\nprint(\"hello\")\n```"),
+        "{gmi}"
+    );
+}
+
+#[test]
+fn test_references_become_link_lines() {
+    let lex_src = "Read the spec [https://example.org/spec] and the notes [./notes.gmi] today.\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(
+        gmi.contains("Read the spec and the notes today.\n=> https://example.org/spec spec\n=> ./notes.gmi notes"),
+        "{gmi}"
+    );
+    assert!(!gmi.contains('['), "no inline links expected: {gmi}");
+}
+
+#[test]
+fn test_citations_stay_inline() {
+    let lex_src = "As shown before [@smith2020].\n";
+    let gmi = lex_to_gemtext(lex_src);
+
+    assert!(gmi.contains("[@smith2020]"), "{gmi}");
+    assert!(!gmi.contains("=>"), "{gmi}");
+}
+
+#[test]
+fn test_inline_references_are_reported() {
+    let registry = FormatRegistry::with_defaults();
+    let doc = registry
+        .parse(
+            "Results\n\n    As shown [@smith2020], see [#1] and [https://example.org].\n",
+            "lex",
+        )
+        .unwrap();
+
+    let (_, report) = registry
+        .serialize_with_report(&doc, "gemtext", &HashMap::new(), &[])
+        .unwrap();
+    let flattened: Vec<String> = report
+        .warnings()
+        .iter()
+        .filter(|warning| warning.kind == WarningKind::ReferenceFlattened)
+        .map(|warning| warning.message.clone())
+        .collect();
+    assert_eq!(flattened.len(), 2, "{flattened:?}");
+    assert!(flattened[0].contains("[@smith2020]"), "{flattened:?}");
+    assert!(flattened[1].contains("[#1]"), "{flattened:?}");
+}
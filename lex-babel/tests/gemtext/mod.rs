@@ -0,0 +1,5 @@
+//! Gemtext format tests
+//!
+//! Tests for Lex → Gemtext export.
+
+mod export;
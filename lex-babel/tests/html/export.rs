@@ -4,11 +4,12 @@
 //! by checking the resulting HTML structure.
 
 use insta::assert_snapshot;
-use lex_babel::format::Format;
+use lex_babel::format::{Format, SerializedDocument};
 use lex_babel::formats::html::{HtmlFormat, HtmlTheme};
 use lex_core::lex::transforms::standard::STRING_TO_AST;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::HashMap;
 
 /// Helper to convert Lex source to HTML
 fn lex_to_html(lex_src: &str, theme: HtmlTheme) -> String {
@@ -17,6 +18,22 @@ fn lex_to_html(lex_src: &str, theme: HtmlTheme) -> String {
     html_format.serialize(&lex_doc).unwrap()
 }
 
+/// Helper to convert Lex source to HTML with format options
+fn lex_to_html_with(lex_src: &str, theme: HtmlTheme, options: &[(&str, &str)]) -> String {
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let options: HashMap<String, String> = options
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    match HtmlFormat::new(theme)
+        .serialize_with_options(&lex_doc, &options)
+        .unwrap()
+    {
+        SerializedDocument::Text(html) => html,
+        SerializedDocument::Binary(_) => panic!("expected text output"),
+    }
+}
+
 // ============================================================================
 // BASIC ELEMENT TESTS
 // ============================================================================
@@ -32,12 +49,81 @@ fn test_paragraph_simple() {
     assert!(html.contains("This is a simple paragraph."));
 }
 
+#[test]
+fn test_metadata_meta_tags() {
+    let lex_src = ":: author ::\n    Ana Lima\n::\n\n:: date ::\n    2025-03-14\n::\n\n:: keywords ::\n    - lex\n    - export\n::\n\nBody text.\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("<meta name=\"author\" content=\"Ana Lima\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<meta name=\"date\" content=\"2025-03-14\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<meta name=\"keywords\" content=\"lex, export\">"),
+        "{html}"
+    );
+    assert!(!html.contains("<!-- lex:author"), "{html}");
+}
+
+#[test]
+fn test_source_lines_option() {
+    let lex_src = "- One\n- Two\n\nA closing paragraph.\n";
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let format = HtmlFormat::new(HtmlTheme::Modern);
+
+    let plain = format.serialize(&lex_doc).unwrap();
+    assert!(!plain.contains("data-lex-line"), "{plain}");
+
+    let mut options = HashMap::new();
+    options.insert("source-lines".to_string(), "true".to_string());
+    let html = match format.serialize_with_options(&lex_doc, &options).unwrap() {
+        SerializedDocument::Text(html) => html,
+        SerializedDocument::Binary(_) => panic!("expected text output"),
+    };
+
+    assert!(
+        html.contains("<ul class=\"lex-list\" data-lex-line=\"1\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<li class=\"lex-list-item\" data-lex-line=\"2\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<p class=\"lex-paragraph\" data-lex-line=\"4\">"),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_block_quote_rule_and_task_items() {
+    let lex_src =
+        ":: blockquote ::\n    Quoted text.\n::\n\n:: hr ::\n\n- [x] Done\n- [ ] Pending\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("<blockquote class=\"lex-blockquote\">"),
+        "{html}"
+    );
+    assert!(html.contains("<hr class=\"lex-thematic-break\">"), "{html}");
+    assert!(
+        html.contains("<input type=\"checkbox\" disabled=\"\" checked=\"\">"),
+        "{html}"
+    );
+    assert!(html.contains("Pending"), "{html}");
+    assert!(!html.contains("[x]"), "{html}");
+}
+
 #[test]
 fn test_heading_simple() {
     let lex_src = "1. Introduction\n\n    Some content.\n";
     let html = lex_to_html(lex_src, HtmlTheme::Modern);
 
-    assert!(html.contains("<section class=\"lex-session lex-session-2\">"));
+    assert!(html.contains("<section class=\"lex-session lex-session-2\""));
     assert!(html.contains("<h2>"));
     assert!(html.contains("Introduction"));
     assert!(html.contains("<p class=\"lex-paragraph\">"));
@@ -49,8 +135,8 @@ fn test_multiple_heading_levels() {
     let lex_src = "1. Level 1\n\n    1.1. Level 2\n\n        Content here.\n";
     let html = lex_to_html(lex_src, HtmlTheme::Modern);
 
-    assert!(html.contains("<section class=\"lex-session lex-session-2\">"));
-    assert!(html.contains("<section class=\"lex-session lex-session-3\">"));
+    assert!(html.contains("<section class=\"lex-session lex-session-2\""));
+    assert!(html.contains("<section class=\"lex-session lex-session-3\""));
     assert!(html.contains("<h2>"));
     assert!(html.contains("<h3>"));
 }
@@ -112,15 +198,13 @@ fn test_code_inline() {
 fn test_code_block() {
     let lex_src =
         "Code Example:\n\n    function hello() {\n        return \"world\";\n    }\n\n:: rust ::\n";
-    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    // Unhighlighted, so the source text stays in one piece
+    let html = lex_to_html_with(lex_src, HtmlTheme::Modern, &[("code-theme", "none")]);
 
-    assert!(html.contains("<pre class=\"lex-verbatim\" data-language=\"rust\">"));
+    assert!(html.contains("<pre class=\"lex-verbatim\" data-language=\"rust\""));
     assert!(html.contains("<code class=\"language-rust\">"));
     assert!(html.contains("function hello()"));
     assert!(html.contains("return \"world\""));
-    // highlight.js CDN should be injected
-    assert!(html.contains("highlight.min.js"));
-    assert!(html.contains("hljs.highlightAll()"));
 }
 
 #[test]
@@ -140,8 +224,60 @@ fn test_math_inline() {
     let lex_src = "The formula is #E = mc^2# here.\n";
     let html = lex_to_html(lex_src, HtmlTheme::Modern);
 
-    assert!(html.contains("<span class=\"lex-math\">"));
-    assert!(html.contains("$E = mc^2$")); // Still outputs $ in HTML
+    assert!(html.contains("<span class=\"lex-math\"><math"), "{html}");
+    assert!(html.contains("<msup><mi>c</mi><mn>2</mn></msup>"), "{html}");
+    assert!(!html.contains("$E = mc^2$"), "{html}");
+}
+
+#[test]
+fn test_math_display_block() {
+    let lex_src = "Fractions:\n\n    \\frac{a}{b}\n\n:: doc.math ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("<div class=\"lex-math-display\"><math display=\"block\">"),
+        "{html}"
+    );
+    assert!(html.contains("<mfrac>"), "{html}");
+    assert!(!html.contains("data-language=\"doc.math\""), "{html}");
+}
+
+#[test]
+fn test_math_that_does_not_convert_stays_tex() {
+    let html = lex_to_html("Broken #x^{2# here.\n", HtmlTheme::Modern);
+    assert!(
+        html.contains("<span class=\"lex-math\">$x^{2$</span>"),
+        "{html}"
+    );
+
+    let lex_src = "Broken:\n\n    \\frac{a}{b\n\n:: doc.math ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    assert!(html.contains("data-language=\"doc.math\""), "{html}");
+    assert!(html.contains("\\frac{a}{b"), "{html}");
+}
+
+#[test]
+fn test_dot_diagram_is_inline_svg() {
+    let lex_src = "Flow:\n\n    digraph { a -> b; }\n\n:: doc.dot ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    assert!(
+        html.contains("<figure class=\"lex-diagram\"><svg"),
+        "{html}"
+    );
+    assert!(!html.contains("data-language=\"doc.dot\""), "{html}");
+
+    // Each diagram's arrow heads have ids of their own
+    let lex_src = "Flow:\n\n    digraph { a -> b; }\n\n:: doc.dot ::\n\nBack:\n\n    digraph { b -> a; }\n\n:: doc.dot ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    assert!(html.contains("id=\"lex-diagram-1-endarrow\""), "{html}");
+    assert!(html.contains("url(#lex-diagram-2-endarrow)"), "{html}");
+
+    // The stylesheet mentions lex-diagram, so look at the body only
+    let lex_src = "Flow:\n\n    digraph { a -> \n\n:: dot ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    let body = &html[html.find("<body").unwrap()..];
+    assert!(!body.contains("lex-diagram"), "{body}");
+    assert!(body.contains("data-language=\"dot\""), "{body}");
 }
 
 #[test]
@@ -199,6 +335,110 @@ fn test_anchor_reference_unchanged() {
     assert!(html.contains("<a href=\"#section-3\">"));
 }
 
+#[test]
+fn test_session_references_link_to_section_ids() {
+    let lex_src = "1. Getting Started\n\n    Text.\n\n    1.1. Scope\n\n        See [#2] and [Getting Started].\n\n2. Usage\n\n    Back to [#1.1].\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("<section class=\"lex-session lex-session-2\" id=\"getting-started\">"),
+        "{html}"
+    );
+    assert!(html.contains("id=\"scope\""), "{html}");
+    assert!(
+        html.contains("<a href=\"#usage\" title=\"Usage\">#2</a>"),
+        "{html}"
+    );
+    assert!(html.contains("href=\"#getting-started\""), "{html}");
+    assert!(
+        html.contains("<a href=\"#scope\" title=\"Scope\">#1.1</a>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_footnotes_link_to_notes_and_back() {
+    let lex_src = "Rivers flood [1] in spring [2].\n\nNotes\n\n    1. Snow melt.\n    2. Mostly.\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains(
+            "<sup class=\"lex-footnote-ref\" id=\"fnref-1\"><a href=\"#fn-1\">1</a></sup>"
+        ),
+        "{html}"
+    );
+    assert!(html.contains("<section class=\"lex-footnotes\">"), "{html}");
+    assert!(html.contains("<li id=\"fn-2\" value=\"2\">"), "{html}");
+    assert!(html.contains("Snow melt."), "{html}");
+    assert!(
+        html.contains("<a href=\"#fnref-1\" class=\"lex-footnote-backref\""),
+        "{html}"
+    );
+    // The notes session itself is not rendered as a section
+    assert!(!html.contains("id=\"notes\""), "{html}");
+}
+
+#[test]
+fn test_toc_placeholder_becomes_nav() {
+    let lex_src = "Guide\n\n    Read on.\n\n:: toc ::\n\nIntroduction\n\n    Text.\n\n    Scope\n\n        More text.\n\nUsage\n\n    Run it.\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("<nav class=\"lex-toc\" aria-label=\"Table of contents\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<a href=\"#introduction\">Introduction</a>"),
+        "{html}"
+    );
+    assert!(html.contains("<a href=\"#scope\">Scope</a>"), "{html}");
+    assert!(
+        html.contains("<section class=\"lex-session lex-session-3\" id=\"scope\""),
+        "{html}"
+    );
+    // The nav stands where the placeholder was, between the two sessions
+    let nav = html.find("<nav").unwrap();
+    assert!(html.find("id=\"guide\"").unwrap() < nav, "{html}");
+    assert!(nav < html.find("id=\"introduction\"").unwrap(), "{html}");
+    assert!(html[..nav].ends_with("</section>"), "{html}");
+    assert!(!html.contains("lex:toc"), "{html}");
+}
+
+#[test]
+fn test_toc_option_inserts_numbered_nav() {
+    let lex_src =
+        "Introduction\n\n    Text.\n\n    Scope\n\n        More text.\n\nUsage\n\n    Run it.\n";
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let format = HtmlFormat::new(HtmlTheme::Modern);
+
+    let plain = format.serialize(&lex_doc).unwrap();
+    assert!(!plain.contains("<nav"), "{plain}");
+
+    let options: HashMap<String, String> = [
+        ("toc", "true"),
+        ("toc-depth", "1"),
+        ("toc-numbered", "true"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    let html = match format.serialize_with_options(&lex_doc, &options).unwrap() {
+        SerializedDocument::Text(html) => html,
+        SerializedDocument::Binary(_) => panic!("expected text output"),
+    };
+
+    let nav_start = html
+        .find("<nav class=\"lex-toc\"")
+        .expect("a table of contents");
+    let nav = &html[nav_start..html.find("</nav>").unwrap()];
+    assert!(
+        nav.contains("<a href=\"#introduction\">1 Introduction</a>"),
+        "{nav}"
+    );
+    assert!(nav.contains("<a href=\"#usage\">2 Usage</a>"), "{nav}");
+    assert!(!nav.contains("Scope"), "{nav}");
+}
+
 // TODO: Annotations are not yet fully supported in HTML export
 // Document-level annotations aren't converted to IR/Events
 // #[test]
@@ -215,10 +455,53 @@ fn test_anchor_reference_unchanged() {
 // SYNTAX HIGHLIGHTING TESTS
 // ============================================================================
 
+#[test]
+#[cfg(feature = "highlight")]
+fn test_code_highlighted_at_export() {
+    let lex_src = "Example:\n\n    def hello():\n        return 1\n\n:: python ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("data-language=\"python\" style=\"background:#ffffff;"),
+        "{html}"
+    );
+    assert!(html.contains("<span style=\"color:#"), "{html}");
+    assert!(html.contains(">def</span>"), "{html}");
+    assert!(!html.contains("highlight.min.js"), "no script needed");
+
+    let fancy = lex_to_html(lex_src, HtmlTheme::FancySerif);
+    assert!(fancy.contains("style=\"background:#fdf6e3;"), "{fancy}");
+    let dark = lex_to_html_with(lex_src, HtmlTheme::Modern, &[("code-theme", "mocha")]);
+    assert!(!dark.contains("style=\"background:#ffffff;"), "{dark}");
+}
+
+#[test]
+#[cfg(feature = "highlight")]
+fn test_unknown_language_is_plain_text() {
+    let lex_src = "Example:\n\n    a <b> c\n\n:: no-such-language ::\n";
+    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+
+    assert!(
+        html.contains("data-language=\"no-such-language\" style=\"background:#ffffff;"),
+        "{html}"
+    );
+    assert!(html.contains("a &lt;b&gt; c"), "{html}");
+}
+
+#[test]
+fn test_unknown_code_theme_is_rejected() {
+    let lex_doc = STRING_TO_AST.run("Hello.\n".to_string()).unwrap();
+    let options = HashMap::from([("code-theme".to_string(), "neon".to_string())]);
+    let err = HtmlFormat::default()
+        .serialize_with_options(&lex_doc, &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("neon"), "{err}");
+}
+
 #[test]
 fn test_highlight_js_injected() {
     let lex_src = "Hello world.\n";
-    let html = lex_to_html(lex_src, HtmlTheme::Modern);
+    let html = lex_to_html_with(lex_src, HtmlTheme::Modern, &[("code-theme", "none")]);
 
     assert!(
         html.contains("highlight.min.js"),
@@ -368,7 +651,7 @@ fn test_trifecta_020_paragraphs_sessions_flat_multiple() {
     let html = lex_to_html(&lex_src, HtmlTheme::Modern);
 
     // Verify multiple sessions exist
-    assert!(html.contains("<section class=\"lex-session lex-session-2\">"));
+    assert!(html.contains("<section class=\"lex-session lex-session-2\""));
 
     // Snapshot test
     assert_snapshot!(snapshot_without_styles(&html));
@@ -382,8 +665,8 @@ fn test_trifecta_060_nesting() {
     let html = lex_to_html(&lex_src, HtmlTheme::Modern);
 
     // Verify nested sessions
-    assert!(html.contains("<section class=\"lex-session lex-session-2\">"));
-    assert!(html.contains("<section class=\"lex-session lex-session-3\">"));
+    assert!(html.contains("<section class=\"lex-session lex-session-2\""));
+    assert!(html.contains("<section class=\"lex-session lex-session-3\""));
 
     // Snapshot test
     assert_snapshot!(snapshot_without_styles(&html));
@@ -526,7 +809,8 @@ fn test_kitchensink() {
     let lex_src = std::fs::read_to_string("../comms/specs/benchmark/010-kitchensink.lex")
         .expect("kitchensink file should exist");
 
-    let html = lex_to_html(&lex_src, HtmlTheme::Modern);
+    // Highlighted tokens are covered above; keep the snapshot readable
+    let html = lex_to_html_with(&lex_src, HtmlTheme::Modern, &[("code-theme", "none")]);
 
     // Verify complete HTML document
     assert!(html.contains("<!DOCTYPE html>"));
@@ -1,5 +1,6 @@
 use lex_babel::format::Format;
 use lex_babel::formats::html::HtmlFormat;
+use lex_babel::formats::lex::LexFormat;
 use lex_babel::formats::markdown::MarkdownFormat;
 
 #[test]
@@ -27,3 +28,20 @@ fn test_table_html_export() {
     assert!(html.contains("text-align: center"));
     assert!(html.contains("Cell 2"));
 }
+
+#[test]
+fn test_table_spans_and_caption_html_export() {
+    let lex = "Results:\n    | Region | Sales | <  |\n    | ^      | Q1    | Q2 |\n    | ------ | ----- | -- |\n    | North  | 10    | 12 |\n:: doc.table caption=\"Sales by quarter\" ::\n";
+    let doc = LexFormat::default()
+        .parse(lex)
+        .expect("Failed to parse lex");
+
+    let html = HtmlFormat::default()
+        .serialize(&doc)
+        .expect("Failed to serialize html");
+
+    assert!(html.contains("<caption>Sales by quarter</caption>"));
+    assert!(html.contains("<th rowspan=\"2\">"));
+    assert!(html.contains("<th colspan=\"2\">"));
+    assert!(html.contains("North"));
+}
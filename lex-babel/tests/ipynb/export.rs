@@ -0,0 +1,75 @@
+//! Export tests for the notebook format (Lex → .ipynb)
+
+use lex_babel::format::Format;
+use lex_babel::formats::ipynb::IpynbFormat;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+use serde_json::Value;
+
+fn lex_to_notebook(lex_src: &str) -> Value {
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let output = IpynbFormat.serialize(&lex_doc).unwrap();
+    serde_json::from_str(&output).expect("Exported notebook should be valid JSON")
+}
+
+fn cell_source(cell: &Value) -> String {
+    cell["source"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+#[test]
+fn test_prose_and_code_cells() {
+    let lex_src = "Intro paragraph.\n\nExample:\n\n    print(\"hello\")\n\n:: python ::\n\nClosing paragraph.\n";
+    let notebook = lex_to_notebook(lex_src);
+
+    assert_eq!(notebook["nbformat"], 4);
+    assert_eq!(notebook["metadata"]["language_info"]["name"], "python");
+
+    let cells = notebook["cells"].as_array().unwrap();
+    let types: Vec<&str> = cells
+        .iter()
+        .map(|cell| cell["cell_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, vec!["markdown", "code", "markdown"]);
+
+    assert!(cell_source(&cells[0]).contains("Intro paragraph."));
+    assert_eq!(cell_source(&cells[1]), "print(\"hello\")");
+    let subject = cells[1]["metadata"]["lex"]["subject"].as_str().unwrap();
+    assert!(subject.starts_with("Example"));
+    assert!(cell_source(&cells[2]).contains("Closing paragraph."));
+}
+
+#[test]
+fn test_code_cells_keep_the_authors_blank_lines() {
+    let lex_src = "Example:\n\n    \n    x = 1\n    \n\n:: python ::\n";
+    let notebook = lex_to_notebook(lex_src);
+
+    let cells = notebook["cells"].as_array().unwrap();
+    assert_eq!(cell_source(&cells[0]), "\nx = 1\n");
+}
+
+#[test]
+fn test_non_kernel_verbatim_stays_in_markdown() {
+    let lex_src = "Config:\n\n    key = value\n\n:: yaml ::\n";
+    let notebook = lex_to_notebook(lex_src);
+
+    let cells = notebook["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 1);
+    assert_eq!(cells[0]["cell_type"], "markdown");
+    assert!(cell_source(&cells[0]).contains("``` yaml\nkey = value\n```"));
+}
+
+#[test]
+fn test_sessions_share_markdown_cell() {
+    let lex_src = "1. First\n\n    Body of first.\n\n2. Second\n\n    Body of second.\n";
+    let notebook = lex_to_notebook(lex_src);
+
+    let cells = notebook["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 1);
+    let source = cell_source(&cells[0]);
+    assert!(source.contains("First"));
+    assert!(source.contains("Body of second."));
+}
@@ -0,0 +1,99 @@
+//! Import tests for the notebook format (.ipynb → Lex)
+
+use lex_babel::error::ErrorKind;
+use lex_babel::format::Format;
+use lex_babel::formats::ipynb::IpynbFormat;
+use lex_core::lex::ast::ContentItem;
+
+const NOTEBOOK: &str = r###"{
+  "cells": [
+    {
+      "cell_type": "markdown",
+      "metadata": {},
+      "source": ["## Analysis\n", "\n", "Load the data."]
+    },
+    {
+      "cell_type": "code",
+      "execution_count": 1,
+      "metadata": {},
+      "outputs": [
+        {"output_type": "stream", "name": "stdout", "text": ["42\n"]},
+        {
+          "output_type": "display_data",
+          "metadata": {},
+          "data": {"image/png": "iVBORw0KGgo=\n", "text/plain": ["<Figure>"]}
+        }
+      ],
+      "source": ["x = 6 * 7\n", "print(x)"]
+    }
+  ],
+  "metadata": {"language_info": {"name": "python"}},
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"###;
+
+#[test]
+fn test_markdown_and_code_cells() {
+    let doc = IpynbFormat.parse(NOTEBOOK).expect("Should parse notebook");
+
+    // The heading in the markdown cell nests the code cell that follows it
+    let session = match &doc.root.children[0] {
+        ContentItem::Session(session) => session,
+        other => panic!("Expected session, found {other:?}"),
+    };
+    assert!(session.title.as_string().contains("Analysis"));
+
+    let verbatim = session
+        .children
+        .iter()
+        .find_map(|item| match item {
+            ContentItem::VerbatimBlock(verbatim) => Some(verbatim),
+            _ => None,
+        })
+        .expect("Code cell should become a verbatim block");
+    assert_eq!(verbatim.closing_data.label.value, "python");
+}
+
+#[test]
+fn test_outputs_become_annotation() {
+    let doc = IpynbFormat.parse(NOTEBOOK).expect("Should parse notebook");
+
+    let session = match &doc.root.children[0] {
+        ContentItem::Session(session) => session,
+        other => panic!("Expected session, found {other:?}"),
+    };
+    let has_output = session.children.iter().any(
+        |item| matches!(item, ContentItem::Annotation(ann) if ann.data.label.value == "output"),
+    );
+    assert!(has_output, "Outputs should be kept in an output annotation");
+}
+
+#[test]
+fn test_round_trip_keeps_code_cells() {
+    let doc = IpynbFormat.parse(NOTEBOOK).expect("Should parse notebook");
+    let exported = IpynbFormat.serialize(&doc).expect("Should export notebook");
+
+    let notebook: serde_json::Value = serde_json::from_str(&exported).unwrap();
+    let code_cells: Vec<_> = notebook["cells"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|cell| cell["cell_type"] == "code")
+        .collect();
+    assert_eq!(code_cells.len(), 1);
+    assert_eq!(code_cells[0]["outputs"][0]["output_type"], "stream");
+    assert_eq!(
+        code_cells[0]["outputs"][1]["data"]["image/png"],
+        "iVBORw0KGgo="
+    );
+}
+
+#[test]
+fn test_invalid_json_is_parse_error() {
+    let err = IpynbFormat.parse("{ not json").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Parse);
+    assert_eq!(err.code(), "json-syntax");
+    assert!(err.message().contains("Invalid notebook JSON"));
+    assert_eq!(err.position().map(|pos| pos.line), Some(0));
+    assert!(std::error::Error::source(&err).is_some());
+}
@@ -0,0 +1,6 @@
+//! Jupyter notebook format tests
+//!
+//! Tests for bidirectional notebook ↔ Lex conversion.
+
+mod export;
+mod import;
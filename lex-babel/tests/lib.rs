@@ -3,14 +3,29 @@
 #[cfg(test)]
 mod common;
 
+#[cfg(test)]
+mod confluence;
+
+#[cfg(test)]
+mod eml;
+
+#[cfg(test)]
+mod gemtext;
+
 #[cfg(test)]
 mod html;
 
+#[cfg(test)]
+mod ipynb;
+
 #[cfg(test)]
 mod markdown;
 
 #[cfg(test)]
 mod pdf;
 
+#[cfg(test)]
+mod rfc_text;
+
 #[cfg(test)]
 mod rfc_xml;
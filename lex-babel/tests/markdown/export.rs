@@ -338,6 +338,43 @@ fn test_reference_anchor_converted_to_link() {
     );
 }
 
+#[test]
+fn test_session_reference_gets_heading_anchor() {
+    let lex_src = "1. Intro\n\n    See [#2].\n\n2. Usage\n\n    Text.\n";
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let md = MarkdownFormat.serialize(&lex_doc).unwrap();
+
+    assert!(md.contains("(#usage \"Usage\")"), "{md}");
+    // Only the heading something links to carries an anchor
+    assert!(md.contains("<a id=\"usage\"></a>"), "{md}");
+    assert!(!md.contains("<a id=\"intro\">"), "{md}");
+}
+
+#[test]
+fn test_notes_become_footnote_definitions() {
+    let lex_src = "Rivers flood [1].\n\nNotes\n\n    1. Snow melt.\n    2. Mostly.\n";
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let md = MarkdownFormat.serialize(&lex_doc).unwrap();
+
+    assert!(md.contains("Rivers flood [^1]."), "{md}");
+    assert!(md.contains("[^1]: Snow melt.\n"), "{md}");
+    assert!(md.contains("[^2]: Mostly.\n"), "{md}");
+    assert!(!md.contains("Notes"), "{md}");
+}
+
+#[test]
+fn test_toc_becomes_nested_link_list() {
+    let lex_src = "Guide\n\n    Read on.\n\n:: toc ::\n\nIntroduction\n\n    Text.\n\n    Scope\n\n        More text.\n\nUsage\n\n    Run it.\n";
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    let md = MarkdownFormat.serialize(&lex_doc).unwrap();
+
+    assert!(md.contains("- [Introduction](#introduction)\n"), "{md}");
+    assert!(md.contains("  - [Scope](#scope)\n"), "{md}");
+    assert!(md.contains("- [Usage](#usage)\n"), "{md}");
+    // The linked headings carry their anchors
+    assert!(md.contains("<a id=\"scope\"></a>"), "{md}");
+}
+
 #[test]
 fn test_citation_converted_to_ref_link() {
     let lex_src = "According to [@smith2023], this is true.\n";
@@ -1,6 +1,8 @@
 use lex_babel::format::Format;
 use lex_babel::formats::markdown::MarkdownFormat;
+use lex_babel::ir::nodes::{DocNode, MetadataValue};
 use lex_core::lex::ast::ContentItem;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
 
 #[test]
 fn test_frontmatter_import() {
@@ -66,3 +68,77 @@ Content.
     assert!(output.contains("tags: [a, b]"));
     assert!(output.contains("---\n\nContent."));
 }
+
+#[test]
+fn test_frontmatter_lists_and_fields_round_trip() {
+    let md = r#"---
+title: Typed
+authors:
+  - Ana
+  - Bo
+author:
+  name: Cy
+  email: cy@example.org
+---
+
+Content.
+"#;
+
+    let doc = MarkdownFormat.parse(md).expect("Failed to parse markdown");
+    let metadata = lex_babel::to_ir(&doc).metadata;
+    assert_eq!(metadata.title().as_deref(), Some("Typed"));
+    assert_eq!(
+        metadata.get("authors"),
+        Some(&MetadataValue::List(vec!["Ana".into(), "Bo".into()]))
+    );
+    assert_eq!(
+        metadata.get("author"),
+        Some(&MetadataValue::Fields(vec![
+            ("name".into(), "Cy".into()),
+            ("email".into(), "cy@example.org".into()),
+        ]))
+    );
+
+    let output = MarkdownFormat
+        .serialize(&doc)
+        .expect("Failed to serialize markdown");
+    assert!(output.contains("authors: [Ana, Bo]\n"), "{output}");
+    assert!(
+        output.contains("author:\n  name: Cy\n  email: cy@example.org\n"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_lex_metadata_annotations_become_front_matter() {
+    let lex_src =
+        ":: title ::\n    Notes\n::\n\n:: tags ::\n    - draft\n    - ideas\n::\n\nBody text.\n";
+    let doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+
+    let ir_doc = lex_babel::to_ir(&doc);
+    assert_eq!(ir_doc.metadata.tags(), vec!["draft", "ideas"]);
+    assert!(ir_doc
+        .children
+        .iter()
+        .all(|node| !matches!(node, DocNode::Annotation(_))));
+
+    let output = MarkdownFormat.serialize(&doc).unwrap();
+    assert!(
+        output.starts_with("---\ntitle: Notes\ntags: [draft, ideas]\n---\n\n"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_metadata_labels_are_configurable() {
+    let lex_src = ":: title ::\n    Notes\n::\n\n:: status ::\n    Draft\n::\n\nBody text.\n";
+    let doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+
+    let ir_doc = lex_babel::to_ir_with_metadata_labels(&doc, &["status"]);
+    assert_eq!(ir_doc.metadata.get_text("status").as_deref(), Some("Draft"));
+    assert_eq!(ir_doc.metadata.title(), None);
+    assert!(ir_doc.children.iter().any(|node| matches!(
+        node,
+        DocNode::Annotation(annotation) if annotation.label == "title"
+    )));
+}
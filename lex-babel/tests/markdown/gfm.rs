@@ -0,0 +1,200 @@
+//! GFM constructs that have their own IR nodes: strikethrough, links with
+//! text, footnote references, block quotes, thematic breaks and task items
+
+use lex_babel::format::Format;
+use lex_babel::formats::lex::LexFormat;
+use lex_babel::formats::markdown::parser::parse_markdown_to_ir;
+use lex_babel::formats::markdown::MarkdownFormat;
+use lex_babel::ir::nodes::{DocNode, InlineContent, Link};
+
+const GFM: &str = r#"Some ~~old~~ text with [the docs](https://example.org/docs) and a note[^1].
+
+> Quoted *wisdom*.
+
+---
+
+- [x] Done
+- [ ] Pending
+
+[^1]: The note.
+"#;
+
+fn md_to_ir(md: &str) -> lex_babel::ir::nodes::Document {
+    parse_markdown_to_ir(md).expect("Should parse markdown")
+}
+
+#[test]
+fn test_import_inline_nodes() {
+    let ir = md_to_ir(GFM);
+    let Some(DocNode::Paragraph(paragraph)) = ir.children.first() else {
+        panic!("Expected a paragraph first: {:?}", ir.children);
+    };
+
+    assert!(paragraph
+        .content
+        .contains(&InlineContent::Strikethrough(vec![InlineContent::Text(
+            "old".to_string()
+        )])));
+    assert!(paragraph.content.contains(&InlineContent::Link(Link {
+        url: "https://example.org/docs".to_string(),
+        content: vec![InlineContent::Text("the docs".to_string())],
+        title: None,
+    })));
+    assert!(paragraph
+        .content
+        .contains(&InlineContent::FootnoteRef("1".to_string())));
+}
+
+#[test]
+fn test_import_block_nodes() {
+    let ir = md_to_ir(GFM);
+
+    assert!(ir
+        .children
+        .iter()
+        .any(|node| matches!(node, DocNode::BlockQuote(quote) if !quote.children.is_empty())));
+    assert!(ir
+        .children
+        .iter()
+        .any(|node| matches!(node, DocNode::ThematicBreak)));
+
+    let list = ir
+        .children
+        .iter()
+        .find_map(|node| match node {
+            DocNode::List(list) => Some(list),
+            _ => None,
+        })
+        .expect("Expected a task list");
+    let checked: Vec<Option<bool>> = list.items.iter().map(|item| item.checked).collect();
+    assert_eq!(checked, vec![Some(true), Some(false)]);
+}
+
+#[test]
+fn test_round_trip_through_lex() {
+    // A heading keeps the first paragraph from becoming the document title
+    let doc = MarkdownFormat
+        .parse(&format!("# GFM\n\n{GFM}"))
+        .expect("Should parse markdown");
+    let md = MarkdownFormat.serialize(&doc).expect("Should serialize");
+
+    // Lex keeps a link's URL as a reference after its text
+    assert!(md.contains("the docs <https://example.org/docs>"), "{md}");
+    assert!(md.contains("[^1]"), "{md}");
+    assert!(md.contains("> Quoted *wisdom*."), "{md}");
+    assert!(md.contains("---"), "{md}");
+    assert!(md.contains("- [x] Done"), "{md}");
+    assert!(md.contains("- [ ] Pending"), "{md}");
+}
+
+#[test]
+fn test_footnote_definitions_become_notes_session() {
+    let ir = md_to_ir("Floods[^why] and droughts[^1].\n\n[^1]: Heat.\n\n[^why]: Snow melt.\n");
+
+    let Some(DocNode::Paragraph(paragraph)) = ir.children.first() else {
+        panic!("Expected a paragraph first: {:?}", ir.children);
+    };
+    let labels: Vec<&str> = paragraph
+        .content
+        .iter()
+        .filter_map(|inline| match inline {
+            InlineContent::FootnoteRef(label) => Some(label.as_str()),
+            _ => None,
+        })
+        .collect();
+    // Numbered in the order they are first cited
+    assert_eq!(labels, vec!["1", "2"]);
+
+    let mut ir = ir;
+    let notes = lex_babel::ir::footnotes::take_notes(&mut ir);
+    let labels: Vec<&str> = notes.iter().map(|note| note.label.as_str()).collect();
+    assert_eq!(labels, vec!["1", "2"]);
+    assert!(format!("{:?}", notes[0].content).contains("Snow melt."));
+}
+
+#[test]
+fn test_footnote_definitions_under_a_heading_become_notes_session() {
+    let mut ir = md_to_ir("# Weather\n\nFloods[^1].\n\n[^1]: Snow melt.\n");
+
+    let notes = lex_babel::ir::footnotes::take_notes(&mut ir);
+    assert_eq!(notes.len(), 1, "{:?}", ir.children);
+    assert!(format!("{:?}", notes[0].content).contains("Snow melt."));
+    assert!(!format!("{:?}", ir.children).contains("footnote"));
+}
+
+#[test]
+fn test_single_note_round_trips_through_lex() {
+    let lex = LexFormat::default();
+    let doc = MarkdownFormat
+        .parse("# Weather\n\nFloods.\n\n# Causes\n\nSnow[^a].\n\n[^a]: Melt water.\n")
+        .expect("Should parse markdown");
+    // A one-item list would read back as a paragraph
+    let source = lex.serialize(&doc).expect("Should serialize to Lex");
+    assert!(source.contains("    1. Melt water."), "{source}");
+
+    let doc = lex.parse(&source).expect("Should parse Lex");
+    let md = MarkdownFormat.serialize(&doc).expect("Should serialize");
+    assert!(md.contains("Snow [^1]."), "{md}");
+    assert!(md.contains("[^1]: Melt water."), "{md}");
+    assert!(!md.contains("Notes"), "{md}");
+}
+
+#[test]
+fn test_number_without_a_note_stays_text() {
+    let doc = LexFormat::default()
+        .parse("Results\n\n    Table [1] shows it.\n")
+        .expect("Should parse Lex");
+    let md = MarkdownFormat.serialize(&doc).expect("Should serialize");
+    assert!(md.contains("Table \\[1\\] shows it."), "{md}");
+    assert!(!md.contains("[^1]"), "{md}");
+}
+
+/// Markdown through Lex source and back, with the Lex source
+fn through_lex_source(md: &str) -> (String, String) {
+    let lex = LexFormat::default();
+    // A heading keeps the first paragraph from becoming the document title
+    let doc = MarkdownFormat
+        .parse(&format!("# Title\n\n# Part\n\n{md}"))
+        .expect("Should parse markdown");
+    let source = lex.serialize(&doc).expect("Should serialize to Lex");
+    let doc = lex.parse(&source).expect("Should parse Lex");
+    let md = MarkdownFormat.serialize(&doc).expect("Should serialize");
+    (source, md)
+}
+
+#[test]
+fn test_strikethrough_round_trips_through_lex_source() {
+    let (source, md) = through_lex_source("Some ~~old~~ text, ~~more~~ and old.\n");
+    assert!(
+        source.contains(
+            "    :: strikethrough text=old ::\n    :: strikethrough text=more ::\n    Some old text, more and old.\n"
+        ),
+        "{source}"
+    );
+    assert!(md.contains("Some ~old~ text, ~more~ and old."), "{md}");
+}
+
+#[test]
+fn test_task_list_round_trips_through_lex_source() {
+    let (source, md) = through_lex_source("Todo:\n\n- [x] done task\n- [ ] open task\n");
+    assert!(
+        source.contains("    Todo:\n\n    - [x] done task\n    - [ ] open task\n"),
+        "{source}"
+    );
+    assert!(md.contains("- [x] done task"), "{md}");
+    assert!(md.contains("- [ ] open task"), "{md}");
+}
+
+#[test]
+fn test_quoted_list_round_trips_through_lex_source() {
+    let (source, md) = through_lex_source("> Quoted para\n>\n> - only item\n\nAfter.\n");
+    assert!(
+        source.contains(
+            "    :: blockquote ::\n        Quoted para\n\n        :: list ::\n        - only item\n    ::\n    After.\n"
+        ),
+        "{source}"
+    );
+    assert!(md.contains("> Quoted para\n> \n> - only item"), "{md}");
+    assert!(md.contains("\n\nAfter."), "{md}");
+    assert!(!md.contains("lex:list"), "{md}");
+}
@@ -5,6 +5,7 @@
 mod annotations;
 mod export;
 mod frontmatter;
+mod gfm;
 mod import;
 mod sessions;
 mod table;
@@ -68,3 +68,21 @@ fn test_table_alignment_import() {
     assert_eq!(row.cells[1].align, TableCellAlignment::Center);
     assert_eq!(row.cells[2].align, TableCellAlignment::Right);
 }
+
+#[test]
+fn test_table_with_spans_falls_back_to_html() {
+    use lex_babel::formats::lex::LexFormat;
+
+    let lex = "Results:\n    | Region | Sales | <  |\n    | ^      | Q1    | Q2 |\n    | ------ | ----- | -- |\n    | North  | 10    | 12 |\n:: doc.table caption=\"Sales by quarter\" ::\n";
+    let doc = LexFormat::default()
+        .parse(lex)
+        .expect("Failed to parse lex");
+
+    let output = MarkdownFormat
+        .serialize(&doc)
+        .expect("Failed to serialize markdown");
+
+    assert!(output.contains("<table class=\"lex-table\"><caption>Sales by quarter</caption>"));
+    assert!(output.contains("<th colspan=\"2\">"));
+    assert!(!output.contains("| Region |"));
+}
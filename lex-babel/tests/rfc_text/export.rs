@@ -0,0 +1,197 @@
+//! Export tests for RFC plain text format (Lex → paginated text)
+
+use lex_babel::format::Format;
+use lex_babel::formats::rfc_text::serializer::{LINE_WIDTH, PAGE_LENGTH};
+use lex_babel::formats::rfc_text::RfcTextFormat;
+use lex_core::lex::transforms::standard::STRING_TO_AST;
+
+fn lex_to_rfc_text(lex_src: &str) -> String {
+    let lex_doc = STRING_TO_AST.run(lex_src.to_string()).unwrap();
+    RfcTextFormat.serialize(&lex_doc).unwrap()
+}
+
+fn pages(text: &str) -> Vec<&str> {
+    text.split("\n\u{c}\n").collect()
+}
+
+/// A document long enough to span several pages
+fn long_document() -> String {
+    let mut src = String::from(
+        ":: title ::\n    Protocol Notes\n::\n\n:: author ::\n    Jane Doe\n::\n\n:: date ::\n    2025-03-14\n::\n\n",
+    );
+    for section in 1..=6 {
+        src.push_str(&format!("{section}. Section {section}\n\n"));
+        for _ in 0..6 {
+            src.push_str(
+                "    This paragraph is long enough that it has to be wrapped over more than one line of output when it is laid out in seventy-two columns.\n\n",
+            );
+        }
+    }
+    src
+}
+
+#[test]
+fn test_lines_fit_in_72_columns() {
+    let text = lex_to_rfc_text(&long_document());
+
+    // The form feed between pages is the only line that is not text
+    for line in pages(&text).iter().flat_map(|page| page.lines()) {
+        assert!(
+            line.chars().count() <= LINE_WIDTH,
+            "line too long: {line:?}"
+        );
+        assert_eq!(line, line.trim_end(), "trailing whitespace: {line:?}");
+    }
+}
+
+#[test]
+fn test_pages_have_fixed_length_and_form_feeds() {
+    let text = lex_to_rfc_text(&long_document());
+    let pages = pages(&text);
+
+    assert!(
+        pages.len() > 2,
+        "expected several pages, got {}",
+        pages.len()
+    );
+    for (index, page) in pages.iter().enumerate() {
+        let lines: Vec<&str> = page.lines().collect();
+        assert_eq!(lines.len(), PAGE_LENGTH, "page {} length", index + 1);
+        assert!(
+            lines[PAGE_LENGTH - 1].ends_with(&format!("[Page {}]", index + 1)),
+            "{:?}",
+            lines[PAGE_LENGTH - 1]
+        );
+        assert!(lines[PAGE_LENGTH - 1].starts_with("Jane Doe"));
+    }
+}
+
+#[test]
+fn test_running_header_from_metadata() {
+    let text = lex_to_rfc_text(&long_document());
+    let pages = pages(&text);
+
+    assert!(pages[0].lines().next().unwrap().is_empty());
+    let header = pages[1].lines().next().unwrap();
+    assert!(header.starts_with("Internet-Draft"), "{header}");
+    assert!(header.contains("Protocol Notes"), "{header}");
+    assert!(header.ends_with("March 2025"), "{header}");
+}
+
+#[test]
+fn test_sections_are_numbered_from_nesting() {
+    let lex_src = "Intro\n\n    Text.\n\n    Details\n\n        More.\n\nUsage\n\n    Last.\n";
+    let text = lex_to_rfc_text(lex_src);
+
+    assert!(text.contains("\n1.  Intro\n"), "{text}");
+    assert!(text.contains("\n1.1.  Details\n"), "{text}");
+    assert!(text.contains("\n2.  Usage\n"), "{text}");
+    assert!(text.contains("\n   Text.\n"), "{text}");
+}
+
+#[test]
+fn test_toc_lists_sections_with_page_numbers() {
+    let text = lex_to_rfc_text(&long_document());
+
+    assert!(text.contains("Table of Contents"));
+    let entries: Vec<&str> = text
+        .lines()
+        .filter(|line| line.starts_with("   ") && line.contains(". . ."))
+        .collect();
+    assert_eq!(entries.len(), 6, "{entries:?}");
+
+    for (index, entry) in entries.iter().enumerate() {
+        let number = format!("{}.", index + 1);
+        assert!(entry.trim_start().starts_with(&number), "{entry}");
+        assert_eq!(entry.chars().count(), LINE_WIDTH, "{entry}");
+
+        // The listed page is the page the heading actually lands on
+        let page: usize = entry.split_whitespace().last().unwrap().parse().unwrap();
+        let heading = format!("{number}  Section {}", index + 1);
+        let page_text = pages(&text)[page - 1];
+        assert!(
+            page_text.lines().any(|line| line == heading),
+            "{heading} not on page {page}"
+        );
+    }
+}
+
+#[test]
+fn test_artwork_is_not_split_across_pages() {
+    let mut src = String::from("1. Filler\n\n");
+    for _ in 0..11 {
+        src.push_str(
+            "    A paragraph that takes up exactly a few lines on the page when it is wrapped.\n\n",
+        );
+    }
+    src.push_str("    Listing:\n\n");
+    for i in 0..20 {
+        src.push_str(&format!("        artwork line {i}\n"));
+    }
+    src.push_str("\n    :: text ::\n");
+    let text = lex_to_rfc_text(&src);
+
+    let page = pages(&text)
+        .into_iter()
+        .find(|page| page.contains("artwork line 0"))
+        .unwrap();
+    assert!(page.contains("artwork line 19"), "{text}");
+    assert!(text.contains("Figure 1: Listing"), "{text}");
+}
+
+#[test]
+fn test_lists_use_hanging_indent() {
+    let lex_src = "- A list item whose text is long enough to wrap onto a second line of the output page.\n- Short\n";
+    let text = lex_to_rfc_text(lex_src);
+    let lines: Vec<&str> = text.lines().collect();
+
+    let first = lines
+        .iter()
+        .position(|line| line.starts_with("   *  A list item"))
+        .expect(&text);
+    let continuation = lines[first + 1];
+    assert!(continuation.starts_with("      "), "{text}");
+    assert!(!continuation[6..].starts_with(' '), "{text}");
+    assert_eq!(lines[first + 2], "   *  Short", "{text}");
+}
+
+#[test]
+fn test_long_toc_titles_wrap_under_the_title() {
+    let lex_src = "1. A Session Title That Is Far Too Long to Fit on a Single Line of the Table of Contents\n\n    Body text.\n";
+    let text = lex_to_rfc_text(lex_src);
+    let lines: Vec<&str> = text.lines().collect();
+
+    for line in &lines {
+        assert!(
+            line.chars().count() <= LINE_WIDTH,
+            "line too long: {line:?}"
+        );
+    }
+    let first = lines
+        .iter()
+        .position(|line| line.starts_with("   1.  A Session Title"))
+        .expect(&text);
+    let continuation = lines[first + 1];
+    assert!(continuation.starts_with("       "), "{text}");
+    assert!(!continuation[7..].starts_with(' '), "{text}");
+    assert!(!lines[first].contains(". . ."), "{text}");
+    assert!(continuation.contains(". . ."), "{text}");
+    assert_eq!(continuation.split_whitespace().last(), Some("1"), "{text}");
+}
+
+#[test]
+fn test_list_item_blocks_hang_under_the_item() {
+    let lex_src =
+        "Intro text.\n\n- First item\n    Example:\n        let x = 1;\n    :: rust ::\n- Second item\n";
+    let text = lex_to_rfc_text(lex_src);
+
+    assert!(
+        text.lines().any(|line| line == "      let x = 1;"),
+        "{text}"
+    );
+    assert!(text.contains("Figure 1: Example"), "{text}");
+    assert!(
+        text.lines().any(|line| line == "   *  Second item"),
+        "{text}"
+    );
+}
@@ -0,0 +1,5 @@
+//! RFC plain text format tests
+//!
+//! Tests for Lex → RFC-style paginated text export.
+
+mod export;
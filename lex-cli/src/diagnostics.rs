@@ -0,0 +1,111 @@
+//! Rendering of format errors for the terminal.
+//!
+//! Errors that know where in the input they happened are shown with the
+//! offending line and a caret under the column, followed by the chain of
+//! underlying causes that the message does not already quote:
+//!
+//! ```text
+//! error[xml-syntax] (rfc_xml): Parse error: XML parsing error: ...
+//!  --> draft.xml:3:3
+//!   |
+//! 3 | </rfc>
+//!   |   ^
+//!   = caused by: ...
+//! ```
+
+use lex_babel::FormatError;
+use std::error::Error;
+
+/// Render `err` for stderr; `source` is the input text when there is one
+pub fn render_error(err: &FormatError, path: &str, source: Option<&str>) -> String {
+    let mut out = match err.format() {
+        Some(format) => format!("error[{}] ({format}): {err}\n", err.code()),
+        None => format!("error[{}]: {err}\n", err.code()),
+    };
+
+    if let Some(position) = err.position() {
+        let line_number = (position.line + 1).to_string();
+        let gutter = " ".repeat(line_number.len());
+        out.push_str(&format!(
+            "{gutter}--> {path}:{}:{}\n",
+            position.line + 1,
+            position.column + 1
+        ));
+
+        if let Some(line) = source.and_then(|text| text.lines().nth(position.line)) {
+            // Keep tabs so the caret lines up however the terminal expands them
+            let pad: String = line
+                .chars()
+                .take(position.column)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            out.push_str(&format!("{gutter} |\n"));
+            out.push_str(&format!("{line_number} | {line}\n"));
+            out.push_str(&format!("{gutter} | {pad}^\n"));
+        }
+    }
+
+    let message = err.message();
+    let mut cause = err.source();
+    while let Some(current) = cause {
+        let text = current.to_string();
+        if !message.contains(&text) {
+            out.push_str(&format!("  = caused by: {text}\n"));
+        }
+        cause = current.source();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lex_babel::error::{ErrorDetail, ErrorKind};
+    use lex_babel::ir::nodes::SourcePosition;
+
+    #[test]
+    fn renders_caret_under_column() {
+        let err: FormatError = ErrorDetail::new(ErrorKind::Parse, "xml-syntax", "bad close tag")
+            .at(SourcePosition::new(1, 2))
+            .caused_by(std::io::Error::other("underlying"))
+            .into();
+
+        let rendered = render_error(&err, "doc.xml", Some("<rfc>\n  </x>\n"));
+        assert_eq!(
+            rendered,
+            "error[xml-syntax]: Parse error: bad close tag\n \
+             --> doc.xml:2:3\n  \
+             |\n\
+             2 |   </x>\n  \
+             |   ^\n  \
+             = caused by: underlying\n"
+        );
+    }
+
+    #[test]
+    fn names_the_format_and_skips_causes_already_in_the_message() {
+        let err: FormatError = ErrorDetail::new(
+            ErrorKind::Parse,
+            "xml-syntax",
+            "XML parsing error: unexpected end",
+        )
+        .in_format("rfc_xml")
+        .caused_by(std::io::Error::other("unexpected end"))
+        .into();
+
+        assert_eq!(
+            render_error(&err, "doc.xml", None),
+            "error[xml-syntax] (rfc_xml): Parse error: XML parsing error: unexpected end\n"
+        );
+    }
+
+    #[test]
+    fn renders_plain_errors_on_one_line() {
+        let err = FormatError::ParseError("oops".to_string());
+        assert_eq!(
+            render_error(&err, "doc.lex", Some("text")),
+            "error[parse-error]: Parse error: oops\n"
+        );
+    }
+}
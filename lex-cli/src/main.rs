@@ -13,8 +13,12 @@
 // Usage:
 //  lex <input> --to <format> [--from <format>] [--output <file>]  - Convert between formats (default)
 //  lex convert <input> --to <format> [--from <format>] [--output <file>]  - Same as above (explicit)
+//  lex convert <input> --to <format> --pass <spec> ...  - Run IR passes before serializing
+//  lex convert <input> --to <format> --bibliography <file>  - Resolve [@key] citations
 //  lex inspect <path> [<transform>]      - Execute a transform (defaults to "ast-treeviz")
 //  lex --list-transforms                 - List available transforms
+//  lex help format <format>              - List the --extra-* options a format accepts
+//  lex completions <shell>               - Print a shell completion script
 //
 // Extra Parameters:
 //
@@ -23,17 +27,25 @@
 // Example:
 //  lex inspect file.lex --extra-all-nodes true --extra-max-depth 5
 
+use lex_cli::diagnostics::render_error;
 use lex_cli::transforms;
 
 use clap::{Arg, ArgAction, Command, ValueHint};
+use clap_complete::Shell;
 use lex_babel::{
-    formats::lex::formatting_rules::FormattingRules, transforms::serialize_to_lex_with_rules,
+    formats::lex::formatting_rules::FormattingRules,
+    ir::citations::CitationStyle,
+    ir::passes::builtin_pass,
+    ir::visit::{run_passes, Pass},
+    options::{parse_bool, FormatOption, OptionType},
+    transforms::serialize_to_lex_with_rules,
     FormatRegistry, SerializedDocument,
 };
 use lex_config::{LexConfig, Loader, PdfPageSize};
 use lex_core::lex::ast::{find_node_path_at_position, Position};
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
 /// Parse extra-* arguments from command line args
 /// Returns (cleaned_args_without_extras, extra_params_map)
@@ -106,6 +118,7 @@ fn build_cli() -> Command {
         )
         .arg_required_else_help(true)
         .subcommand_required(false)
+        .disable_help_subcommand(true)
         .arg(
             Arg::new("list-transforms")
                 .long("list-transforms")
@@ -180,6 +193,11 @@ fn build_cli() -> Command {
                     Supported formats:\n  \
                     - lex:      Lex format (.lex)\n  \
                     - markdown: Markdown (.md)\n  \
+                    - ipynb:    Jupyter notebook (.ipynb)\n  \
+                    - gemtext:  Gemini gemtext (.gmi), export only\n  \
+                    - confluence: Confluence storage format, export only\n  \
+                    - rfc_text: RFC-style paginated plain text (.rfctxt), export only\n  \
+                    - eml:      MIME email with HTML and text parts (.eml), export only\n  \
                     - html:     HTML with optional themes (.html)\n  \
                     - tag:      XML-like tag format\n\n\
                     The source format is auto-detected from the file extension.\n\
@@ -214,7 +232,7 @@ fn build_cli() -> Command {
                         .help("Target format (required)")
                         .long_help(
                             "Target format to convert to.\n\n\
-                            Available formats: lex, markdown, html, tag\n\
+                            Available formats: lex, markdown, ipynb, gemtext, confluence, rfc_text, eml, html, tag\n\
                             Use the format name, not the file extension."
                         )
                         .required(true)
@@ -231,6 +249,63 @@ fn build_cli() -> Command {
                             The file extension should match the target format."
                         )
                         .value_hint(ValueHint::FilePath),
+                )
+                .arg(
+                    Arg::new("pass")
+                        .long("pass")
+                        .help("IR pass to run before serializing (repeatable)")
+                        .long_help(
+                            "Transform the document between parsing and serializing.\n\n\
+                            Passes run in the order given. Available passes:\n  \
+                            shift-headings=N            Move every heading N levels (may be negative)\n  \
+                            number-sessions[=STYLE]     Number sessions 1., 1.1, ... or in STYLE, e.g. I.A.1\n                                \
+                            (add ,appendix=A to letter the appendices)\n  \
+                            strip-annotations=a,b       Drop annotations with these labels\n  \
+                            toc[=N]                     Fill :: toc :: placeholders (or add one), N levels deep\n\n\
+                            Example:\n  \
+                            lex convert doc.lex --to html --pass shift-headings=1 --pass number-sessions"
+                        )
+                        .value_name("SPEC")
+                        .action(ArgAction::Append)
+                        .value_hint(ValueHint::Other),
+                )
+                .arg(
+                    Arg::new("bibliography")
+                        .long("bibliography")
+                        .help("BibTeX or CSL-JSON file to resolve [@key] citations against")
+                        .long_help(
+                            "Resolve [@key] citations against this bibliography.\n\n\
+                            Overrides the document's `bibliography` metadata. Files ending in\n\
+                            .json are read as CSL-JSON, anything else as BibTeX. Cited works\n\
+                            are listed in a References session at the end of the document."
+                        )
+                        .value_name("FILE")
+                        .value_hint(ValueHint::FilePath),
+                )
+                .arg(
+                    Arg::new("citation-style")
+                        .long("citation-style")
+                        .help("How citations are written: author-year or numeric")
+                        .long_help(
+                            "How citations and the References session are written.\n\n\
+                            Overrides the document's `citation-style` metadata.\n  \
+                            author-year   (Smith and Lee 2023), references sorted by author\n  \
+                            numeric       [1], references numbered in order of citation"
+                        )
+                        .value_name("STYLE")
+                        .value_parser(["author-year", "numeric"]),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .help("Fail if the conversion drops or degrades any content")
+                        .long_help(
+                            "Treat conversion warnings as errors.\n\n\
+                            Warnings (deep headings flattened, references written as text,\n\
+                            raw HTML dropped, ...) are always printed to stderr. With --strict\n\
+                            the command exits with an error instead of writing the output."
+                        )
+                        .action(ArgAction::SetTrue),
                 ),
         )
         .subcommand(
@@ -253,6 +328,27 @@ fn build_cli() -> Command {
                         .value_hint(ValueHint::FilePath),
                 ),
         )
+        .subcommand(
+            Command::new("check")
+                .about("Check the files a lex file refers to")
+                .long_about(
+                    "Check the files a lex file refers to, without converting it.\n\n\
+                    Fails when a table, include or code sample names a src= file that\n\
+                    cannot be read, and warns about code samples whose inline copy\n\
+                    differs from the lines their src= file selects. Exports always use\n\
+                    the file, so the warning means the Lex source is out of date.\n\
+                    The command exits with an error when there are warnings.\n\n\
+                    Examples:\n  \
+                    lex check guide.lex                   # Check code samples are current"
+                )
+                .arg(
+                    Arg::new("input")
+                        .help("Input file path")
+                        .required(true)
+                        .index(1)
+                        .value_hint(ValueHint::FilePath),
+                ),
+        )
         .subcommand(
             Command::new("element-at")
                 .about("Get information about the element at a specific position")
@@ -297,6 +393,40 @@ fn build_cli() -> Command {
                     lex generate-lex-css > custom.css       # Save to file for editing"
                 ),
         )
+        .subcommand(
+            Command::new("help")
+                .about("Print help for a command, or the options of a format")
+                .long_about(
+                    "Print help for a command, or the options of a format.\n\n\
+                    Examples:\n  \
+                    lex help convert                        # Help for the convert command\n  \
+                    lex help format html                    # Options of the HTML format"
+                )
+                .arg(
+                    Arg::new("topic")
+                        .help("Command name, or 'format <name>'")
+                        .num_args(0..=2)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .long_about(
+                    "Print a completion script for the given shell, including the\n\
+                    --extra-<name> options of every format.\n\n\
+                    Examples:\n  \
+                    lex completions bash > ~/.local/share/bash-completion/completions/lex\n  \
+                    lex completions zsh > ~/.zfunc/_lex"
+                )
+                .arg(
+                    Arg::new("shell")
+                        .help("Shell to generate completions for")
+                        .required(true)
+                        .index(1)
+                        .value_parser(clap::value_parser!(Shell)),
+                ),
+        )
 }
 
 fn main() {
@@ -316,8 +446,10 @@ fn main() {
                 && !cleaned_args[1].starts_with('-')
                 && cleaned_args[1] != "inspect"
                 && cleaned_args[1] != "convert"
+                && cleaned_args[1] != "check"
                 && cleaned_args[1] != "generate-lex-css"
                 && cleaned_args[1] != "help"
+                && cleaned_args[1] != "completions"
             {
                 // Inject "convert" as the subcommand
                 let mut new_args = vec![cleaned_args[0].clone(), "convert".to_string()];
@@ -377,14 +509,40 @@ fn main() {
             };
 
             let output = sub_matches.get_one::<String>("output").map(|s| s.as_str());
-            handle_convert_command(input, &from, to, output, &extra_params, &config);
+            let passes: Vec<String> = sub_matches
+                .get_many::<String>("pass")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default();
+            let settings = ConvertSettings {
+                passes,
+                strict: sub_matches.get_flag("strict"),
+                bibliography: sub_matches.get_one::<String>("bibliography").cloned(),
+                citation_style: sub_matches
+                    .get_one::<String>("citation-style")
+                    .map(|style| style.parse().expect("validated by clap")),
+            };
+            handle_convert_command(input, &from, to, output, &settings, &extra_params, &config);
         }
         Some(("format", sub_matches)) => {
             let input = sub_matches
                 .get_one::<String>("input")
                 .expect("input is required");
             // Format command always outputs to stdout (no -o flag)
-            handle_convert_command(input, "lex", "lex", None, &extra_params, &config);
+            handle_convert_command(
+                input,
+                "lex",
+                "lex",
+                None,
+                &ConvertSettings::default(),
+                &extra_params,
+                &config,
+            );
+        }
+        Some(("check", sub_matches)) => {
+            let input = sub_matches
+                .get_one::<String>("input")
+                .expect("input is required");
+            handle_check_command(input);
         }
         Some(("element-at", sub_matches)) => {
             let path = sub_matches
@@ -402,6 +560,19 @@ fn main() {
         Some(("generate-lex-css", _)) => {
             handle_generate_lex_css_command();
         }
+        Some(("help", sub_matches)) => {
+            let topic: Vec<&str> = sub_matches
+                .get_many::<String>("topic")
+                .map(|values| values.map(String::as_str).collect())
+                .unwrap_or_default();
+            handle_help_command(&topic);
+        }
+        Some(("completions", sub_matches)) => {
+            let shell = *sub_matches
+                .get_one::<Shell>("shell")
+                .expect("shell is required");
+            handle_completions_command(shell);
+        }
         _ => {
             eprintln!("Unknown subcommand. Use --help for usage information.");
             std::process::exit(1);
@@ -431,16 +602,45 @@ fn handle_inspect_command(
     print!("{output}");
 }
 
+/// Per-run choices for the convert command
+#[derive(Default)]
+struct ConvertSettings {
+    /// Specs of the IR passes to run, in order
+    passes: Vec<String>,
+    /// Fail instead of writing output when the conversion loses content
+    strict: bool,
+    /// Bibliography overriding the document's own
+    bibliography: Option<String>,
+    /// Citation style overriding the document's own
+    citation_style: Option<CitationStyle>,
+}
+
 /// Handle the convert command
 fn handle_convert_command(
     input: &str,
     from: &str,
     to: &str,
     output: Option<&str>,
+    settings: &ConvertSettings,
     extra_params: &HashMap<String, String>,
     config: &LexConfig,
 ) {
-    let registry = FormatRegistry::default();
+    let mut registry = FormatRegistry::default();
+    // Files the document refers to are relative to the document
+    registry.set_base_dir(Path::new(input).parent());
+    registry.set_bibliography(settings.bibliography.as_deref().map(Path::new));
+    registry.set_citation_style(settings.citation_style);
+
+    let passes: Vec<Box<dyn Pass>> = settings
+        .passes
+        .iter()
+        .map(|spec| {
+            builtin_pass(spec).unwrap_or_else(|e| {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            })
+        })
+        .collect();
 
     // Validate formats exist
     if let Err(e) = registry.get(from) {
@@ -459,16 +659,28 @@ fn handle_convert_command(
     });
 
     // Parse
-    let doc = registry.parse(&source, from).unwrap_or_else(|e| {
-        eprintln!("Parse error: {e}");
-        std::process::exit(1);
-    });
+    let (doc, mut report) = registry
+        .parse_with_report(&source, from)
+        .unwrap_or_else(|e| {
+            eprint!("{}", render_error(&e, input, Some(&source)));
+            std::process::exit(1);
+        });
 
     let mut format_options = HashMap::new();
 
     // Serialize (format-specific parameters allowed via --extra-*)
     let result = if to == "lex" {
         let rules = formatting_rules_from_config(config);
+        let doc = if passes.is_empty() {
+            doc
+        } else {
+            let ctx = registry.context();
+            let mut ir = lex_babel::to_ir_with_context(&doc, &ctx);
+            run_passes(&mut ir, &passes);
+            let mut rewritten = lex_babel::from_ir_with_context(&ir, &ctx);
+            rewritten.root.title = doc.root.title.clone();
+            rewritten
+        };
         match serialize_to_lex_with_rules(&doc, rules) {
             Ok(text) => SerializedDocument::Text(text),
             Err(err) => {
@@ -484,18 +696,50 @@ fn handle_convert_command(
             if let Some(css_path) = &config.convert.html.custom_css {
                 format_options.insert("css-path".to_string(), css_path.clone());
             }
+            if let Some(code_theme) = &config.convert.html.code_theme {
+                format_options.insert("code-theme".to_string(), code_theme.clone());
+            }
+        } else if to == "eml" {
+            // Relative image paths are resolved against the input file
+            if let Some(dir) = Path::new(input).parent() {
+                format_options.insert("base-dir".to_string(), dir.display().to_string());
+            }
+        } else if to == "markdown" {
+            // Rendered diagrams go next to the output file, if there is one
+            if let Some(path) = output {
+                let dir = Path::new(path)
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                format_options.insert("asset-dir".to_string(), dir.display().to_string());
+            }
         }
         for (key, value) in extra_params {
             format_options.insert(key.clone(), value.clone());
         }
-        registry
-            .serialize_with_options(&doc, to, &format_options)
+        let (serialized, serialize_report) = registry
+            .serialize_with_report(&doc, to, &format_options, &passes)
             .unwrap_or_else(|e| {
-                eprintln!("Serialization error: {e}");
+                // Errors in referenced files point back into a Lex input
+                let source = (from == "lex").then_some(source.as_str());
+                eprint!("{}", render_error(&e, input, source));
                 std::process::exit(1);
-            })
+            });
+        report.extend(serialize_report);
+        serialized
     };
 
+    for warning in report.warnings() {
+        eprintln!("warning[{}]: {warning}", warning.kind);
+    }
+    if settings.strict && !report.is_empty() {
+        eprintln!(
+            "Error: conversion from {from} to {to} is lossy ({} warning(s)) and --strict is set",
+            report.len()
+        );
+        std::process::exit(1);
+    }
+
     // Output
     match (output, result) {
         (Some(path), data) => {
@@ -514,6 +758,141 @@ fn handle_convert_command(
     }
 }
 
+/// Handle the check command
+fn handle_check_command(input: &str) {
+    let mut registry = FormatRegistry::default();
+    registry.set_base_dir(Path::new(input).parent());
+
+    let source = fs::read_to_string(input).unwrap_or_else(|e| {
+        eprintln!("Error reading file '{input}': {e}");
+        std::process::exit(1);
+    });
+    let report = registry
+        .parse(&source, "lex")
+        .and_then(|doc| registry.check(&doc))
+        .unwrap_or_else(|e| {
+            eprint!("{}", render_error(&e, input, Some(&source)));
+            std::process::exit(1);
+        });
+
+    for warning in report.warnings() {
+        eprintln!("warning[{}]: {warning}", warning.kind);
+    }
+    if !report.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Handle the help command: `lex help [command]` or `lex help format <name>`
+fn handle_help_command(topic: &[&str]) {
+    let mut cli = build_cli();
+    match topic {
+        ["format", name] => handle_format_help(name),
+        [] => {
+            cli.print_long_help().ok();
+        }
+        [command, ..] => match cli.find_subcommand_mut(command) {
+            Some(sub) => {
+                sub.print_long_help().ok();
+                if *command == "format" {
+                    println!("\nRun `lex help format <name>` for the options of an output format.");
+                }
+            }
+            None => {
+                eprintln!("Error: Unknown command '{command}'");
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Print the description and options of one format
+fn handle_format_help(name: &str) {
+    let registry = FormatRegistry::default();
+    let format = registry.get(name).unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        eprintln!("Available formats: {}", registry.list_formats().join(", "));
+        std::process::exit(1);
+    });
+
+    println!("{} - {}", format.name(), format.description());
+    if !format.file_extensions().is_empty() {
+        println!("Extensions: {}", format.file_extensions().join(", "));
+    }
+    let directions: Vec<&str> = [
+        (format.supports_parsing(), "parse"),
+        (format.supports_serialization(), "serialize"),
+    ]
+    .into_iter()
+    .filter_map(|(supported, direction)| supported.then_some(direction))
+    .collect();
+    println!("Supports: {}", directions.join(", "));
+
+    let options = format.options();
+    if options.is_empty() {
+        println!("\nThis format takes no options.");
+        return;
+    }
+
+    println!("\nOptions (pass as --extra-<name> <value>):");
+    for option in options {
+        println!("  --extra-{} <{}>", option.name, option.kind);
+        println!("      {}", option.help);
+        if !option.allowed_values.is_empty() {
+            println!("      values: {}", option.allowed_values.join(", "));
+        }
+        if let Some(default) = option.default {
+            println!("      default: {default}");
+        }
+        if !option.aliases.is_empty() {
+            println!("      also accepted as: {}", option.aliases.join(", "));
+        }
+    }
+}
+
+/// Print a completion script that knows every format's `--extra-*` options
+fn handle_completions_command(shell: Shell) {
+    let registry = FormatRegistry::default();
+    let extra_args = extra_option_args(&registry);
+    let mut cli = build_cli().mut_subcommand("convert", |convert| convert.args(extra_args));
+    clap_complete::generate(shell, &mut cli, "lex", &mut std::io::stdout());
+}
+
+/// Completion-only arguments for the `--extra-*` options of all formats
+///
+/// The real parse strips `--extra-*` before clap sees it, so these only
+/// describe the options to the completion generator.
+fn extra_option_args(registry: &FormatRegistry) -> Vec<Arg> {
+    let mut seen: Vec<(&'static str, FormatOption, Vec<String>)> = Vec::new();
+    for name in registry.list_formats() {
+        let Ok(format) = registry.get(&name) else {
+            continue;
+        };
+        for option in format.options() {
+            match seen.iter_mut().find(|(key, _, _)| *key == option.name) {
+                Some((_, _, formats)) => formats.push(name.clone()),
+                None => seen.push((option.name, *option, vec![name.clone()])),
+            }
+        }
+    }
+
+    seen.into_iter()
+        .map(|(key, option, formats)| {
+            // clap wants 'static names; this runs once per process
+            let long: &'static str = Box::leak(format!("extra-{key}").into_boxed_str());
+            let help = format!("{} [{}]", option.help, formats.join(", "));
+            let arg = Arg::new(long).long(long).help(help).value_name("VALUE");
+            match option.kind {
+                OptionType::Bool => arg.num_args(0..=1).value_parser(["true", "false"]),
+                OptionType::Choice => arg.value_parser(option.allowed_values.to_vec()),
+                OptionType::Integer => arg.value_name("N").value_hint(ValueHint::Other),
+                OptionType::Path => arg.value_hint(ValueHint::AnyPath),
+                OptionType::String => arg.value_hint(ValueHint::Other),
+            }
+        })
+        .collect()
+}
+
 /// Handle the element-at command
 fn handle_element_at_command(path: &str, row: usize, col: usize, all: bool) {
     let source = fs::read_to_string(path).unwrap_or_else(|e| {
@@ -523,7 +902,7 @@ fn handle_element_at_command(path: &str, row: usize, col: usize, all: bool) {
 
     let registry = FormatRegistry::default();
     let doc = registry.parse(&source, "lex").unwrap_or_else(|e| {
-        eprintln!("Parse error: {e}");
+        eprint!("{}", render_error(&e, path, Some(&source)));
         std::process::exit(1);
     });
 
@@ -718,14 +1097,10 @@ fn take_override(map: &mut HashMap<String, String>, keys: &[&str]) -> Option<Str
 }
 
 fn parse_bool_arg(flag: &str, raw: &str) -> bool {
-    match raw.to_lowercase().as_str() {
-        "true" | "1" | "yes" | "y" => true,
-        "false" | "0" | "no" | "n" => false,
-        other => {
-            eprintln!("Invalid boolean value '{other}' for --extra-{flag}");
-            std::process::exit(1);
-        }
-    }
+    parse_bool(raw).unwrap_or_else(|| {
+        eprintln!("Invalid boolean value '{raw}' for --extra-{flag}");
+        std::process::exit(1);
+    })
 }
 
 #[cfg(test)]
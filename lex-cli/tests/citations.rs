@@ -0,0 +1,81 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+const BIB: &str =
+    "@article{smith2023,\n  author = {Smith, John},\n  title = {Rivers},\n  year = 2023,\n}\n";
+
+#[test]
+fn convert_resolves_citations_from_metadata() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("report.lex");
+    fs::write(
+        &input_path,
+        ":: bibliography :: refs.bib\n\nFloods rise [@smith2023].\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("refs.bib"), BIB).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let html = String::from_utf8(output).unwrap();
+    assert!(html.contains(">(Smith 2023)</a>"), "{html}");
+    assert!(html.contains("id=\"bibliography\""), "{html}");
+    assert!(html.contains("Smith, J. (2023). Rivers."), "{html}");
+}
+
+#[test]
+fn convert_takes_bibliography_and_style_options() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("report.lex");
+    fs::write(&input_path, "Floods rise [@smith2023] and [@nobody].\n").unwrap();
+    let bib_path = dir.path().join("refs.bib");
+    fs::write(&bib_path, BIB).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("markdown")
+        .arg("--bibliography")
+        .arg(bib_path.as_os_str())
+        .arg("--citation-style")
+        .arg("numeric");
+
+    let assert = cmd.assert().success();
+    let md = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(md.contains("](#bibliography \"Rivers\")"), "{md}");
+    assert!(md.contains("<a id=\"bibliography\"></a>"), "{md}");
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("warning[unknown-citation]"), "{stderr}");
+}
+
+#[test]
+fn convert_reports_missing_bibliography() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("report.lex");
+    fs::write(
+        &input_path,
+        ":: bibliography :: missing.bib\n\nFloods rise [@smith2023].\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(
+        stderr.starts_with("error[bibliography-source]:"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("missing.bib"), "{stderr}");
+}
@@ -0,0 +1,63 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn convert_writes_diagrams_next_to_markdown() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("design.lex");
+    fs::write(
+        &input_path,
+        "Flow:\n\n    digraph { client -> server; }\n\n:: doc.dot ::\n",
+    )
+    .unwrap();
+    let output_path = dir.path().join("design.md");
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("markdown")
+        .arg("--output")
+        .arg(output_path.as_os_str());
+    cmd.assert().success();
+
+    let markdown = fs::read_to_string(&output_path).unwrap();
+    let svgs: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".svg"))
+        .collect();
+    assert_eq!(svgs.len(), 1, "{svgs:?}");
+    assert!(
+        markdown.contains(&format!("![Flow]({})", svgs[0])),
+        "{markdown}"
+    );
+    assert!(!markdown.contains("digraph"), "{markdown}");
+}
+
+#[test]
+fn convert_keeps_broken_diagrams_as_source() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("design.lex");
+    fs::write(
+        &input_path,
+        "Flow:\n\n    digraph { client -> \n\n:: doc.dot ::\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+    let assert = cmd.assert().success();
+    let output = assert.get_output();
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+    assert!(
+        stderr.starts_with("warning[diagram-fallback]: line 1:"),
+        "{stderr}"
+    );
+    let html = String::from_utf8(output.stdout.clone()).unwrap();
+    assert!(html.contains("digraph { client -&gt;"), "{html}");
+}
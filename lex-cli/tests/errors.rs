@@ -0,0 +1,30 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn convert_shows_snippet_for_xml_errors() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("draft.xml");
+    fs::write(&input_path, "<rfc>\n  <front>\n</rfc>\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--from")
+        .arg("rfc_xml")
+        .arg("--to")
+        .arg("lex");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(
+        stderr.starts_with("error[xml-syntax] (rfc_xml): Parse error:"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("draft.xml:3:"), "{stderr}");
+    assert!(stderr.contains("3 | </rfc>"), "{stderr}");
+    assert!(stderr.contains("^\n"), "{stderr}");
+    // The XML error is already part of the message
+    assert!(!stderr.contains("= caused by:"), "{stderr}");
+}
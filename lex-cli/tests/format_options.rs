@@ -0,0 +1,57 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn help_format_lists_options() {
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("help").arg("format").arg("html");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.starts_with("html - "));
+    assert!(stdout.contains("--extra-theme <choice>"));
+    assert!(stdout.contains("values: modern, default, fancy-serif"));
+    assert!(stdout.contains("also accepted as: css_path"));
+}
+
+#[test]
+fn help_format_without_options() {
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("help").arg("format").arg("gemtext");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.contains("This format takes no options."));
+}
+
+#[test]
+fn completions_include_extra_options() {
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("completions").arg("bash");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let script = String::from_utf8(output).unwrap();
+    assert!(script.contains("--extra-theme"));
+    assert!(script.contains("--extra-size-mobile"));
+}
+
+#[test]
+fn convert_rejects_unknown_extra_option() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.lex");
+    fs::write(&input_path, "Session:\n    Body\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html")
+        .arg("--extra-colour")
+        .arg("red");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.contains("error[unknown-option]"), "{stderr}");
+    assert!(stderr.contains("Unknown option 'colour' for format 'html'"));
+}
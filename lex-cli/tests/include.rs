@@ -0,0 +1,71 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+const BOOK: &str = "Guide\n\n    Intro.\n\n    :: doc.include src=\"chapters/setup.lex\" ::\n";
+const SETUP: &str = "Setup\n\n    Install it.\n";
+
+#[test]
+fn convert_resolves_includes_at_the_insertion_level() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("book.lex");
+    fs::write(&input_path, BOOK).unwrap();
+    fs::create_dir(dir.path().join("chapters")).unwrap();
+    fs::write(dir.path().join("chapters/setup.lex"), SETUP).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("markdown");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let markdown = String::from_utf8(output).unwrap();
+    assert!(markdown.contains("## Guide"), "{markdown}");
+    assert!(markdown.contains("### Setup"), "{markdown}");
+    assert!(markdown.contains("Install it."), "{markdown}");
+}
+
+#[test]
+fn convert_reports_include_cycles() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("a.lex");
+    fs::write(
+        &input_path,
+        "A\n\n    Text.\n\n:: doc.include src=\"b.lex\" ::\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.lex"),
+        "B\n\n    Text.\n\n:: doc.include src=\"a.lex\" ::\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.starts_with("error[include-cycle]:"), "{stderr}");
+    assert!(stderr.contains("b.lex → a.lex → b.lex"), "{stderr}");
+}
+
+#[test]
+fn format_keeps_includes() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("book.lex");
+    fs::write(&input_path, BOOK).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("format").arg(input_path.as_os_str());
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let formatted = String::from_utf8(output).unwrap();
+    assert!(
+        formatted.contains(":: doc.include src=\"chapters/setup.lex\" ::"),
+        "{formatted}"
+    );
+}
@@ -0,0 +1,105 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn convert_runs_passes_in_order() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.lex");
+    fs::write(
+        &input_path,
+        "Intro\n\n    Body.\n\n    :: todo :: rewrite this\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html")
+        .arg("--pass")
+        .arg("shift-headings=1")
+        .arg("--pass")
+        .arg("number-sessions")
+        .arg("--pass")
+        .arg("strip-annotations=todo");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let html = String::from_utf8(output).unwrap();
+
+    assert!(html.contains("<h3"), "headings shifted down a level");
+    assert!(html.contains(r#"<span class="seq_marker">1.</span>"#));
+    assert!(!html.contains("rewrite this"), "todo annotation stripped");
+}
+
+#[test]
+fn convert_rejects_unknown_pass() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.lex");
+    fs::write(&input_path, "Session:\n    Body\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html")
+        .arg("--pass")
+        .arg("reticulate-splines");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.contains("Unknown pass 'reticulate-splines'"));
+}
+
+#[test]
+fn convert_numbers_sessions_in_roman_style() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.lex");
+    fs::write(
+        &input_path,
+        "Intro\n\n    See [#2].\n\nUsage\n\n    Text.\n\nAppendix: Data\n\n    Tables.\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html")
+        .arg("--pass")
+        .arg("number-sessions=I.A.1,appendix=A");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let html = String::from_utf8(output).unwrap();
+
+    assert!(
+        html.contains(r#"<span class="seq_marker">II.</span>"#),
+        "{html}"
+    );
+    assert!(
+        html.contains(r#"<span class="seq_marker">A.</span>"#),
+        "{html}"
+    );
+    // The reference follows the session to its new number
+    assert!(html.contains(">#II</a>"), "{html}");
+    assert!(html.contains(r##"href="#usage""##), "{html}");
+}
+
+#[test]
+fn pass_help_lines_fit_without_wrapping() {
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert").arg("--help");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let help = String::from_utf8(output).unwrap();
+
+    // clap wraps help at 100 columns, which would split an entry's text
+    assert!(
+        help.lines().all(|line| line.chars().count() <= 100),
+        "{help}"
+    );
+    assert!(
+        help.contains("(add ,appendix=A to letter the appendices)"),
+        "{help}"
+    );
+}
@@ -0,0 +1,61 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+const RAW_HTML: &str = "# Title\n\nText.\n\n<div class=\"note\">kept out</div>\n";
+
+#[test]
+fn convert_prints_warnings_to_stderr() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.md");
+    fs::write(&input_path, RAW_HTML).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("lex");
+
+    let output = cmd.assert().success().get_output().clone();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("warning[content-dropped]: line 5:"));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("Text."));
+}
+
+#[test]
+fn convert_strict_fails_on_loss() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.md");
+    let output_path = dir.path().join("doc.lex");
+    fs::write(&input_path, RAW_HTML).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("lex")
+        .arg("--strict")
+        .arg("-o")
+        .arg(output_path.as_os_str());
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.contains("--strict"));
+    assert!(!output_path.exists(), "nothing written on a strict failure");
+}
+
+#[test]
+fn convert_strict_passes_without_loss() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("doc.lex");
+    fs::write(&input_path, "Session:\n    Body\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("markdown")
+        .arg("--strict");
+
+    cmd.assert().success();
+}
@@ -0,0 +1,68 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+const DOC: &str = "Setup:\n    let x = 1;\n:: rust src=\"main.rs\", region=\"setup\" ::\n";
+
+fn write_main(dir: &std::path::Path, setup: &str) {
+    fs::write(
+        dir.join("main.rs"),
+        format!("fn main() {{\n    // #region setup\n    {setup}\n    // #endregion\n}}\n"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn check_passes_when_samples_match() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("guide.lex");
+    fs::write(&input_path, DOC).unwrap();
+    write_main(dir.path(), "let x = 1;");
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("check").arg(input_path.as_os_str());
+    cmd.assert().success();
+}
+
+#[test]
+fn check_reports_drifted_samples() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("guide.lex");
+    fs::write(&input_path, DOC).unwrap();
+    write_main(dir.path(), "let x = 2;");
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("check").arg(input_path.as_os_str());
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(
+        stderr.starts_with("warning[source-drift]: line 1:"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("main.rs"), "{stderr}");
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html")
+        .arg("--extra-code-theme")
+        .arg("none");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let html = String::from_utf8(output).unwrap();
+    assert!(html.contains("let x = 2;"), "{html}");
+}
+
+#[test]
+fn check_reports_unreadable_sources() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("guide.lex");
+    fs::write(&input_path, DOC).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("check").arg(input_path.as_os_str());
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.starts_with("error[verbatim-source]:"), "{stderr}");
+    assert!(stderr.contains("guide.lex:3:"), "{stderr}");
+}
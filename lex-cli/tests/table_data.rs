@@ -0,0 +1,44 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+const DOC: &str = "Stock:\n:: doc.table src=\"data.csv\", header=true ::\n";
+
+#[test]
+fn convert_reads_table_data_next_to_the_input() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("stock.lex");
+    fs::write(&input_path, DOC).unwrap();
+    fs::write(dir.path().join("data.csv"), "Fruit,Qty\nPear,3\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let html = String::from_utf8(output).unwrap();
+    assert!(html.contains("<th"), "{html}");
+    assert!(html.contains("Fruit"), "{html}");
+    assert!(html.contains("Pear"), "{html}");
+}
+
+#[test]
+fn convert_reports_missing_table_data() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("stock.lex");
+    fs::write(&input_path, DOC).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("lex");
+    cmd.arg("convert")
+        .arg(input_path.as_os_str())
+        .arg("--to")
+        .arg("html");
+
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.starts_with("error[table-source]:"), "{stderr}");
+    assert!(stderr.contains("stock.lex:2:"), "{stderr}");
+    assert!(stderr.contains("data.csv"), "{stderr}");
+}
@@ -26,19 +26,13 @@ theme = "dark"
         .arg("--config")
         .arg(config_path.as_os_str());
 
-    let output = cmd.assert().success().get_output().stdout.clone();
-    let _stdout = String::from_utf8(output).unwrap();
-
-    // We expect the HTML output to contain the theme class or reference
-    // Note: This assumes the HTML formatter puts the theme in the output.
-    // If the "dark" theme doesn't exist or isn't outputted directly, this test might need adjustment
-    // based on how the HTML formatter works. For now, we assume it does something visible.
-    // If the HTML formatter just passes "dark" to the template, we might see it in a class.
-    // Let's assume a class="theme-dark" or similar, or just check if "dark" is present if we are unsure.
-    // Actually, let's check for the presence of "dark" which is safer for a generic test.
-    // But wait, "dark" might be in the content? No, "Body" is the content.
-    // So if "dark" appears, it's likely from the theme.
-    // To be safer, let's use a very unique theme name.
+    // "dark" is not an HTML theme; it used to fall back to modern silently
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(
+        stderr.contains("Invalid value 'dark' for option 'theme'"),
+        "{stderr}"
+    );
 
     // Re-write config with unique theme name
     fs::write(
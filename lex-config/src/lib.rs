@@ -115,6 +115,9 @@ pub struct HtmlConfig {
     /// Optional path to a custom CSS file to append after the baseline CSS.
     #[serde(default)]
     pub custom_css: Option<String>,
+    /// Optional colour scheme for highlighted code blocks (`none` disables it).
+    #[serde(default)]
+    pub code_theme: Option<String>,
 }
 
 /// Helper for layering user overrides over the built-in defaults.